@@ -114,6 +114,11 @@ contract PriceFeed {
             LibPriceFeedStorage.NodePriceMeasurement.perCount,
             baseAmount / 10
         );
+        setLitActionPriceConfig(
+            LibPriceFeedStorage.LitActionPriceComponent.storageBytes,
+            LibPriceFeedStorage.NodePriceMeasurement.perMegabyte,
+            baseAmount / 10
+        );
     }
 
     function setLitActionPriceConfig(
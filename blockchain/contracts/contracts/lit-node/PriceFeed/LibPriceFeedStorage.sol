@@ -28,7 +28,8 @@ library LibPriceFeedStorage {
         contractCalls,
         callDepth,
         decrypts,
-        fetches
+        fetches,
+        storageBytes
     }
 
     enum NodePriceMeasurement {
@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use anyhow::{Context as _, Result, bail};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The outcome of checking a set of access control conditions, which the emulator can't
+/// evaluate itself as that requires querying chains.
+#[derive(Debug, Deserialize)]
+struct ConditionFixture {
+    conditions: Value,
+    result: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct ConditionFixtures(Vec<ConditionFixture>);
+
+impl ConditionFixtures {
+    pub(super) fn load(path: &Path) -> Result<Self> {
+        let fixtures = std::fs::read(path).with_context(|| format!("Could not read {path:?}"))?;
+        serde_json::from_slice(&fixtures)
+            .with_context(|| format!("Invalid condition fixtures in {path:?}"))
+    }
+
+    /// Looks up the result for the JSON-encoded conditions passed to an op.
+    pub(super) fn check(&self, conditions: &[u8]) -> Result<bool> {
+        let conditions: Value = serde_json::from_slice(conditions)?;
+        match self
+            .0
+            .iter()
+            .find(|fixture| fixture.conditions == conditions)
+        {
+            Some(fixture) => Ok(fixture.result),
+            None => bail!(
+                "No fixture for access control conditions {conditions}, add one to the --conditions file"
+            ),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use blsful::inner_types::GroupEncoding as _;
+use blsful::{Bls12381G2Impl, SecretKey, SignatureSchemes, TimeCryptCiphertext};
+use k256::ecdsa::SigningKey;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+
+/// Throwaway keys standing in for the PKPs and the BLS root key of a Lit network.
+///
+/// All virtual nodes share the same keys, so they produce the same signatures, just like
+/// the nodes of a real network produce shares of the same signature.
+pub(super) struct LocalKeys {
+    // Keyed by the PKP public key requested by the action
+    ecdsa: Mutex<BTreeMap<String, SigningKey>>,
+    bls: SecretKey<Bls12381G2Impl>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct LocalSignature {
+    pub(super) public_key: String,
+    pub(super) signed_data: String,
+    pub(super) signature: String,
+    pub(super) recovery_id: u8,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct KeysReport {
+    bls_public_key: String,
+    // PKP public key requested by the action -> public key of the local key used instead
+    pkps: BTreeMap<String, String>,
+}
+
+impl LocalKeys {
+    pub(super) fn new(bls_secret_key: Option<&str>) -> Result<Self> {
+        let bls = match bls_secret_key {
+            Some(hex_key) => {
+                let bytes: [u8; 32] = hex::decode(hex_key.trim_start_matches("0x"))
+                    .context("BLS secret key must be hex-encoded")?
+                    .try_into()
+                    .map_err(|_| anyhow!("BLS secret key must be 32 bytes long"))?;
+                Option::from(SecretKey::from_be_bytes(&bytes))
+                    .context("BLS secret key is not a valid scalar")?
+            }
+            None => SecretKey::new(),
+        };
+
+        Ok(Self {
+            ecdsa: Default::default(),
+            bls,
+        })
+    }
+
+    /// Signs a 32-byte hash with the local key standing in for the given PKP.
+    pub(super) fn sign_ecdsa(
+        &self,
+        pkp_public_key: &str,
+        prehash: &[u8],
+    ) -> Result<LocalSignature> {
+        if prehash.len() != 32 {
+            bail!(
+                "Message to sign must be a 32-byte hash, got {} bytes",
+                prehash.len()
+            );
+        }
+
+        let mut keys = self.ecdsa.lock().expect("poisoned lock");
+        let key = keys
+            .entry(normalize_public_key(pkp_public_key))
+            .or_insert_with(|| SigningKey::random(&mut rand::rngs::OsRng));
+        let (signature, recovery_id) = key.sign_prehash_recoverable(prehash)?;
+
+        Ok(LocalSignature {
+            public_key: hex::encode(key.verifying_key().to_encoded_point(false).as_bytes()),
+            signed_data: hex::encode(prehash),
+            signature: hex::encode(signature.to_bytes()),
+            recovery_id: recovery_id.to_byte(),
+        })
+    }
+
+    /// Encrypts like `Lit.Actions.encrypt`, returning the ciphertext and the hash of the data.
+    pub(super) fn encrypt(&self, conditions: &[u8], to_encrypt: &[u8]) -> Result<(String, String)> {
+        let data_to_encrypt_hash = hex::encode(Sha256::digest(to_encrypt));
+        let identity = identity(conditions, &data_to_encrypt_hash)?;
+
+        let ciphertext = self
+            .bls
+            .public_key()
+            .encrypt_time_lock(SignatureSchemes::ProofOfPossession, to_encrypt, &identity)
+            .map_err(|e| anyhow!("Failed to encrypt: {e:?}"))?;
+        let ciphertext = data_encoding::BASE64.encode(&serde_bare::to_vec(&ciphertext)?);
+
+        Ok((ciphertext, data_to_encrypt_hash))
+    }
+
+    /// Decrypts a ciphertext returned by `encrypt`, provided the conditions match.
+    pub(super) fn decrypt(
+        &self,
+        conditions: &[u8],
+        ciphertext: &str,
+        data_to_encrypt_hash: &str,
+    ) -> Result<String> {
+        let identity = identity(conditions, data_to_encrypt_hash)?;
+        let ciphertext: TimeCryptCiphertext<Bls12381G2Impl> = serde_bare::from_slice(
+            &data_encoding::BASE64
+                .decode(ciphertext.as_bytes())
+                .context("Ciphertext must be base64-encoded")?,
+        )?;
+
+        let signature = self
+            .bls
+            .sign(SignatureSchemes::ProofOfPossession, &identity)
+            .map_err(|e| anyhow!("Failed to sign identity: {e:?}"))?;
+        let plaintext = Option::<Vec<u8>>::from(ciphertext.decrypt(&signature)).context(
+            "Failed to decrypt, was the ciphertext encrypted by the emulator with the same BLS key?",
+        )?;
+
+        String::from_utf8(plaintext).context("Failed to convert decrypted bytes to string")
+    }
+
+    pub(super) fn report(&self) -> KeysReport {
+        KeysReport {
+            bls_public_key: hex::encode(self.bls.public_key().0.to_bytes()),
+            pkps: self
+                .ecdsa
+                .lock()
+                .expect("poisoned lock")
+                .iter()
+                .map(|(pkp, key)| {
+                    let local = key.verifying_key().to_encoded_point(false);
+                    (pkp.clone(), hex::encode(local.as_bytes()))
+                })
+                .collect(),
+        }
+    }
+}
+
+// Lit hashes canonicalized conditions into the identity, which is overkill here, as only the
+// emulator's own ciphertexts can be decrypted with its key anyway.
+fn identity(conditions: &[u8], data_to_encrypt_hash: &str) -> Result<Vec<u8>> {
+    // Round-trip through Value to ignore formatting and key order
+    let conditions: serde_json::Value = serde_json::from_slice(conditions)?;
+    let conditions_hash = hex::encode(Sha256::digest(serde_json::to_vec(&conditions)?));
+    Ok(
+        format!("lit-accesscontrolcondition://{conditions_hash}/{data_to_encrypt_hash}")
+            .into_bytes(),
+    )
+}
+
+fn normalize_public_key(public_key: &str) -> String {
+    public_key.trim_start_matches("0x").to_lowercase()
+}
@@ -0,0 +1,250 @@
+//! Runs Lit Actions locally, without a Lit network.
+//!
+//! Like on a real network, the action is executed once per node: each virtual node runs it
+//! in an in-process lit_actions server and answers its ops in place of lit-node. Signatures
+//! are made with throwaway local keys, access control conditions are looked up in a fixture
+//! file, and broadcasts are exchanged between the virtual nodes.
+
+mod conditions;
+mod keys;
+mod network;
+mod node;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result, bail, ensure};
+use lit_actions_server::{TestServer, proto::ExecutionRequest};
+use serde::Serialize;
+use tokio::task::JoinSet;
+
+use conditions::ConditionFixtures;
+use keys::{KeysReport, LocalKeys};
+use network::VirtualNetwork;
+use node::{NodeReport, VirtualNode};
+
+#[derive(Debug, clap::Args)]
+pub struct EmulateArgs {
+    #[arg(help = "Path to the Lit Action to run")]
+    action: PathBuf,
+
+    #[arg(long, help = "Path to a JSON object passed to the action as jsParams")]
+    js_params: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Path to a JSON array of {\"conditions\": [...], \"result\": bool} fixtures"
+    )]
+    conditions: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value = "3",
+        help = "Number of virtual nodes running the action"
+    )]
+    nodes: usize,
+
+    #[arg(
+        long,
+        help = "Hex-encoded BLS secret key to encrypt and decrypt with (random by default)"
+    )]
+    bls_secret_key: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "30000",
+        help = "Maximum runtime of the action in ms"
+    )]
+    timeout_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EmulationReport {
+    keys: KeysReport,
+    nodes: Vec<NodeReport>,
+}
+
+pub async fn run(args: EmulateArgs) -> Result<()> {
+    ensure!(args.nodes > 0, "At least one virtual node is required");
+
+    let code = std::fs::read_to_string(&args.action)
+        .with_context(|| format!("Could not read action {:?}", args.action))?;
+    let js_params = args.js_params.as_deref().map(read_js_params).transpose()?;
+    let conditions = match &args.conditions {
+        Some(path) => ConditionFixtures::load(path)?,
+        None => ConditionFixtures::default(),
+    };
+
+    let keys = LocalKeys::new(args.bls_secret_key.as_deref())?;
+
+    let report = emulate(
+        code,
+        js_params,
+        conditions,
+        keys,
+        args.nodes,
+        Duration::from_millis(args.timeout_ms),
+    )
+    .await;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    let failed = report.nodes.iter().filter(|report| !report.success).count();
+    if failed > 0 {
+        bail!(
+            "The action failed on {failed} of {} virtual nodes",
+            args.nodes
+        );
+    }
+    Ok(())
+}
+
+/// Runs the action once on each of `nodes` virtual nodes.
+async fn emulate(
+    code: String,
+    js_params: Option<Vec<u8>>,
+    conditions: ConditionFixtures,
+    keys: LocalKeys,
+    nodes: usize,
+    timeout: Duration,
+) -> EmulationReport {
+    let keys = Arc::new(keys);
+    let conditions = Arc::new(conditions);
+    let network = Arc::new(VirtualNetwork::new(nodes, timeout));
+
+    let server = TestServer::start();
+
+    let mut executions = JoinSet::new();
+    for index in 0..nodes {
+        let node = VirtualNode::new(index, network.clone(), keys.clone(), conditions.clone());
+        let request = ExecutionRequest {
+            code: code.clone(),
+            js_params: js_params.clone(),
+            timeout: Some(timeout.as_millis() as u64),
+            ..Default::default()
+        };
+        executions.spawn(node.execute(server.socket_path(), request));
+    }
+
+    let mut nodes = executions.join_all().await;
+    nodes.sort_by_key(|report| report.node);
+
+    EmulationReport {
+        keys: keys.report(),
+        nodes,
+    }
+}
+
+fn read_js_params(path: &Path) -> Result<Vec<u8>> {
+    let params = std::fs::read(path).with_context(|| format!("Could not read {path:?}"))?;
+    let value: serde_json::Value =
+        serde_json::from_slice(&params).with_context(|| format!("Invalid JSON in {path:?}"))?;
+    ensure!(
+        value.is_object(),
+        "jsParams in {path:?} must be a JSON object"
+    );
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+    use std::time::Duration;
+
+    use serde_json::{Value, json};
+
+    use super::{ConditionFixtures, LocalKeys, emulate};
+
+    const CONDITIONS: &str = r#"[{
+        "conditionType": "evmBasic",
+        "contractAddress": "",
+        "standardContractType": "",
+        "chain": "ethereum",
+        "method": "eth_getBalance",
+        "parameters": [":userAddress", "latest"],
+        "returnValueTest": { "comparator": ">=", "value": "0" }
+    }]"#;
+
+    fn action() -> String {
+        format!(
+            r#"(async () => {{
+                const accessControlConditions = {CONDITIONS};
+                const greetings = await Lit.Actions.broadcastAndCollect({{ name: "greeting", value: secret }});
+                await Lit.Actions.signEcdsa({{ toSign: new Uint8Array(32).fill(1), publicKey: "0x04ab", sigName: "sig" }});
+                const {{ ciphertext, dataToEncryptHash }} = await Lit.Actions.encrypt({{
+                    accessControlConditions,
+                    to_encrypt: new TextEncoder().encode(secret),
+                }});
+                const decrypted = await Lit.Actions.decryptAndCombine({{
+                    accessControlConditions,
+                    ciphertext,
+                    dataToEncryptHash,
+                    chain: "ethereum",
+                }});
+                Lit.Actions.setResponse({{ response: JSON.stringify({{ greetings, decrypted }}) }});
+            }})()"#
+        )
+    }
+
+    fn conditions(result: bool) -> ConditionFixtures {
+        let conditions: Value = serde_json::from_str(CONDITIONS).unwrap();
+        serde_json::from_value(json!([{ "conditions": conditions, "result": result }])).unwrap()
+    }
+
+    async fn run(conditions: ConditionFixtures) -> Value {
+        static INIT: Once = Once::new();
+        INIT.call_once(lit_actions_server::init_v8);
+
+        let report = emulate(
+            action(),
+            Some(br#"{"secret": "hello"}"#.to_vec()),
+            conditions,
+            LocalKeys::new(None).unwrap(),
+            3,
+            Duration::from_secs(10),
+        )
+        .await;
+        serde_json::to_value(report).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_emulate_runs_action_on_every_node() {
+        let report = run(conditions(true)).await;
+
+        let nodes = report["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), 3);
+        for (index, node) in nodes.iter().enumerate() {
+            assert_eq!(node["node"], index);
+            assert_eq!(node["success"], true, "{node}");
+            let response: Value = serde_json::from_str(node["response"].as_str().unwrap()).unwrap();
+            assert_eq!(response["greetings"], json!(["hello", "hello", "hello"]));
+            assert_eq!(response["decrypted"], "hello");
+            // Every node signs with the same local key standing in for the PKP
+            assert_eq!(node["signedData"]["sig"], nodes[0]["signedData"]["sig"]);
+        }
+
+        let local_key = &report["keys"]["pkps"]["04ab"];
+        assert_eq!(&nodes[0]["signedData"]["sig"]["publicKey"], local_key);
+        assert_eq!(
+            nodes[0]["signedData"]["sig"]["signedData"],
+            hex::encode([1; 32])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_emulate_reports_failed_conditions() {
+        let report = run(conditions(false)).await;
+
+        for node in report["nodes"].as_array().unwrap() {
+            assert_eq!(node["success"], false);
+            assert!(
+                node["error"]
+                    .as_str()
+                    .unwrap()
+                    .contains("Access control conditions check failed"),
+                "{node}"
+            );
+        }
+    }
+}
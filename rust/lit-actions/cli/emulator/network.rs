@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use tokio::sync::watch;
+
+/// Carries broadcasts between the virtual nodes executing the same action.
+pub(super) struct VirtualNetwork {
+    size: usize,
+    timeout: Duration,
+    state: Mutex<NetworkState>,
+    // Bumped whenever the state changes, to wake up nodes waiting for broadcasts
+    version: watch::Sender<u64>,
+}
+
+#[derive(Default)]
+struct NetworkState {
+    // Broadcast name -> node index -> value
+    broadcasts: HashMap<String, BTreeMap<usize, String>>,
+    finished: HashSet<usize>,
+}
+
+impl VirtualNetwork {
+    pub(super) fn new(size: usize, timeout: Duration) -> Self {
+        Self {
+            size,
+            timeout,
+            state: Default::default(),
+            version: watch::Sender::new(0),
+        }
+    }
+
+    pub(super) fn leader(&self) -> usize {
+        0
+    }
+
+    pub(super) fn peers_of(&self, node: usize) -> Vec<usize> {
+        (0..self.size).filter(|&peer| peer != node).collect()
+    }
+
+    pub(super) fn broadcast(&self, node: usize, name: &str, value: String) -> Result<()> {
+        {
+            let mut state = self.state.lock().expect("poisoned lock");
+            let values = state.broadcasts.entry(name.to_string()).or_default();
+            if values.contains_key(&node) {
+                bail!("Virtual node {node} already broadcast \"{name}\"");
+            }
+            values.insert(node, value);
+        }
+        self.version.send_modify(|version| *version += 1);
+        Ok(())
+    }
+
+    /// Waits for the values that the given nodes broadcast under `name`.
+    pub(super) async fn collect(&self, name: &str, from: &[usize]) -> Result<Vec<String>> {
+        let wait = async {
+            let mut changes = self.version.subscribe();
+            loop {
+                {
+                    let state = self.state.lock().expect("poisoned lock");
+                    let values = state.broadcasts.get(name);
+                    let missing = from
+                        .iter()
+                        .find(|node| values.is_none_or(|values| !values.contains_key(node)));
+                    match (missing, values) {
+                        (None, Some(values)) => {
+                            return Ok(from.iter().map(|node| values[node].clone()).collect());
+                        }
+                        (None, None) => return Ok(vec![]), // nothing to wait for
+                        (Some(node), _) if state.finished.contains(node) => {
+                            bail!("Virtual node {node} finished without broadcasting \"{name}\"")
+                        }
+                        (Some(_), _) => {}
+                    }
+                }
+                changes.changed().await?;
+            }
+        };
+
+        tokio::time::timeout(self.timeout, wait)
+            .await
+            .map_err(|_| {
+                anyhow!("Timed out waiting for other virtual nodes to broadcast \"{name}\"")
+            })?
+    }
+
+    /// Marks a node as done, so that nobody waits for its broadcasts anymore.
+    pub(super) fn finish(&self, node: usize) {
+        self.state
+            .lock()
+            .expect("poisoned lock")
+            .finished
+            .insert(node);
+        self.version.send_modify(|version| *version += 1);
+    }
+}
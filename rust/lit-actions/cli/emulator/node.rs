@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Result, bail};
+use lit_actions_server::{proto::*, unix};
+use serde::Serialize;
+use sha3::{Digest as _, Keccak256};
+use tokio_stream::StreamExt as _;
+use tonic::Request;
+
+use super::conditions::ConditionFixtures;
+use super::keys::{LocalKeys, LocalSignature};
+use super::network::VirtualNetwork;
+
+// Same default limit as in lit-node's action client
+const MAX_FETCH_COUNT: u32 = 50;
+
+/// Answers the ops of one execution of the action in place of lit-node.
+pub(super) struct VirtualNode {
+    index: usize,
+    network: Arc<VirtualNetwork>,
+    keys: Arc<LocalKeys>,
+    conditions: Arc<ConditionFixtures>,
+    fetch_count: u32,
+    // (PKP public key, key) -> value; like on a real network, each node keeps its own copy
+    storage: BTreeMap<(String, String), String>,
+    response: String,
+    chunks: Vec<String>,
+    logs: String,
+    signed_data: BTreeMap<String, LocalSignature>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct NodeReport {
+    pub(super) node: usize,
+    pub(super) success: bool,
+    pub(super) error: Option<String>,
+    response: String,
+    chunks: Vec<String>,
+    logs: String,
+    signed_data: BTreeMap<String, LocalSignature>,
+}
+
+impl VirtualNode {
+    pub(super) fn new(
+        index: usize,
+        network: Arc<VirtualNetwork>,
+        keys: Arc<LocalKeys>,
+        conditions: Arc<ConditionFixtures>,
+    ) -> Self {
+        Self {
+            index,
+            network,
+            keys,
+            conditions,
+            fetch_count: 0,
+            storage: BTreeMap::new(),
+            response: String::new(),
+            chunks: vec![],
+            logs: String::new(),
+            signed_data: BTreeMap::new(),
+        }
+    }
+
+    pub(super) async fn execute(
+        mut self,
+        socket_path: PathBuf,
+        request: ExecutionRequest,
+    ) -> NodeReport {
+        let result = self.execute_inner(socket_path, request).await;
+        self.network.finish(self.index);
+
+        NodeReport {
+            node: self.index,
+            success: result.is_ok(),
+            error: result.err().map(|e| format!("{e:#}")),
+            response: self.response,
+            chunks: self.chunks,
+            logs: self.logs,
+            signed_data: self.signed_data,
+        }
+    }
+
+    async fn execute_inner(
+        &mut self,
+        socket_path: PathBuf,
+        request: ExecutionRequest,
+    ) -> Result<()> {
+        let (outbound_tx, outbound_rx) = flume::bounded(0);
+        let channel = unix::connect_to_socket(socket_path).await?;
+        let mut stream = ActionClient::new(channel)
+            .execute_js(Request::new(outbound_rx.into_stream()))
+            .await?
+            .into_inner();
+
+        // Send initial execution request to server
+        outbound_tx.send_async(request.into()).await?;
+
+        // Handle responses from server
+        while let Some(resp) = stream.try_next().await? {
+            match resp.union {
+                // Return final result from server
+                Some(UnionResponse::Result(res)) => {
+                    if !res.success {
+                        bail!(res.error);
+                    }
+                    return Ok(());
+                }
+                // Handle op requests
+                Some(op) => {
+                    let resp = self.handle_op(op).await.unwrap_or_else(|e| {
+                        ErrorResponse {
+                            error: e.to_string(),
+                        }
+                        .into()
+                    });
+                    outbound_tx.send_async(resp).await?;
+                }
+                // Ignore empty responses
+                None => {}
+            };
+        }
+
+        bail!("Server unexpectedly closed connection")
+    }
+
+    async fn handle_op(&mut self, op: UnionResponse) -> Result<ExecuteJsRequest> {
+        Ok(match op {
+            UnionResponse::SetResponse(SetResponseRequest { response }) => {
+                self.response = response;
+                SetResponseResponse {}.into()
+            }
+            UnionResponse::StreamChunk(StreamChunkRequest { chunk }) => {
+                self.chunks.push(chunk);
+                StreamChunkResponse {}.into()
+            }
+            UnionResponse::Print(PrintRequest { message }) => {
+                self.logs.push_str(&message);
+                PrintResponse {}.into()
+            }
+            UnionResponse::IncrementFetchCount(IncrementFetchCountRequest {}) => {
+                self.fetch_count += 1;
+                if self.fetch_count > MAX_FETCH_COUNT {
+                    bail!(
+                        "You may not send more than {MAX_FETCH_COUNT} HTTP requests per session and you have attempted to exceed that limit."
+                    );
+                }
+                IncrementFetchCountResponse {
+                    fetch_count: self.fetch_count,
+                }
+                .into()
+            }
+            UnionResponse::UpdateResourceUsage(UpdateResourceUsageRequest { .. }) => {
+                UpdateResourceUsageResponse {
+                    cancel_action: false,
+                }
+                .into()
+            }
+            UnionResponse::RecordFetch(RecordFetchRequest { .. }) => RecordFetchResponse {}.into(),
+            UnionResponse::StorageGet(StorageGetRequest {
+                key,
+                pkp_public_key,
+            }) => StorageGetResponse {
+                value: self.storage.get(&(pkp_public_key, key)).cloned(),
+            }
+            .into(),
+            UnionResponse::StorageSet(StorageSetRequest {
+                key,
+                value,
+                pkp_public_key,
+            }) => {
+                self.storage.insert((pkp_public_key, key), value);
+                StorageSetResponse {}.into()
+            }
+            UnionResponse::StorageDelete(StorageDeleteRequest {
+                key,
+                pkp_public_key,
+            }) => StorageDeleteResponse {
+                deleted: self.storage.remove(&(pkp_public_key, key)).is_some(),
+            }
+            .into(),
+            UnionResponse::CheckConditions(CheckConditionsRequest { conditions, .. }) => {
+                CheckConditionsResponse {
+                    success: self.conditions.check(&conditions)?,
+                }
+                .into()
+            }
+            UnionResponse::SignEcdsa(SignEcdsaRequest {
+                to_sign,
+                public_key,
+                sig_name,
+                eth_personal_sign,
+            }) => {
+                let to_sign = if eth_personal_sign {
+                    // Prepend the Ethereum Signed Message according to EIP-191
+                    let mut message =
+                        format!("\x19Ethereum Signed Message:\n{}", to_sign.len()).into_bytes();
+                    message.extend(&to_sign);
+                    Keccak256::digest(message).to_vec()
+                } else {
+                    to_sign
+                };
+                self.sign(&to_sign, &public_key, sig_name)?;
+                SignEcdsaResponse {
+                    success: "success".to_string(),
+                }
+                .into()
+            }
+            UnionResponse::Sign(SignRequest {
+                to_sign,
+                public_key,
+                sig_name,
+                signing_scheme,
+            }) => {
+                ensure_supported_scheme(&signing_scheme)?;
+                self.sign(&to_sign, &public_key, sig_name)?;
+                SignResponse {
+                    success: "success".to_string(),
+                }
+                .into()
+            }
+            UnionResponse::SignAndCombineEcdsa(SignAndCombineEcdsaRequest {
+                to_sign,
+                public_key,
+                sig_name: _,
+            }) => SignAndCombineEcdsaResponse {
+                result: self.sign_and_combine(&to_sign, &public_key)?,
+            }
+            .into(),
+            UnionResponse::SignAndCombine(SignAndCombineRequest {
+                to_sign,
+                public_key,
+                sig_name: _,
+                signing_scheme,
+            }) => {
+                ensure_supported_scheme(&signing_scheme)?;
+                SignAndCombineResponse {
+                    result: self.sign_and_combine(&to_sign, &public_key)?,
+                }
+                .into()
+            }
+            UnionResponse::BroadcastAndCollect(BroadcastAndCollectRequest { name, value }) => {
+                self.network.broadcast(self.index, &name, value.clone())?;
+                let mut values = self
+                    .network
+                    .collect(&name, &self.network.peers_of(self.index))
+                    .await?;
+                values.push(value);
+                BroadcastAndCollectResponse { name, values }.into()
+            }
+            UnionResponse::P2pBroadcast(P2pBroadcastRequest { name, value }) => {
+                self.network.broadcast(self.index, &name, value)?;
+                P2pBroadcastResponse { result: true }.into()
+            }
+            UnionResponse::P2pCollectFromLeader(P2pCollectFromLeaderRequest { name }) => {
+                let value = self
+                    .network
+                    .collect(&name, &[self.network.leader()])
+                    .await?
+                    .pop()
+                    .unwrap_or_default();
+                P2pCollectFromLeaderResponse { name, value }.into()
+            }
+            UnionResponse::IsLeader(IsLeaderRequest {}) => IsLeaderResponse {
+                result: self.index == self.network.leader(),
+            }
+            .into(),
+            UnionResponse::EncryptBls(EncryptBlsRequest {
+                access_control_conditions,
+                to_encrypt,
+            }) => {
+                let (ciphertext, data_to_encrypt_hash) =
+                    self.keys.encrypt(&access_control_conditions, &to_encrypt)?;
+                EncryptBlsResponse {
+                    ciphertext,
+                    data_to_encrypt_hash,
+                }
+                .into()
+            }
+            UnionResponse::DecryptAndCombine(DecryptAndCombineRequest {
+                access_control_conditions,
+                ciphertext,
+                data_to_encrypt_hash,
+                ..
+            }) => DecryptAndCombineResponse {
+                result: self.decrypt(
+                    &access_control_conditions,
+                    &ciphertext,
+                    &data_to_encrypt_hash,
+                )?,
+            }
+            .into(),
+            UnionResponse::DecryptToSingleNode(DecryptToSingleNodeRequest {
+                access_control_conditions,
+                ciphertext,
+                data_to_encrypt_hash,
+                ..
+            }) => {
+                let decrypted = self.decrypt(
+                    &access_control_conditions,
+                    &ciphertext,
+                    &data_to_encrypt_hash,
+                )?;
+                // Like on a real network, only the leader gets the plaintext
+                DecryptToSingleNodeResponse {
+                    result: if self.index == self.network.leader() {
+                        decrypted
+                    } else {
+                        String::new()
+                    },
+                }
+                .into()
+            }
+            UnionResponse::Result(_) => unreachable!(), // handled in main loop
+            // These need chain access, IPFS or keys derived by the network
+            op @ (UnionResponse::PubkeyToTokenId(_)
+            | UnionResponse::PkpPermissionsGetPermitted(_)
+            | UnionResponse::PkpPermissionsGetPermittedAuthMethodScopes(_)
+            | UnionResponse::PkpPermissionsIsPermitted(_)
+            | UnionResponse::PkpPermissionsIsPermittedAuthMethod(_)
+            | UnionResponse::AesDecrypt(_)
+            | UnionResponse::GetLatestNonce(_)
+            | UnionResponse::ClaimKeyIdentifier(_)
+            | UnionResponse::CallContract(_)
+            | UnionResponse::CallChild(_)
+            | UnionResponse::GetRpcUrl(_)
+            | UnionResponse::SignAsAction(_)
+            | UnionResponse::GetActionPublicKey(_)
+            | UnionResponse::VerifyActionSignature(_)
+            | UnionResponse::GetModule(_)
+            | UnionResponse::GetWasmModule(_)
+            | UnionResponse::GetSecret(_)) => {
+                let op = format!("{op:?}");
+                let op = op.split('(').next().unwrap_or_default();
+                bail!("{op} is not supported by the emulator")
+            }
+        })
+    }
+
+    fn sign(&mut self, to_sign: &[u8], public_key: &str, sig_name: String) -> Result<()> {
+        let signature = self.keys.sign_ecdsa(public_key, to_sign)?;
+        self.signed_data.insert(sig_name, signature);
+        Ok(())
+    }
+
+    // Every virtual node signs with the same key, so there is nothing to combine
+    fn sign_and_combine(&self, to_sign: &[u8], public_key: &str) -> Result<String> {
+        let signature = self.keys.sign_ecdsa(public_key, to_sign)?;
+        let (r, s) = signature.signature.split_at(64);
+        Ok(serde_json::json!({
+            "r": r,
+            "s": s,
+            "v": signature.recovery_id,
+        })
+        .to_string())
+    }
+
+    fn decrypt(
+        &self,
+        access_control_conditions: &[u8],
+        ciphertext: &str,
+        data_to_encrypt_hash: &str,
+    ) -> Result<String> {
+        if !self.conditions.check(access_control_conditions)? {
+            bail!(
+                "Access control conditions check failed.  Check that you are allowed to decrypt this item."
+            );
+        }
+        self.keys
+            .decrypt(access_control_conditions, ciphertext, data_to_encrypt_hash)
+    }
+}
+
+fn ensure_supported_scheme(signing_scheme: &str) -> Result<()> {
+    if signing_scheme != "EcdsaK256Sha256" {
+        bail!("Signing scheme {signing_scheme} is not supported by the emulator");
+    }
+    Ok(())
+}
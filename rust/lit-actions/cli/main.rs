@@ -1,5 +1,7 @@
+mod emulator;
+
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use lit_core::utils::unix::raise_fd_limit;
 use lit_observability::{
     opentelemetry::{KeyValue, global},
@@ -22,6 +24,15 @@ struct Args {
 
     #[arg(long, default_value = "false", help = "Enable observability export")]
     enable_observability_export: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run a Lit Action locally, answering node ops with mocks instead of a Lit network
+    Emulate(emulator::EmulateArgs),
 }
 
 fn main() -> Result<()> {
@@ -38,10 +49,16 @@ fn main() -> Result<()> {
 
     lit_actions_server::init_v8();
 
-    info!("Listening on {:?}", args.socket);
-
     let main_rt = tokio::runtime::Runtime::new().expect("failed to create runtime");
 
+    if let Some(Command::Emulate(emulate_args)) = args.command {
+        let res = main_rt.block_on(emulator::run(emulate_args));
+        observability_providers.shutdown();
+        return res;
+    }
+
+    info!("Listening on {:?}", args.socket);
+
     main_rt.block_on(async {
         let signal = async {
             let _ = tokio::signal::ctrl_c().await;
@@ -7,6 +7,7 @@ use lit_actions_grpc::proto::*;
 use serde_json::json;
 use tracing::instrument;
 
+use crate::egress::EgressPolicy;
 use crate::macros::*;
 
 #[instrument(skip_all, ret)]
@@ -19,6 +20,11 @@ fn op_print(state: &mut OpState, #[string] msg: &str, is_err: bool) -> Result<()
         static ref IS_ATTY_STDERR: bool = atty::is(atty::Stream::Stderr);
     }
 
+    let msg = match state.try_borrow::<RevealedSecrets>() {
+        Some(revealed) => revealed.redact(msg).into_owned(),
+        None => msg.to_string(),
+    };
+
     let prepended = format!("[JSEnv] {msg}");
     if is_err && *IS_ATTY_STDERR {
         stderr()
@@ -43,7 +49,7 @@ fn op_print(state: &mut OpState, #[string] msg: &str, is_err: bool) -> Result<()
 
     remote_op!(op_print,
         state,
-        PrintRequest { message: msg.to_string() }, // may be empty
+        PrintRequest { message: msg }, // may be empty
         UnionRequest::Print(_) => Ok(())
     )
 }
@@ -75,6 +81,133 @@ fn op_set_response(state: &mut OpState, #[string] response: String) -> Result<()
     )
 }
 
+#[instrument(skip_all, ret)]
+#[op2(async, reentrant)]
+async fn op_stream_chunk(
+    state: Rc<RefCell<OpState>>,
+    #[string] chunk: String,
+) -> Result<(), JsErrorBox> {
+    remote_op_async!(op_stream_chunk,
+        state,
+        StreamChunkRequest { chunk },
+        UnionRequest::StreamChunk(_) => Ok(())
+    )
+}
+
+#[instrument(skip_all, ret)]
+#[op2(async, reentrant)]
+#[serde]
+async fn op_storage_get(
+    state: Rc<RefCell<OpState>>,
+    #[string] key: String,
+    #[string] pkp_public_key: String,
+) -> Result<Option<String>, JsErrorBox> {
+    ensure_not_empty!(key);
+
+    remote_op_async!(op_storage_get,
+        state,
+        StorageGetRequest { key, pkp_public_key },
+        UnionRequest::StorageGet(resp) => Ok(resp.value)
+    )
+}
+
+#[instrument(skip_all, ret)]
+#[op2(async, reentrant)]
+async fn op_storage_set(
+    state: Rc<RefCell<OpState>>,
+    #[string] key: String,
+    #[string] value: String,
+    #[string] pkp_public_key: String,
+) -> Result<(), JsErrorBox> {
+    ensure_not_empty!(key);
+
+    remote_op_async!(op_storage_set,
+        state,
+        StorageSetRequest { key, value, pkp_public_key },
+        UnionRequest::StorageSet(_) => Ok(())
+    )
+}
+
+#[instrument(skip_all, ret)]
+#[op2(async, reentrant)]
+async fn op_storage_delete(
+    state: Rc<RefCell<OpState>>,
+    #[string] key: String,
+    #[string] pkp_public_key: String,
+) -> Result<bool, JsErrorBox> {
+    ensure_not_empty!(key);
+
+    remote_op_async!(op_storage_delete,
+        state,
+        StorageDeleteRequest { key, pkp_public_key },
+        UnionRequest::StorageDelete(resp) => Ok(resp.deleted)
+    )
+}
+
+/// Secrets revealed to the action so far, which are redacted from whatever it prints.
+#[derive(Default)]
+pub struct RevealedSecrets(Vec<String>);
+
+impl RevealedSecrets {
+    pub fn redact<'a>(&self, msg: &'a str) -> std::borrow::Cow<'a, str> {
+        let mut msg = std::borrow::Cow::Borrowed(msg);
+        for secret in &self.0 {
+            if msg.contains(secret.as_str()) {
+                msg = msg.replace(secret.as_str(), "[REDACTED]").into();
+            }
+        }
+        msg
+    }
+}
+
+// Doesn't log its return value, which is the secret in plaintext
+#[instrument(skip_all)]
+#[op2(async, reentrant)]
+#[serde]
+async fn op_get_secret(
+    state: Rc<RefCell<OpState>>,
+    #[string] name: String,
+) -> Result<Option<String>, JsErrorBox> {
+    ensure_not_blank!(name);
+
+    let value = remote_op_async!(op_get_secret,
+        state,
+        GetSecretRequest { name },
+        UnionRequest::GetSecret(resp) => Ok(resp.value)
+    )?;
+
+    if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+        let mut state = state.borrow_mut();
+        if !state.has::<RevealedSecrets>() {
+            state.put(RevealedSecrets::default());
+        }
+        let revealed = state.borrow_mut::<RevealedSecrets>();
+        revealed.0.push(value.clone());
+        // Redact longer secrets first, in case one contains another
+        revealed
+            .0
+            .sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    }
+
+    Ok(value)
+}
+
+#[instrument(skip_all, ret)]
+#[op2(async, reentrant)]
+#[serde]
+async fn op_get_wasm_module(
+    state: Rc<RefCell<OpState>>,
+    #[string] cid: String,
+) -> Result<deno_core::ToJsBuffer, JsErrorBox> {
+    ensure_not_blank!(cid, "ipfsId");
+
+    remote_op_async!(op_get_wasm_module,
+        state,
+        GetWasmModuleRequest { cid },
+        UnionRequest::GetWasmModule(resp) => Ok(resp.code.into())
+    )
+}
+
 #[instrument(skip_all, ret)]
 #[op2(async, reentrant)]
 async fn op_increment_fetch_count(state: Rc<RefCell<OpState>>) -> Result<u32, JsErrorBox> {
@@ -641,20 +774,68 @@ async fn op_update_resource_usage(
 ) -> Result<bool, JsErrorBox> {
     remote_op_async!(op_update_resource_usage,
         state,
-        UpdateResourceUsageRequest { tick, used_kb },
+        UpdateResourceUsageRequest { tick, used_kb, ..Default::default() },
         UnionRequest::UpdateResourceUsage(resp) => Ok(resp.cancel_action)
     )
 }
 
+/// Marks an execution whose sources of nondeterminism have been pinned by lit-node,
+/// in which case fetch responses are recorded so that nodes can compare them.
+pub struct DeterministicMode;
+
+#[op2(fast)]
+fn op_is_deterministic(state: &mut OpState) -> bool {
+    state.has::<DeterministicMode>()
+}
+
+/// Checks a URL against the egress policy before it's fetched, returning the limits
+/// that apply to the response.
+#[instrument(skip_all, ret)]
+#[op2(async)]
+#[serde]
+async fn op_check_egress(
+    state: Rc<RefCell<OpState>>,
+    #[string] url: String,
+) -> Result<serde_json::Value, JsErrorBox> {
+    let policy = state.borrow().try_borrow::<EgressPolicy>().cloned();
+    let Some(policy) = policy else {
+        return Ok(json!({}));
+    };
+
+    policy.check_url(&url).await?;
+
+    Ok(json!({
+        "maxResponseSize": policy.max_response_size,
+        "fetchTimeout": policy.fetch_timeout_ms,
+    }))
+}
+
+#[instrument(skip_all, ret)]
+#[op2(async, reentrant)]
+async fn op_record_fetch(
+    state: Rc<RefCell<OpState>>,
+    #[string] url: String,
+    status: u32,
+    #[buffer(copy)] body_sha256: Vec<u8>,
+) -> Result<(), JsErrorBox> {
+    remote_op_async!(op_record_fetch,
+        state,
+        RecordFetchRequest { url, status, body_sha256 },
+        UnionRequest::RecordFetch(_) => Ok(())
+    )
+}
+
 #[instrument(skip_all, ret)]
 pub async fn op_update_resource_usage_external(
     state: Rc<RefCell<OpState>>,
     tick: u32,
     used_kb: u32,
+    used_heap_size: u64,
+    external_memory: u64,
 ) -> Result<bool, JsErrorBox> {
     remote_op_async!(op_update_resource_usage,
         state,
-        UpdateResourceUsageRequest { tick, used_kb },
+        UpdateResourceUsageRequest { tick, used_kb, used_heap_size, external_memory },
         UnionRequest::UpdateResourceUsage(resp) => Ok(resp.cancel_action)
     )
 }
@@ -671,12 +852,21 @@ extension!(
         op_claim_key_identifier,
         op_get_latest_nonce,
         op_increment_fetch_count,
+        op_is_deterministic,
+        op_check_egress,
+        op_record_fetch,
         op_pkp_permissions_get_permitted_auth_method_scopes,
         op_pkp_permissions_get_permitted,
         op_pkp_permissions_is_permitted_auth_method,
         op_pkp_permissions_is_permitted,
         op_pubkey_to_token_id,
         op_set_response,
+        op_stream_chunk,
+        op_storage_get,
+        op_storage_set,
+        op_storage_delete,
+        op_get_secret,
+        op_get_wasm_module,
         op_sign_ecdsa_eth_personal_sign_message,
         op_sign_ecdsa,
         op_sign,
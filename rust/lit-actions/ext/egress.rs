@@ -0,0 +1,240 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use deno_core::url::{Host, Url};
+use deno_error::JsErrorBox;
+use lit_actions_grpc::proto;
+
+// Leading comment lines of the action declaring the hosts it fetches from, e.g.
+// "// @lit-egress allow api.coingecko.com *.infura.io"
+const METADATA_TAG: &str = "@lit-egress";
+
+/// Which hosts a Lit Action may fetch from, and how much it may download.
+///
+/// Combines the node operator's policy with the allow and deny lists that the action
+/// declares in its leading comments. Stored in the `OpState` and checked by the fetch
+/// wrapper before every request, including redirects. The denied IP ranges are checked
+/// again by fetch's DNS resolver when it connects, so that a host can't resolve to an
+/// allowed address for the check and to a denied one for the request.
+#[derive(Debug, Default, Clone)]
+pub struct EgressPolicy {
+    allowed_hosts: Option<Vec<String>>,
+    denied_hosts: Vec<String>,
+    denied_ip_ranges: Vec<IpRange>,
+    pub max_response_size: Option<u64>,
+    pub fetch_timeout_ms: Option<u64>,
+}
+
+impl EgressPolicy {
+    pub fn new(node_policy: Option<&proto::EgressPolicy>, code: &str) -> Result<Self, String> {
+        let mut policy = Self::default();
+
+        if let Some(node_policy) = node_policy {
+            policy.denied_hosts = node_policy
+                .denied_hosts
+                .iter()
+                .map(|host| normalize_host(host))
+                .collect();
+            policy.denied_ip_ranges = node_policy
+                .denied_ip_ranges
+                .iter()
+                .map(|range| range.parse())
+                .collect::<Result<_, _>>()?;
+            policy.max_response_size = node_policy.max_response_size;
+            policy.fetch_timeout_ms = node_policy.fetch_timeout;
+        }
+
+        for line in code.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = line.strip_prefix("//") else {
+                break; // Only the leading comments are metadata
+            };
+            let Some(directive) = comment.trim().strip_prefix(METADATA_TAG) else {
+                continue;
+            };
+
+            // An "allow" without hosts denies all egress
+            let mut words = directive.split_whitespace();
+            match words.next() {
+                Some("allow") => policy
+                    .allowed_hosts
+                    .get_or_insert_with(Vec::new)
+                    .extend(words.map(normalize_host)),
+                Some("deny") => policy.denied_hosts.extend(words.map(normalize_host)),
+                other => {
+                    return Err(format!(
+                        "Invalid {METADATA_TAG} directive: expected \"allow\" or \"deny\", got {other:?}"
+                    ));
+                }
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// Checks a URL before fetching it, resolving its host to check the denied IP ranges.
+    /// Hosts that can't be resolved are rejected.
+    pub async fn check_url(&self, url: &str) -> Result<(), JsErrorBox> {
+        let url = Url::parse(url).map_err(|e| JsErrorBox::type_error(format!("{url}: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Ok(()); // e.g. data: URLs don't leave the isolate
+        }
+
+        match url.host() {
+            Some(Host::Domain(domain)) => {
+                let domain = normalize_host(domain);
+                self.check_host(&domain)?;
+                self.resolve_checked(&domain).await?;
+            }
+            Some(Host::Ipv4(ip)) => {
+                self.check_host(&ip.to_string())?;
+                self.check_ip(&ip.to_string(), IpAddr::V4(ip))?;
+            }
+            Some(Host::Ipv6(ip)) => {
+                self.check_host(&ip.to_string())?;
+                self.check_ip(&ip.to_string(), IpAddr::V6(ip))?;
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a host for fetch to connect to, failing if any of its addresses is in a
+    /// denied range.
+    pub async fn resolve(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        self.resolve_checked(&normalize_host(host))
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e.to_string()))
+    }
+
+    async fn resolve_checked(&self, host: &str) -> Result<Vec<SocketAddr>, JsErrorBox> {
+        // The port is set by whoever connects to the addresses
+        let addrs: Vec<SocketAddr> = match tokio::net::lookup_host((host, 0)).await {
+            Ok(addrs) => addrs.collect(),
+            Err(_) => vec![],
+        };
+        if addrs.is_empty() {
+            return Err(denied(host, "the host can't be resolved"));
+        }
+        for addr in &addrs {
+            self.check_ip(host, addr.ip())?;
+        }
+        Ok(addrs)
+    }
+
+    fn check_host(&self, host: &str) -> Result<(), JsErrorBox> {
+        if self
+            .denied_hosts
+            .iter()
+            .any(|pattern| host_matches(pattern, host))
+        {
+            return Err(denied(host, "the host is denied"));
+        }
+        if let Some(allowed_hosts) = &self.allowed_hosts {
+            if !allowed_hosts
+                .iter()
+                .any(|pattern| host_matches(pattern, host))
+            {
+                return Err(denied(host, "the host is not allowed by the action"));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), JsErrorBox> {
+        let ip = ip.to_canonical();
+        if let Some(range) = self.denied_ip_ranges.iter().find(|r| r.contains(ip)) {
+            return Err(denied(
+                host,
+                &format!("{ip} is in the denied range {range}"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn denied(host: &str, reason: &str) -> JsErrorBox {
+    JsErrorBox::new(
+        "PermissionDenied",
+        format!("fetch to {host} blocked by the egress policy: {reason}"),
+    )
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+}
+
+// "*.example.com" matches any subdomain of example.com, but not example.com itself
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host
+            .strip_suffix(suffix)
+            .is_some_and(|prefix| prefix.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid IP range in egress policy: {s}");
+
+        let (network, prefix_len) = match s.trim().split_once('/') {
+            Some((network, prefix_len)) => (
+                network.parse::<IpAddr>().map_err(|_| invalid())?,
+                Some(prefix_len.parse::<u8>().map_err(|_| invalid())?),
+            ),
+            None => (s.trim().parse::<IpAddr>().map_err(|_| invalid())?, None),
+        };
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl std::fmt::Display for IpRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
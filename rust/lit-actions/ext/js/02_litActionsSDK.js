@@ -295,6 +295,43 @@ function setResponse({ response }) {
   return ops.op_set_response(response);
 }
 
+/**
+ * Stream a chunk of the response to the client while the action is still running.  Only has an effect when the action was invoked through the streaming endpoint, where chunks are delivered in order as server-sent events.
+ * @name Lit.Actions.streamChunk
+ * @function streamChunk
+ * @param {Object} params
+ * @param {string} params.chunk The chunk to send to the client
+ * @returns {Promise<void>} Resolves once the node has accepted the chunk
+ */
+function streamChunk({ chunk }) {
+  return ops.op_stream_chunk(chunk);
+}
+
+/**
+ * Load a WebAssembly module from IPFS.  The node verifies the binary against its CID before handing it to the action, and caches the binary so later loads skip IPFS, but the module is compiled again on every run.  Memory allocated by the module counts toward the action's memory limit.
+ * @name Lit.Actions.loadWasm
+ * @function loadWasm
+ * @param {Object} params
+ * @param {string} params.ipfsId The IPFS CID of the WebAssembly binary
+ * @returns {Promise<WebAssembly.Module>} The compiled module, ready to be instantiated with `WebAssembly.instantiate(module, imports)`
+ */
+async function loadWasm({ ipfsId }) {
+  const code = await ops.op_get_wasm_module(ipfsId);
+  return WebAssembly.compile(code);
+}
+
+/**
+ * Get a secret that the action's owner stored for the action's IPFS CID.  Secrets are encrypted to the network under a condition that only this CID can decrypt them, and are decrypted by the nodes when the action asks for them.  They're redacted from anything the action logs with console.log.
+ * @name Lit.Actions.getSecret
+ * @function getSecret
+ * @param {Object} params
+ * @param {string} params.name The name the secret was stored under
+ * @returns {Promise<string|null>} The secret, or null if there is none with that name
+ */
+function getSecret({ name }) {
+  return ops.op_get_secret(name);
+}
+
 /**
  * Call a child Lit Action
  * @name Lit.Actions.call
@@ -562,6 +599,52 @@ function getRpcUrl({ chain }) {
 function encrypt({ accessControlConditions, to_encrypt }) {
   return ops.op_encrypt_bls(accessControlConditions, to_encrypt);
 }
+/**
+ * Persistent key-value storage, kept by the nodes between runs of the action.  Values are namespaced by the IPFS CID of the action, so no other action can read or overwrite them, and optionally by a PKP public key.  Writes are only applied once a threshold of nodes agreed on them, and are billed by size.
+ * @name Lit.Actions.storage
+ */
+const storage = {
+  /**
+   * Get a value from the action's storage
+   * @name Lit.Actions.storage.get
+   * @function get
+   * @param {Object} params
+   * @param {string} params.key The key to look up
+   * @param {string=} params.pkpPublicKey Optional PKP public key to namespace the key by
+   * @returns {Promise<string|null>} The stored value, or null if there is none
+   */
+  get({ key, pkpPublicKey }) {
+    return ops.op_storage_get(key, pkpPublicKey ?? '');
+  },
+
+  /**
+   * Set a value in the action's storage
+   * @name Lit.Actions.storage.set
+   * @function set
+   * @param {Object} params
+   * @param {string} params.key The key to set
+   * @param {string} params.value The value to store.  You can put any string here, like you could use JSON.stringify on a JS object and store it here.
+   * @param {string=} params.pkpPublicKey Optional PKP public key to namespace the key by
+   * @returns {Promise<void>} Resolves once the value is stored
+   */
+  set({ key, value, pkpPublicKey }) {
+    return ops.op_storage_set(key, value, pkpPublicKey ?? '');
+  },
+
+  /**
+   * Delete a value from the action's storage
+   * @name Lit.Actions.storage.delete
+   * @function delete
+   * @param {Object} params
+   * @param {string} params.key The key to delete
+   * @param {string=} params.pkpPublicKey Optional PKP public key to namespace the key by
+   * @returns {Promise<boolean>} Whether there was a value to delete
+   */
+  delete({ key, pkpPublicKey }) {
+    return ops.op_storage_delete(key, pkpPublicKey ?? '');
+  },
+};
+
 globalThis.LitActions = {
   isPermittedAction,
   isPermittedAddress,
@@ -583,6 +666,9 @@ globalThis.LitActions = {
 
   checkConditions,
   setResponse,
+  streamChunk,
+  loadWasm,
+  getSecret,
   call,
   callContract,
   pubkeyToTokenId,
@@ -598,4 +684,6 @@ globalThis.LitActions = {
   getRpcUrl,
   encrypt,
   decryptToSingleNode,
+
+  storage,
 };
@@ -1,4 +1,11 @@
-import { op_increment_fetch_count, op_panic } from 'ext:core/ops';
+import {
+  op_check_egress,
+  op_increment_fetch_count,
+  op_is_deterministic,
+  op_panic,
+  op_record_fetch,
+} from 'ext:core/ops';
+import { crypto } from 'ext:deno_crypto/00_crypto.js';
 
 // Import modules to suppress build error:
 // "Following modules were not evaluated; make sure they are imported from other code"
@@ -10,7 +17,69 @@ import * as _jwt from 'ext:lit_actions/03_jsonwebtoken.js';
 // this block scopes oldFetch so that nobody can ever use it after
 {
   const oldFetch = globalThis.fetch;
-  const fetch = async function () {
+  const REDIRECT_STATUSES = [301, 302, 303, 307, 308];
+  const MAX_REDIRECTS = 20;
+  const BODY_HEADERS = [
+    'content-encoding',
+    'content-language',
+    'content-length',
+    'content-location',
+    'content-type',
+  ];
+
+  // Error the body stream once more than maxResponseSize bytes have been received
+  const limitBody = (response, maxResponseSize) => {
+    const contentLength = Number(response.headers.get('content-length'));
+    if (contentLength > maxResponseSize) {
+      response.body?.cancel();
+      throw new RangeError(
+        `Fetch response of ${contentLength} bytes exceeds the maximum size of ${maxResponseSize} bytes`
+      );
+    }
+    if (response.body === null) {
+      return response;
+    }
+
+    let received = 0;
+    const body = response.body.pipeThrough(
+      new TransformStream({
+        transform(chunk, controller) {
+          received += chunk.byteLength;
+          if (received > maxResponseSize) {
+            controller.error(
+              new RangeError(
+                `Fetch response exceeds the maximum size of ${maxResponseSize} bytes`
+              )
+            );
+          } else {
+            controller.enqueue(chunk);
+          }
+        },
+      })
+    );
+    const limited = new Response(body, {
+      status: response.status,
+      statusText: response.statusText,
+      headers: response.headers,
+    });
+    Object.defineProperty(limited, 'url', { value: response.url });
+    return limited;
+  };
+
+  // Redirects are followed here rather than by Deno, so that every hop is checked
+  // against the egress policy
+  const fetch = async function (input, init) {
+    let request = new Request(input, init);
+    const redirectMode = request.redirect;
+
+    const { maxResponseSize, fetchTimeout } = await op_check_egress(
+      request.url
+    );
+    const signal =
+      fetchTimeout == null
+        ? request.signal
+        : AbortSignal.any([request.signal, AbortSignal.timeout(fetchTimeout)]);
+
     const fetchCount = await op_increment_fetch_count();
     // console.log(
     //   "fetchCount: " +
@@ -18,7 +87,73 @@ import * as _jwt from 'ext:lit_actions/03_jsonwebtoken.js';
     //     " and arguments: " +
     //     JSON.stringify(arguments, null, 2)
     // );
-    return oldFetch.apply(null, arguments);
+
+    let response;
+    let redirects = 0;
+    while (true) {
+      // Keep the body around in case it has to be resent after a 307 or 308
+      const retained = redirectMode === 'follow' ? request.clone() : request;
+      response = await oldFetch(request, { redirect: 'manual', signal });
+
+      const location = response.headers.get('location');
+      if (
+        !REDIRECT_STATUSES.includes(response.status) ||
+        location === null ||
+        redirectMode === 'manual'
+      ) {
+        break;
+      }
+      await response.body?.cancel();
+      if (redirectMode === 'error') {
+        throw new TypeError(`Fetch to ${request.url} was redirected`);
+      }
+      if (++redirects > MAX_REDIRECTS) {
+        throw new TypeError(
+          `Fetch to ${request.url} was redirected too many times`
+        );
+      }
+
+      const url = new URL(location, request.url);
+      await op_check_egress(url.href);
+
+      const headers = new Headers(retained.headers);
+      if (url.origin !== new URL(request.url).origin) {
+        headers.delete('authorization');
+      }
+      const toGet =
+        response.status === 303
+          ? retained.method !== 'HEAD'
+          : [301, 302].includes(response.status) && retained.method === 'POST';
+      if (toGet) {
+        BODY_HEADERS.forEach((header) => headers.delete(header));
+      }
+      request = new Request(url, {
+        method: toGet ? 'GET' : retained.method,
+        headers,
+        body: toGet ? null : retained.body,
+        redirect: 'manual',
+        duplex: 'half',
+      });
+    }
+
+    if (maxResponseSize != null) {
+      response = limitBody(response, maxResponseSize);
+    }
+    if (redirects > 0) {
+      Object.defineProperty(response, 'redirected', { value: true });
+    }
+
+    if (op_is_deterministic()) {
+      // Record what this node was served so that nodes can detect diverging inputs
+      const body = await response.clone().arrayBuffer();
+      const bodySha256 = await crypto.subtle.digest('SHA-256', body);
+      await op_record_fetch(
+        response.url,
+        response.status,
+        new Uint8Array(bodySha256)
+      );
+    }
+    return response;
   };
   Object.freeze(fetch);
 
@@ -11,6 +11,8 @@ fn main() {
         .file_descriptor_set_path(descriptor_path)
         .protoc_arg("--experimental_allow_proto3_optional")
         .btree_map(["ExecutionRequest.http_headers"])
+        // Has a Debug impl in proto.rs that redacts the secret
+        .message_attribute("ExecuteJsRequest.GetSecretResponse", "#[prost(skip_debug)]")
         .compile_protos(&["schema/lit_actions.proto"], &["schema/"])
         .unwrap();
 }
@@ -10,7 +10,7 @@ pub use action_client::ActionClient;
 pub use action_server::{Action, ActionServer};
 
 pub use execute_js_request::ErrorResponse;
-pub use execute_js_request::{ExecutionRequest, Union as UnionRequest};
+pub use execute_js_request::{Determinism, EgressPolicy, ExecutionRequest, Union as UnionRequest};
 pub use execute_js_response::{ExecutionResult, Union as UnionResponse};
 
 impl From<&str> for ExecutionRequest {
@@ -87,6 +87,16 @@ impl std::fmt::Debug for DebugExecutionRequest<'_> {
             .field("timeout", &req.timeout)
             .field("memory_limit", &req.memory_limit)
             .field("http_headers", &req.http_headers)
+            .field("determinism", &req.determinism)
+            .field("egress_policy", &req.egress_policy)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for GetSecretResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GetSecretResponse")
+            .field("value", &self.value.as_ref().map(|_| "<redacted>"))
             .finish()
     }
 }
@@ -149,3 +159,11 @@ decl_op!(UpdateResourceUsage);
 decl_op!(SignAsAction);
 decl_op!(GetActionPublicKey);
 decl_op!(VerifyActionSignature);
+decl_op!(GetModule);
+decl_op!(RecordFetch);
+decl_op!(StreamChunk);
+decl_op!(StorageGet);
+decl_op!(StorageSet);
+decl_op!(StorageDelete);
+decl_op!(GetWasmModule);
+decl_op!(GetSecret);
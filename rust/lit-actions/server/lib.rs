@@ -1,3 +1,4 @@
+mod module_loader;
 mod runtime;
 
 pub mod server;
@@ -0,0 +1,116 @@
+//! Resolves ES module imports of Lit Actions against IPFS.
+//!
+//! Modules are imported by CID, e.g. `await import("ipfs://Qm...")`, and fetched by lit-node,
+//! which verifies that their content hashes to the CID before handing them over. Imported
+//! modules may in turn import other modules by CID, so shared libraries can be pinned once
+//! and reused by any action instead of being bundled into each of them.
+
+use deno_core::{
+    ModuleLoadResponse, ModuleLoader, ModuleSource, ModuleSourceCode, ModuleSpecifier, ModuleType,
+    RequestedModuleType, ResolutionKind, resolve_import,
+};
+use deno_error::JsErrorBox;
+use lit_actions_grpc::proto::*;
+use lit_observability::channels::TracedReceiver;
+use tracing::{debug, instrument};
+
+const IPFS_SCHEME: &str = "ipfs";
+
+pub(crate) struct IpfsModuleLoader {
+    outbound_tx: flume::Sender<tonic::Result<ExecuteJsResponse>>,
+    inbound_rx: TracedReceiver<ExecuteJsRequest>,
+}
+
+impl IpfsModuleLoader {
+    pub(crate) fn new(
+        outbound_tx: flume::Sender<tonic::Result<ExecuteJsResponse>>,
+        inbound_rx: TracedReceiver<ExecuteJsRequest>,
+    ) -> Self {
+        Self {
+            outbound_tx,
+            inbound_rx,
+        }
+    }
+
+    // Blocking like remote_op! to preserve the order of requests sent to lit-node
+    #[instrument(skip(self), err)]
+    fn get_module(&self, cid: &str) -> Result<String, JsErrorBox> {
+        self.outbound_tx
+            .send(Ok(GetModuleRequest {
+                cid: cid.to_string(),
+            }
+            .into()))
+            .map_err(|e| JsErrorBox::generic(format!("get_module: {e}")))?;
+
+        let (msg, _span) = self
+            .inbound_rx
+            .recv()
+            .map_err(|e| JsErrorBox::generic(format!("get_module: {e}")))?;
+
+        match msg.data().to_owned().union {
+            Some(UnionRequest::GetModule(GetModuleResponse { code })) => Ok(code),
+            Some(UnionRequest::ReportError(ErrorResponse { error })) => {
+                Err(JsErrorBox::generic(error))
+            }
+            other => Err(JsErrorBox::generic(format!(
+                "get_module: unexpected response: {other:?}"
+            ))),
+        }
+    }
+}
+
+impl ModuleLoader for IpfsModuleLoader {
+    fn resolve(
+        &self,
+        specifier: &str,
+        referrer: &str,
+        _kind: ResolutionKind,
+    ) -> Result<ModuleSpecifier, JsErrorBox> {
+        let resolved = resolve_import(specifier, referrer)
+            .map_err(|e| JsErrorBox::type_error(e.to_string()))?;
+
+        // A CID names exactly one file, so there is nothing to resolve relative to it
+        let is_cid = resolved.scheme() == IPFS_SCHEME
+            && resolved.host_str().is_some_and(|cid| !cid.is_empty())
+            && matches!(resolved.path(), "" | "/")
+            && resolved.query().is_none();
+        if !is_cid {
+            return Err(JsErrorBox::type_error(format!(
+                "Cannot import \"{specifier}\": modules must be imported by IPFS CID, e.g. \"ipfs://<cid>\""
+            )));
+        }
+
+        Ok(resolved)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<&ModuleSpecifier>,
+        _is_dyn_import: bool,
+        requested_module_type: RequestedModuleType,
+    ) -> ModuleLoadResponse {
+        let module_type = match requested_module_type {
+            RequestedModuleType::None => ModuleType::JavaScript,
+            RequestedModuleType::Json => ModuleType::Json,
+            other => {
+                return ModuleLoadResponse::Sync(Err(JsErrorBox::type_error(format!(
+                    "Cannot import \"{module_specifier}\": unsupported module type {other:?}"
+                ))));
+            }
+        };
+
+        // Verified by resolve()
+        let cid = module_specifier.host_str().unwrap_or_default();
+        debug!("Loading module {cid} as {module_type:?}");
+
+        ModuleLoadResponse::Sync(self.get_module(cid).map(|code| {
+            ModuleSource::new(
+                module_type,
+                ModuleSourceCode::String(code.into()),
+                module_specifier,
+                None,
+            )
+        }))
+    }
+}
@@ -2,15 +2,16 @@ use std::collections::BTreeMap;
 use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
-use std::sync::Once;
+use std::sync::{LazyLock, Once};
 use std::thread;
 use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow, bail};
-use deno_core::{JsRuntime, NoopModuleLoader, v8};
+use deno_core::{CompiledWasmModuleStore, JsRuntime, v8};
 use deno_resolver::npm::{DenoInNpmPackageChecker, ManagedNpmResolver};
 use deno_runtime::{
     BootstrapOptions, WorkerLogLevel,
+    deno_fetch::dns::{Resolve, Resolver, Resolving},
     deno_fs::RealFs,
     deno_permissions::{Permissions, PermissionsContainer, PermissionsOptions},
     fmt_errors::format_js_error,
@@ -18,14 +19,18 @@ use deno_runtime::{
     tokio_util::create_and_run_current_thread,
     worker::{MainWorker, WorkerOptions, WorkerServiceOptions},
 };
+use hyper_util::client::legacy::connect::dns::Name;
 use indoc::formatdoc;
-use lit_actions_grpc::proto::{ExecuteJsRequest, ExecuteJsResponse};
+use lit_actions_ext::egress::EgressPolicy;
+use lit_actions_grpc::proto::{self, Determinism, ExecuteJsRequest, ExecuteJsResponse};
 use lit_observability::channels::TracedReceiver;
 use sys_traits::impls::RealSys;
 use tokio::sync::{mpsc, oneshot};
 use tonic::Status;
 use tracing::{debug, error, info_span, instrument};
 
+use crate::module_loader::IpfsModuleLoader;
+
 // Same default limits as in lit-node's action client
 const DEFAULT_TIMEOUT_MS: u64 = 30000; // 30s
 const DEFAULT_MEMORY_LIMIT_MB: usize = 256; // 256MB
@@ -39,6 +44,10 @@ enum ExecutionResult {
     OutOfMemory,
 }
 
+// Shared by all isolates in this process so that compiled WebAssembly modules can be reused
+static COMPILED_WASM_MODULE_STORE: LazyLock<CompiledWasmModuleStore> =
+    LazyLock::new(Default::default);
+
 static RUNTIME_SNAPSHOT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/RUNTIME_SNAPSHOT.bin"));
 
 fn deno_isolate_init() -> Option<&'static [u8]> {
@@ -46,6 +55,18 @@ fn deno_isolate_init() -> Option<&'static [u8]> {
     Some(RUNTIME_SNAPSHOT)
 }
 
+// Resolves the hosts fetch connects to, so that the egress policy applies to the addresses
+// it actually connects to rather than only to those it saw when checking the URL
+#[derive(Debug)]
+struct EgressResolver(EgressPolicy);
+
+impl Resolve for EgressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.0.clone();
+        Box::pin(async move { policy.resolve(name.as_str()).await.map(Vec::into_iter) })
+    }
+}
+
 // using the worker built into deno
 #[instrument(skip_all, err)]
 fn build_main_worker_and_inject_sdk(
@@ -53,6 +74,9 @@ fn build_main_worker_and_inject_sdk(
     auth_context: &Option<serde_json::Value>,
     http_headers: BTreeMap<String, String>,
     memory_limit_mb: Option<usize>,
+    module_loader: IpfsModuleLoader,
+    determinism: Option<&Determinism>,
+    egress_policy: &EgressPolicy,
 ) -> Result<MainWorker> {
     let options = WorkerOptions {
         bootstrap: BootstrapOptions {
@@ -68,7 +92,8 @@ fn build_main_worker_and_inject_sdk(
         create_params: memory_limit_mb
             .map(|limit| v8::CreateParams::default().heap_limits(0, limit * 1024 * 1024)),
         unsafely_ignore_certificate_errors: None,
-        seed: None,
+        // Seeds crypto.getRandomValues and friends, but not Math.random
+        seed: determinism.map(|d| d.seed),
         create_web_worker_cb: Arc::new(|_| {
             unreachable!("web workers are disabled in PatchDeno.js")
         }),
@@ -100,14 +125,14 @@ fn build_main_worker_and_inject_sdk(
             broadcast_channel: Default::default(),
             feature_checker: Default::default(),
             fs: Arc::new(RealFs),
-            module_loader: Rc::new(NoopModuleLoader),
+            module_loader: Rc::new(module_loader),
             node_services: Default::default(),
             npm_process_state_provider: Default::default(),
             permissions: PermissionsContainer::new(desc_parser, perms),
             root_cert_store_provider: Default::default(),
-            fetch_dns_resolver: Default::default(),
+            fetch_dns_resolver: Resolver::Custom(Arc::new(EgressResolver(egress_policy.clone()))),
             shared_array_buffer_store: Default::default(),
-            compiled_wasm_module_store: Default::default(),
+            compiled_wasm_module_store: Some(COMPILED_WASM_MODULE_STORE.clone()),
             v8_code_cache: Default::default(),
         };
 
@@ -167,6 +192,14 @@ fn build_main_worker_and_inject_sdk(
             delete Deno.permissions;
             delete Deno.version;
             delete globalThis.Worker;
+            // fetch is the only network API, as it's where the egress policy is enforced
+            delete Deno.connect;
+            delete Deno.connectTls;
+            delete Deno.listen;
+            delete Deno.listenTls;
+            delete Deno.resolveDns;
+            delete Deno.startTls;
+            delete globalThis.WebSocket;
         "#};
 
         worker
@@ -174,6 +207,53 @@ fn build_main_worker_and_inject_sdk(
             .context("Error patching Deno runtime")?;
     }
 
+    if let Some(determinism) = determinism {
+        let _span = info_span!("Deterministic.js").entered();
+
+        debug!("Pinning sources of nondeterminism: {determinism:?}");
+
+        // Math.random only needs to be reproducible, so mulberry32 is good enough.
+        // The clock is frozen rather than started at the timestamp, as nodes would
+        // otherwise still observe different durations.
+        let code = formatdoc! {r#"
+            "use strict";
+            (function (seed, timestamp) {{
+                let state = seed;
+                Math.random = function random() {{
+                    state = (state + 0x6d2b79f5) | 0;
+                    let t = Math.imul(state ^ (state >>> 15), 1 | state);
+                    t = (t + Math.imul(t ^ (t >>> 7), 61 | t)) ^ t;
+                    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+                }};
+
+                const RealDate = Date;
+                const FrozenDate = function Date(...args) {{
+                    if (new.target === undefined) {{
+                        return new RealDate(timestamp).toString();
+                    }}
+                    return Reflect.construct(RealDate, args.length === 0 ? [timestamp] : args, new.target);
+                }};
+                FrozenDate.prototype = RealDate.prototype;
+                FrozenDate.now = () => timestamp;
+                FrozenDate.parse = RealDate.parse;
+                FrozenDate.UTC = RealDate.UTC;
+                globalThis.Date = FrozenDate;
+
+                Object.defineProperties(performance, {{
+                    now: {{ value: () => 0 }},
+                    timeOrigin: {{ value: timestamp }},
+                }});
+            }})({seed}, {timestamp});
+            "#,
+            seed = (determinism.seed ^ (determinism.seed >> 32)) as u32,
+            timestamp = determinism.timestamp,
+        };
+
+        worker
+            .execute_script("Deterministic.js", code.into())
+            .context("Error enabling deterministic mode")?;
+    }
+
     if let Some(params) = globals_to_inject {
         let _span = info_span!("Params.js").entered();
 
@@ -226,6 +306,8 @@ pub(crate) async fn execute_js(
     http_headers: BTreeMap<String, String>,
     timeout_ms: Option<u64>,
     memory_limit_mb: Option<usize>,
+    determinism: Option<Determinism>,
+    egress_policy: Option<proto::EgressPolicy>,
     outbound_tx: flume::Sender<tonic::Result<ExecuteJsResponse>>,
     inbound_rx: TracedReceiver<ExecuteJsRequest>,
     is_test_server: bool,
@@ -241,12 +323,16 @@ pub(crate) async fn execute_js(
 
     let timeout_ms = timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
     let memory_limit_mb = memory_limit_mb.unwrap_or(DEFAULT_MEMORY_LIMIT_MB);
+    let egress_policy = EgressPolicy::new(egress_policy.as_ref(), &code).map_err(|e| anyhow!(e))?;
 
     let mut worker = build_main_worker_and_inject_sdk(
         &js_params,
         &auth_context,
         http_headers,
         Some(memory_limit_mb),
+        IpfsModuleLoader::new(outbound_tx.clone(), inbound_rx.clone()),
+        determinism.as_ref(),
+        &egress_policy,
     )
     .context("Error building main worker")
     .map_err(|e| anyhow!("{e:#}"))?; // Ensure to keep context when downcasting JS errors later
@@ -257,6 +343,10 @@ pub(crate) async fn execute_js(
         let mut state = op_state.borrow_mut();
         state.put(outbound_tx);
         state.put(inbound_rx);
+        if determinism.is_some() {
+            state.put(lit_actions_ext::bindings::DeterministicMode);
+        }
+        state.put(egress_policy);
         drop(state);
     }
 
@@ -271,6 +361,7 @@ pub(crate) async fn execute_js(
     );
 
     // Terminate isolate when approaching memory limit
+    let external_memory_limit_tx = memory_limit_tx.clone();
     worker
         .js_runtime
         .add_near_heap_limit_callback(move |current_limit, _initial_limit| {
@@ -323,6 +414,14 @@ pub(crate) async fn execute_js(
                 }
             }
             tick_no = interval.tick() => {
+                // WebAssembly memories and other ArrayBuffers live outside the V8 heap,
+                // so they aren't bounded by heap_limits and are checked here instead.
+                worker.js_runtime.v8_isolate().get_heap_statistics(&mut heap_stats);
+                if used_memory(&heap_stats) > memory_limit_mb * 1024 * 1024 {
+                    let _ = external_memory_limit_tx.send(memory_limit_mb * 1024 * 1024);
+                    continue;
+                }
+
                 // note that if we error out trying to update resource usage, we will not continue execution
                 update_resource_usage(&mut worker.js_runtime, &mut heap_stats, tick_no, is_test_server).await?;
             }
@@ -345,16 +444,18 @@ async fn update_resource_usage(
 
     let op_state = js_runtime.op_state();
     js_runtime.v8_isolate().get_heap_statistics(heap_stats);
-    let mb_used_heap_size = heap_stats.used_heap_size() / 1024 / 1024;
+    let mb_used_memory = used_memory(heap_stats) / 1024 / 1024;
     debug!(
         "MB used at {}: {}",
         tick_no.elapsed().as_millis(),
-        mb_used_heap_size
+        mb_used_memory
     );
     match lit_actions_ext::bindings::op_update_resource_usage_external(
         op_state,
         tick_no.elapsed().as_millis() as u32,
-        mb_used_heap_size as u32,
+        mb_used_memory as u32,
+        heap_stats.used_heap_size() as u64,
+        heap_stats.external_memory() as u64,
     )
     .await
     {
@@ -382,6 +483,11 @@ async fn update_resource_usage(
     Ok(())
 }
 
+// Memory counted toward the limit: the V8 heap plus external allocations such as WebAssembly memories
+fn used_memory(heap_stats: &v8::HeapStatistics) -> usize {
+    heap_stats.used_heap_size() + heap_stats.external_memory()
+}
+
 fn start_controller_thread(
     js_runtime: &mut JsRuntime,
     worker_timeout_ms: u64,
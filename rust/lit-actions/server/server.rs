@@ -106,6 +106,8 @@ impl Action for Server {
                                     req.http_headers,
                                     req.timeout,
                                     req.memory_limit.map(|limit| limit as usize),
+                                    req.determinism,
+                                    req.egress_policy,
                                     outbound_tx.clone(),
                                     inbound_rx.clone(),
                                     is_test_server,
@@ -212,6 +212,38 @@ impl TestClient {
                 self.messages.put(req);
                 self.messages.take::<VerifyActionSignatureResponse>().into()
             }
+            UnionResponse::GetModule(req) => {
+                self.messages.put(req);
+                self.messages.take::<GetModuleResponse>().into()
+            }
+            UnionResponse::RecordFetch(req) => {
+                self.messages.put(req);
+                self.messages.take::<RecordFetchResponse>().into()
+            }
+            UnionResponse::StreamChunk(req) => {
+                self.messages.put(req);
+                self.messages.take::<StreamChunkResponse>().into()
+            }
+            UnionResponse::StorageGet(req) => {
+                self.messages.put(req);
+                self.messages.take::<StorageGetResponse>().into()
+            }
+            UnionResponse::StorageSet(req) => {
+                self.messages.put(req);
+                self.messages.take::<StorageSetResponse>().into()
+            }
+            UnionResponse::StorageDelete(req) => {
+                self.messages.put(req);
+                self.messages.take::<StorageDeleteResponse>().into()
+            }
+            UnionResponse::GetWasmModule(req) => {
+                self.messages.put(req);
+                self.messages.take::<GetWasmModuleResponse>().into()
+            }
+            UnionResponse::GetSecret(req) => {
+                self.messages.put(req);
+                self.messages.take::<GetSecretResponse>().into()
+            }
         }
     }
 
@@ -431,6 +463,98 @@ async fn set_response(mut client: TestClient) {
     assert!(client.received::<ExecutionResult>().success);
 }
 
+#[rstest]
+#[tokio::test]
+async fn stream_chunk(mut client: TestClient) {
+    client
+        .respond_with(StreamChunkResponse {})
+        .execute_js(r#"(async () => { await Lit.Actions.streamChunk({chunk: "partial"}) })()"#)
+        .await
+        .unwrap();
+
+    assert_eq!(client.received::<StreamChunkRequest>().chunk, "partial");
+    assert!(client.received::<ExecutionResult>().success);
+}
+
+#[rstest]
+#[tokio::test]
+async fn storage(mut client: TestClient) {
+    let code = indoc! {r#"
+        (async () => {
+            await Lit.Actions.storage.set({key: "count", value: "1", pkpPublicKey: "0x04"});
+            const count = await Lit.Actions.storage.get({key: "count"});
+            const deleted = await Lit.Actions.storage.delete({key: "count"});
+            Lit.Actions.setResponse({response: JSON.stringify({count, deleted})});
+        })()
+    "#};
+
+    client
+        .respond_with(StorageSetResponse {})
+        .respond_with(StorageGetResponse {
+            value: Some("1".to_string()),
+        })
+        .respond_with(StorageDeleteResponse { deleted: true })
+        .respond_with(SetResponseResponse {})
+        .execute_js(code)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client.received::<StorageSetRequest>(),
+        StorageSetRequest {
+            key: "count".to_string(),
+            value: "1".to_string(),
+            pkp_public_key: "0x04".to_string(),
+        }
+    );
+    assert_eq!(
+        client.received::<StorageGetRequest>(),
+        StorageGetRequest {
+            key: "count".to_string(),
+            pkp_public_key: "".to_string(),
+        }
+    );
+    assert_eq!(
+        client.received::<StorageDeleteRequest>().key,
+        "count".to_string()
+    );
+    assert_eq!(
+        client.received::<SetResponseRequest>().response,
+        r#"{"count":"1","deleted":true}"#
+    );
+    assert!(client.received::<ExecutionResult>().success);
+}
+
+#[rstest]
+#[tokio::test]
+async fn get_secret(mut client: TestClient) {
+    let code = indoc! {r#"
+        (async () => {
+            const apiKey = await Lit.Actions.getSecret({name: "apiKey"});
+            console.log(`Using ${apiKey} of length ${apiKey.length}`);
+            Lit.Actions.setResponse({response: String(apiKey === "sk-12345")});
+        })()
+    "#};
+
+    client
+        .respond_with(GetSecretResponse {
+            value: Some("sk-12345".to_string()),
+        })
+        .respond_with(PrintResponse {})
+        .respond_with(SetResponseResponse {})
+        .execute_js(code)
+        .await
+        .unwrap();
+
+    assert_eq!(client.received::<GetSecretRequest>().name, "apiKey");
+    assert_eq!(
+        client.received::<PrintRequest>().message,
+        "Using [REDACTED] of length 8\n"
+    );
+    assert_eq!(client.received::<SetResponseRequest>().response, "true");
+    assert!(client.received::<ExecutionResult>().success);
+}
+
 #[rstest]
 #[tokio::test]
 async fn fetch(mut client: TestClient) {
@@ -464,6 +588,235 @@ async fn fetch(mut client: TestClient) {
     assert!(client.received::<ExecutionResult>().success);
 }
 
+#[rstest]
+#[tokio::test]
+async fn egress_policy(mut client: TestClient) {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let code = formatdoc! {r#"
+        (async () => {{
+            await fetch("{uri}")
+        }})()
+        "#,
+        uri = &mock_server.uri()
+    };
+
+    // Denied by the node
+    let res = client
+        .execute_js(ExecutionRequest {
+            code: code.clone(),
+            egress_policy: Some(EgressPolicy {
+                denied_ip_ranges: vec!["10.0.0.0/8".to_string(), "127.0.0.0/8".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await;
+    assert!(res.unwrap_err().to_string().contains(
+        "fetch to 127.0.0.1 blocked by the egress policy: 127.0.0.1 is in the denied range 127.0.0.0/8"
+    ));
+
+    // Not allowed by the action
+    let res = client
+        .execute_js(format!("// @lit-egress allow *.example.com\n{code}"))
+        .await;
+    assert!(res.unwrap_err().to_string().contains(
+        "fetch to 127.0.0.1 blocked by the egress policy: the host is not allowed by the action"
+    ));
+
+    // Allowed by the action
+    client
+        .respond_with(IncrementFetchCountResponse { fetch_count: 1 })
+        .execute_js(format!("// @lit-egress allow 127.0.0.1\n{code}"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client.received::<IncrementFetchCountRequest>(),
+        IncrementFetchCountRequest {}
+    );
+    assert!(client.received::<ExecutionResult>().success);
+}
+
+#[rstest]
+#[tokio::test]
+async fn egress_policy_unresolvable_host(mut client: TestClient) {
+    let res = client
+        .execute_js(indoc! {r#"
+            (async () => {
+                await fetch("http://lit-egress-test.invalid/")
+            })()
+        "#})
+        .await;
+
+    assert!(res.unwrap_err().to_string().contains(
+        "fetch to lit-egress-test.invalid blocked by the egress policy: the host can't be resolved"
+    ));
+}
+
+#[rstest]
+#[tokio::test]
+async fn egress_policy_redirect(mut client: TestClient) {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+    let redirect_to = mock_server.uri().replace("127.0.0.1", "localhost");
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(302).insert_header("location", redirect_to))
+        .mount(&mock_server)
+        .await;
+
+    let code = formatdoc! {r#"
+        (async () => {{
+            await fetch("{uri}")
+        }})()
+        "#,
+        uri = &mock_server.uri()
+    };
+
+    let res = client
+        .respond_with(IncrementFetchCountResponse { fetch_count: 1 })
+        .execute_js(ExecutionRequest {
+            code,
+            egress_policy: Some(EgressPolicy {
+                denied_hosts: vec!["localhost".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .contains("fetch to localhost blocked by the egress policy: the host is denied")
+    );
+    assert_eq!(
+        client.received::<IncrementFetchCountRequest>(),
+        IncrementFetchCountRequest {}
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn egress_policy_response_size(mut client: TestClient) {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello world"))
+        .mount(&mock_server)
+        .await;
+
+    let code = formatdoc! {r#"
+        (async () => {{
+            await fetch("{uri}")
+        }})()
+        "#,
+        uri = &mock_server.uri()
+    };
+
+    let res = client
+        .respond_with(IncrementFetchCountResponse { fetch_count: 1 })
+        .execute_js(ExecutionRequest {
+            code,
+            egress_policy: Some(EgressPolicy {
+                max_response_size: Some(5),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .contains("Fetch response of 11 bytes exceeds the maximum size of 5 bytes")
+    );
+    assert_eq!(
+        client.received::<IncrementFetchCountRequest>(),
+        IncrementFetchCountRequest {}
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn deterministic(mut client: TestClient) {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+        .mount(&mock_server)
+        .await;
+
+    let code = formatdoc! {r#"
+        (async () => {{
+            await fetch("{uri}");
+            console.log(
+                Math.random(),
+                crypto.getRandomValues(new Uint8Array(4)).join(),
+                Date.now(),
+                new Date().getTime(),
+                performance.now(),
+            );
+        }})()
+        "#,
+        uri = &mock_server.uri()
+    };
+
+    let mut logs = vec![];
+    for _ in 0..2 {
+        client
+            .respond_with(IncrementFetchCountResponse { fetch_count: 1 })
+            .respond_with(RecordFetchResponse {})
+            .respond_with(PrintResponse {})
+            .execute_js(ExecutionRequest {
+                code: code.clone(),
+                determinism: Some(Determinism {
+                    seed: 42,
+                    timestamp: 1_700_000_000_000,
+                }),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.received::<IncrementFetchCountRequest>(),
+            IncrementFetchCountRequest {}
+        );
+        assert_eq!(
+            client.received::<RecordFetchRequest>(),
+            RecordFetchRequest {
+                url: format!("{}/", mock_server.uri()),
+                status: 200,
+                body_sha256: vec![
+                    0x2c, 0xf2, 0x4d, 0xba, 0x5f, 0xb0, 0xa3, 0x0e, 0x26, 0xe8, 0x3b, 0x2a, 0xc5,
+                    0xb9, 0xe2, 0x9e, 0x1b, 0x16, 0x1e, 0x5c, 0x1f, 0xa7, 0x42, 0x5e, 0x73, 0x04,
+                    0x33, 0x62, 0x93, 0x8b, 0x98, 0x24,
+                ], // sha256("hello")
+            }
+        );
+        logs.push(client.received::<PrintRequest>().message);
+        assert!(client.received::<ExecutionResult>().success);
+    }
+
+    // Same seed and timestamp, same result
+    assert_eq!(logs[0], logs[1]);
+    assert!(logs[0].ends_with(" 1700000000000 1700000000000 0\n"));
+}
+
 #[rstest]
 #[tokio::test]
 async fn pkp_get_permitted(mut client: TestClient) {
@@ -848,6 +1201,49 @@ async fn call_child(mut client: TestClient) {
     }
 }
 
+#[rstest]
+#[tokio::test]
+async fn import_module(mut client: TestClient) {
+    {
+        client
+            .respond_with(GetModuleResponse {
+                code: "export const answer = 42;".to_string(),
+            })
+            .respond_with(SetResponseResponse {})
+            .execute_js(indoc! {r#"
+                (async () => {
+                    const { answer } = await import("ipfs://QmSomeModule");
+                    Lit.Actions.setResponse({response: String(answer)});
+                })()
+            "#})
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.received::<GetModuleRequest>(),
+            GetModuleRequest {
+                cid: "QmSomeModule".to_string(),
+            }
+        );
+        assert_eq!(client.received::<SetResponseRequest>().response, "42");
+        assert!(client.received::<ExecutionResult>().success);
+    }
+
+    // Only modules pinned by CID can be imported
+    {
+        let res = client
+            .execute_js(r#"(async () => { await import("https://example.com/mod.js") })()"#)
+            .await;
+
+        assert!(
+            res.unwrap_err()
+                .to_string()
+                .contains("modules must be imported by IPFS CID")
+        );
+        assert_eq!(client.received::<ExecutionResult>().success, false);
+    }
+}
+
 #[rstest]
 #[tokio::test]
 async fn call_contract(mut client: TestClient) {
@@ -1301,3 +1697,62 @@ async fn wasm(mut client: TestClient) {
     assert_eq!(client.received::<PrintRequest>().message, "579\n");
     assert!(client.received::<ExecutionResult>().success);
 }
+
+#[rstest]
+#[tokio::test]
+async fn load_wasm(mut client: TestClient) {
+    // Same add function as above
+    let wasm_code = vec![
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f,
+        0x01, 0x7f, 0x03, 0x02, 0x01, 0x00, 0x07, 0x07, 0x01, 0x03, 0x61, 0x64, 0x64, 0x00, 0x00,
+        0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b,
+    ];
+    let code = indoc! {r#"
+        (async () => {
+            const wasmModule = await Lit.Actions.loadWasm({ ipfsId: "QmWasm" });
+            const { exports } = await WebAssembly.instantiate(wasmModule);
+            console.log(exports.add(123, 456));
+        })();
+    "#};
+
+    client
+        .respond_with(GetWasmModuleResponse { code: wasm_code })
+        .respond_with(PrintResponse {})
+        .execute_js(code)
+        .await
+        .unwrap();
+
+    assert_eq!(client.received::<GetWasmModuleRequest>().cid, "QmWasm");
+    assert_eq!(client.received::<PrintRequest>().message, "579\n");
+    assert!(client.received::<ExecutionResult>().success);
+}
+
+#[rstest]
+#[tokio::test]
+async fn wasm_memory_counts_toward_limit(mut client: TestClient) {
+    // 200 pages of 64KiB = 12.5MB, allocated outside the V8 heap
+    let code = indoc! {r#"
+        (async () => {
+            const memories = [];
+            while (true) {
+                memories.push(new WebAssembly.Memory({ initial: 200 }));
+                await new Promise((resolve) => setTimeout(resolve, 10));
+            }
+        })();
+    "#};
+
+    let res = client
+        .execute_js(ExecutionRequest {
+            code: code.into(),
+            memory_limit: Some(100),
+            ..Default::default()
+        })
+        .await;
+    let status = res.unwrap_err().downcast::<Status>().unwrap();
+
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(
+        status.message(),
+        "Your function exceeded the maximum memory of 100 MB and was terminated."
+    );
+}
@@ -5,8 +5,10 @@ use async_compression::tokio::bufread::GzipEncoder;
 use rocket::futures::stream::{Stream, StreamExt};
 use rocket::http::hyper::header::CONTENT_DISPOSITION;
 use rocket::http::{ContentType, Header};
-use rocket::response::{self, Responder, stream::ReaderStream};
+use rocket::response::stream::{Event, EventStream, ReaderStream};
+use rocket::response::{self, Responder};
 use rocket::{Request, Response};
+use serde::Serialize;
 use tokio::process::Child;
 use tracing::warn;
 
@@ -86,3 +88,25 @@ impl fmt::Debug for ChildStream {
         builder.finish()
     }
 }
+
+/// Streams `(event, data)` pairs as server-sent events with JSON-encoded data.
+pub struct JsonEventStream<S>(pub S);
+
+impl<'r, S, T> Responder<'r, 'r> for JsonEventStream<S>
+where
+    S: Stream<Item = (&'static str, T)> + Send + 'r,
+    T: Serialize,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        EventStream::from(self.0.map(|(event, data)| Event::json(&data).event(event)))
+            .respond_to(req)
+    }
+}
+
+impl<S> fmt::Debug for JsonEventStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut builder = f.debug_struct("JsonEventStream");
+
+        builder.finish()
+    }
+}
@@ -148,6 +148,7 @@ pub enum RpcKind {
     IPFS,
     SOLANA,
     COSMOS,
+    BITCOIN,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Eq, PartialEq, Hash)]
@@ -73,7 +73,7 @@ impl<'a> RpcHealthcheckPoller for StandardRpcHealthcheckPoller<'a> {
             .request(
                 match kind {
                     RpcKind::EVM | RpcKind::SOLANA => Method::POST,
-                    RpcKind::IPFS | RpcKind::COSMOS => Method::GET,
+                    RpcKind::IPFS | RpcKind::COSMOS | RpcKind::BITCOIN => Method::GET,
                 },
                 host,
             )
@@ -145,6 +145,13 @@ impl<'a> RpcHealthcheckPoller for StandardRpcHealthcheckPoller<'a> {
                     ));
                 }
 
+                Ok("healthy".to_string())
+            }
+            RpcKind::BITCOIN => {
+                // Esplora returns the tip height as a plain-text integer
+                let body = response.text().await.map_err(|e| unexpected_err(e, None))?;
+                body.trim().parse::<u64>().map_err(|e| unexpected_err(e, None))?;
+
                 Ok("healthy".to_string())
             }
         }?;
@@ -223,6 +230,16 @@ pub trait RpcHealthcheckPoller: Sync {
                                 cosmos_url.set_path("/health");
                                 self.healthcheck(cosmos_url.as_str(), kind, None).await
                             }
+                            RpcKind::BITCOIN => {
+                                let mut bitcoin_url =
+                                    Url::parse(&u).expect("URL Must parse as config verified");
+                                let path = format!(
+                                    "{}/blocks/tip/height",
+                                    bitcoin_url.path().trim_end_matches('/')
+                                );
+                                bitcoin_url.set_path(&path);
+                                self.healthcheck(bitcoin_url.as_str(), kind, None).await
+                            }
                         };
 
                         // let (h, latency) = Self::measure_latency_of(healthcheck).await;
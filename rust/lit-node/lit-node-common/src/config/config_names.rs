@@ -22,3 +22,9 @@ pub const CFG_KEY_PAYMENT_INTERVAL_MS: &str = "payment_interval";
 pub const CFG_KEY_WEB_CLIENT_TIMEOUT_SEC: &str = "web_client_timeout";
 pub const CFG_KEY_GRPC_SERVER_CONC_LIMIT_PER_CONN: &str = "grpc_server_conc_limit_per_conn";
 pub const CFG_KEY_GRPC_POOL_SIZE: &str = "grpc_client_pool_size";
+pub const CFG_KEY_ACC_CONCURRENCY_LIMIT: &str = "acc_concurrency_limit";
+pub const CFG_KEY_OIDC_PROVIDERS: &str = "oidc_providers";
+pub const CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS: &str = "actions_egress_denied_hosts";
+pub const CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES: &str = "actions_egress_denied_ip_ranges";
+pub const CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE: &str = "actions_egress_max_response_size";
+pub const CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS: &str = "actions_egress_fetch_timeout";
@@ -23,15 +23,18 @@ pub mod config_names;
 pub const CFG_SECTION_KEY: &str = "node";
 
 use config_names::{
-    CFG_KEY_ACTIONS_SOCKET, CFG_KEY_ADMIN_ADDRESS, CFG_KEY_CHAIN_POLLING_INTERVAL_MS,
-    CFG_KEY_CHATTER_CLIENT_TIMEOUT, CFG_KEY_COMS_KEYS_RECEIVER_PRIVKEY,
-    CFG_KEY_COMS_KEYS_SENDER_PRIVKEY, CFG_KEY_ENABLE_ACTIONS_ALLOWLIST,
-    CFG_KEY_ENABLE_EPOCH_TRANSITIONS, CFG_KEY_ENABLE_OBSERVABILITY_EXPORT, CFG_KEY_ENABLE_PAYMENT,
+    CFG_KEY_ACC_CONCURRENCY_LIMIT, CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS,
+    CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES, CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS,
+    CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE, CFG_KEY_ACTIONS_SOCKET, CFG_KEY_ADMIN_ADDRESS,
+    CFG_KEY_CHAIN_POLLING_INTERVAL_MS, CFG_KEY_CHATTER_CLIENT_TIMEOUT,
+    CFG_KEY_COMS_KEYS_RECEIVER_PRIVKEY, CFG_KEY_COMS_KEYS_SENDER_PRIVKEY,
+    CFG_KEY_ENABLE_ACTIONS_ALLOWLIST, CFG_KEY_ENABLE_EPOCH_TRANSITIONS,
+    CFG_KEY_ENABLE_OBSERVABILITY_EXPORT, CFG_KEY_ENABLE_PAYMENT,
     CFG_KEY_ENABLE_PROXIED_CHATTER_CLIENT, CFG_KEY_ENABLE_SIWE_VALIDATION, CFG_KEY_GRPC_POOL_SIZE,
     CFG_KEY_GRPC_SERVER_CONC_LIMIT_PER_CONN, CFG_KEY_HEALTH_POLL_INTERVAL_MS,
-    CFG_KEY_PAYMENT_INTERVAL_MS, CFG_KEY_RESTORE_LOG_INTERVAL_MS, CFG_KEY_RPC_URL,
-    CFG_KEY_SIGNING_ROUND_TIMEOUT, CFG_KEY_STAKER_ADDRESS, CFG_KEY_WEB_CLIENT_TIMEOUT_SEC,
-    CFG_KEY_WEBAUTHN_ALLOWED_ORIGINS,
+    CFG_KEY_OIDC_PROVIDERS, CFG_KEY_PAYMENT_INTERVAL_MS, CFG_KEY_RESTORE_LOG_INTERVAL_MS,
+    CFG_KEY_RPC_URL, CFG_KEY_SIGNING_ROUND_TIMEOUT, CFG_KEY_STAKER_ADDRESS,
+    CFG_KEY_WEB_CLIENT_TIMEOUT_SEC, CFG_KEY_WEBAUTHN_ALLOWED_ORIGINS,
 };
 // NB: Before adding keys here ensure they don't conflict with LitApiConfig
 // - port, address, ident e.t.c. are all reserved.
@@ -63,6 +66,14 @@ pub static CFG_KEY_RESTORE_LOG_INTERVAL_MS_DEFAULT: i64 = 1000 * 60 * 10;
 pub static CFG_KEY_ACTIONS_SOCKET_DEFAULT: &str = "/tmp/lit_actions.sock";
 pub static CFG_KEY_PAYMENT_INTERVAL_MS_DEFAULT: i64 = 5000;
 pub static CFG_KEY_WEB_CLIENT_TIMEOUT_SEC_DEFAULT: i64 = 30;
+pub static CFG_KEY_ACC_CONCURRENCY_LIMIT_DEFAULT: i64 = 8;
+pub static CFG_KEY_OIDC_PROVIDERS_DEFAULT: &str = "[]";
+pub static CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS_DEFAULT: &str =
+    "localhost,metadata.google.internal,metadata.goog";
+// Loopback, RFC1918, carrier-grade NAT, link-local (incl. cloud metadata services) and their IPv6 equivalents
+pub static CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES_DEFAULT: &str = "0.0.0.0/8,10.0.0.0/8,100.64.0.0/10,127.0.0.0/8,169.254.0.0/16,172.16.0.0/12,192.168.0.0/16,::/128,::1/128,fc00::/7,fe80::/10,64:ff9b::/96,2002::/16";
+pub static CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE_DEFAULT: i64 = 32 * 1024 * 1024;
+pub static CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS_DEFAULT: i64 = 20000;
 
 static REQUIRED_CFG_KEYS: [&str; 8] = [
     CFG_KEY_STAKER_ADDRESS,
@@ -75,7 +86,7 @@ static REQUIRED_CFG_KEYS: [&str; 8] = [
     CFG_KEY_DOMAIN,
 ];
 
-static USER_EDITABLE_KEYS: [&str; 12] = [
+static USER_EDITABLE_KEYS: [&str; 15] = [
     CFG_KEY_RPC_URL,
     CFG_KEY_ADMIN_ADDRESS,
     CFG_KEY_STAKER_ADDRESS,
@@ -88,6 +99,9 @@ static USER_EDITABLE_KEYS: [&str; 12] = [
     CFG_KEY_CHAIN_POLLING_INTERVAL_MS,
     CFG_KEY_ENABLE_SIWE_VALIDATION,
     CFG_KEY_HEALTH_POLL_INTERVAL_MS,
+    CFG_KEY_OIDC_PROVIDERS,
+    CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS,
+    CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES,
 ];
 
 static USER_EDITABLE_KEYS_IN_SECTIONS: [&str; 2] =
@@ -138,6 +152,18 @@ pub trait LitNodeConfig {
     fn grpc_pool_size(&self) -> Result<i64>;
     fn payment_interval_ms(&self) -> Result<i64>;
     fn web_client_timeout_s(&self) -> Result<i64>;
+
+    // max access control conditions evaluated concurrently for one request
+    fn acc_concurrency_limit(&self) -> Result<i64>;
+
+    // JSON array of the OpenID Connect providers accepted as auth methods
+    fn oidc_providers(&self) -> Result<String>;
+
+    // limits on fetch() from Lit Actions, on top of the hosts declared by each action
+    fn actions_egress_denied_hosts(&self) -> Result<Vec<String>>;
+    fn actions_egress_denied_ip_ranges(&self) -> Result<Vec<String>>;
+    fn actions_egress_max_response_size(&self) -> Result<i64>;
+    fn actions_egress_fetch_timeout_ms(&self) -> Result<i64>;
 }
 
 impl LitNodeConfig for LitConfig {
@@ -178,7 +204,28 @@ impl LitNodeConfig for LitConfig {
             .set_section_default(CFG_KEY_ENABLE_OBSERVABILITY_EXPORT, "false")
             .set_section_default(CFG_KEY_ENABLE_SIWE_VALIDATION, "true")
             .set_section_default(CFG_KEY_ACTIONS_SOCKET, CFG_KEY_ACTIONS_SOCKET_DEFAULT)
-            .set_section_default(CFG_KEY_HEALTH_POLL_INTERVAL_MS, "60000");
+            .set_section_default(CFG_KEY_HEALTH_POLL_INTERVAL_MS, "60000")
+            .set_section_default(
+                CFG_KEY_ACC_CONCURRENCY_LIMIT,
+                CFG_KEY_ACC_CONCURRENCY_LIMIT_DEFAULT.to_string(),
+            )
+            .set_section_default(CFG_KEY_OIDC_PROVIDERS, CFG_KEY_OIDC_PROVIDERS_DEFAULT)
+            .set_section_default(
+                CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS,
+                CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS_DEFAULT,
+            )
+            .set_section_default(
+                CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES,
+                CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES_DEFAULT,
+            )
+            .set_section_default(
+                CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE,
+                CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE_DEFAULT.to_string(),
+            )
+            .set_section_default(
+                CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS,
+                CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS_DEFAULT.to_string(),
+            );
 
         // Apply others
         builder = <LitConfig as LitBlockchainConfig>::apply_defaults(builder)?;
@@ -403,6 +450,41 @@ impl LitNodeConfig for LitConfig {
     fn grpc_pool_size(&self) -> Result<i64> {
         self.get_section_int(CFG_KEY_GRPC_POOL_SIZE)
     }
+
+    fn acc_concurrency_limit(&self) -> Result<i64> {
+        self.get_section_int(CFG_KEY_ACC_CONCURRENCY_LIMIT)
+    }
+
+    fn oidc_providers(&self) -> Result<String> {
+        self.get_section_string(CFG_KEY_OIDC_PROVIDERS)
+    }
+
+    fn actions_egress_denied_hosts(&self) -> Result<Vec<String>> {
+        self.get_section_string(CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS)
+            .map(|s| split_list(&s))
+    }
+
+    fn actions_egress_denied_ip_ranges(&self) -> Result<Vec<String>> {
+        self.get_section_string(CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES)
+            .map(|s| split_list(&s))
+    }
+
+    fn actions_egress_max_response_size(&self) -> Result<i64> {
+        self.get_section_int(CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE)
+    }
+
+    fn actions_egress_fetch_timeout_ms(&self) -> Result<i64> {
+        self.get_section_int(CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS)
+    }
+}
+
+// Comma-separated list, where an empty string is an empty list
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 pub fn key_path(staker_address: &str) -> PathBuf {
@@ -427,6 +509,13 @@ pub fn presign_path(key_type: &str, staker_address: &str) -> PathBuf {
     path
 }
 
+pub fn frost_nonce_path(key_type: &str, staker_address: &str) -> PathBuf {
+    let mut path = key_path(staker_address);
+    path.push("frost_nonces");
+    path.push(key_type);
+    path
+}
+
 pub fn typed_key_path(key_type: &str, staker_address: &str) -> PathBuf {
     let mut path = key_path(staker_address);
     path.push(key_type);
@@ -12,6 +12,8 @@ pub const CHAIN_EVMOS: &str = "evmos";
 pub const CHAIN_EVMOS_COSMOS: &str = "evmosCosmos";
 pub const CHAIN_EVMOS_COSMOS_TESTNET: &str = "evmosCosmosTestnet";
 pub const CHAIN_LOCALCHAIN: &str = "localchain";
+pub const CHAIN_BITCOIN: &str = "bitcoin";
+pub const CHAIN_BITCOIN_TESTNET: &str = "bitcoinTestnet";
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,6 +30,8 @@ pub enum Chain {
     Localchain,
     EvmosCosmos,
     EvmosCosmosTestnet,
+    Bitcoin,
+    BitcoinTestnet,
 }
 
 impl fmt::Display for Chain {
@@ -45,6 +49,8 @@ impl fmt::Display for Chain {
             Chain::Localchain => write!(f, "{}", CHAIN_LOCALCHAIN),
             Chain::EvmosCosmos => write!(f, "{}", CHAIN_EVMOS_COSMOS),
             Chain::EvmosCosmosTestnet => write!(f, "{}", CHAIN_EVMOS_COSMOS_TESTNET),
+            Chain::Bitcoin => write!(f, "{}", CHAIN_BITCOIN),
+            Chain::BitcoinTestnet => write!(f, "{}", CHAIN_BITCOIN_TESTNET),
         }
     }
 }
@@ -69,6 +75,8 @@ impl FromStr for Chain {
             CHAIN_LOCALCHAIN => Ok(Chain::Localchain),
             CHAIN_EVMOS_COSMOS => Ok(Chain::EvmosCosmos),
             CHAIN_EVMOS_COSMOS_TESTNET => Ok(Chain::EvmosCosmosTestnet),
+            CHAIN_BITCOIN => Ok(Chain::Bitcoin),
+            CHAIN_BITCOIN_TESTNET => Ok(Chain::BitcoinTestnet),
             _ => Ok(Chain::Ethereum), // until the below todo is done, assume EVM chain
                                       // TODO: check rpc_config.yaml and return error if chain is not supported
                                       // _ => Err(ParseChainError),
@@ -93,5 +101,7 @@ pub fn is_evm_compatible_chain(chain: &Chain) -> bool {
             | Chain::Solana
             | Chain::EvmosCosmos
             | Chain::EvmosCosmosTestnet
+            | Chain::Bitcoin
+            | Chain::BitcoinTestnet
     )
 }
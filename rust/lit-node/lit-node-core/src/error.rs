@@ -7,6 +7,10 @@ pub enum Error {
     Parse(String),
     #[error("Invalid type error {0}")]
     InvalidType(String),
+    #[error("Key derivation error {0}")]
+    Derivation(String),
+    #[error("MuSig2 error {0}")]
+    MuSig2(String),
 }
 
 /// Results returned in this crate
@@ -13,10 +13,12 @@ mod auth_sig_item;
 mod blinders;
 mod control_condition_item;
 mod curve_type;
+mod derivation_path;
 mod dynamic_payment_item;
 mod endpoint_version;
 mod invocation;
 mod multiple_auth_sigs;
+mod musig2;
 mod node_set;
 mod payment_delegation_resource;
 mod peer_id;
@@ -42,10 +44,12 @@ pub use auth_sig_item::*;
 pub use blinders::*;
 pub use control_condition_item::*;
 pub use curve_type::*;
+pub use derivation_path::*;
 pub use dynamic_payment_item::*;
 pub use endpoint_version::*;
 pub use invocation::*;
 pub use multiple_auth_sigs::*;
+pub use musig2::*;
 pub use node_set::*;
 pub use payment_delegation_resource::*;
 pub use peer_id::*;
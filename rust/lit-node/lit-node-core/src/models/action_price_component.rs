@@ -15,6 +15,7 @@ pub enum LitActionPriceComponent {
     CallDepth,
     Decrypts,
     Fetches,
+    StorageBytes,
 }
 
 impl From<LitActionPriceComponent> for u8 {
@@ -31,6 +32,7 @@ impl From<LitActionPriceComponent> for u8 {
             LitActionPriceComponent::CallDepth => 8,
             LitActionPriceComponent::Decrypts => 9,
             LitActionPriceComponent::Fetches => 10,
+            LitActionPriceComponent::StorageBytes => 11,
         }
     }
 }
@@ -51,6 +53,7 @@ impl TryFrom<u8> for LitActionPriceComponent {
             8 => Ok(LitActionPriceComponent::CallDepth),
             9 => Ok(LitActionPriceComponent::Decrypts),
             10 => Ok(LitActionPriceComponent::Fetches),
+            11 => Ok(LitActionPriceComponent::StorageBytes),
             _ => Err(format!("Invalid lit action price component: {value}")),
         }
     }
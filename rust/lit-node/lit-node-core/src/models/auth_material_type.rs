@@ -14,4 +14,7 @@ pub enum AuthMaterialType {
 
     /// This is an auth sig that was signed by the BLS network key
     BLSNetworkSig,
+
+    /// This is an auth sig that was derived via a WebAuthn passkey assertion.
+    PasskeySig,
 }
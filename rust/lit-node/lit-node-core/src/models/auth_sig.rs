@@ -2,7 +2,8 @@ use crate::AuthMaterialType;
 use crate::constants::{
     AUTH_SIG_BLS_NETWORK_SIG_ALGO, AUTH_SIG_DERIVED_VIA_BLS_NETWORK_SIG,
     AUTH_SIG_DERIVED_VIA_CONTRACT_SIG, AUTH_SIG_DERIVED_VIA_CONTRACT_SIG_SHA256,
-    AUTH_SIG_DERIVED_VIA_SESSION_SIG, AUTH_SIG_SESSION_SIG_ALGO, Chain,
+    AUTH_SIG_DERIVED_VIA_PASSKEY_SIG, AUTH_SIG_DERIVED_VIA_SESSION_SIG, AUTH_SIG_SESSION_SIG_ALGO,
+    Chain,
 };
 use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
@@ -124,6 +125,10 @@ impl JsonAuthSig {
             return AuthMaterialType::ContractSig;
         }
 
+        if derived_via == AUTH_SIG_DERIVED_VIA_PASSKEY_SIG {
+            return AuthMaterialType::PasskeySig;
+        }
+
         if derived_via == AUTH_SIG_DERIVED_VIA_BLS_NETWORK_SIG {
             if let Some(algo) = algo {
                 if algo == AUTH_SIG_BLS_NETWORK_SIG_ALGO {
@@ -6,6 +6,7 @@ pub type SolRpcConditionItem = ControlConditionItem<SolRpcConditionV2Options>;
 pub type UnifiedAccessControlConditionItem = ControlConditionItem<UnifiedAccessControlCondition>;
 pub type EVMContractConditionItem = ControlConditionItem<EVMContractCondition>;
 pub type CosmosConditionItem = ControlConditionItem<CosmosCondition>;
+pub type BitcoinConditionItem = ControlConditionItem<BitcoinCondition>;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", untagged)]
@@ -23,6 +24,7 @@ pub enum UnifiedAccessControlCondition {
     SolRpcCondition(SolRpcConditionV2Options),
     EVMContractCondition(EVMContractCondition),
     CosmosCondition(CosmosCondition),
+    BitcoinCondition(BitcoinCondition),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -82,6 +84,12 @@ pub struct EVMContractCondition {
     pub function_abi: ethabi::Function,
     pub chain: String,
     pub return_value_test: JsonReturnValueTestV2,
+    /// Block to evaluate the condition at instead of the latest one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Block tag such as `finalized` to evaluate the condition at. Mutually exclusive with `block_number`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_tag: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -93,6 +101,12 @@ pub struct JsonAccessControlCondition {
     pub method: String,
     pub parameters: Vec<String>,
     pub return_value_test: JsonReturnValueTest,
+    /// Block to evaluate the condition at instead of the latest one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Block tag such as `finalized` to evaluate the condition at. Mutually exclusive with `block_number`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_tag: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -127,6 +141,18 @@ pub struct CosmosCondition {
     pub return_value_test: JsonReturnValueTestV2,
 }
 
+/// A condition evaluated against an Esplora-style Bitcoin REST API.
+///
+/// `parameters` holds the method arguments, e.g. the address for `getBalance`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitcoinCondition {
+    pub chain: String,
+    pub method: String,
+    pub parameters: Vec<String>,
+    pub return_value_test: JsonReturnValueTestV2,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CosmosBlock {
@@ -0,0 +1,405 @@
+//! Non-hardened derivation of child keys from a PKP, compatible with wallet standards.
+//!
+//! Children are derived from the public key alone, so every node can add the same tweak to
+//! its key share of the PKP. secp256k1 keys follow BIP-32, and Ed25519 keys the public
+//! derivation of BIP32-Ed25519, as SLIP-10 only defines hardened derivation for Ed25519.
+//! PKPs have no seed, so the chain code of a PKP is hashed from its public key, which makes
+//! its extended public key known to anyone who knows the PKP.
+
+use crate::{CurveType, Error, Result};
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY, traits::IsIdentity,
+};
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::{
+    PrimeField,
+    group::Group,
+    sec1::{FromEncodedPoint, ToEncodedPoint},
+};
+use ripemd::Ripemd160;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+const CHAIN_CODE_DST: &[u8] = b"LIT_PKP_BIP32_CHAIN_CODE";
+const HARDENED_OFFSET: u32 = 1 << 31;
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// A path of non-hardened child indices, like `m/0/1`
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The most children an extended key can be derived through
+    pub const MAX_DEPTH: usize = u8::MAX as usize;
+
+    pub fn new(indices: Vec<u32>) -> Result<Self> {
+        if indices.len() > Self::MAX_DEPTH {
+            return Err(Error::Parse(format!(
+                "Derivation paths can't be longer than {}",
+                Self::MAX_DEPTH
+            )));
+        }
+        if let Some(index) = indices.iter().find(|i| **i >= HARDENED_OFFSET) {
+            return Err(Error::Parse(format!(
+                "Child index {} is hardened, which can't be derived from a PKP",
+                index
+            )));
+        }
+        Ok(Self(indices))
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// The public key of the child at this path, in the compressed encoding of the curve
+    pub fn derive_public_key(&self, curve_type: CurveType, public_key: &[u8]) -> Result<Vec<u8>> {
+        match curve_type {
+            CurveType::K256 => {
+                let child = self.derive_k256(parse_k256_point(public_key)?, None)?;
+                Ok(k256_compressed(&child.public_key))
+            }
+            CurveType::Ed25519 => {
+                let (_, public_key) = self.derive_ed25519(public_key)?;
+                Ok(public_key.compress().to_bytes().to_vec())
+            }
+            _ => Err(unsupported_curve(curve_type)),
+        }
+    }
+
+    /// Adds the tweak of the child at this path to a share of the secret key, returning the
+    /// child's secret share and public key.  Scalars and points use the compressed encodings
+    /// of the curve: big-endian scalars and SEC1 points for secp256k1, and little-endian
+    /// scalars and compressed Edwards points for Ed25519.
+    pub fn derive_key_share(
+        &self,
+        curve_type: CurveType,
+        secret_share: &[u8],
+        public_key: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        match curve_type {
+            CurveType::K256 => {
+                let repr = k256::FieldBytes::from_exact_iter(secret_share.iter().copied())
+                    .ok_or_else(|| Error::Parse("Invalid secp256k1 secret share".to_string()))?;
+                let share = Option::<k256::Scalar>::from(k256::Scalar::from_repr(repr))
+                    .ok_or_else(|| Error::Parse("Invalid secp256k1 secret share".to_string()))?;
+                let child = self.derive_k256(parse_k256_point(public_key)?, None)?;
+                Ok((
+                    (share + child.tweak).to_repr().to_vec(),
+                    k256_compressed(&child.public_key),
+                ))
+            }
+            CurveType::Ed25519 => {
+                let bytes = <[u8; 32]>::try_from(secret_share)
+                    .map_err(|_| Error::Parse("Invalid Ed25519 secret share".to_string()))?;
+                let share = Option::<curve25519_dalek::Scalar>::from(
+                    curve25519_dalek::Scalar::from_canonical_bytes(bytes),
+                )
+                .ok_or_else(|| Error::Parse("Invalid Ed25519 secret share".to_string()))?;
+                let (tweak, public_key) = self.derive_ed25519(public_key)?;
+                Ok((
+                    (share + tweak).to_bytes().to_vec(),
+                    public_key.compress().to_bytes().to_vec(),
+                ))
+            }
+            _ => Err(unsupported_curve(curve_type)),
+        }
+    }
+
+    /// The BIP-32 extended public key of the secp256k1 child at this path, which wallets
+    /// and indexers can watch and derive further children from.
+    pub fn xpub(&self, public_key: &[u8]) -> Result<String> {
+        let child = self.derive_k256(parse_k256_point(public_key)?, None)?;
+        Ok(child.xpub(self))
+    }
+
+    fn derive_k256(
+        &self,
+        public_key: k256::ProjectivePoint,
+        chain_code: Option<[u8; 32]>,
+    ) -> Result<K256Child> {
+        let mut child = K256Child {
+            chain_code: chain_code.unwrap_or_else(|| pkp_chain_code(&k256_compressed(&public_key))),
+            public_key,
+            tweak: k256::Scalar::ZERO,
+            parent_fingerprint: [0u8; 4],
+        };
+
+        for index in &self.0 {
+            let parent = k256_compressed(&child.public_key);
+            let mut mac = hmac_sha512(&child.chain_code);
+            mac.update(&parent);
+            mac.update(&index.to_be_bytes());
+            let i = mac.finalize().into_bytes();
+
+            let il = Option::<k256::Scalar>::from(k256::Scalar::from_repr(
+                *k256::FieldBytes::from_slice(&i[..32]),
+            ))
+            .ok_or_else(|| invalid_child(*index))?;
+            let public_key = k256::ProjectivePoint::GENERATOR * il + child.public_key;
+            if bool::from(public_key.is_identity()) {
+                return Err(invalid_child(*index));
+            }
+
+            child.parent_fingerprint = fingerprint(&parent);
+            child.public_key = public_key;
+            child.tweak += il;
+            child.chain_code.copy_from_slice(&i[32..]);
+        }
+
+        Ok(child)
+    }
+
+    fn derive_ed25519(
+        &self,
+        public_key: &[u8],
+    ) -> Result<(curve25519_dalek::Scalar, curve25519_dalek::EdwardsPoint)> {
+        let mut point = CompressedEdwardsY::from_slice(public_key)
+            .ok()
+            .and_then(|p| p.decompress())
+            .ok_or_else(|| Error::Parse("Invalid Ed25519 public key".to_string()))?;
+        let mut chain_code = pkp_chain_code(public_key);
+        let mut tweak = curve25519_dalek::Scalar::ZERO;
+
+        for index in &self.0 {
+            let parent = point.compress().to_bytes();
+
+            let mut mac = hmac_sha512(&chain_code);
+            mac.update(&[0x02]);
+            mac.update(&parent);
+            mac.update(&index.to_le_bytes());
+            let z = mac.finalize().into_bytes();
+            // 8 * ZL, where ZL is the first 28 bytes of Z
+            let mut zl = [0u8; 32];
+            zl[..28].copy_from_slice(&z[..28]);
+            let zl = curve25519_dalek::Scalar::from_bytes_mod_order(zl)
+                * curve25519_dalek::Scalar::from(8u8);
+
+            point += ED25519_BASEPOINT_POINT * zl;
+            if point.is_identity() {
+                return Err(invalid_child(*index));
+            }
+            tweak += zl;
+
+            let mut mac = hmac_sha512(&chain_code);
+            mac.update(&[0x03]);
+            mac.update(&parent);
+            mac.update(&index.to_le_bytes());
+            chain_code.copy_from_slice(&mac.finalize().into_bytes()[32..]);
+        }
+
+        Ok((tweak, point))
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(Error::Parse(format!(
+                "Derivation path '{}' must start with 'm'",
+                s
+            )));
+        }
+        let indices = parts
+            .map(|part| {
+                if part.ends_with(['\'', 'h', 'H']) {
+                    return Err(Error::Parse(format!(
+                        "Child index {} is hardened, which can't be derived from a PKP",
+                        part
+                    )));
+                }
+                part.parse::<u32>()
+                    .map_err(|_| Error::Parse(format!("Invalid child index '{}'", part)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(indices)
+    }
+}
+
+impl Serialize for DerivationPath {
+    fn serialize<S>(&self, s: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DerivationPath {
+    fn deserialize<D>(d: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(d)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The chain code of a PKP's extended keys, which is public like the PKP itself
+pub fn pkp_chain_code(public_key: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(CHAIN_CODE_DST);
+    hasher.update(public_key);
+    hasher.finalize().into()
+}
+
+struct K256Child {
+    public_key: k256::ProjectivePoint,
+    chain_code: [u8; 32],
+    // the sum of the tweaks of every level, to add to the PKP's secret key
+    tweak: k256::Scalar,
+    parent_fingerprint: [u8; 4],
+}
+
+impl K256Child {
+    fn xpub(&self, path: &DerivationPath) -> String {
+        let mut data = Vec::with_capacity(82);
+        data.extend_from_slice(&XPUB_VERSION);
+        data.push(path.0.len() as u8);
+        data.extend_from_slice(&self.parent_fingerprint);
+        data.extend_from_slice(&path.0.last().copied().unwrap_or_default().to_be_bytes());
+        data.extend_from_slice(&self.chain_code);
+        data.extend_from_slice(&k256_compressed(&self.public_key));
+        let checksum = Sha256::digest(Sha256::digest(&data));
+        data.extend_from_slice(&checksum[..4]);
+        bs58::encode(data).into_string()
+    }
+}
+
+fn parse_k256_point(public_key: &[u8]) -> Result<k256::ProjectivePoint> {
+    k256::EncodedPoint::from_bytes(public_key)
+        .ok()
+        .and_then(|p| Option::from(k256::ProjectivePoint::from_encoded_point(&p)))
+        .ok_or_else(|| Error::Parse("Invalid secp256k1 public key".to_string()))
+}
+
+fn k256_compressed(point: &k256::ProjectivePoint) -> Vec<u8> {
+    k256::AffinePoint::from(*point)
+        .to_encoded_point(true)
+        .as_bytes()
+        .to_vec()
+}
+
+fn fingerprint(public_key: &[u8]) -> [u8; 4] {
+    let hash = Ripemd160::digest(Sha256::digest(public_key));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn hmac_sha512(key: &[u8]) -> Hmac<Sha512> {
+    Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any size")
+}
+
+fn invalid_child(index: u32) -> Error {
+    Error::Derivation(format!(
+        "Child index {} derives an invalid key, use the next index instead",
+        index
+    ))
+}
+
+fn unsupported_curve(curve_type: CurveType) -> Error {
+    Error::InvalidType(format!(
+        "Derivation paths are only supported for Secp256k1 and Ed25519 keys, not {}",
+        curve_type.as_str()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_derivation_path() {
+        let path = DerivationPath::from_str("m/0/1/2147483647").unwrap();
+        assert_eq!(path.indices(), &[0, 1, 2147483647]);
+        assert_eq!(path.to_string(), "m/0/1/2147483647");
+        assert_eq!(
+            DerivationPath::from_str("m").unwrap().indices(),
+            &[] as &[u32]
+        );
+
+        assert!(DerivationPath::from_str("m/0'").is_err());
+        assert!(DerivationPath::from_str("m/0h").is_err());
+        assert!(DerivationPath::from_str("m/2147483648").is_err());
+        assert!(DerivationPath::from_str("0/1").is_err());
+        assert!(DerivationPath::from_str("m/").is_err());
+    }
+
+    #[test]
+    fn k256_matches_bip32_test_vector_2() {
+        let master = bs58::decode("xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB")
+            .into_vec()
+            .unwrap();
+        let chain_code = <[u8; 32]>::try_from(&master[13..45]).unwrap();
+        let public_key = parse_k256_point(&master[45..78]).unwrap();
+
+        let path = DerivationPath::from_str("m/0").unwrap();
+        let child = path.derive_k256(public_key, Some(chain_code)).unwrap();
+        assert_eq!(
+            child.xpub(&path),
+            "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH"
+        );
+    }
+
+    #[test]
+    fn derived_key_shares_match_derived_public_keys() {
+        let path = DerivationPath::from_str("m/7/0/42").unwrap();
+
+        let secret = k256::Scalar::from(123456789u64);
+        let public_key = k256_compressed(&(k256::ProjectivePoint::GENERATOR * secret));
+        let (child_secret, child_public_key) = path
+            .derive_key_share(CurveType::K256, &secret.to_repr(), &public_key)
+            .unwrap();
+        let child_secret =
+            k256::Scalar::from_repr(*k256::FieldBytes::from_slice(&child_secret)).unwrap();
+        assert_eq!(
+            k256_compressed(&(k256::ProjectivePoint::GENERATOR * child_secret)),
+            child_public_key
+        );
+        assert_eq!(
+            path.derive_public_key(CurveType::K256, &public_key)
+                .unwrap(),
+            child_public_key
+        );
+
+        let secret = curve25519_dalek::Scalar::from(987654321u64);
+        let public_key = (ED25519_BASEPOINT_POINT * secret).compress().to_bytes();
+        let (child_secret, child_public_key) = path
+            .derive_key_share(CurveType::Ed25519, &secret.to_bytes(), &public_key)
+            .unwrap();
+        let child_secret =
+            curve25519_dalek::Scalar::from_canonical_bytes(child_secret.try_into().unwrap())
+                .unwrap();
+        assert_eq!(
+            (ED25519_BASEPOINT_POINT * child_secret)
+                .compress()
+                .to_bytes()
+                .to_vec(),
+            child_public_key
+        );
+        assert_eq!(
+            path.derive_public_key(CurveType::Ed25519, &public_key)
+                .unwrap(),
+            child_public_key
+        );
+
+        assert!(
+            path.derive_public_key(CurveType::P256, &public_key)
+                .is_err()
+        );
+    }
+}
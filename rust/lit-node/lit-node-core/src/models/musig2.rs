@@ -0,0 +1,435 @@
+//! MuSig2 (BIP-327) signing with a PKP as one of the co-signers, and Taproot (BIP-341) tweaks.
+//!
+//! The nodes that sign with a PKP act as a single co-signer. Each node commits to a pair of
+//! nonces of its own, and the public nonce of the PKP is the sum of theirs, so the nodes don't
+//! need to talk to each other. Once the co-signers have aggregated their nonces, each node signs
+//! with its Lagrange-weighted share of the PKP's key, and the sum of the nodes' shares is the
+//! PKP's partial signature. When a PKP has no co-signers, its own key is the internal key, and
+//! the signature is a plain BIP-340 signature under the (tweaked) key of the PKP.
+
+use crate::{Error, Result};
+use k256::elliptic_curve::{
+    PrimeField,
+    group::Group,
+    ops::Reduce,
+    point::AffineCoordinates,
+    sec1::{FromEncodedPoint, ToEncodedPoint},
+};
+use k256::{AffinePoint, FieldBytes, ProjectivePoint, Scalar, U256};
+use sha2::{Digest, Sha256};
+
+/// The length of a public nonce, which is two compressed points
+pub const MUSIG2_PUBLIC_NONCE_LENGTH: usize = 66;
+/// The length of a partial signature, or of a node's share of one
+pub const MUSIG2_PARTIAL_SIGNATURE_LENGTH: usize = 32;
+
+/// The secret nonces of one signer for one signature.  They must never sign twice.
+pub struct MuSig2SecretNonce {
+    k1: Scalar,
+    k2: Scalar,
+}
+
+impl MuSig2SecretNonce {
+    pub fn new(k1: Scalar, k2: Scalar) -> Result<Self> {
+        if bool::from(k1.is_zero()) || bool::from(k2.is_zero()) {
+            return Err(Error::MuSig2("Secret nonces can't be zero".to_string()));
+        }
+        Ok(Self { k1, k2 })
+    }
+
+    /// The public nonce to hand to the co-signers
+    pub fn public_nonce(&self) -> Vec<u8> {
+        let mut nonce = compressed(&(ProjectivePoint::GENERATOR * self.k1));
+        nonce.extend_from_slice(&compressed(&(ProjectivePoint::GENERATOR * self.k2)));
+        nonce
+    }
+}
+
+/// Sums public nonces, which gives the public nonce of a PKP from those of its nodes, and the
+/// aggregate nonce from those of the co-signers.  Sums at infinity are encoded as zeros.
+pub fn aggregate_public_nonces(public_nonces: &[Vec<u8>]) -> Result<Vec<u8>> {
+    if public_nonces.is_empty() {
+        return Err(Error::MuSig2("No public nonces to aggregate".to_string()));
+    }
+    let mut r1 = ProjectivePoint::IDENTITY;
+    let mut r2 = ProjectivePoint::IDENTITY;
+    for public_nonce in public_nonces {
+        let (p1, p2) = parse_nonce(public_nonce)?;
+        r1 += p1;
+        r2 += p2;
+    }
+    let mut nonce = compressed_or_zero(&r1);
+    nonce.extend_from_slice(&compressed_or_zero(&r2));
+    Ok(nonce)
+}
+
+/// One signature of the co-signers, from which a co-signer makes its partial signature
+#[derive(Debug)]
+pub struct MuSig2Session {
+    output_key: ProjectivePoint,
+    // the parity and tweak accumulators of BIP-327
+    gacc: Scalar,
+    tacc: Scalar,
+    // the key aggregation coefficient of the signer
+    coefficient: Scalar,
+    nonce_coefficient: Scalar,
+    nonce: ProjectivePoint,
+    challenge: Scalar,
+}
+
+impl MuSig2Session {
+    /// Starts a signature of `message` by `signer` and the other co-signers, all of which are in
+    /// `public_keys` in the order they were aggregated.  Without co-signers, `public_keys` is
+    /// empty and the signer's key is used as is.  With a `taproot_merkle_root`, the aggregate
+    /// key is tweaked into a Taproot output key as by BIP-341: an empty root tweaks a key that
+    /// commits to no scripts, and a 32-byte root one that commits to a script tree.
+    pub fn new(
+        signer: &[u8],
+        public_keys: &[Vec<u8>],
+        taproot_merkle_root: Option<&[u8]>,
+        aggregate_nonce: &[u8],
+        message: &[u8],
+    ) -> Result<Self> {
+        let signer_key = parse_point(signer)?;
+        let (mut output_key, coefficient) = match public_keys {
+            [] => (signer_key, Scalar::ONE),
+            _ => {
+                if public_keys
+                    .iter()
+                    .filter(|pk| pk.as_slice() == signer)
+                    .count()
+                    != 1
+                {
+                    return Err(Error::MuSig2(
+                        "The signer's key must be in the public keys exactly once".to_string(),
+                    ));
+                }
+                let (aggregate_key, coefficients) = aggregate_keys(public_keys)?;
+                let index = public_keys
+                    .iter()
+                    .position(|pk| pk.as_slice() == signer)
+                    .expect("the signer's key was found");
+                (aggregate_key, coefficients[index])
+            }
+        };
+
+        let mut gacc = Scalar::ONE;
+        let mut tacc = Scalar::ZERO;
+        if let Some(merkle_root) = taproot_merkle_root {
+            if !merkle_root.is_empty() && merkle_root.len() != 32 {
+                return Err(Error::MuSig2(
+                    "Taproot merkle roots must be empty or 32 bytes long".to_string(),
+                ));
+            }
+            let tweak = Option::<Scalar>::from(Scalar::from_repr(tagged_hash(
+                "TapTweak",
+                &[x_only(&output_key).as_slice(), merkle_root],
+            )))
+            .ok_or_else(|| Error::MuSig2("The Taproot tweak is out of range".to_string()))?;
+            let g = parity(&output_key);
+            output_key = output_key * g + ProjectivePoint::GENERATOR * tweak;
+            if bool::from(output_key.is_identity()) {
+                return Err(Error::MuSig2(
+                    "The Taproot tweak gives the point at infinity".to_string(),
+                ));
+            }
+            gacc *= g;
+            tacc = tweak + g * tacc;
+        }
+
+        if aggregate_nonce.len() != MUSIG2_PUBLIC_NONCE_LENGTH {
+            return Err(invalid_nonce());
+        }
+        let r1 = parse_point_or_zero(&aggregate_nonce[..33])?;
+        let r2 = parse_point_or_zero(&aggregate_nonce[33..])?;
+        let nonce_coefficient = hash_to_scalar(
+            "MuSig/noncecoef",
+            &[aggregate_nonce, x_only(&output_key).as_slice(), message],
+        );
+        let mut nonce = r1 + r2 * nonce_coefficient;
+        if bool::from(nonce.is_identity()) {
+            nonce = ProjectivePoint::GENERATOR;
+        }
+        let challenge = hash_to_scalar(
+            "BIP0340/challenge",
+            &[
+                x_only(&nonce).as_slice(),
+                x_only(&output_key).as_slice(),
+                message,
+            ],
+        );
+
+        Ok(Self {
+            output_key,
+            gacc,
+            tacc,
+            coefficient,
+            nonce_coefficient,
+            nonce,
+            challenge,
+        })
+    }
+
+    /// The x-only key that the signature verifies under
+    pub fn output_key(&self) -> [u8; 32] {
+        x_only(&self.output_key)
+    }
+
+    /// Signs with the secret nonce and a share of the signer's key, which is the whole key of
+    /// a single signer, or the Lagrange-weighted share of a node of a PKP.  The shares of a
+    /// PKP's nodes add up to the PKP's partial signature.
+    pub fn sign(&self, secret_nonce: &MuSig2SecretNonce, secret_share: &Scalar) -> Vec<u8> {
+        let nonce_parity = parity(&self.nonce);
+        let k1 = secret_nonce.k1 * nonce_parity;
+        let k2 = secret_nonce.k2 * nonce_parity;
+        let d = parity(&self.output_key) * self.gacc * secret_share;
+        let s = k1 + self.nonce_coefficient * k2 + self.challenge * self.coefficient * d;
+        s.to_repr().to_vec()
+    }
+
+    /// Sums partial signatures, or the shares of one, into a scalar
+    pub fn sum_partial_signatures(partial_signatures: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let mut s = Scalar::ZERO;
+        for partial_signature in partial_signatures {
+            s += parse_scalar(partial_signature)?;
+        }
+        Ok(s.to_repr().to_vec())
+    }
+
+    /// Aggregates the partial signatures of every co-signer into a BIP-340 signature
+    pub fn aggregate_partial_signatures(&self, partial_signatures: &[Vec<u8>]) -> Result<Vec<u8>> {
+        let s = parse_scalar(&Self::sum_partial_signatures(partial_signatures)?)?
+            + self.challenge * parity(&self.output_key) * self.tacc;
+        let mut signature = x_only(&self.nonce).to_vec();
+        signature.extend_from_slice(&s.to_repr());
+        Ok(signature)
+    }
+}
+
+// KeyAgg of BIP-327, returning the aggregate key and the coefficient of each key
+fn aggregate_keys(public_keys: &[Vec<u8>]) -> Result<(ProjectivePoint, Vec<Scalar>)> {
+    let list = tagged_hash(
+        "KeyAgg list",
+        &public_keys.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+    );
+    let second_key = public_keys.iter().find(|pk| **pk != public_keys[0]);
+
+    let mut aggregate_key = ProjectivePoint::IDENTITY;
+    let mut coefficients = Vec::with_capacity(public_keys.len());
+    for public_key in public_keys {
+        let coefficient = match second_key == Some(public_key) {
+            true => Scalar::ONE,
+            false => hash_to_scalar(
+                "KeyAgg coefficient",
+                &[list.as_slice(), public_key.as_slice()],
+            ),
+        };
+        aggregate_key += parse_point(public_key)? * coefficient;
+        coefficients.push(coefficient);
+    }
+    if bool::from(aggregate_key.is_identity()) {
+        return Err(Error::MuSig2(
+            "The public keys aggregate to the point at infinity".to_string(),
+        ));
+    }
+    Ok((aggregate_key, coefficients))
+}
+
+fn tagged_hash(tag: &str, data: &[&[u8]]) -> FieldBytes {
+    let tag = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    hasher.update(tag);
+    for d in data {
+        hasher.update(d);
+    }
+    hasher.finalize()
+}
+
+fn hash_to_scalar(tag: &str, data: &[&[u8]]) -> Scalar {
+    <Scalar as Reduce<U256>>::reduce_bytes(&tagged_hash(tag, data))
+}
+
+// -1 if the point has an odd y coordinate, and 1 otherwise
+fn parity(point: &ProjectivePoint) -> Scalar {
+    match bool::from(point.to_affine().y_is_odd()) {
+        true => -Scalar::ONE,
+        false => Scalar::ONE,
+    }
+}
+
+fn x_only(point: &ProjectivePoint) -> [u8; 32] {
+    point.to_affine().x().into()
+}
+
+fn compressed(point: &ProjectivePoint) -> Vec<u8> {
+    AffinePoint::from(*point)
+        .to_encoded_point(true)
+        .as_bytes()
+        .to_vec()
+}
+
+fn compressed_or_zero(point: &ProjectivePoint) -> Vec<u8> {
+    match bool::from(point.is_identity()) {
+        true => vec![0u8; 33],
+        false => compressed(point),
+    }
+}
+
+fn parse_point(bytes: &[u8]) -> Result<ProjectivePoint> {
+    if bytes.len() != 33 {
+        return Err(Error::Parse(
+            "Expected a compressed secp256k1 public key".to_string(),
+        ));
+    }
+    k256::EncodedPoint::from_bytes(bytes)
+        .ok()
+        .and_then(|p| Option::from(ProjectivePoint::from_encoded_point(&p)))
+        .ok_or_else(|| Error::Parse("Invalid secp256k1 public key".to_string()))
+}
+
+fn parse_point_or_zero(bytes: &[u8]) -> Result<ProjectivePoint> {
+    match bytes.iter().all(|b| *b == 0) {
+        true => Ok(ProjectivePoint::IDENTITY),
+        false => parse_point(bytes),
+    }
+}
+
+fn parse_nonce(public_nonce: &[u8]) -> Result<(ProjectivePoint, ProjectivePoint)> {
+    if public_nonce.len() != MUSIG2_PUBLIC_NONCE_LENGTH {
+        return Err(invalid_nonce());
+    }
+    let r1 = parse_point(&public_nonce[..33]).map_err(|_| invalid_nonce())?;
+    let r2 = parse_point(&public_nonce[33..]).map_err(|_| invalid_nonce())?;
+    Ok((r1, r2))
+}
+
+fn parse_scalar(bytes: &[u8]) -> Result<Scalar> {
+    FieldBytes::from_exact_iter(bytes.iter().copied())
+        .and_then(|repr| Option::<Scalar>::from(Scalar::from_repr(repr)))
+        .ok_or_else(|| Error::Parse("Invalid partial signature".to_string()))
+}
+
+fn invalid_nonce() -> Error {
+    Error::Parse(format!(
+        "Public nonces must be {} bytes of two compressed points",
+        MUSIG2_PUBLIC_NONCE_LENGTH
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP-340 verification, with the key lifted to an even y coordinate
+    fn verify(output_key: &[u8; 32], message: &[u8], signature: &[u8]) -> bool {
+        let mut key = vec![0x02];
+        key.extend_from_slice(output_key);
+        let key = parse_point(&key).unwrap();
+        let s = parse_scalar(&signature[32..]).unwrap();
+        let e = hash_to_scalar(
+            "BIP0340/challenge",
+            &[&signature[..32], output_key.as_slice(), message],
+        );
+        let r = ProjectivePoint::GENERATOR * s - key * e;
+        !bool::from(r.is_identity())
+            && !bool::from(r.to_affine().y_is_odd())
+            && x_only(&r) == signature[..32]
+    }
+
+    fn nonce(seed: u64) -> MuSig2SecretNonce {
+        MuSig2SecretNonce::new(Scalar::from(seed), Scalar::from(seed * 31 + 7)).unwrap()
+    }
+
+    // A 2-of-3 PKP signing with nodes 1 and 3, with or without an external co-signer
+    fn sign(with_co_signer: bool, taproot_merkle_root: Option<&[u8]>) -> bool {
+        let secret = Scalar::from(0x1234_5678_9abc_u64);
+        let coefficient = Scalar::from(0xfeed_u64);
+        let share = |id: u64| secret + coefficient * Scalar::from(id);
+        let lagrange = |id: u64, other: u64| {
+            Scalar::from(other) * (Scalar::from(other) - Scalar::from(id)).invert().unwrap()
+        };
+        let pkp_key = compressed(&(ProjectivePoint::GENERATOR * secret));
+        let node_nonces = [nonce(11), nonce(13)];
+        let pkp_nonce = aggregate_public_nonces(
+            &node_nonces
+                .iter()
+                .map(MuSig2SecretNonce::public_nonce)
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let co_signer_secret = Scalar::from(0xc0ffee_u64);
+        let co_signer_key = compressed(&(ProjectivePoint::GENERATOR * co_signer_secret));
+        let co_signer_nonce = nonce(17);
+        let (public_keys, public_nonces) = match with_co_signer {
+            true => (
+                vec![co_signer_key.clone(), pkp_key.clone()],
+                vec![co_signer_nonce.public_nonce(), pkp_nonce],
+            ),
+            false => (vec![], vec![pkp_nonce]),
+        };
+        let aggregate_nonce = aggregate_public_nonces(&public_nonces).unwrap();
+        let message = b"spend the output";
+
+        let session = MuSig2Session::new(
+            &pkp_key,
+            &public_keys,
+            taproot_merkle_root,
+            &aggregate_nonce,
+            message,
+        )
+        .unwrap();
+        let node_shares = [
+            session.sign(&node_nonces[0], &(lagrange(1, 3) * share(1))),
+            session.sign(&node_nonces[1], &(lagrange(3, 1) * share(3))),
+        ];
+        let mut partial_signatures =
+            vec![MuSig2Session::sum_partial_signatures(&node_shares).unwrap()];
+        if with_co_signer {
+            let co_signer_session = MuSig2Session::new(
+                &co_signer_key,
+                &public_keys,
+                taproot_merkle_root,
+                &aggregate_nonce,
+                message,
+            )
+            .unwrap();
+            assert_eq!(co_signer_session.output_key(), session.output_key());
+            partial_signatures.push(co_signer_session.sign(&co_signer_nonce, &co_signer_secret));
+        }
+
+        let signature = session
+            .aggregate_partial_signatures(&partial_signatures)
+            .unwrap();
+        verify(&session.output_key(), message, &signature)
+    }
+
+    #[test]
+    fn signatures_verify_under_the_output_key() {
+        for with_co_signer in [false, true] {
+            assert!(sign(with_co_signer, None));
+            assert!(sign(with_co_signer, Some(&[])));
+            assert!(sign(with_co_signer, Some(&[0x5a; 32])));
+        }
+    }
+
+    #[test]
+    fn signer_must_be_a_co_signer() {
+        let key = compressed(&(ProjectivePoint::GENERATOR * Scalar::from(5u64)));
+        let other = compressed(&(ProjectivePoint::GENERATOR * Scalar::from(6u64)));
+        let aggregate_nonce = aggregate_public_nonces(&[nonce(1).public_nonce()]).unwrap();
+        assert!(MuSig2Session::new(&key, &[other.clone()], None, &aggregate_nonce, b"m").is_err());
+        assert!(
+            MuSig2Session::new(
+                &key,
+                &[key.clone(), key.clone()],
+                None,
+                &aggregate_nonce,
+                b"m"
+            )
+            .is_err()
+        );
+        assert!(MuSig2Session::new(&key, &[], Some(&[1; 31]), &aggregate_nonce, b"m").is_err());
+    }
+}
@@ -1,11 +1,13 @@
 use super::default_epoch;
 use crate::{
-    AccessControlConditionItem, AuthMethod, AuthSigItem, CurveType, EVMContractConditionItem,
-    Invocation, NodeSet, SigningScheme, SolRpcConditionItem, UnifiedAccessControlConditionItem,
+    AccessControlConditionItem, AuthMethod, AuthSigItem, CurveType, DerivationPath,
+    EVMContractConditionItem, Invocation, JsonAuthSig, NodeSet, SigningScheme, SolRpcConditionItem,
+    UnifiedAccessControlConditionItem,
 };
 use ethers::types::U256;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -26,6 +28,9 @@ pub struct EncryptionSignRequest {
     pub auth_sig: AuthSigItem,
     #[serde(default = "default_epoch")]
     pub epoch: u64,
+    /// Blocks by chain to evaluate EVM conditions at when they don't pin one themselves.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub block_numbers: HashMap<String, u64>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -57,6 +62,90 @@ pub struct JsonPKPSigningRequest {
     #[serde(default = "default_epoch")]
     pub epoch: u64,
     pub node_set: Vec<NodeSet>,
+    /// Signs with the child of the PKP at this non-hardened BIP-32 path, like `m/0/1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<DerivationPath>,
+    /// Proves possession of a BLS PKP by signing its public key, which must be `to_sign`
+    #[serde(default)]
+    pub proof_of_possession: bool,
+}
+
+/// Signs several messages with the same PKP in one request
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPKPBatchSigningRequest {
+    pub messages: Vec<PKPBatchSigningMessage>,
+    pub pubkey: String,
+    pub auth_sig: AuthSigItem,
+    pub auth_methods: Option<Vec<AuthMethod>>,
+    /// The scheme of the messages that don't specify their own
+    pub signing_scheme: SigningScheme,
+    #[serde(default = "default_epoch")]
+    pub epoch: u64,
+    pub node_set: Vec<NodeSet>,
+    /// Signs every message with the child of the PKP at this path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<DerivationPath>,
+}
+
+impl JsonPKPBatchSigningRequest {
+    /// The messages with the scheme each one is signed with
+    pub fn messages_with_schemes(&self) -> Vec<(Vec<u8>, SigningScheme)> {
+        self.messages
+            .iter()
+            .map(|m| {
+                (
+                    m.to_sign.clone(),
+                    m.signing_scheme.unwrap_or(self.signing_scheme),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PKPBatchSigningMessage {
+    pub to_sign: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_scheme: Option<SigningScheme>,
+}
+
+/// The first phase of a MuSig2 signature, in which each node commits to a nonce for the PKP
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPKPMuSig2NonceRequest {
+    /// Chosen by the client to find the nonces again in the second phase
+    pub session_id: String,
+    pub pubkey: String,
+    pub auth_sig: AuthSigItem,
+    pub auth_methods: Option<Vec<AuthMethod>>,
+    #[serde(default = "default_epoch")]
+    pub epoch: u64,
+    pub node_set: Vec<NodeSet>,
+}
+
+/// The second phase of a MuSig2 signature, in which each node signs with its nonce
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPKPMuSig2SigningRequest {
+    pub session_id: String,
+    pub to_sign: Vec<u8>,
+    pub pubkey: String,
+    pub auth_sig: AuthSigItem,
+    pub auth_methods: Option<Vec<AuthMethod>>,
+    #[serde(default = "default_epoch")]
+    pub epoch: u64,
+    pub node_set: Vec<NodeSet>,
+    /// The sum of the public nonces of every co-signer, the PKP's included
+    pub aggregate_nonce: Vec<u8>,
+    /// The compressed keys of every co-signer in aggregation order, or none for the PKP alone
+    #[serde(default)]
+    pub public_keys: Vec<Vec<u8>>,
+    /// Tweaks the aggregate key into a Taproot output key committing to this script tree, or
+    /// to no scripts if it's empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taproot_merkle_root: Option<Vec<u8>>,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize)]
@@ -72,6 +161,12 @@ pub struct JsonExecutionRequest {
     pub node_set: Vec<NodeSet>,
     #[serde(default)]
     pub invocation: Invocation,
+    /// Pins randomness and the clock across nodes and compares their fetch responses.
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Returns a trace of the ops the action invoked, with their timings and prices.
+    #[serde(default)]
+    pub trace: bool,
 }
 
 impl JsonExecutionRequest {
@@ -105,6 +200,8 @@ impl std::fmt::Debug for JsonExecutionRequest {
             .field("epoch", &self.epoch)
             .field("node_set", &self.node_set)
             .field("invocation", &self.invocation)
+            .field("deterministic", &self.deterministic)
+            .field("trace", &self.trace)
             .finish()
     }
 }
@@ -115,3 +212,16 @@ pub struct JsonPKPClaimKeyRequest {
     pub auth_method: AuthMethod,
     pub credential_public_key: Option<String>,
 }
+
+/// Revokes a session key before its session sigs expire.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSessionRevocationRequest {
+    /// Signed with EIP-191 by the wallet or PKP that delegated to the session key. The signed
+    /// message is a SIWE message whose URI is `lit:revoke:<session key>` and whose resources
+    /// include `lit-subnet:<subnet id>` of the network it is meant for.
+    pub auth_sig: JsonAuthSig,
+    /// The chain to verify the signature on when the issuer is a smart account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain: Option<String>,
+}
@@ -103,6 +103,34 @@ pub struct JsonPKPSigningResponse {
     pub signature_share: SignableOutput,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPKPBatchSigningResponse {
+    pub success: bool,
+    pub signed_data: Vec<Vec<u8>>,
+    /// In the order of the messages of the request
+    pub signature_shares: Vec<SignableOutput>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPKPMuSig2NonceResponse {
+    pub success: bool,
+    pub peer_id: String,
+    /// This node's part of the PKP's public nonce, which is the sum of those of the node set
+    pub public_nonce: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPKPMuSig2SigningResponse {
+    pub success: bool,
+    pub signed_data: Vec<u8>,
+    pub peer_id: String,
+    /// This node's share of the PKP's partial signature, which is the sum of those of the node set
+    pub partial_signature_share: Vec<u8>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonExecutionResponse {
@@ -113,6 +141,64 @@ pub struct JsonExecutionResponse {
     pub response: String,
     pub logs: String,
     pub payment_detail: Option<Vec<DynamicPaymentItem>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deterministic_report: Option<DeterministicExecutionReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<ExecutionTrace>,
+}
+
+/// Where the time and money of a Lit Action execution went, as seen by this node.
+/// Timestamps are in milliseconds since epoch.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionTrace {
+    pub started_at: u64,
+    pub ops: Vec<OpTrace>,
+    pub heap_samples: Vec<HeapSample>,
+    /// Whether entries were dropped because the action invoked too many ops
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// An op invoked by the action, e.g. `Sign` or `Print`, including those of child actions.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpTrace {
+    pub op: String,
+    pub call_depth: u32,
+    pub started_at: u64,
+    pub ended_at: u64,
+    /// Encoded sizes of the op's request and response in bytes
+    pub request_size: usize,
+    pub response_size: usize,
+    /// What the op was charged, by price component. Includes the charges of child actions'
+    /// ops for `CallChild`.
+    pub charges: Vec<DynamicPaymentItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Memory used by the action's isolate, sampled by lit_actions while it runs.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapSample {
+    pub sampled_at: u64,
+    pub used_heap_size: u64,
+    /// Allocations outside the V8 heap, such as WebAssembly memories
+    pub external_memory: u64,
+}
+
+/// The inputs a deterministic Lit Action execution was pinned to, and which
+/// nodes were served different fetch responses than this one.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeterministicExecutionReport {
+    pub seed: u64,
+    /// Consensus timestamp in milliseconds since epoch
+    pub timestamp: u64,
+    /// Hex-encoded keccak256 of the fetch responses recorded by this node, in order
+    pub fetch_digest: String,
+    pub divergent_peers: Vec<String>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
@@ -121,3 +207,30 @@ pub struct JsonPKPClaimKeyResponse {
     pub signature: String,
     pub derived_key_id: String,
 }
+
+/// What happened when a single condition was evaluated by the explain endpoint.
+///
+/// `result` is `None` when the condition was never resolved, e.g. because a
+/// sibling condition short-circuited the enclosing boolean operator.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessControlConditionTrace {
+    /// The position of the condition in the request: its index in the top-level list,
+    /// followed by its index in each nested group.
+    pub index: Vec<usize>,
+    pub condition: Value,
+    pub rpc_calls: Vec<String>,
+    pub returned_values: Vec<String>,
+    pub comparator: Option<String>,
+    pub expected_value: Option<String>,
+    pub result: Option<bool>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAccessControlExplainResponse {
+    pub result: bool,
+    pub error: Option<String>,
+    pub trace: Vec<AccessControlConditionTrace>,
+}
@@ -201,7 +201,11 @@ impl SigningScheme {
     }
 
     pub fn supports_curve(&self, curve_type: CurveType) -> bool {
-        self.curve_type() == curve_type
+        match self {
+            // PKPs sign with the basic scheme using the same G1 keys as proof of possession
+            Self::Bls12381 => matches!(curve_type, CurveType::BLS | CurveType::BLS12381G1),
+            _ => self.curve_type() == curve_type,
+        }
     }
 
     pub fn preferred_format(&self) -> KeyFormatPreference {
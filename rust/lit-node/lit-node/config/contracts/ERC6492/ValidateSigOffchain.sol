@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MIT
+// Reference implementation from https://eips.ethereum.org/EIPS/eip-6492
+
+pragma solidity ^0.8.0;
+
+interface IERC1271Wallet {
+    function isValidSignature(bytes32 hash, bytes calldata signature) external view returns (bytes4 magicValue);
+}
+
+/**
+ * @dev Validates EOA, ERC-1271 and ERC-6492 signatures without being deployed: the creation
+ * code and constructor arguments are sent as the data of an `eth_call` without a `to`, and the
+ * call returns a single byte, 0x01 for a valid signature and 0x00 otherwise.
+ *
+ * ValidateSigOffchain.hex is the creation code of this contract.
+ */
+contract ValidateSigOffchain {
+    bytes32 private constant ERC6492_DETECTION_SUFFIX =
+        0x6492649264926492649264926492649264926492649264926492649264926492;
+    bytes4 private constant ERC1271_SUCCESS = 0x1626ba7e;
+
+    constructor(address _signer, bytes32 _hash, bytes memory _signature) {
+        bool isValidSig = isValidSigImpl(_signer, _hash, _signature);
+        assembly {
+            mstore(0, isValidSig)
+            return(31, 1)
+        }
+    }
+
+    function isValidSigImpl(address _signer, bytes32 _hash, bytes memory _signature) internal returns (bool) {
+        bytes memory contractCode = address(_signer).code;
+        // the signature of an account that may not be deployed yet, wrapped with its deployment
+        if (trailingBytes32(_signature) == ERC6492_DETECTION_SUFFIX) {
+            (address create2Factory, bytes memory factoryCalldata, bytes memory sigToValidate) =
+                abi.decode(_signature, (address, bytes, bytes));
+            if (contractCode.length == 0) {
+                (bool success, ) = create2Factory.call(factoryCalldata);
+                require(success, "SignatureValidator: deployment");
+            }
+            return IERC1271Wallet(_signer).isValidSignature(_hash, sigToValidate) == ERC1271_SUCCESS;
+        }
+
+        if (contractCode.length > 0) {
+            return IERC1271Wallet(_signer).isValidSignature(_hash, _signature) == ERC1271_SUCCESS;
+        }
+
+        require(_signature.length == 65, "SignatureValidator#recoverSigner: invalid signature length");
+        bytes32 r;
+        bytes32 s;
+        assembly {
+            r := mload(add(_signature, 0x20))
+            s := mload(add(_signature, 0x40))
+        }
+        uint8 v = uint8(_signature[64]);
+        require(v == 27 || v == 28, "SignatureValidator#recoverSigner: invalid signature v value");
+        return ecrecover(_hash, v, r, s) == _signer;
+    }
+
+    function trailingBytes32(bytes memory data) internal pure returns (bytes32 ret) {
+        require(data.length >= 32);
+        assembly {
+            ret := mload(add(data, mload(data)))
+        }
+    }
+}
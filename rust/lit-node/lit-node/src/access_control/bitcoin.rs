@@ -0,0 +1,372 @@
+use super::evaluator::{ConditionContext, ConditionEvaluator, any_auth_sig};
+use super::{compare_return_value, explain, substitute_special_params};
+use crate::error::{EC, Result, blockchain_err_code, unexpected_err_code, validation_err_code};
+use lit_node_core::constants::{CHAIN_BITCOIN, CHAIN_BITCOIN_TESTNET};
+use lit_node_core::{BitcoinCondition, JsonAuthSig, MultipleAuthSigs};
+use serde::Deserialize;
+use tracing::{Instrument, debug_span, warn};
+
+const VALID_CHAIN_NAMES: [&str; 2] = [CHAIN_BITCOIN, CHAIN_BITCOIN_TESTNET];
+
+const METHOD_GET_BALANCE: &str = "getBalance";
+const METHOD_GET_TX_COUNT: &str = "getTxCount";
+const METHOD_GET_BLOCK_HEIGHT: &str = "getBlockHeight";
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_ALPHABET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+#[derive(Debug, Deserialize)]
+struct EsploraAddress {
+    chain_stats: EsploraAddressStats,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraAddressStats {
+    funded_txo_sum: u64,
+    spent_txo_sum: u64,
+    tx_count: u64,
+}
+
+fn rpc_url<C>(chain: C) -> Result<String>
+where
+    C: AsRef<str>,
+{
+    if !VALID_CHAIN_NAMES.contains(&chain.as_ref()) {
+        return Err(blockchain_err_code(
+            format!("invalid chain for bitcoin: {}", chain.as_ref()),
+            EC::NodeBlockchainChainUnknown,
+            None,
+        ));
+    }
+    super::rpc_url(chain.as_ref())
+}
+
+#[async_trait::async_trait]
+impl ConditionEvaluator for BitcoinCondition {
+    // There is no bitcoin auth sig, so bitcoin conditions can't bind to the requester's
+    // wallet and any provided auth sig identifies the requester.
+    fn auth_sig(&self, auth_sigs: &MultipleAuthSigs) -> Result<JsonAuthSig> {
+        any_auth_sig(auth_sigs)
+    }
+
+    async fn check(&self, auth_sig: &JsonAuthSig, ctx: &ConditionContext<'_>) -> Result<bool> {
+        let base_url = rpc_url(&self.chain)?;
+        check_condition(
+            self,
+            &base_url,
+            auth_sig,
+            ctx.bls_root_pubkey,
+            ctx.current_action_ipfs_id,
+            ctx.http_client.clone(),
+        )
+        .await
+    }
+}
+
+pub async fn check_condition(
+    condition: &BitcoinCondition,
+    base_url: &str,
+    auth_sig: &JsonAuthSig,
+    bls_root_pubkey: &str,
+    current_action_ipfs_id: Option<&String>,
+    http_client: reqwest::Client,
+) -> Result<bool> {
+    let base_url = base_url.trim_end_matches('/');
+    let returned_value = match condition.method.as_str() {
+        METHOD_GET_BALANCE | METHOD_GET_TX_COUNT => {
+            let address =
+                get_address_param(condition, auth_sig, bls_root_pubkey, current_action_ipfs_id)
+                    .await?;
+            let url = format!("{}/address/{}", base_url, address);
+            explain::record_rpc_call(condition, || format!("GET {}", url));
+            let body = fetch(&http_client, &url).await?;
+            let stats = serde_json::from_str::<EsploraAddress>(&body)
+                .map_err(|e| {
+                    unexpected_err_code(
+                        e,
+                        EC::NodeBitcoinResponseError,
+                        Some("Could not parse bitcoin address response".into()),
+                    )
+                })?
+                .chain_stats;
+
+            if condition.method == METHOD_GET_BALANCE {
+                stats.funded_txo_sum.saturating_sub(stats.spent_txo_sum)
+            } else {
+                stats.tx_count
+            }
+        }
+        METHOD_GET_BLOCK_HEIGHT => {
+            let url = format!("{}/blocks/tip/height", base_url);
+            explain::record_rpc_call(condition, || format!("GET {}", url));
+            let body = fetch(&http_client, &url).await?;
+            body.trim().parse::<u64>().map_err(|e| {
+                unexpected_err_code(
+                    e,
+                    EC::NodeBitcoinResponseError,
+                    Some("Could not parse bitcoin block height".into()),
+                )
+            })?
+        }
+        method => {
+            return Err(validation_err_code(
+                format!("unsupported bitcoin method: {}", method),
+                EC::NodeBitcoinInvalidCondition,
+                None,
+            ));
+        }
+    };
+
+    check_return_value_uint(condition, returned_value)
+}
+
+async fn get_address_param(
+    condition: &BitcoinCondition,
+    auth_sig: &JsonAuthSig,
+    bls_root_pubkey: &str,
+    current_action_ipfs_id: Option<&String>,
+) -> Result<String> {
+    let param = match condition.parameters.first() {
+        Some(param) => param,
+        None => {
+            return Err(validation_err_code(
+                format!("{} requires an address parameter", condition.method),
+                EC::NodeBitcoinInvalidCondition,
+                None,
+            ));
+        }
+    };
+
+    // :userAddress would resolve to the address of a non-bitcoin auth sig
+    if param == ":userAddress" {
+        return Err(validation_err_code(
+            ":userAddress is not supported for bitcoin conditions",
+            EC::NodeBitcoinInvalidCondition,
+            None,
+        ));
+    }
+
+    let address =
+        substitute_special_params(param, auth_sig, bls_root_pubkey, current_action_ipfs_id).await?;
+    // the address becomes part of the Esplora URL path
+    if !is_bitcoin_address(&address) {
+        return Err(validation_err_code(
+            format!("invalid bitcoin address: {}", address),
+            EC::NodeBitcoinInvalidCondition,
+            None,
+        ));
+    }
+    Ok(address)
+}
+
+// Checks the shape of base58 (P2PKH / P2SH) and bech32 (segwit) addresses.  Esplora does the
+// checksum, this only keeps anything but an address out of the URL.
+fn is_bitcoin_address(address: &str) -> bool {
+    let lowercase = address.to_lowercase();
+    if let Some((hrp, data)) = lowercase.split_once('1') {
+        if matches!(hrp, "bc" | "tb" | "bcrt") {
+            return (address == lowercase || address == address.to_uppercase())
+                && (14..=74).contains(&address.len())
+                && data.len() >= 6
+                && data.chars().all(|c| BECH32_ALPHABET.contains(c));
+        }
+    }
+    (26..=35).contains(&address.len())
+        && matches!(address.chars().next(), Some('1' | '3' | 'm' | 'n' | '2'))
+        && address.chars().all(|c| BASE58_ALPHABET.contains(c))
+}
+
+async fn fetch(http_client: &reqwest::Client, url: &str) -> Result<String> {
+    debug!("hitting bitcoin url: {}", url);
+    let resp = http_client
+        .get(url)
+        .send()
+        .instrument(debug_span!("fetch_bitcoin"))
+        .await
+        .map_err(|e| {
+            unexpected_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
+        })?;
+
+    if !resp.status().is_success() {
+        return Err(unexpected_err_code(
+            format!("bitcoin RPC returned status {}", resp.status()),
+            EC::NodeRpcError,
+            None,
+        ));
+    }
+
+    resp.text()
+        .await
+        .map_err(|e| unexpected_err_code(e, EC::NodeBitcoinResponseError, None))
+}
+
+fn check_return_value_uint(condition: &BitcoinCondition, returned_value: u64) -> Result<bool> {
+    debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
+
+    let valid_return_value: u64 = condition.return_value_test.value.parse().map_err(|e| {
+        validation_err_code(
+            e,
+            EC::NodeInvalidACCReturnValueTest,
+            Some("failed to parse valid return value from access control condition".into()),
+        )
+        .add_msg_to_details()
+    })?;
+
+    trace!(
+        "Testing: Is {:?} {:?} {:?}",
+        returned_value, condition.return_value_test.comparator, valid_return_value
+    );
+
+    Ok(compare_return_value(
+        &condition.return_value_test.comparator,
+        returned_value,
+        valid_return_value,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_condition, is_bitcoin_address};
+    use crate::utils::web::default_http_client;
+    use lit_node_core::{
+        BitcoinCondition, JsonAuthSig, JsonReturnValueTestV2, constants::CHAIN_BITCOIN,
+    };
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ADDRESS: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    fn get_auth_sig() -> JsonAuthSig {
+        JsonAuthSig::new(
+            "0x".to_string(),
+            "web3.eth.personal.sign".to_string(),
+            "".to_string(),
+            "0x0000000000000000000000000000000000000000".to_string(),
+            None,
+        )
+    }
+
+    fn condition(
+        method: &str,
+        parameters: Vec<String>,
+        comparator: &str,
+        value: &str,
+    ) -> BitcoinCondition {
+        BitcoinCondition {
+            chain: CHAIN_BITCOIN.to_string(),
+            method: method.to_string(),
+            parameters,
+            return_value_test: JsonReturnValueTestV2 {
+                key: "".to_string(),
+                comparator: comparator.to_string(),
+                value: value.to_string(),
+            },
+        }
+    }
+
+    async fn mock_esplora() -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/address/{}", ADDRESS)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "address": ADDRESS,
+                "chain_stats": {
+                    "funded_txo_count": 3,
+                    "funded_txo_sum": 150000,
+                    "spent_txo_count": 1,
+                    "spent_txo_sum": 50000,
+                    "tx_count": 4
+                },
+                "mempool_stats": {
+                    "funded_txo_count": 0,
+                    "funded_txo_sum": 0,
+                    "spent_txo_count": 0,
+                    "spent_txo_sum": 0,
+                    "tx_count": 0
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/blocks/tip/height"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("850000"))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    async fn check(condition: &BitcoinCondition, base_url: &str) -> crate::error::Result<bool> {
+        check_condition(
+            condition,
+            base_url,
+            &get_auth_sig(),
+            "",
+            None,
+            default_http_client(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_check_condition_balance() {
+        let mock_server = mock_esplora().await;
+
+        let pass = condition("getBalance", vec![ADDRESS.to_string()], ">=", "100000");
+        assert!(check(&pass, &mock_server.uri()).await.unwrap());
+
+        let fail = condition("getBalance", vec![ADDRESS.to_string()], ">", "100000");
+        assert!(!check(&fail, &mock_server.uri()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_condition_tx_count() {
+        let mock_server = mock_esplora().await;
+
+        let tx_count = condition("getTxCount", vec![ADDRESS.to_string()], "=", "4");
+        assert!(check(&tx_count, &mock_server.uri()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_condition_block_height() {
+        let mock_server = mock_esplora().await;
+
+        let height = condition("getBlockHeight", vec![], ">", "840000");
+        assert!(check(&height, &mock_server.uri()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_condition_rejects_user_address() {
+        let mock_server = mock_esplora().await;
+
+        let balance = condition("getBalance", vec![":userAddress".to_string()], ">", "0");
+        assert!(check(&balance, &mock_server.uri()).await.is_err());
+
+        let unknown = condition("getUtxoSet", vec![ADDRESS.to_string()], ">", "0");
+        assert!(check(&unknown, &mock_server.uri()).await.is_err());
+
+        let injected = condition("getBalance", vec![format!("{}/txs?x=", ADDRESS)], ">", "0");
+        assert!(check(&injected, &mock_server.uri()).await.is_err());
+    }
+
+    #[test]
+    fn test_is_bitcoin_address() {
+        assert!(is_bitcoin_address(ADDRESS));
+        assert!(is_bitcoin_address(&ADDRESS.to_uppercase()));
+        assert!(is_bitcoin_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2"));
+        assert!(is_bitcoin_address("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"));
+        assert!(is_bitcoin_address(
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+        ));
+
+        assert!(!is_bitcoin_address("../blocks/tip/height"));
+        assert!(!is_bitcoin_address(
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2?a=b"
+        ));
+        assert!(!is_bitcoin_address(
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdQ"
+        ));
+        assert!(!is_bitcoin_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNV0l"));
+    }
+}
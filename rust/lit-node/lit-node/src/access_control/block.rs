@@ -0,0 +1,365 @@
+//! Pinning EVM condition evaluation to a block.
+//!
+//! A condition may carry a `blockNumber` or a `blockTag` such as `finalized`, and a
+//! request may carry default `blockNumbers` by chain for conditions that don't pin one.
+//! Pinned block numbers must be recent enough that a non-archive node still has their
+//! state, otherwise every node would fail the call in its own way. Tags are resolved by
+//! the chain's own RPC, so they aren't held to that limit; `finalized` on an L2 can be
+//! far behind its head.
+//!
+//! Pins only affect evaluation. They are stripped before the conditions are hashed
+//! into a decryption identity, so a ciphertext stays decryptable after its pinned
+//! block falls out of the allowed lag.
+
+use super::cache::RpcResultCache;
+use crate::error::{EC, Result, blockchain_err_code, validation_err_code};
+use lit_node_core::{
+    ControlConditionItem, EVMContractCondition, JsonAccessControlCondition,
+    UnifiedAccessControlCondition,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use web3::Transport;
+use web3::types::{BlockId, BlockNumber};
+
+/// How far behind the chain head a pinned block may be. Non-archive nodes keep the
+/// state of the last 128 blocks.
+pub(crate) const MAX_BLOCK_LAG: u64 = 128;
+
+const SUPPORTED_BLOCK_TAGS: [&str; 3] = ["latest", "safe", "finalized"];
+
+/// Resolves a condition's block pin to a block number. `None` means the latest block.
+pub(crate) async fn resolve_block(
+    web3: &web3::Web3<web3::transports::Http>,
+    rpc_cache: &RpcResultCache,
+    chain: &str,
+    block_number: Option<u64>,
+    block_tag: Option<&str>,
+) -> Result<Option<u64>> {
+    match (block_number, block_tag) {
+        (Some(_), Some(_)) => Err(validation_err_code(
+            "Only one of blockNumber and blockTag may be set",
+            EC::NodeAccessControlInvalidBlock,
+            None,
+        )),
+        (None, None) | (None, Some("latest")) => Ok(None),
+        (Some(block), None) => {
+            let head = rpc_cache
+                .latest_block(chain, latest_block_number(web3))
+                .await?;
+            check_block_lag(block, head)?;
+            Ok(Some(block))
+        }
+        (None, Some(tag)) => block_number_for_tag(web3, tag).await.map(Some),
+    }
+}
+
+fn check_block_lag(block: u64, head: u64) -> Result<()> {
+    if block > head {
+        return Err(validation_err_code(
+            format!("Block {block} is ahead of the chain head {head}"),
+            EC::NodeAccessControlInvalidBlock,
+            None,
+        ));
+    }
+    if head - block > MAX_BLOCK_LAG {
+        return Err(validation_err_code(
+            format!(
+                "Block {block} is more than {MAX_BLOCK_LAG} blocks behind the chain head {head}"
+            ),
+            EC::NodeAccessControlBlockTooOld,
+            None,
+        ));
+    }
+    Ok(())
+}
+
+async fn latest_block_number(web3: &web3::Web3<web3::transports::Http>) -> Result<u64> {
+    web3.eth()
+        .block_number()
+        .await
+        .map(|block| block.as_u64())
+        .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, None))
+}
+
+async fn block_number_for_tag(web3: &web3::Web3<web3::transports::Http>, tag: &str) -> Result<u64> {
+    if !SUPPORTED_BLOCK_TAGS.contains(&tag) {
+        return Err(validation_err_code(
+            format!("Unsupported block tag: {tag}"),
+            EC::NodeAccessControlInvalidBlock,
+            None,
+        ));
+    }
+
+    let block = web3
+        .transport()
+        .execute("eth_getBlockByNumber", vec![json!(tag), json!(false)])
+        .await
+        .map_err(|e| {
+            blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
+        })?;
+    parse_block_number(&block)
+}
+
+fn parse_block_number(block: &Value) -> Result<u64> {
+    block
+        .get("number")
+        .and_then(Value::as_str)
+        .and_then(|number| u64::from_str_radix(number.trim_start_matches("0x"), 16).ok())
+        .ok_or_else(|| {
+            blockchain_err_code(
+                format!("Could not get block number from block: {block}"),
+                EC::NodeRpcError,
+                None,
+            )
+        })
+}
+
+/// Converts a resolved block for use in web3 calls.
+pub(crate) fn block_id(block: Option<u64>) -> Option<BlockId> {
+    block.map(|block| BlockId::Number(BlockNumber::Number(block.into())))
+}
+
+/// Conditions that can be pinned to a block.
+pub(crate) trait BlockPin {
+    /// Pins the condition to the block in `blocks` for its chain unless it already pins
+    /// a block or tag.
+    fn set_default_block(&mut self, blocks: &HashMap<String, u64>);
+
+    /// Removes the condition's block number and tag.
+    fn clear_block_pin(&mut self);
+}
+
+impl BlockPin for JsonAccessControlCondition {
+    fn set_default_block(&mut self, blocks: &HashMap<String, u64>) {
+        if self.block_number.is_none() && self.block_tag.is_none() {
+            self.block_number = blocks.get(&self.chain).copied();
+        }
+    }
+
+    fn clear_block_pin(&mut self) {
+        self.block_number = None;
+        self.block_tag = None;
+    }
+}
+
+impl BlockPin for EVMContractCondition {
+    fn set_default_block(&mut self, blocks: &HashMap<String, u64>) {
+        if self.block_number.is_none() && self.block_tag.is_none() {
+            self.block_number = blocks.get(&self.chain).copied();
+        }
+    }
+
+    fn clear_block_pin(&mut self) {
+        self.block_number = None;
+        self.block_tag = None;
+    }
+}
+
+impl BlockPin for UnifiedAccessControlCondition {
+    fn set_default_block(&mut self, blocks: &HashMap<String, u64>) {
+        match self {
+            UnifiedAccessControlCondition::JsonAccessControlCondition(condition) => {
+                condition.set_default_block(blocks)
+            }
+            UnifiedAccessControlCondition::EVMContractCondition(condition) => {
+                condition.set_default_block(blocks)
+            }
+            // only EVM conditions can be pinned
+            _ => {}
+        }
+    }
+
+    fn clear_block_pin(&mut self) {
+        match self {
+            UnifiedAccessControlCondition::JsonAccessControlCondition(condition) => {
+                condition.clear_block_pin()
+            }
+            UnifiedAccessControlCondition::EVMContractCondition(condition) => {
+                condition.clear_block_pin()
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T: BlockPin> BlockPin for ControlConditionItem<T> {
+    fn set_default_block(&mut self, blocks: &HashMap<String, u64>) {
+        match self {
+            ControlConditionItem::Condition(condition) => condition.set_default_block(blocks),
+            ControlConditionItem::Group(group) => group.set_default_block(blocks),
+            ControlConditionItem::Operator(_) => {}
+        }
+    }
+
+    fn clear_block_pin(&mut self) {
+        match self {
+            ControlConditionItem::Condition(condition) => condition.clear_block_pin(),
+            ControlConditionItem::Group(group) => group.clear_block_pin(),
+            ControlConditionItem::Operator(_) => {}
+        }
+    }
+}
+
+impl<T: BlockPin> BlockPin for Vec<T> {
+    fn set_default_block(&mut self, blocks: &HashMap<String, u64>) {
+        self.iter_mut()
+            .for_each(|item| item.set_default_block(blocks));
+    }
+
+    fn clear_block_pin(&mut self) {
+        self.iter_mut().for_each(|item| item.clear_block_pin());
+    }
+}
+
+/// Returns `conditions` with the block for each chain in `blocks` applied to every EVM
+/// condition on that chain that doesn't pin its own.
+pub(crate) fn with_default_blocks<T>(
+    conditions: &Option<T>,
+    blocks: &HashMap<String, u64>,
+) -> Option<T>
+where
+    T: BlockPin + Clone,
+{
+    let mut conditions = conditions.clone();
+    if let Some(conditions) = conditions.as_mut() {
+        conditions.set_default_block(blocks);
+    }
+    conditions
+}
+
+/// Returns `conditions` with every block pin removed, as they are hashed into an identity.
+pub(crate) fn without_block_pins<T>(conditions: &T) -> T
+where
+    T: BlockPin + Clone,
+{
+    let mut conditions = conditions.clone();
+    conditions.clear_block_pin();
+    conditions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockPin, MAX_BLOCK_LAG, check_block_lag, parse_block_number, without_block_pins};
+    use crate::error::EC;
+    use lit_node_core::{
+        AccessControlConditionItem, ControlConditionItem, JsonAccessControlCondition,
+        JsonReturnValueTest,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn condition(chain: &str, block_tag: Option<&str>) -> JsonAccessControlCondition {
+        JsonAccessControlCondition {
+            contract_address: "".to_string(),
+            chain: chain.to_string(),
+            standard_contract_type: "".to_string(),
+            method: "eth_getBalance".to_string(),
+            parameters: vec![":userAddress".to_string()],
+            return_value_test: JsonReturnValueTest {
+                comparator: ">=".to_string(),
+                value: "0".to_string(),
+            },
+            block_number: None,
+            block_tag: block_tag.map(str::to_string),
+        }
+    }
+
+    fn block_number(item: &AccessControlConditionItem) -> Option<u64> {
+        match item {
+            ControlConditionItem::Condition(condition) => condition.block_number,
+            _ => panic!("expected a condition"),
+        }
+    }
+
+    #[test]
+    fn test_default_block_only_applies_to_unpinned_conditions() {
+        let mut conditions = vec![
+            ControlConditionItem::Condition(condition("ethereum", None)),
+            ControlConditionItem::Group(vec![ControlConditionItem::Condition(condition(
+                "ethereum", None,
+            ))]),
+            ControlConditionItem::Condition(condition("ethereum", Some("finalized"))),
+        ];
+        conditions.set_default_block(&HashMap::from([("ethereum".to_string(), 100)]));
+
+        assert_eq!(block_number(&conditions[0]), Some(100));
+        match &conditions[1] {
+            ControlConditionItem::Group(group) => assert_eq!(block_number(&group[0]), Some(100)),
+            _ => panic!("expected a group"),
+        }
+        assert_eq!(block_number(&conditions[2]), None);
+    }
+
+    #[test]
+    fn test_default_block_is_per_chain() {
+        let mut conditions = vec![
+            ControlConditionItem::Condition(condition("ethereum", None)),
+            ControlConditionItem::Condition(condition("base", None)),
+            ControlConditionItem::Condition(condition("polygon", None)),
+        ];
+        conditions.set_default_block(&HashMap::from([
+            ("ethereum".to_string(), 100),
+            ("base".to_string(), 20_000),
+        ]));
+
+        assert_eq!(block_number(&conditions[0]), Some(100));
+        assert_eq!(block_number(&conditions[1]), Some(20_000));
+        assert_eq!(block_number(&conditions[2]), None);
+    }
+
+    #[test]
+    fn test_without_block_pins() {
+        let mut pinned = condition("ethereum", None);
+        pinned.block_number = Some(100);
+        let conditions = vec![
+            ControlConditionItem::Condition(pinned),
+            ControlConditionItem::Group(vec![ControlConditionItem::Condition(condition(
+                "ethereum",
+                Some("finalized"),
+            ))]),
+        ];
+
+        let unpinned = without_block_pins(&conditions);
+        assert_eq!(block_number(&unpinned[0]), None);
+        match &unpinned[1] {
+            ControlConditionItem::Group(group) => match &group[0] {
+                ControlConditionItem::Condition(condition) => {
+                    assert_eq!(condition.block_tag, None)
+                }
+                _ => panic!("expected a condition"),
+            },
+            _ => panic!("expected a group"),
+        }
+        assert_eq!(
+            serde_json::to_string(&unpinned).unwrap(),
+            serde_json::to_string(&vec![
+                ControlConditionItem::Condition(condition("ethereum", None)),
+                ControlConditionItem::Group(vec![ControlConditionItem::Condition(condition(
+                    "ethereum", None,
+                ))]),
+            ])
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_block_lag() {
+        assert!(check_block_lag(100, 100).is_ok());
+        assert!(check_block_lag(100, 100 + MAX_BLOCK_LAG).is_ok());
+
+        let err = check_block_lag(100, 101 + MAX_BLOCK_LAG).unwrap_err();
+        assert!(err.is_code(EC::NodeAccessControlBlockTooOld, true));
+        let err = check_block_lag(101, 100).unwrap_err();
+        assert!(err.is_code(EC::NodeAccessControlInvalidBlock, true));
+    }
+
+    #[test]
+    fn test_parse_block_number() {
+        assert_eq!(
+            parse_block_number(&json!({ "number": "0x1b4" })).unwrap(),
+            436
+        );
+        assert!(parse_block_number(&json!(null)).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Concurrent, short-circuiting evaluation of condition groups.
+//!
+//! Every operand of a group is started at once and operators are applied left to
+//! right as operands resolve, so an OR finishes on its first satisfied operand and
+//! an AND on its first unsatisfied one. Operands that are no longer needed are
+//! dropped, which cancels their outstanding RPC calls. As with sequential evaluation,
+//! an operand that fails fails its group, even if another operand of an OR would have
+//! satisfied it. Leaf conditions run under the request's [`ConditionLimiter`] so a
+//! wide group can't fan out without bound.
+
+use crate::error::{EC, Result, unexpected_err, validation_err_code};
+use crate::models::UnifiedConditionCheckResult;
+use futures::FutureExt;
+use futures::future::{BoxFuture, Either, select};
+use lit_core::config::LitConfig;
+use lit_core::error::Unexpected;
+use lit_node_common::config::{CFG_KEY_ACC_CONCURRENCY_LIMIT_DEFAULT, LitNodeConfig};
+use lit_node_core::AccessControlBooleanOperator;
+use std::future::Future;
+use tokio::sync::Semaphore;
+
+pub(crate) type OperandFuture<'a, T> = BoxFuture<'a, Result<T>>;
+
+/// The result of evaluating a condition or a group of conditions.
+pub(crate) trait ConditionOutcome {
+    fn is_satisfied(&self) -> bool;
+}
+
+impl ConditionOutcome for bool {
+    fn is_satisfied(&self) -> bool {
+        *self
+    }
+}
+
+impl ConditionOutcome for UnifiedConditionCheckResult {
+    fn is_satisfied(&self) -> bool {
+        self.result
+    }
+}
+
+/// Caps the number of leaf conditions of a single request that are evaluated at once.
+pub(crate) struct ConditionLimiter(Semaphore);
+
+impl ConditionLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self(Semaphore::new(limit.clamp(1, Semaphore::MAX_PERMITS)))
+    }
+
+    pub fn from_config(cfg: &LitConfig) -> Self {
+        let limit = cfg
+            .acc_concurrency_limit()
+            .unwrap_or(CFG_KEY_ACC_CONCURRENCY_LIMIT_DEFAULT);
+        Self::new(usize::try_from(limit).unwrap_or(1))
+    }
+
+    /// Evaluates a leaf condition once a permit is available.
+    pub async fn run<T, F>(&self, condition_check: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let _permit = self
+            .0
+            .acquire()
+            .await
+            .map_err(|e| unexpected_err(e, Some("Condition limiter closed".into())))?;
+        condition_check.await
+    }
+}
+
+/// Evaluates `operands[0] operators[0] operands[1] ...` left to right.
+pub(crate) async fn evaluate<'a, T>(
+    operands: Vec<OperandFuture<'a, T>>,
+    operators: Vec<AccessControlBooleanOperator>,
+) -> Result<T>
+where
+    T: ConditionOutcome + Send + 'a,
+{
+    if operands.len() != operators.len() + 1 {
+        return Err(validation_err_code(
+            "Invalid number of operators",
+            EC::NodeAccessControlConditionsCheckFailed,
+            None,
+        ));
+    }
+
+    let mut operands = operands.into_iter();
+    let first = operands
+        .next()
+        .expect_or_err("Could not get first operand of condition group")?;
+
+    operators
+        .into_iter()
+        .zip(operands)
+        .fold(first, |lhs, (operator, rhs)| combine(operator, lhs, rhs))
+        .await
+}
+
+fn combine<'a, T>(
+    operator: AccessControlBooleanOperator,
+    lhs: OperandFuture<'a, T>,
+    rhs: OperandFuture<'a, T>,
+) -> OperandFuture<'a, T>
+where
+    T: ConditionOutcome + Send + 'a,
+{
+    async move {
+        let (first, remaining) = match select(lhs, rhs).await {
+            Either::Left((first, remaining)) | Either::Right((first, remaining)) => {
+                (first, remaining)
+            }
+        };
+
+        let first = first?;
+        match operator {
+            AccessControlBooleanOperator::And if !first.is_satisfied() => Ok(first),
+            AccessControlBooleanOperator::Or if first.is_satisfied() => Ok(first),
+            _ => remaining.await,
+        }
+    }
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConditionLimiter, OperandFuture, evaluate};
+    use crate::error::{Result, unexpected_err};
+    use futures::FutureExt;
+    use lit_node_core::AccessControlBooleanOperator::{And, Or};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn ready(result: bool) -> OperandFuture<'static, bool> {
+        futures::future::ok(result).boxed()
+    }
+
+    fn never() -> OperandFuture<'static, bool> {
+        futures::future::pending().boxed()
+    }
+
+    fn failed() -> OperandFuture<'static, bool> {
+        futures::future::err(unexpected_err("rpc failed", None)).boxed()
+    }
+
+    async fn eval(
+        operands: Vec<OperandFuture<'static, bool>>,
+        operators: Vec<lit_node_core::AccessControlBooleanOperator>,
+    ) -> Result<bool> {
+        tokio::time::timeout(Duration::from_secs(1), evaluate(operands, operators))
+            .await
+            .expect("condition group did not short circuit")
+    }
+
+    #[tokio::test]
+    async fn test_or_short_circuits_on_first_true() {
+        let result = eval(vec![never(), ready(true), never()], vec![Or, Or]).await;
+        assert!(result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_and_short_circuits_on_first_false() {
+        let result = eval(vec![never(), never(), ready(false)], vec![And, And]).await;
+        assert!(!result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_operators_apply_left_to_right() {
+        // (true or false) and false
+        let result = eval(vec![ready(true), ready(false), ready(false)], vec![Or, And]).await;
+        assert!(!result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        assert!(eval(vec![failed(), ready(false)], vec![Or]).await.is_err());
+        assert!(eval(vec![failed(), never()], vec![Or]).await.is_err());
+        assert!(eval(vec![failed(), never()], vec![And]).await.is_err());
+        assert!(eval(vec![ready(true), failed()], vec![And]).await.is_err());
+        assert!(eval(vec![ready(true)], vec![And]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_error_or_true_fails() {
+        assert!(eval(vec![failed(), ready(true)], vec![Or]).await.is_err());
+        assert!(
+            eval(vec![ready(false), failed(), ready(true)], vec![Or, Or])
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limiter_caps_concurrent_conditions() {
+        let limiter = ConditionLimiter::new(2);
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        let operands = (0..6)
+            .map(|_| {
+                limiter
+                    .run(async {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_running.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(false)
+                    })
+                    .boxed()
+            })
+            .collect();
+
+        let result = evaluate(operands, vec![Or; 5]).await;
+        assert!(!result.unwrap());
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    }
+}
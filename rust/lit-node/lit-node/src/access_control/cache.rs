@@ -0,0 +1,212 @@
+//! Per-block cache of access control RPC results.
+//!
+//! Results are keyed by chain, block and request, so once a chain produces a new
+//! block lookups miss and fetch fresh data, while stale entries age out on their
+//! own. The latest block of each chain is cached for [`HEAD_TTL`] so a burst of
+//! requests shares a single head lookup. If the head can't be fetched the request
+//! goes straight to the chain uncached. Requests pinned to a block skip the head
+//! lookup and are cached under that block.
+//!
+//! The node keeps one [`RpcResultCache`] in Rocket's managed state and hands it to
+//! every condition check.
+
+use crate::error::Result;
+use crate::metrics;
+use crate::metrics::access_control::{ATTRIBUTE_CHAIN, AccessControlCacheMetrics};
+use lit_observability::opentelemetry::KeyValue;
+use moka::future::Cache;
+use serde_json::Value;
+use std::future::Future;
+use std::result::Result as StdResult;
+use std::time::Duration;
+use tracing::warn;
+
+const HEAD_TTL: Duration = Duration::from_secs(1);
+const RESULT_TTL: Duration = Duration::from_secs(120);
+const RESULT_CAPACITY: u64 = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct RpcResultCache {
+    heads: Cache<String, u64>,
+    results: Cache<(String, u64, String), Value>,
+}
+
+impl Default for RpcResultCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RpcResultCache {
+    pub fn new() -> Self {
+        Self {
+            heads: Cache::builder().time_to_live(HEAD_TTL).build(),
+            results: Cache::builder()
+                .max_capacity(RESULT_CAPACITY)
+                .time_to_live(RESULT_TTL)
+                .build(),
+        }
+    }
+
+    /// Returns the cached result of `request` at the chain's latest block, calling `fetch`
+    /// on a miss. `fetch` receives the block the result will be cached under.
+    pub(crate) async fn get_or_fetch<H, F, Fut, E>(
+        &self,
+        chain: &str,
+        latest_block: H,
+        request: String,
+        fetch: F,
+    ) -> StdResult<Value, E>
+    where
+        H: Future<Output = Result<u64>>,
+        F: FnOnce(Option<u64>) -> Fut,
+        Fut: Future<Output = StdResult<Value, E>>,
+    {
+        match self.latest_block(chain, latest_block).await {
+            Ok(block) => {
+                self.get_or_fetch_at(chain, block, request, || fetch(Some(block)))
+                    .await
+            }
+            Err(e) => {
+                warn!("Not caching RPC result, couldn't get latest block of {chain}: {e:?}");
+                fetch(None).await
+            }
+        }
+    }
+
+    /// Returns the chain's latest block, sharing one lookup between requests for [`HEAD_TTL`].
+    pub(crate) async fn latest_block<H>(&self, chain: &str, latest_block: H) -> Result<u64>
+    where
+        H: Future<Output = Result<u64>>,
+    {
+        if let Some(block) = self.heads.get(chain).await {
+            return Ok(block);
+        }
+        let block = latest_block.await?;
+        self.heads.insert(chain.to_string(), block).await;
+        Ok(block)
+    }
+
+    /// Returns the cached result of `request` at `block`, calling `fetch` on a miss.
+    pub(crate) async fn get_or_fetch_at<F, Fut, E>(
+        &self,
+        chain: &str,
+        block: u64,
+        request: String,
+        fetch: F,
+    ) -> StdResult<Value, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = StdResult<Value, E>>,
+    {
+        let key = (chain.to_string(), block, request);
+        let attributes = [KeyValue::new(ATTRIBUTE_CHAIN, chain.to_string())];
+        if let Some(result) = self.results.get(&key).await {
+            metrics::counter::add_one(AccessControlCacheMetrics::Hit, &attributes);
+            return Ok(result);
+        }
+        metrics::counter::add_one(AccessControlCacheMetrics::Miss, &attributes);
+
+        let result = fetch().await?;
+        self.results.insert(key, result.clone()).await;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RpcResultCache;
+    use serde_json::{Value, json};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn fetch(
+        cache: &RpcResultCache,
+        chain: &str,
+        request: &str,
+        calls: &AtomicUsize,
+    ) -> Result<Value, ()> {
+        cache
+            .get_or_fetch(
+                chain,
+                async { Ok(100) },
+                request.to_string(),
+                |block| async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(json!(block))
+                },
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_results_are_cached_per_request_and_chain() {
+        let cache = RpcResultCache::new();
+        let calls = AtomicUsize::new(0);
+
+        assert_eq!(
+            fetch(&cache, "ethereum", "balanceOf", &calls).await,
+            Ok(json!(100))
+        );
+        assert_eq!(
+            fetch(&cache, "ethereum", "balanceOf", &calls).await,
+            Ok(json!(100))
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        fetch(&cache, "ethereum", "ownerOf", &calls).await.unwrap();
+        fetch(&cache, "polygon", "balanceOf", &calls).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_new_block_invalidates_results() {
+        let cache = RpcResultCache::new();
+        let calls = AtomicUsize::new(0);
+
+        fetch(&cache, "ethereum", "balanceOf", &calls)
+            .await
+            .unwrap();
+        cache.heads.insert("ethereum".to_string(), 101).await;
+
+        assert_eq!(
+            fetch(&cache, "ethereum", "balanceOf", &calls).await,
+            Ok(json!(101))
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let cache = RpcResultCache::new();
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let result: Result<Value, ()> = cache
+                .get_or_fetch("ethereum", async { Ok(100) }, "call".to_string(), |_| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    async { Err(()) }
+                })
+                .await;
+            assert!(result.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_block_skips_head_lookup() {
+        let cache = RpcResultCache::new();
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let result: Result<Value, ()> = cache
+                .get_or_fetch_at("ethereum", 42, "call".to_string(), || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    async { Ok(json!(42)) }
+                })
+                .await;
+            assert_eq!(result, Ok(json!(42)));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache.heads.get("ethereum").await.is_none());
+    }
+}
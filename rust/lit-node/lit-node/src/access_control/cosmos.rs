@@ -1,9 +1,15 @@
-use super::{substitute_special_params, validate_boolean_expression};
+use super::boolean::{self, ConditionLimiter, OperandFuture};
+use super::cache::RpcResultCache;
+use super::{
+    compare_return_value, explain, substitute_special_params, validate_boolean_expression,
+};
 use crate::auth::validators::cosmos::validate_cosmos_auth_sig;
 use crate::error::{
     EC, Result, blockchain_err_code, serializer_err_code, unexpected_err_code, validation_err_code,
 };
 use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use lit_core::config::LitConfig;
 use lit_core::error::Unexpected;
 use lit_node_core::{
     AccessControlBooleanOperator, CosmosBlock, CosmosCondition, CosmosConditionItem, JsonAuthSig,
@@ -11,6 +17,9 @@ use lit_node_core::{
 };
 use tracing::{Instrument, debug_span, warn};
 
+// the Cosmos SDK REST gateway answers at this height instead of the latest block
+const COSMOS_BLOCK_HEIGHT_HEADER: &str = "x-cosmos-block-height";
+
 const VALID_CHAIN_NAMES: [&str; 6] = [
     "cosmos",
     "kyve",
@@ -37,10 +46,13 @@ where
 pub async fn check_access_control_conditions(
     conditions: &Vec<CosmosConditionItem>,
     auth_sigs: &MultipleAuthSigs,
+    cfg: &LitConfig,
     bls_root_pubkey: &String,
     current_action_ipfs_id: Option<&String>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
+    let limiter = ConditionLimiter::from_config(cfg);
     // need to loop over all conditions and check auth sig for all of them
     check_condition_group(
         conditions,
@@ -48,81 +60,77 @@ pub async fn check_access_control_conditions(
         bls_root_pubkey,
         current_action_ipfs_id,
         http_client,
+        rpc_cache,
+        &limiter,
     )
     .await
 }
 
-async fn check_condition_group(
-    conditions: &Vec<CosmosConditionItem>,
-    auth_sigs: &MultipleAuthSigs,
-    bls_root_pubkey: &String,
-    current_action_ipfs_id: Option<&String>,
+fn check_condition_group<'a>(
+    conditions: &'a Vec<CosmosConditionItem>,
+    auth_sigs: &'a MultipleAuthSigs,
+    bls_root_pubkey: &'a String,
+    current_action_ipfs_id: Option<&'a String>,
     http_client: reqwest::Client,
-) -> Result<bool> {
-    if !validate_boolean_expression(conditions) {
-        return Err(validation_err_code(
-            "Invalid boolean Cosmos Access Control Conditions",
-            EC::NodeInvalidBooleanConditionType,
-            None,
-        ));
-    }
-
-    let mut results: Vec<bool> = Vec::new();
-    let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
-    for condition_item in conditions {
-        match condition_item {
-            CosmosConditionItem::Condition(condition) => {
-                results.push(
-                    check_condition(
-                        condition,
-                        auth_sigs,
-                        bls_root_pubkey,
-                        current_action_ipfs_id,
-                        http_client.clone(),
-                    )
-                    .await?,
-                );
-            }
-            CosmosConditionItem::Operator(operator) => {
-                operators.push(operator.operator);
-            }
-            CosmosConditionItem::Group(group) => {
-                results.push(
-                    Box::pin(check_condition_group(
-                        group,
-                        auth_sigs,
-                        bls_root_pubkey,
-                        current_action_ipfs_id,
-                        http_client.clone(),
-                    ))
-                    .await?,
-                );
-            }
+    rpc_cache: &'a RpcResultCache,
+    limiter: &'a ConditionLimiter,
+) -> OperandFuture<'a, bool> {
+    Box::pin(async move {
+        if !validate_boolean_expression(conditions) {
+            return Err(validation_err_code(
+                "Invalid boolean Cosmos Access Control Conditions",
+                EC::NodeInvalidBooleanConditionType,
+                None,
+            ));
         }
-    }
 
-    // okay we now have a bunch of results and operators.  We need to apply the operators.
-    // reverse the results so that we can pop off the first 2 results at a time
-    results.reverse();
-
-    // pull 2 results out, apply the operator, and push the result back on the results vector
-    for operator in operators {
-        let res1 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-        let res2 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-
-        match operator {
-            AccessControlBooleanOperator::And => results.push(res1 && res2),
-            AccessControlBooleanOperator::Or => results.push(res1 || res2),
+        let mut operands: Vec<OperandFuture<'a, bool>> = Vec::new();
+        let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
+        for (index, condition_item) in conditions.iter().enumerate() {
+            match condition_item {
+                CosmosConditionItem::Condition(condition) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            limiter
+                                .run(check_condition(
+                                    condition,
+                                    auth_sigs,
+                                    bls_root_pubkey,
+                                    current_action_ipfs_id,
+                                    http_client.clone(),
+                                    rpc_cache,
+                                ))
+                                .inspect(move |result| explain::record_result(condition, result)),
+                        )
+                        .boxed(),
+                    );
+                }
+                CosmosConditionItem::Operator(operator) => {
+                    operators.push(operator.operator);
+                }
+                CosmosConditionItem::Group(group) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            check_condition_group(
+                                group,
+                                auth_sigs,
+                                bls_root_pubkey,
+                                current_action_ipfs_id,
+                                http_client.clone(),
+                                rpc_cache,
+                                limiter,
+                            ),
+                        )
+                        .boxed(),
+                    );
+                }
+            }
         }
-    }
 
-    Ok(*results
-        .last()
-        .expect_or_err("Could not get last result from results vector")?)
+        boolean::evaluate(operands, operators).await
+    })
 }
 
 pub fn get_auth_sig_for_chain_string(
@@ -182,6 +190,7 @@ pub async fn check_condition(
     bls_root_pubkey: &str,
     current_action_ipfs_id: Option<&String>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     // delegate to timelock, if applicable
     if condition.method.is_some()
@@ -211,6 +220,7 @@ pub async fn check_condition(
     }
 
     if condition.path == ":userAddress" {
+        explain::record_returned_value(condition, &auth_sig.address);
         // just check the address against the auth sig address
         return check_return_value_string(
             condition,
@@ -228,38 +238,74 @@ pub async fn check_condition(
     let base_url = rpc_url(&condition.chain)?;
     let url = format!("{}{}", base_url, substituted_path);
     debug!("hitting cosmos url: {}", url);
-    let resp = http_client
-        .get(&url)
-        .send()
-        .instrument(debug_span!("fetch_cosmos"))
-        .await;
+    explain::record_rpc_call(condition, || format!("GET {}", url));
+    let (http_client, url) = (&http_client, &url);
+    let latest_block = get_latest_block_height(http_client, &base_url);
+    let result = rpc_cache
+        .get_or_fetch(
+            &condition.chain,
+            latest_block,
+            url.clone(),
+            |block| async move {
+                let mut request = http_client.get(url);
+                // query at the height the result is cached under
+                if let Some(block) = block {
+                    request = request.header(COSMOS_BLOCK_HEIGHT_HEADER, block.to_string());
+                }
+                let body = request
+                    .send()
+                    .instrument(debug_span!("fetch_cosmos"))
+                    .await
+                    .map_err(|e| {
+                        unexpected_err_code(
+                            e,
+                            EC::NodeRpcError,
+                            Some("Error making RPC Call".into()),
+                        )
+                    })?
+                    .text()
+                    .await
+                    .map_err(|e| unexpected_err_code(e, EC::NodeCosmosResponseBodyError, None))?;
+                debug!("cosmos response body: {}", body);
+
+                serde_json::from_str::<serde_json::Value>(&body)
+                    .map_err(|e| serializer_err_code(e, EC::NodeCosmosJSONError, None))
+            },
+        )
+        .await?;
 
-    match resp {
-        Ok(result) => {
-            let body = result
-                .text()
-                .await
-                .map_err(|e| unexpected_err_code(e, EC::NodeCosmosResponseBodyError, None))?;
-            debug!("cosmos response body: {}", body);
+    check_return_value(
+        condition,
+        &result,
+        &auth_sig,
+        bls_root_pubkey,
+        current_action_ipfs_id,
+    )
+    .await
+}
 
-            let result = serde_json::from_str::<serde_json::Value>(&body)
-                .map_err(|e| serializer_err_code(e, EC::NodeCosmosJSONError, None))?;
+async fn get_latest_block_height(http_client: &reqwest::Client, base_url: &str) -> Result<u64> {
+    let url = format!("{}/cosmos/base/tendermint/v1beta1/blocks/latest", base_url);
+    let body = http_client
+        .get(&url)
+        .send()
+        .instrument(debug_span!("get_latest_block_height"))
+        .await
+        .map_err(|e| unexpected_err_code(e, EC::NodeCosmosBlockHeightRequestError, None))?
+        .text()
+        .await
+        .map_err(|e| unexpected_err_code(e, EC::NodeCosmosResponseBodyError, None))?;
 
-            check_return_value(
-                condition,
-                &result,
-                &auth_sig,
-                bls_root_pubkey,
-                current_action_ipfs_id,
+    serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|block| block["block"]["header"]["height"].as_str()?.parse().ok())
+        .ok_or_else(|| {
+            unexpected_err_code(
+                format!("no block height in response from {}", url),
+                EC::NodeCosmosBlockHeightParseError,
+                None,
             )
-            .await
-        }
-        Err(err) => Err(unexpected_err_code(
-            err,
-            EC::NodeRpcError,
-            Some("Error making RPC Call".into()),
-        )),
-    }
+        })
 }
 
 pub async fn check_condition_timelock(
@@ -348,6 +394,7 @@ pub async fn check_condition_timelock(
     // hit the URL, check the value
     let url = format!("{}{}", base_url, substituted_path);
     debug!("hitting cosmos url: {}", url);
+    explain::record_rpc_call(condition, || format!("GET {}", url));
     let resp = http_client
         .get(&url)
         .send()
@@ -383,6 +430,7 @@ async fn check_return_value(
     current_action_ipfs_id: Option<&String>,
 ) -> Result<bool> {
     debug!("check_return_value of {:?}", returned_value.to_string());
+    explain::record_returned_value(condition, returned_value);
     let mut value_to_check;
 
     if condition.return_value_test.key.is_empty() {
@@ -565,22 +613,11 @@ fn check_return_value_uint(condition: &CosmosCondition, returned_value: u64) ->
         returned_value, condition.return_value_test.comparator, valid_return_value
     );
 
-    if condition.return_value_test.comparator == ">" {
-        Ok(returned_value > valid_return_value)
-    } else if condition.return_value_test.comparator == "<" {
-        Ok(returned_value < valid_return_value)
-    } else if condition.return_value_test.comparator == ">=" {
-        Ok(returned_value >= valid_return_value)
-    } else if condition.return_value_test.comparator == "<=" {
-        Ok(returned_value <= valid_return_value)
-    } else if condition.return_value_test.comparator == "=" {
-        Ok(returned_value == valid_return_value)
-    } else if condition.return_value_test.comparator == "!=" {
-        Ok(returned_value != valid_return_value)
-    } else {
-        warn!("Error - unsupported return value test comparator");
-        Ok(false)
-    }
+    Ok(compare_return_value(
+        &condition.return_value_test.comparator,
+        returned_value,
+        valid_return_value,
+    ))
 }
 
 async fn check_return_value_string(
@@ -704,7 +741,7 @@ pub async fn get_timestamp_from_block_height(
 
 #[cfg(test)]
 mod tests {
-    use super::check_condition;
+    use super::{RpcResultCache, check_condition};
     use crate::utils::web::default_http_client;
     use lit_node_core::{
         CosmosCondition, JsonAuthSig, JsonReturnValueTestV2, MultipleAuthSigs,
@@ -808,6 +845,7 @@ mod tests {
             &"".to_string(),
             None,
             http_client,
+            &RpcResultCache::new(),
         )
         .await;
         assert!(check_balance_condition.is_ok());
@@ -838,6 +876,7 @@ mod tests {
             &"".to_string(),
             None,
             http_client,
+            &RpcResultCache::new(),
         )
         .await;
         assert!(check_balance_condition.is_ok());
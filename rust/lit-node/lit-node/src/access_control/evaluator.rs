@@ -0,0 +1,67 @@
+//! Extension point for chain families that are not built into `unified.rs`.
+//!
+//! EVM, Solana and Cosmos conditions keep their dedicated code paths. Newer chain
+//! families implement [`ConditionEvaluator`] for their condition type and register
+//! it in [`evaluator_for`], so adding one only requires a new
+//! `UnifiedAccessControlCondition` variant and a module in this directory.
+
+use crate::error::{EC, Result, validation_err_code};
+use lit_node_core::{JsonAuthSig, MultipleAuthSigs, UnifiedAccessControlCondition};
+
+/// Request scoped values shared by every condition evaluator.
+pub(crate) struct ConditionContext<'a> {
+    pub auth_sigs: &'a MultipleAuthSigs,
+    pub bls_root_pubkey: &'a str,
+    pub current_action_ipfs_id: Option<&'a String>,
+    pub http_client: reqwest::Client,
+}
+
+#[async_trait::async_trait]
+pub(crate) trait ConditionEvaluator: Send + Sync {
+    /// Selects the auth sig that is reported as the successful one when the condition passes.
+    fn auth_sig(&self, auth_sigs: &MultipleAuthSigs) -> Result<JsonAuthSig>;
+
+    /// Evaluates the condition and returns whether it is satisfied.
+    async fn check(&self, auth_sig: &JsonAuthSig, ctx: &ConditionContext<'_>) -> Result<bool>;
+}
+
+/// Returns the registered evaluator for a condition, or `None` if the condition
+/// is handled directly by `unified.rs`.
+pub(crate) fn evaluator_for(
+    condition: &UnifiedAccessControlCondition,
+) -> Option<&dyn ConditionEvaluator> {
+    match condition {
+        UnifiedAccessControlCondition::BitcoinCondition(c) => Some(c),
+        _ => None,
+    }
+}
+
+/// Picks the first auth sig present, for chain families that do not have an auth sig of their own.
+pub(crate) fn any_auth_sig(auth_sigs: &MultipleAuthSigs) -> Result<JsonAuthSig> {
+    [
+        &auth_sigs.ethereum,
+        &auth_sigs.solana,
+        &auth_sigs.cosmos,
+        &auth_sigs.kyve,
+        &auth_sigs.cheqd,
+        &auth_sigs.juno,
+    ]
+    .into_iter()
+    .find_map(|auth_sig| auth_sig.clone())
+    .ok_or_else(|| {
+        validation_err_code(
+            "No auth sig provided for the condition",
+            EC::NodeInvalidMultipleAuthSigs,
+            None,
+        )
+    })
+}
+
+pub(crate) async fn check_condition(
+    evaluator: &dyn ConditionEvaluator,
+    ctx: &ConditionContext<'_>,
+) -> Result<(bool, JsonAuthSig)> {
+    let auth_sig = evaluator.auth_sig(ctx.auth_sigs)?;
+    let result = evaluator.check(&auth_sig, ctx).await?;
+    Ok((result, auth_sig))
+}
@@ -1,20 +1,24 @@
-use super::{rpc_call, substitute_special_params, validate_boolean_expression};
+use super::boolean::{self, ConditionLimiter, OperandFuture};
+use super::cache::RpcResultCache;
+use super::{
+    block, compare_return_value, explain, get_web3, rpc_call, substitute_special_params,
+    validate_boolean_expression,
+};
 use crate::auth::auth_material::JsonAuthSigExtendedRef;
-use crate::error::{EC, Result, conversion_err_code, validation_err, validation_err_code};
+use crate::error::{
+    EC, Result, blockchain_err_code, conversion_err_code, validation_err, validation_err_code,
+};
 use crate::utils::encoding;
 use ethabi::Token;
 use ethabi::ethereum_types::{H160, U256};
 use ethabi::token::{LenientTokenizer, Tokenizer};
 use futures::FutureExt;
-use futures::future::Either;
 use lit_core::config::LitConfig;
 use lit_core::error::Unexpected;
 use lit_node_core::{
     AccessControlBooleanOperator, EVMContractCondition, EVMContractConditionItem, EndpointVersion,
     JsonAuthSig, LitResourceAbility,
 };
-use std::future::Future;
-use std::pin::Pin;
 use web3::types::{Bytes, CallRequest};
 
 #[allow(clippy::too_many_arguments)]
@@ -27,6 +31,7 @@ pub(crate) async fn check_access_control_conditions(
     bls_root_pubkey: &String,
     endpoint_version: &EndpointVersion,
     current_action_ipfs_id: Option<&String>,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     let validate_res = JsonAuthSigExtendedRef::from(auth_sig)
         .validate_and_get_wallet_sig(
@@ -42,11 +47,14 @@ pub(crate) async fn check_access_control_conditions(
         });
     match validate_res {
         Ok(valid_auth_sig) => {
+            let limiter = ConditionLimiter::from_config(cfg);
             let conditions_met = check_condition_group(
                 conditions,
                 &valid_auth_sig,
                 bls_root_pubkey,
                 current_action_ipfs_id,
+                rpc_cache,
+                &limiter,
             )
             .await?;
             Ok(conditions_met)
@@ -63,7 +71,9 @@ fn check_condition_group<'a>(
     auth_sig: &'a JsonAuthSig,
     bls_root_pubkey: &'a String,
     current_action_ipfs_id: Option<&'a String>,
-) -> Pin<Box<dyn Future<Output = Result<bool>> + Send + 'a>> {
+    rpc_cache: &'a RpcResultCache,
+    limiter: &'a ConditionLimiter,
+) -> OperandFuture<'a, bool> {
     Box::pin(async move {
         if !validate_boolean_expression(conditions) {
             return Err(validation_err_code(
@@ -73,81 +83,50 @@ fn check_condition_group<'a>(
             ));
         }
 
-        let mut results: Vec<_> = Vec::new();
+        let mut operands: Vec<OperandFuture<'a, bool>> = Vec::new();
         let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
-        for condition_item in conditions {
+        for (index, condition_item) in conditions.iter().enumerate() {
             match condition_item {
                 EVMContractConditionItem::Condition(condition) => {
-                    results.push(Either::Left(Either::Left(
-                        check_condition(
-                            condition,
-                            auth_sig,
-                            bls_root_pubkey,
-                            current_action_ipfs_id,
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            limiter
+                                .run(check_condition(
+                                    condition,
+                                    auth_sig,
+                                    bls_root_pubkey,
+                                    current_action_ipfs_id,
+                                    rpc_cache,
+                                ))
+                                .inspect(move |result| explain::record_result(condition, result)),
                         )
                         .boxed(),
-                    )));
+                    );
                 }
                 EVMContractConditionItem::Operator(operator) => {
                     operators.push(operator.operator);
                 }
                 EVMContractConditionItem::Group(group) => {
-                    results.push(Either::Left(Either::Right(check_condition_group(
-                        group,
-                        auth_sig,
-                        bls_root_pubkey,
-                        current_action_ipfs_id,
-                    ))));
-                }
-            }
-        }
-
-        // okay we now have a bunch of results and operators.  We need to apply the operators.
-        // reverse the results so that we can pop off the first 2 results at a time
-        results.reverse();
-
-        // pull 2 results out, apply the operator, and push the result back on the results vector
-        for operator in operators {
-            let res1 = results
-                .pop()
-                .expect_or_err("Could not pop result off of results vector")?;
-            let res2 = results
-                .pop()
-                .expect_or_err("Could not pop result off of results vector")?;
-
-            match operator {
-                AccessControlBooleanOperator::And => results.push(Either::Right(Either::Left(
-                    futures::future::select(res1, res2)
-                        .map(|either| match either {
-                            Either::Left((r1, other_future)) => (r1, other_future),
-                            Either::Right((r2, other_future)) => (r2, other_future),
-                        })
-                        .then(|(r, other_future)| match r {
-                            Err(e) => Either::Left(futures::future::err(e)),
-                            Ok(false) => Either::Right(Either::Left(futures::future::ok(false))),
-                            Ok(true) => Either::Right(Either::Right(other_future)),
-                        })
-                        .boxed(),
-                ))),
-                AccessControlBooleanOperator::Or => results.push(Either::Right(Either::Right(
-                    futures::future::select(res1, res2)
-                        .map(|either| match either {
-                            Either::Left((r1, other_future)) => (r1, other_future),
-                            Either::Right((r2, other_future)) => (r2, other_future),
-                        })
-                        .then(|(r, other_future)| match r {
-                            Ok(v) if v => Either::Left(futures::future::ok(true)),
-                            _ => Either::Right(other_future),
-                        })
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            check_condition_group(
+                                group,
+                                auth_sig,
+                                bls_root_pubkey,
+                                current_action_ipfs_id,
+                                rpc_cache,
+                                limiter,
+                            ),
+                        )
                         .boxed(),
-                ))),
+                    );
+                }
             }
         }
 
-        results
-            .pop()
-            .expect_or_err("Could not get last result from results vector")?
-            .await
+        boolean::evaluate(operands, operators).await
     })
 }
 
@@ -156,6 +135,7 @@ pub async fn check_condition(
     auth_sig: &JsonAuthSig,
     bls_root_pubkey: &str,
     current_action_ipfs_id: Option<&String>,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     let func = condition.function_abi.clone();
 
@@ -255,7 +235,32 @@ pub async fn check_condition(
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
     };
-    let call_result = rpc_call(&call_request, &condition.chain).await?;
+    explain::record_rpc_call(condition, || {
+        format!(
+            "eth_call to {:?} with data 0x{} on {}",
+            call_request.to.unwrap_or_default(),
+            hex::encode(
+                call_request
+                    .data
+                    .as_ref()
+                    .map(|d| d.0.as_slice())
+                    .unwrap_or_default()
+            ),
+            condition.chain
+        )
+    });
+    let web3 = get_web3(&condition.chain).map_err(|e| {
+        blockchain_err_code(e, EC::NodeRpcError, Some("Web3 Error".into())).add_msg_to_details()
+    })?;
+    let block = block::resolve_block(
+        &web3,
+        rpc_cache,
+        &condition.chain,
+        condition.block_number,
+        condition.block_tag.as_deref(),
+    )
+    .await?;
+    let call_result = rpc_call(&call_request, &condition.chain, block, rpc_cache).await?;
 
     let parsed_result: Vec<Token> = func
         .decode_output(&call_result.0)
@@ -332,6 +337,7 @@ pub async fn check_condition(
 #[allow(clippy::bool_comparison)]
 fn check_return_value_bool(condition: &EVMContractCondition, returned_value: bool) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
 
     let valid_return_value = condition.return_value_test.value.parse().map_err(|e| {
         validation_err_code(
@@ -368,6 +374,7 @@ fn check_return_value_string(
     returned_value: String,
 ) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
 
     let valid_return_value = condition.return_value_test.value.clone();
 
@@ -433,6 +440,7 @@ fn check_return_value_string(
 
 fn check_return_value_uint(condition: &EVMContractCondition, returned_value: U256) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
 
     let valid_return_value =
         U256::from_dec_str(&condition.return_value_test.value).map_err(|e| {
@@ -447,22 +455,11 @@ fn check_return_value_uint(condition: &EVMContractCondition, returned_value: U25
         returned_value, condition.return_value_test.comparator, valid_return_value
     );
 
-    if condition.return_value_test.comparator == ">" {
-        Ok(returned_value > valid_return_value)
-    } else if condition.return_value_test.comparator == "<" {
-        return Ok(returned_value < valid_return_value);
-    } else if condition.return_value_test.comparator == ">=" {
-        return Ok(returned_value >= valid_return_value);
-    } else if condition.return_value_test.comparator == "<=" {
-        return Ok(returned_value <= valid_return_value);
-    } else if condition.return_value_test.comparator == "=" {
-        return Ok(returned_value == valid_return_value);
-    } else if condition.return_value_test.comparator == "!=" {
-        return Ok(returned_value != valid_return_value);
-    } else {
-        warn!("Error - unsupported return value test comparator");
-        return Ok(false);
-    }
+    Ok(compare_return_value(
+        &condition.return_value_test.comparator,
+        returned_value,
+        valid_return_value,
+    ))
 }
 
 async fn check_return_value_addr(
@@ -473,6 +470,7 @@ async fn check_return_value_addr(
     current_action_ipfs_id: Option<&String>,
 ) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
     let subbed_param = substitute_special_params(
         &condition.return_value_test.value,
         auth_sig,
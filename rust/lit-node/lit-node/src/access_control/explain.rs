@@ -0,0 +1,201 @@
+//! Per-condition tracing for the access control explain endpoint.
+//!
+//! Evaluation code calls the `record_*` functions unconditionally; they are no-ops
+//! unless the current task is running inside [`with_trace`]. Entries are keyed by
+//! the condition's position in the request, set by [`at_index`] around each condition,
+//! so records made by concurrently evaluated conditions land on the right entry, and
+//! identical conditions get an entry each.
+
+use crate::error::Result;
+use lit_node_core::response::AccessControlConditionTrace;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Mutex;
+
+tokio::task_local! {
+    static TRACE: Mutex<Vec<AccessControlConditionTrace>>;
+    static CONDITION_INDEX: Vec<usize>;
+}
+
+/// Runs `fut` with condition tracing enabled and returns its output along with the trace.
+pub(crate) async fn with_trace<F>(fut: F) -> (F::Output, Vec<AccessControlConditionTrace>)
+where
+    F: Future,
+{
+    TRACE
+        .scope(Mutex::new(Vec::new()), async move {
+            let output = fut.await;
+            let trace = TRACE.with(|trace| match trace.lock() {
+                Ok(mut trace) => std::mem::take(&mut *trace),
+                Err(_) => Vec::new(),
+            });
+            (output, trace)
+        })
+        .await
+}
+
+/// Runs `fut`, the evaluation of the condition or group at `index` in the enclosing group,
+/// so that its records go to the entry for that position.
+pub(crate) async fn at_index<F>(index: usize, fut: F) -> F::Output
+where
+    F: Future,
+{
+    if TRACE.try_with(|_| ()).is_err() {
+        return fut.await;
+    }
+    let mut path = CONDITION_INDEX.try_with(Clone::clone).unwrap_or_default();
+    path.push(index);
+    CONDITION_INDEX.scope(path, fut).await
+}
+
+/// Records an outbound call made while evaluating `condition`.
+pub(crate) fn record_rpc_call<C, F>(condition: &C, call: F)
+where
+    C: Serialize,
+    F: FnOnce() -> String,
+{
+    with_entry(condition, |entry| entry.rpc_calls.push(call()));
+}
+
+/// Records a raw value returned by the chain before it is compared against the return value test.
+pub(crate) fn record_returned_value<C, V>(condition: &C, returned_value: &V)
+where
+    C: Serialize,
+    V: Debug,
+{
+    with_entry(condition, |entry| {
+        entry.returned_values.push(format!("{:?}", returned_value))
+    });
+}
+
+/// Records the outcome of a leaf condition.
+pub(crate) fn record_result<C>(condition: &C, result: &Result<bool>)
+where
+    C: Serialize,
+{
+    with_entry(condition, |entry| match result {
+        Ok(result) => entry.result = Some(*result),
+        Err(e) => entry.error = Some(e.to_string()),
+    });
+}
+
+fn with_entry<C, F>(condition: &C, f: F)
+where
+    C: Serialize,
+    F: FnOnce(&mut AccessControlConditionTrace),
+{
+    let Ok(path) = CONDITION_INDEX.try_with(Clone::clone) else {
+        return;
+    };
+    let _ = TRACE.try_with(|trace| {
+        let Ok(mut trace) = trace.lock() else {
+            return;
+        };
+
+        let index = match trace.iter().position(|entry| entry.index == path) {
+            Some(index) => index,
+            None => {
+                let Ok(condition) = serde_json::to_value(condition) else {
+                    return;
+                };
+                let test = condition.get("returnValueTest");
+                let field = |name: &str| {
+                    test.and_then(|test| test.get(name))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                };
+                trace.push(AccessControlConditionTrace {
+                    index: path,
+                    comparator: field("comparator"),
+                    expected_value: field("value"),
+                    condition,
+                    ..Default::default()
+                });
+                trace.len() - 1
+            }
+        };
+        f(&mut trace[index]);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{at_index, record_result, record_returned_value, record_rpc_call, with_trace};
+    use lit_node_core::{JsonReturnValueTestV2, SolRpcConditionV2Options};
+
+    fn condition(value: &str) -> SolRpcConditionV2Options {
+        SolRpcConditionV2Options {
+            method: "getBalance".to_string(),
+            params: vec![],
+            pda_params: None,
+            pda_interface: None,
+            pda_key: None,
+            chain: "solana".to_string(),
+            return_value_test: JsonReturnValueTestV2 {
+                key: "".to_string(),
+                comparator: ">=".to_string(),
+                value: value.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_are_grouped_by_condition() {
+        let first = condition("1");
+        let second = condition("2");
+
+        let ((), trace) = with_trace(async {
+            at_index(0, async {
+                record_rpc_call(&first, || "getBalance".to_string());
+                record_returned_value(&first, &5u64);
+            })
+            .await;
+            at_index(2, async {
+                record_rpc_call(&second, || "getBalance".to_string())
+            })
+            .await;
+            at_index(0, async { record_result(&first, &Ok(true)) }).await;
+            at_index(2, async { record_result(&second, &Ok(false)) }).await;
+        })
+        .await;
+
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].index, vec![0]);
+        assert_eq!(trace[0].returned_values, vec!["5".to_string()]);
+        assert_eq!(trace[0].comparator.as_deref(), Some(">="));
+        assert_eq!(trace[0].expected_value.as_deref(), Some("1"));
+        assert_eq!(trace[0].result, Some(true));
+        assert_eq!(trace[1].index, vec![2]);
+        assert_eq!(trace[1].result, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_identical_conditions_get_an_entry_each() {
+        let same = condition("1");
+
+        let ((), trace) = with_trace(async {
+            at_index(0, async { record_result(&same, &Ok(true)) }).await;
+            at_index(2, async {
+                at_index(0, async { record_result(&same, &Ok(false)) }).await;
+            })
+            .await;
+        })
+        .await;
+
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].index, vec![0]);
+        assert_eq!(trace[0].result, Some(true));
+        assert_eq!(trace[1].index, vec![2, 0]);
+        assert_eq!(trace[1].result, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_records_outside_trace_are_ignored() {
+        at_index(0, async { record_result(&condition("1"), &Ok(true)) }).await;
+
+        let ((), trace) = with_trace(async {}).await;
+        assert!(trace.is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use futures::FutureExt;
 use lit_blockchain::resolver::rpc;
 use lit_blockchain::resolver::rpc::RpcHealthcheckPoller;
 use lit_core::{config::LitConfig, error::Unexpected};
@@ -8,7 +9,7 @@ use std::sync::Arc;
 use tracing::{Instrument, debug_span};
 use web3::{
     contract::{Contract, Options},
-    types::{Address, Bytes, CallRequest, U256},
+    types::{Address, BlockNumber, Bytes, CallRequest, U256},
 };
 
 use crate::auth::auth_material::JsonAuthSigExtendedRef;
@@ -20,14 +21,22 @@ use crate::error::{
 use crate::functions::action_client;
 use crate::models::PoapEntry;
 use crate::utils::encoding;
+use boolean::{ConditionLimiter, OperandFuture};
+use cache::RpcResultCache;
 use lit_node_core::{
     AccessControlBooleanOperator, AccessControlConditionItem, AuthSigItem, ControlConditionItem,
     EndpointVersion, JsonAccessControlCondition, JsonAccessControlConditionOperator, JsonAuthSig,
     LitResourceAbility,
 };
 
+pub mod bitcoin;
+pub(crate) mod block;
+pub(crate) mod boolean;
+pub(crate) mod cache;
 pub mod cosmos;
+pub(crate) mod evaluator;
 pub mod evm_contract;
+pub(crate) mod explain;
 pub mod sol_rpc;
 pub mod unified;
 
@@ -90,24 +99,61 @@ where
     Ok(web3::Web3::new(transport))
 }
 
-pub async fn rpc_call<C>(call_request: &CallRequest, chain: C) -> Result<Bytes>
+/// Makes an `eth_call`, at `block` if it's set and at the latest block otherwise.
+pub async fn rpc_call<C>(
+    call_request: &CallRequest,
+    chain: C,
+    block: Option<u64>,
+    rpc_cache: &RpcResultCache,
+) -> Result<Bytes>
 where
     C: AsRef<str>,
 {
-    get_web3(chain.as_ref())
-        .map_err(|e| {
-            blockchain_err_code(e, EC::NodeRpcError, Some("Web3 Error".into())).add_msg_to_details()
-        })?
-        .eth()
-        .call(call_request.to_owned(), None)
-        .await
-        .map_err(|e| {
-            blockchain_err_code(
-                e,
-                EC::NodeBlockchainError,
-                Some("Error making RPC Call".into()),
-            )
-        })
+    let web3 = get_web3(chain.as_ref()).map_err(|e| {
+        blockchain_err_code(e, EC::NodeRpcError, Some("Web3 Error".into())).add_msg_to_details()
+    })?;
+    let request = serde_json::to_string(call_request)
+        .map_err(|e| serializer_err_code(e, EC::NodeSerializationError, None))?;
+
+    let eth = web3.eth();
+    let call = |block: Option<u64>| async move {
+        // pin the call to the block it's cached under
+        let result = eth
+            .call(call_request.to_owned(), block::block_id(block))
+            .await
+            .map_err(|e| {
+                blockchain_err_code(
+                    e,
+                    EC::NodeBlockchainError,
+                    Some("Error making RPC Call".into()),
+                )
+            })?;
+        serde_json::to_value(result)
+            .map_err(|e| serializer_err_code(e, EC::NodeSerializationError, None))
+    };
+
+    let result = match block {
+        Some(block) => {
+            rpc_cache
+                .get_or_fetch_at(chain.as_ref(), block, request, || call(Some(block)))
+                .await?
+        }
+        None => {
+            let block_number = web3.eth().block_number();
+            let latest_block = async move {
+                block_number
+                    .await
+                    .map(|block| block.as_u64())
+                    .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, None))
+            };
+            rpc_cache
+                .get_or_fetch(chain.as_ref(), latest_block, request, call)
+                .await?
+        }
+    };
+
+    serde_json::from_value(result)
+        .map_err(|e| serializer_err_code(e, EC::NodeSerializationError, None))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -123,6 +169,7 @@ pub(crate) async fn check_access_control_conditions(
     current_action_ipfs_id: Option<&String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     let validate_res = JsonAuthSigExtendedRef::from(auth_sig)
         .validate_and_get_wallet_sig(
@@ -139,6 +186,7 @@ pub(crate) async fn check_access_control_conditions(
     match validate_res {
         Ok(valid_auth_sig) => {
             let fixed_conditions = insert_ands_if_necessary(conditions);
+            let limiter = ConditionLimiter::from_config(&cfg);
             let conditions_met = check_condition_group(
                 &fixed_conditions,
                 &valid_auth_sig,
@@ -149,6 +197,8 @@ pub(crate) async fn check_access_control_conditions(
                 current_action_ipfs_id,
                 ipfs_cache,
                 http_client,
+                rpc_cache,
+                &limiter,
             )
             .await?;
             Ok(conditions_met)
@@ -191,6 +241,27 @@ fn insert_ands_if_necessary(
     conditions_with_ands
 }
 
+/// Compares a returned value with the value of a return value test.  Unsupported comparators
+/// fail the test.
+pub(crate) fn compare_return_value<T: PartialOrd + std::fmt::Debug>(
+    comparator: &str,
+    returned_value: T,
+    valid_return_value: T,
+) -> bool {
+    match comparator {
+        ">" => returned_value > valid_return_value,
+        "<" => returned_value < valid_return_value,
+        ">=" => returned_value >= valid_return_value,
+        "<=" => returned_value <= valid_return_value,
+        "=" => returned_value == valid_return_value,
+        "!=" => returned_value != valid_return_value,
+        _ => {
+            warn!("Error - unsupported return value test comparator");
+            false
+        }
+    }
+}
+
 pub fn validate_boolean_expression<T>(conditions: &Vec<ControlConditionItem<T>>) -> bool {
     #[derive(PartialEq)]
     enum State {
@@ -223,98 +294,83 @@ pub fn validate_boolean_expression<T>(conditions: &Vec<ControlConditionItem<T>>)
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn check_condition_group(
-    conditions: &Vec<AccessControlConditionItem>,
-    auth_sig: &JsonAuthSig,
+fn check_condition_group<'a>(
+    conditions: &'a Vec<AccessControlConditionItem>,
+    auth_sig: &'a JsonAuthSig,
     cfg: Arc<LitConfig>,
-    request_id: &String,
-    bls_root_pubkey: &String,
-    endpoint_version: &EndpointVersion,
-    current_action_ipfs_id: Option<&String>,
+    request_id: &'a String,
+    bls_root_pubkey: &'a String,
+    endpoint_version: &'a EndpointVersion,
+    current_action_ipfs_id: Option<&'a String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
-) -> Result<bool> {
-    if !validate_boolean_expression(conditions) {
-        return Err(validation_err_code(
-            "Invalid boolean Access Control Conditions",
-            EC::NodeInvalidBooleanConditionType,
-            None,
-        ));
-    }
-
-    let mut results: Vec<bool> = Vec::new();
-    let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
-    for condition_item in conditions {
-        match condition_item {
-            AccessControlConditionItem::Condition(condition) => {
-                results.push(
-                    check_condition(
-                        condition,
-                        auth_sig,
-                        cfg.clone(),
-                        request_id,
-                        bls_root_pubkey,
-                        endpoint_version,
-                        current_action_ipfs_id,
-                        ipfs_cache.clone(),
-                        http_client.clone(),
-                    )
-                    .await?,
-                );
-            }
-            AccessControlConditionItem::Operator(operator) => {
-                operators.push(operator.operator);
-            }
-            AccessControlConditionItem::Group(group) => {
-                results.push(
-                    Box::pin(check_condition_group(
-                        group,
-                        auth_sig,
-                        cfg.clone(),
-                        request_id,
-                        bls_root_pubkey,
-                        endpoint_version,
-                        current_action_ipfs_id,
-                        ipfs_cache.clone(),
-                        http_client.clone(),
-                    ))
-                    .await?,
-                );
-            }
+    rpc_cache: &'a RpcResultCache,
+    limiter: &'a ConditionLimiter,
+) -> OperandFuture<'a, bool> {
+    Box::pin(async move {
+        if !validate_boolean_expression(conditions) {
+            return Err(validation_err_code(
+                "Invalid boolean Access Control Conditions",
+                EC::NodeInvalidBooleanConditionType,
+                None,
+            ));
         }
-    }
-
-    // okay we now have a bunch of results and operators.  We need to apply the operators.
-    // reverse the results so that we can pop off the first 2 results at a time
-    results.reverse();
-
-    // check if the number of operators is the same as the size of results
-    if operators.len() != results.len() - 1 {
-        return Err(validation_err_code(
-            "Invalid number of operators",
-            EC::NodeAccessControlConditionsCheckFailed,
-            None,
-        ));
-    }
 
-    // pull 2 results out, apply the operator, and push the result back on the results vector
-    for operator in operators {
-        let res1 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-        let res2 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-
-        match operator {
-            AccessControlBooleanOperator::And => results.push(res1 && res2),
-            AccessControlBooleanOperator::Or => results.push(res1 || res2),
+        let mut operands: Vec<OperandFuture<'a, bool>> = Vec::new();
+        let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
+        for (index, condition_item) in conditions.iter().enumerate() {
+            match condition_item {
+                AccessControlConditionItem::Condition(condition) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            limiter
+                                .run(check_condition(
+                                    condition,
+                                    auth_sig,
+                                    cfg.clone(),
+                                    request_id,
+                                    bls_root_pubkey,
+                                    endpoint_version,
+                                    current_action_ipfs_id,
+                                    ipfs_cache.clone(),
+                                    http_client.clone(),
+                                    rpc_cache,
+                                ))
+                                .inspect(move |result| explain::record_result(condition, result)),
+                        )
+                        .boxed(),
+                    );
+                }
+                AccessControlConditionItem::Operator(operator) => {
+                    operators.push(operator.operator);
+                }
+                AccessControlConditionItem::Group(group) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            check_condition_group(
+                                group,
+                                auth_sig,
+                                cfg.clone(),
+                                request_id,
+                                bls_root_pubkey,
+                                endpoint_version,
+                                current_action_ipfs_id,
+                                ipfs_cache.clone(),
+                                http_client.clone(),
+                                rpc_cache,
+                                limiter,
+                            ),
+                        )
+                        .boxed(),
+                    );
+                }
+            }
         }
-    }
 
-    Ok(*results
-        .last()
-        .expect_or_err("Could not get last result from results vector")?)
+        boolean::evaluate(operands, operators).await
+    })
 }
 
 // recursively search condition groups for operators
@@ -360,16 +416,39 @@ async fn check_condition(
     current_action_ipfs_id: Option<&String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
+    explain::record_rpc_call(condition, || {
+        format!(
+            "{} {}.{}({}) on {}",
+            condition.standard_contract_type,
+            condition.contract_address,
+            condition.method,
+            condition.parameters.join(", "),
+            condition.chain
+        )
+    });
+
     let web3 = get_web3(condition.chain.as_str()).map_err(|e| {
         blockchain_err_code(e, EC::NodeRpcError, Some("Web3 Error".into())).add_msg_to_details()
     })?;
+    // only conditions that read chain state are pinned to a block
+    let resolve_block = || {
+        block::resolve_block(
+            &web3,
+            rpc_cache,
+            &condition.chain,
+            condition.block_number,
+            condition.block_tag.as_deref(),
+        )
+    };
 
     if !condition.standard_contract_type.is_empty() {
         if condition.standard_contract_type == "POAP" {
             check_condition_via_poap(condition, auth_sig, http_client).await
         } else if condition.standard_contract_type == "timestamp" {
-            return check_condition_via_timestamp(condition).await;
+            let block = resolve_block().await?;
+            return check_condition_via_timestamp(&web3, condition, block).await;
         } else if condition.standard_contract_type == "SIWE" {
             return check_condition_via_siwe(condition, auth_sig).await;
         } else if condition.standard_contract_type == "LitAction" {
@@ -384,6 +463,7 @@ async fn check_condition(
                 current_action_ipfs_id,
                 ipfs_cache,
                 http_client,
+                rpc_cache,
             )
             .await;
             #[cfg(not(feature = "lit-actions"))]
@@ -393,9 +473,11 @@ async fn check_condition(
                 None,
             ));
         } else {
+            let block = resolve_block().await?;
             return check_condition_via_contract_call(
                 &web3,
                 condition,
+                block,
                 auth_sig,
                 bls_root_pubkey,
                 current_action_ipfs_id,
@@ -412,9 +494,11 @@ async fn check_condition(
         )
         .await;
     } else {
+        let block = resolve_block().await?;
         return check_condition_via_rpc_method(
             &web3,
             condition,
+            block,
             auth_sig,
             bls_root_pubkey,
             current_action_ipfs_id,
@@ -455,20 +539,23 @@ async fn check_condition_via_signature(
     }
 }
 
-async fn check_condition_via_timestamp(condition: &JsonAccessControlCondition) -> Result<bool> {
-    let web3 = get_web3(condition.chain.as_str()).map_err(|e| {
-        blockchain_err_code(e, EC::NodeRpcError, Some("Web3 Error".into())).add_msg_to_details()
-    })?;
-
-    let latest_block = web3
-        .eth()
-        .block_number()
-        .await
-        .map_err(|e| blockchain_err_code(e, EC::NodeBlockchainError, None))?;
+async fn check_condition_via_timestamp(
+    web3: &web3::Web3<web3::transports::Http>,
+    condition: &JsonAccessControlCondition,
+    block: Option<u64>,
+) -> Result<bool> {
+    let block = match block {
+        Some(block) => block.into(),
+        None => web3
+            .eth()
+            .block_number()
+            .await
+            .map_err(|e| blockchain_err_code(e, EC::NodeBlockchainError, None))?,
+    };
 
     let possible_block = web3
         .eth()
-        .block(web3::types::BlockId::from(latest_block))
+        .block(web3::types::BlockId::from(block))
         .await
         .map_err(|e| {
             blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -554,6 +641,7 @@ async fn check_condition_via_poap(
 async fn check_condition_via_rpc_method(
     web3: &web3::Web3<web3::transports::Http>,
     condition: &JsonAccessControlCondition,
+    block: Option<u64>,
     auth_sig: &JsonAuthSig,
     bls_root_pubkey: &str,
     current_action_ipfs_id: Option<&String>,
@@ -574,7 +662,7 @@ async fn check_condition_via_rpc_method(
                 Address::from_slice(&encoding::hex_to_bytes(&address_to_check).map_err(|e| {
                     conversion_err_code(e, EC::NodeConditionAddressConversionError, None)
                 })?),
-                None,
+                block.map(|block| BlockNumber::Number(block.into())),
             )
             .await
             .map_err(|e| {
@@ -631,6 +719,7 @@ async fn check_condition_via_lit_action(
     current_action_ipfs_id: Option<&String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     use crate::error::{memory_limit_err_code, timeout_err_code};
     use crate::models::DenoExecutionEnv;
@@ -684,11 +773,13 @@ async fn check_condition_via_lit_action(
     .await
     .map_err(|e| unexpected_err(e, Some("Error getting auth context".into())))?;
 
+    let egress_policy = action_client::egress_policy(&cfg);
     let deno_execution_env = DenoExecutionEnv {
         tss_state: None,
         cfg,
         ipfs_cache: Some(ipfs_cache.clone()),
         http_client: Some(http_client.clone()),
+        rpc_cache: Some(rpc_cache.clone()),
     };
 
     let mut client = action_client::ClientBuilder::default()
@@ -697,6 +788,7 @@ async fn check_condition_via_lit_action(
         .auth_sig(Some(auth_sig.clone()))
         .request_id(request_id.to_string())
         .endpoint_version(*endpoint_version)
+        .egress_policy(egress_policy)
         .build()
         .map_err(|e| {
             unexpected_err_code(
@@ -746,6 +838,7 @@ async fn check_condition_via_lit_action(
 async fn check_condition_via_contract_call(
     web3: &web3::Web3<web3::transports::Http>,
     condition: &JsonAccessControlCondition,
+    block: Option<u64>,
     auth_sig: &JsonAuthSig,
     bls_root_pubkey: &str,
     current_action_ipfs_id: Option<&String>,
@@ -824,7 +917,13 @@ async fn check_condition_via_contract_call(
 
                 debug!("isPermittedAction params: {:?}", params);
                 resp = contract
-                    .query(&condition.method, params, None, Options::default(), None)
+                    .query(
+                        &condition.method,
+                        params,
+                        None,
+                        Options::default(),
+                        block::block_id(block),
+                    )
                     .await;
                 debug!("Got response from isPermittedAddress: {:?}", resp);
             } else {
@@ -843,7 +942,13 @@ async fn check_condition_via_contract_call(
 
                 debug!("isPermittedAddress params: {:?}", params);
                 resp = contract
-                    .query(&condition.method, params, None, Options::default(), None)
+                    .query(
+                        &condition.method,
+                        params,
+                        None,
+                        Options::default(),
+                        block::block_id(block),
+                    )
                     .await;
                 debug!("Got response from isPermittedAddress: {:?}", resp);
             }
@@ -902,7 +1007,13 @@ async fn check_condition_via_contract_call(
             let params = (account_address, token_id);
             debug!("balanceOf params: {:?}", params);
             let balance = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -943,7 +1054,13 @@ async fn check_condition_via_contract_call(
             let params = (account_addresses, token_ids);
             debug!("balanceOfBatch params: {:?}", params);
             let balances: Vec<U256> = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -984,7 +1101,13 @@ async fn check_condition_via_contract_call(
             debug!("ownerOf params: {:?}", params);
 
             let owner = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -1013,7 +1136,13 @@ async fn check_condition_via_contract_call(
             let params = (account_address,);
             debug!("balanceOf params: {:?}", params);
             let balance = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -1040,7 +1169,13 @@ async fn check_condition_via_contract_call(
             let params = (account_address,);
             debug!("balanceOf params: {:?}", params);
             let balance = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -1067,7 +1202,13 @@ async fn check_condition_via_contract_call(
             let params = (account_address,);
             debug!("members params: {:?}", params);
             let member: (Address, U256, U256, bool, U256, U256) = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, Some("rpc_error".into())))?;
 
@@ -1102,7 +1243,13 @@ async fn check_condition_via_contract_call(
             let params = (account_address,);
             debug!("subscribers params: {:?}", params);
             let status: u8 = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Callr".into()))
@@ -1131,7 +1278,13 @@ async fn check_condition_via_contract_call(
             let params = (account_address,);
             debug!("isRegistered params: {:?}", params);
             let status: bool = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, Some("rpc_error".into())))?;
 
@@ -1181,7 +1334,13 @@ async fn check_condition_via_contract_call(
             let params = (consumer_address, provider_address, account_id);
             debug!("getActiveSubscriptionCount params: {:?}", params);
             let sub_count: U256 = contract
-                .query(&condition.method, params, None, Options::default(), None)
+                .query(
+                    &condition.method,
+                    params,
+                    None,
+                    Options::default(),
+                    block::block_id(block),
+                )
                 .await
                 .map_err(|e| {
                     blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
@@ -1204,6 +1363,7 @@ fn check_return_value_bool(
     returned_value: bool,
 ) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
 
     let valid_return_value = condition
         .return_value_test
@@ -1238,6 +1398,7 @@ fn check_return_value_int(
     returned_value: U256,
 ) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
 
     let valid_return_value = match U256::from_dec_str(&condition.return_value_test.value) {
         Ok(valid_return_value) => valid_return_value,
@@ -1281,6 +1442,7 @@ async fn check_return_value_addr(
     current_action_ipfs_id: Option<&String>,
 ) -> Result<bool> {
     debug!("Checking return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
     let subbed_param = substitute_special_params(
         &condition.return_value_test.value,
         auth_sig,
@@ -1318,6 +1480,7 @@ fn check_return_value_str(
     returned_value: String,
 ) -> Result<bool> {
     debug!("Checking string return value {:?}", returned_value);
+    explain::record_returned_value(condition, &returned_value);
     let valid_return_value = condition.return_value_test.value.clone();
 
     trace!(
@@ -1353,6 +1516,7 @@ fn check_return_value_array(
     auth_sig: &JsonAuthSig,
 ) -> Result<bool> {
     debug!("Checking array return values {:?}", returned_values);
+    explain::record_returned_value(condition, &returned_values);
     let valid_return_value = condition.return_value_test.value.clone();
 
     trace!(
@@ -1435,12 +1599,24 @@ pub async fn substitute_special_params(
 
 #[cfg(test)]
 mod tests {
-    use crate::access_control::validate_boolean_expression;
+    use crate::access_control::{compare_return_value, validate_boolean_expression};
     use lit_node_core::{
         AccessControlBooleanOperator, AccessControlConditionItem, JsonAccessControlCondition,
         JsonAccessControlConditionOperator, JsonReturnValueTest,
     };
 
+    #[test]
+    fn test_compare_return_value() {
+        assert!(compare_return_value(">", 2u64, 1));
+        assert!(compare_return_value(">=", 1u64, 1));
+        assert!(compare_return_value("<", 1u64, 2));
+        assert!(compare_return_value("<=", 2u64, 2));
+        assert!(compare_return_value("=", 3u64, 3));
+        assert!(compare_return_value("!=", 3u64, 4));
+        assert!(!compare_return_value("=", 3u64, 4));
+        assert!(!compare_return_value("contains", 3u64, 3));
+    }
+
     #[test]
     fn test_validate_boolean_expression() {
         let condition_a = AccessControlConditionItem::Condition(JsonAccessControlCondition {
@@ -1453,6 +1629,8 @@ mod tests {
                 comparator: ">=".to_string(),
                 value: "0".to_string(),
             },
+            block_number: None,
+            block_tag: None,
         });
         let condition_b = AccessControlConditionItem::Condition(JsonAccessControlCondition {
             contract_address: "0xc0ad7861fe8848002a3d9530999dd29f6b6cae75".to_string(),
@@ -1464,6 +1642,8 @@ mod tests {
                 comparator: ">".to_string(),
                 value: "10".to_string(),
             },
+            block_number: None,
+            block_tag: None,
         });
         let operator_a = AccessControlConditionItem::Operator(JsonAccessControlConditionOperator {
             operator: AccessControlBooleanOperator::And,
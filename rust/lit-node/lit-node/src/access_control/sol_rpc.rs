@@ -3,6 +3,8 @@ use crate::error::{
     EC, Result, blockchain_err_code, conversion_err, parser_err, unexpected_err_code,
     validation_err_code,
 };
+use futures::FutureExt;
+use lit_core::config::LitConfig;
 use lit_core::error::Unexpected;
 use lit_node_core::{
     AccessControlBooleanOperator, JsonAuthSig, SolPdaInterface, SolRpcConditionItem,
@@ -10,16 +12,22 @@ use lit_node_core::{
 };
 use mpl_token_metadata::state::Metadata;
 use serde_json::{Value, json};
+use solana_client::client_error::ClientError;
 use solana_client::client_error::ClientErrorKind::RpcError;
-use solana_client::{rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
 use solana_program::borsh::try_from_slice_unchecked;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use spl_associated_token_account::get_associated_token_address;
 use std::collections::HashMap;
 use std::result::Result as StdResult;
 use std::str::FromStr;
 
-use super::{substitute_special_params, validate_boolean_expression};
+use super::boolean::{self, ConditionLimiter, OperandFuture};
+use super::cache::RpcResultCache;
+use super::{
+    compare_return_value, explain, substitute_special_params, validate_boolean_expression,
+};
 
 const VALID_CHAIN_NAMES: [&str; 3] = ["solana", "solanaDevnet", "solanaTestnet"];
 
@@ -47,11 +55,60 @@ where
     Ok(client)
 }
 
+/// Sends an RPC request through the per-slot result cache.
+///
+/// Results are cached under the request's commitment (the client's, unless the request
+/// sets one) and the latest slot at that commitment, and requests that take a config
+/// object are pinned to that slot with `minContextSlot` so a cached result is never
+/// older than the slot it is keyed by.
+async fn send_cached(
+    rpc_cache: &RpcResultCache,
+    sol_client: &RpcClient,
+    chain: &str,
+    request: RpcRequest,
+    params: Value,
+) -> StdResult<Value, ClientError> {
+    let commitment = request_commitment(&params).unwrap_or_else(|| sol_client.commitment());
+    let latest_slot = async {
+        sol_client
+            .get_slot_with_commitment(commitment)
+            .await
+            .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, None))
+    };
+    let cache_key = format!("{:?} {} {:?}", request, params, commitment.commitment);
+    rpc_cache
+        .get_or_fetch(chain, latest_slot, cache_key, |slot| async move {
+            let params = match slot {
+                Some(slot) => pin_to_slot(params, commitment, slot),
+                None => params,
+            };
+            sol_client.send(request, params).await
+        })
+        .await
+}
+
+/// The commitment set in a request's trailing config object, if any.
+fn request_commitment(params: &Value) -> Option<CommitmentConfig> {
+    let commitment = params.as_array()?.last()?.get("commitment")?;
+    serde_json::from_value(json!({ "commitment": commitment })).ok()
+}
+
+/// Sets the commitment and minimum context slot on a request's trailing config object.
+fn pin_to_slot(mut params: Value, commitment: CommitmentConfig, slot: u64) -> Value {
+    if let Some(Value::Object(config)) = params.as_array_mut().and_then(|p| p.last_mut()) {
+        config.insert("commitment".to_string(), json!(commitment.commitment));
+        config.insert("minContextSlot".to_string(), json!(slot));
+    }
+    params
+}
+
 pub async fn check_access_control_conditions(
     conditions: &Vec<SolRpcConditionItem>,
     auth_sig: &JsonAuthSig,
+    cfg: &LitConfig,
     bls_root_pubkey: &String,
     current_action_ipfs_id: Option<&String>,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     // No need to add Ok false message since we're displaying a generic message in web_client_endpoints
     match validate_solana_auth_sig(auth_sig) {
@@ -59,11 +116,14 @@ pub async fn check_access_control_conditions(
             debug!("Signature checked and the result is {:?}", sig_is_valid);
             match sig_is_valid {
                 true => {
+                    let limiter = ConditionLimiter::from_config(cfg);
                     check_condition_group(
                         conditions,
                         auth_sig,
                         bls_root_pubkey,
                         current_action_ipfs_id,
+                        rpc_cache,
+                        &limiter,
                     )
                     .await
                 }
@@ -77,69 +137,68 @@ pub async fn check_access_control_conditions(
     }
 }
 
-async fn check_condition_group(
-    conditions: &Vec<SolRpcConditionItem>,
-    auth_sig: &JsonAuthSig,
-    bls_root_pubkey: &String,
-    current_action_ipfs_id: Option<&String>,
-) -> Result<bool> {
-    if !validate_boolean_expression(conditions) {
-        return Err(validation_err_code(
-            "Invalid boolean Solana Access Control Conditions",
-            EC::NodeInvalidBooleanConditionType,
-            None,
-        ));
-    }
-
-    let mut results: Vec<bool> = Vec::new();
-    let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
-    for condition_item in conditions {
-        match condition_item {
-            SolRpcConditionItem::Condition(condition) => {
-                results.push(
-                    check_condition(condition, auth_sig, bls_root_pubkey, current_action_ipfs_id)
-                        .await?,
-                );
-            }
-            SolRpcConditionItem::Operator(operator) => {
-                operators.push(operator.operator);
-            }
-            SolRpcConditionItem::Group(group) => {
-                results.push(
-                    Box::pin(check_condition_group(
-                        group,
-                        auth_sig,
-                        bls_root_pubkey,
-                        current_action_ipfs_id,
-                    ))
-                    .await?,
-                );
-            }
+fn check_condition_group<'a>(
+    conditions: &'a Vec<SolRpcConditionItem>,
+    auth_sig: &'a JsonAuthSig,
+    bls_root_pubkey: &'a String,
+    current_action_ipfs_id: Option<&'a String>,
+    rpc_cache: &'a RpcResultCache,
+    limiter: &'a ConditionLimiter,
+) -> OperandFuture<'a, bool> {
+    Box::pin(async move {
+        if !validate_boolean_expression(conditions) {
+            return Err(validation_err_code(
+                "Invalid boolean Solana Access Control Conditions",
+                EC::NodeInvalidBooleanConditionType,
+                None,
+            ));
         }
-    }
 
-    // okay we now have a bunch of results and operators.  We need to apply the operators.
-    // reverse the results so that we can pop off the first 2 results at a time
-    results.reverse();
-
-    // pull 2 results out, apply the operator, and push the result back on the results vector
-    for operator in operators {
-        let res1 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-        let res2 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-
-        match operator {
-            AccessControlBooleanOperator::And => results.push(res1 && res2),
-            AccessControlBooleanOperator::Or => results.push(res1 || res2),
+        let mut operands: Vec<OperandFuture<'a, bool>> = Vec::new();
+        let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
+        for (index, condition_item) in conditions.iter().enumerate() {
+            match condition_item {
+                SolRpcConditionItem::Condition(condition) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            limiter
+                                .run(check_condition(
+                                    condition,
+                                    auth_sig,
+                                    bls_root_pubkey,
+                                    current_action_ipfs_id,
+                                    rpc_cache,
+                                ))
+                                .inspect(move |result| explain::record_result(condition, result)),
+                        )
+                        .boxed(),
+                    );
+                }
+                SolRpcConditionItem::Operator(operator) => {
+                    operators.push(operator.operator);
+                }
+                SolRpcConditionItem::Group(group) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            check_condition_group(
+                                group,
+                                auth_sig,
+                                bls_root_pubkey,
+                                current_action_ipfs_id,
+                                rpc_cache,
+                                limiter,
+                            ),
+                        )
+                        .boxed(),
+                    );
+                }
+            }
         }
-    }
 
-    Ok(*results
-        .last()
-        .expect_or_err("Could not get last result from results vector")?)
+        boolean::evaluate(operands, operators).await
+    })
 }
 
 pub async fn check_condition(
@@ -147,7 +206,10 @@ pub async fn check_condition(
     auth_sig: &JsonAuthSig,
     bls_root_pubkey: &str,
     current_action_ipfs_id: Option<&String>,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
+    // the trace shows the condition as it was sent
+    let requested_condition = condition;
     let condition = SolRpcConditionV2 {
         method: condition.method.clone(),
         params: condition.params.clone(),
@@ -181,6 +243,7 @@ pub async fn check_condition(
         && substituted_params.len() == 1
         && substituted_params[0].is_string()
     {
+        explain::record_returned_value(requested_condition, &substituted_params[0]);
         // check wallet address
         return check_return_value_string(
             &condition,
@@ -252,8 +315,14 @@ pub async fn check_condition(
         // now we have to call getAccountInfo and parse the data into fields
         let sol_client = get_sol_rpc_client(&condition.chain)?;
         let rpc_request = RpcRequest::GetAccountInfo;
-        let resp: StdResult<Value, solana_client::client_error::ClientError> =
-            sol_client.send(rpc_request, params.clone());
+        let resp: StdResult<Value, solana_client::client_error::ClientError> = send_cached(
+            rpc_cache,
+            &sol_client,
+            &condition.chain,
+            rpc_request,
+            params.clone(),
+        )
+        .await;
         debug!("resp: {:?}", resp);
         let mut pda_fields: HashMap<String, Pubkey> = HashMap::new();
         match resp {
@@ -333,7 +402,7 @@ pub async fn check_condition(
 
     if condition.method == "balanceOfMetaplexCollection" {
         // check the balance of an nft collection for the user
-        return check_balance_of_metaplex_collection(&condition, auth_sig);
+        return check_balance_of_metaplex_collection(&condition, auth_sig, rpc_cache).await;
     } else if condition.method == "balanceOfToken" {
         // check the balance of a token that the user should hold
         return check_balance_of_token(
@@ -341,9 +410,11 @@ pub async fn check_condition(
             auth_sig,
             bls_root_pubkey,
             current_action_ipfs_id,
+            rpc_cache,
         )
         .await;
     } else if condition.method == "getPDA" {
+        explain::record_returned_value(requested_condition, &pda_address);
         return check_return_value_string(
             &condition,
             pda_address,
@@ -376,9 +447,19 @@ pub async fn check_condition(
     // call substitute to get the address
     debug!("params: {:?}", params);
 
-    let parsed_response: Value = sol_client.send(rpc_request, params).map_err(|e| {
-        unexpected_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
-    })?;
+    explain::record_rpc_call(requested_condition, || {
+        format!("{} {} on {}", rpc_method, params, condition.chain)
+    });
+    let parsed_response: Value = send_cached(
+        rpc_cache,
+        &sol_client,
+        &condition.chain,
+        rpc_request,
+        params,
+    )
+    .await
+    .map_err(|e| unexpected_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into())))?;
+    explain::record_returned_value(requested_condition, &parsed_response["value"]);
 
     check_return_value(
         &condition,
@@ -468,22 +549,11 @@ fn check_return_value_uint(condition: &SolRpcConditionV2, returned_value: u64) -
         returned_value, condition.return_value_test.comparator, valid_return_value
     );
 
-    if condition.return_value_test.comparator == ">" {
-        Ok(returned_value > valid_return_value)
-    } else if condition.return_value_test.comparator == "<" {
-        return Ok(returned_value < valid_return_value);
-    } else if condition.return_value_test.comparator == ">=" {
-        return Ok(returned_value >= valid_return_value);
-    } else if condition.return_value_test.comparator == "<=" {
-        return Ok(returned_value <= valid_return_value);
-    } else if condition.return_value_test.comparator == "=" {
-        return Ok(returned_value == valid_return_value);
-    } else if condition.return_value_test.comparator == "!=" {
-        return Ok(returned_value != valid_return_value);
-    } else {
-        warn!("Error - unsupported return value test comparator");
-        return Ok(false);
-    }
+    Ok(compare_return_value(
+        &condition.return_value_test.comparator,
+        returned_value,
+        valid_return_value,
+    ))
 }
 
 async fn check_return_value_string(
@@ -526,9 +596,10 @@ async fn check_return_value_string(
     }
 }
 
-fn check_balance_of_metaplex_collection(
+async fn check_balance_of_metaplex_collection(
     condition: &SolRpcConditionV2,
     auth_sig: &JsonAuthSig,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     // 0. get the metaplex collection address
     // 1. find all tokens that the user holds
@@ -562,8 +633,14 @@ fn check_balance_of_metaplex_collection(
     let token_program_address = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
     let sol_client = get_sol_rpc_client(&condition.chain)?;
     let user_tokens_params = serde_json::json!([auth_sig.address, { "programId": token_program_address }, { "encoding": "jsonParsed"}]);
-    let user_tokens_resp: StdResult<Value, solana_client::client_error::ClientError> =
-        sol_client.send(RpcRequest::GetTokenAccountsByOwner, user_tokens_params);
+    let user_tokens_resp: StdResult<Value, solana_client::client_error::ClientError> = send_cached(
+        rpc_cache,
+        &sol_client,
+        &condition.chain,
+        RpcRequest::GetTokenAccountsByOwner,
+        user_tokens_params,
+    )
+    .await;
     match user_tokens_resp {
         Ok(resp) => {
             let all_tokens = resp["value"]
@@ -594,7 +671,7 @@ fn check_balance_of_metaplex_collection(
                     .as_str()
                     .expect_or_err("Could not get token address from nft")?
                     .to_string();
-                let metadata_result = get_metaplex_metadata(condition, token_address.clone());
+                let metadata_result = get_metaplex_metadata(condition, token_address.clone()).await;
                 if let Ok(metadata) = metadata_result {
                     if let Some(collection) = metadata.collection {
                         if collection.verified && collection.key == collection_address {
@@ -714,6 +791,7 @@ async fn check_balance_of_token(
     auth_sig: &JsonAuthSig,
     bls_root_pubkey: &str,
     current_action_ipfs_id: Option<&String>,
+    rpc_cache: &RpcResultCache,
 ) -> Result<bool> {
     let token_account_address = get_token_account_address(
         auth_sig,
@@ -726,8 +804,14 @@ async fn check_balance_of_token(
     let params: serde_json::Value = json!([token_account_address.to_string()]);
     debug!("params: {:?}", params);
 
-    let resp: StdResult<Value, solana_client::client_error::ClientError> =
-        sol_client.send(RpcRequest::GetTokenAccountBalance, params);
+    let resp: StdResult<Value, solana_client::client_error::ClientError> = send_cached(
+        rpc_cache,
+        &sol_client,
+        &condition.chain,
+        RpcRequest::GetTokenAccountBalance,
+        params,
+    )
+    .await;
 
     match resp {
         Ok(parsed_response) => {
@@ -786,7 +870,10 @@ fn get_token_account_address(auth_sig: &JsonAuthSig, token_address: &str) -> Res
 
 // this fn is not currently used, but i put all the work in to figure out how to pull down metadata so
 // i am preserving that work in this function for future use
-fn get_metaplex_metadata(condition: &SolRpcConditionV2, token_address: String) -> Result<Metadata> {
+async fn get_metaplex_metadata(
+    condition: &SolRpcConditionV2,
+    token_address: String,
+) -> Result<Metadata> {
     // example using a known token...
     // let token_pubkey = Pubkey::from_str("GkLTynMPrX62ezmdfcXaTHhpmYubypVTei2sdwq4KBg")
     //     .expect("Could not get token_pubkey from token_address");
@@ -817,6 +904,7 @@ fn get_metaplex_metadata(condition: &SolRpcConditionV2, token_address: String) -
 
     let parsed_response: Value = sol_client
         .send(RpcRequest::GetAccountInfo, account_into_params)
+        .await
         .map_err(|e| {
             unexpected_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
         })?;
@@ -861,12 +949,14 @@ fn get_metaplex_metadata(condition: &SolRpcConditionV2, token_address: String) -
 
 #[cfg(test)]
 mod tests {
-    use super::check_condition;
+    use super::{RpcResultCache, check_condition, pin_to_slot, request_commitment};
     use lit_node_core::{
         JsonAuthSig, JsonReturnValueTestV2, SolPdaInterface, SolRpcConditionV2Options,
         constants::CHAIN_SOLANA,
     };
     use rocket::serde::json::Value::String;
+    use serde_json::json;
+    use solana_sdk::commitment_config::CommitmentConfig;
     use std::collections::HashMap;
 
     fn get_auth_sig() -> JsonAuthSig {
@@ -879,6 +969,39 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_pin_to_slot() {
+        let commitment = CommitmentConfig::confirmed();
+        let pinned = pin_to_slot(
+            json!(["address", { "encoding": "jsonParsed" }]),
+            commitment,
+            42,
+        );
+        assert_eq!(
+            pinned,
+            json!(["address", { "encoding": "jsonParsed", "commitment": "confirmed", "minContextSlot": 42 }])
+        );
+
+        // requests without a config object are left alone
+        assert_eq!(
+            pin_to_slot(json!(["address"]), commitment, 42),
+            json!(["address"])
+        );
+    }
+
+    #[test]
+    fn test_request_commitment() {
+        assert_eq!(
+            request_commitment(&json!(["address", { "commitment": "finalized" }])),
+            Some(CommitmentConfig::finalized())
+        );
+        assert_eq!(
+            request_commitment(&json!(["address", { "encoding": "jsonParsed" }])),
+            None
+        );
+        assert_eq!(request_commitment(&json!(["address"])), None);
+    }
+
     #[tokio::test]
     async fn test_check_condition_string() {
         let address_condition = SolRpcConditionV2Options {
@@ -897,14 +1020,19 @@ mod tests {
                 value: "5Th1tyAQGFaZ2c6gBVW4MPe6L5xhvpnBc9pjMzAkEPPz".to_string(),
             },
         };
-        let check_balance_condition =
-            check_condition(&address_condition, &get_auth_sig(), &"".to_string(), None).await;
+        let check_balance_condition = check_condition(
+            &address_condition,
+            &get_auth_sig(),
+            &"".to_string(),
+            None,
+            &RpcResultCache::new(),
+        )
+        .await;
         assert!(check_balance_condition.is_ok());
         assert!(check_balance_condition.unwrap());
     }
 
-    // Commented for now since the `get_sol_rpc_client()` panics if run in a tokio async context.
-    // Can be uncommented when we refactor & extract out the Solana Client initiation in a Global Config.
+    // Commented for now since it needs a live Solana RPC endpoint.
     // #[tokio::test]
     // async fn test_check_condition_numerical() {
     //     let balance_condition = SolRpcConditionV2Options {
@@ -1,9 +1,13 @@
+use super::boolean::{self, ConditionLimiter, OperandFuture};
+use super::cache::RpcResultCache;
+use super::evaluator::{self, ConditionContext};
+use super::explain;
 use super::{cosmos, evm_contract, sol_rpc, validate_boolean_expression};
 use crate::auth::auth_material::{JsonAuthSigExtendedRef, MultipleAuthSigsExtended};
 use crate::error::{EC, Result, validation_err, validation_err_code};
 use crate::models::UnifiedConditionCheckResult;
+use futures::FutureExt;
 use lit_core::config::LitConfig;
-use lit_core::error::Unexpected;
 use lit_node_core::{
     AccessControlBooleanOperator, AuthSigItem, EndpointVersion, LitResourceAbility,
     MultipleAuthSigs, UnifiedAccessControlCondition, UnifiedAccessControlConditionItem,
@@ -24,6 +28,7 @@ pub(crate) async fn check_access_control_conditions(
     current_action_ipfs_id: Option<&String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<UnifiedConditionCheckResult> {
     // massage auth_sig into the MultipleAuthSigs struct before doing the check
     let auth_sigs = {
@@ -56,6 +61,7 @@ pub(crate) async fn check_access_control_conditions(
         }
     };
 
+    let limiter = ConditionLimiter::from_config(&cfg);
     let res = check_condition_group(
         conditions,
         &auth_sigs,
@@ -66,102 +72,96 @@ pub(crate) async fn check_access_control_conditions(
         current_action_ipfs_id,
         ipfs_cache,
         http_client,
+        rpc_cache,
+        &limiter,
     )
     .await?;
     Ok(res)
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn check_condition_group(
-    conditions: &Vec<UnifiedAccessControlConditionItem>,
-    auth_sigs: &MultipleAuthSigs,
+fn check_condition_group<'a>(
+    conditions: &'a Vec<UnifiedAccessControlConditionItem>,
+    auth_sigs: &'a MultipleAuthSigs,
     cfg: Arc<LitConfig>,
-    request_id: &String,
-    bls_root_pubkey: &String,
-    endpoint_version: &EndpointVersion,
-    current_action_ipfs_id: Option<&String>,
+    request_id: &'a String,
+    bls_root_pubkey: &'a String,
+    endpoint_version: &'a EndpointVersion,
+    current_action_ipfs_id: Option<&'a String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
-) -> Result<UnifiedConditionCheckResult> {
-    if !validate_boolean_expression(conditions) {
-        return Err(validation_err_code(
-            "Invalid boolean Unified Access Control Conditions",
-            EC::NodeInvalidBooleanConditionType,
-            None,
-        ));
-    }
-
-    let mut results: Vec<UnifiedConditionCheckResult> = Vec::new();
-    let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
-    for condition_item in conditions {
-        match condition_item {
-            UnifiedAccessControlConditionItem::Condition(condition) => {
-                results.push(
-                    check_condition(
-                        condition,
-                        auth_sigs,
-                        cfg.clone(),
-                        request_id,
-                        bls_root_pubkey,
-                        endpoint_version,
-                        current_action_ipfs_id,
-                        ipfs_cache.clone(),
-                        http_client.clone(),
-                    )
-                    .await?,
-                );
-            }
-            UnifiedAccessControlConditionItem::Operator(operator) => {
-                operators.push(operator.operator);
-            }
-            UnifiedAccessControlConditionItem::Group(group) => {
-                results.push(
-                    Box::pin(check_condition_group(
-                        group,
-                        auth_sigs,
-                        cfg.clone(),
-                        request_id,
-                        bls_root_pubkey,
-                        endpoint_version,
-                        current_action_ipfs_id,
-                        ipfs_cache.clone(),
-                        http_client.clone(),
-                    ))
-                    .await?,
-                );
-            }
+    rpc_cache: &'a RpcResultCache,
+    limiter: &'a ConditionLimiter,
+) -> OperandFuture<'a, UnifiedConditionCheckResult> {
+    Box::pin(async move {
+        if !validate_boolean_expression(conditions) {
+            return Err(validation_err_code(
+                "Invalid boolean Unified Access Control Conditions",
+                EC::NodeInvalidBooleanConditionType,
+                None,
+            ));
         }
-    }
-
-    // okay we now have a bunch of results and operators.  We need to apply the operators.
-    // reverse the results so that we can pop off the first 2 results at a time
-    results.reverse();
-
-    // pull 2 results out, apply the operator, and push the result back on the results vector
-    for operator in operators {
-        let res1 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
-        let res2 = results
-            .pop()
-            .expect_or_err("Could not pop result off of results vector")?;
 
-        match operator {
-            // just pick one randomly, since both wallet sigs were successful
-            AccessControlBooleanOperator::And if res1.result && res2.result => results.push(res1),
-            AccessControlBooleanOperator::Or if res1.result => results.push(res1),
-            AccessControlBooleanOperator::Or if res2.result => results.push(res2),
-            _ => results.push(UnifiedConditionCheckResult {
-                result: false,
-                successful_auth_sig: res1.successful_auth_sig, // irrelevant since success is false
-            }),
+        let mut operands: Vec<OperandFuture<'a, UnifiedConditionCheckResult>> = Vec::new();
+        let mut operators: Vec<AccessControlBooleanOperator> = Vec::new();
+        for (index, condition_item) in conditions.iter().enumerate() {
+            match condition_item {
+                UnifiedAccessControlConditionItem::Condition(condition) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            limiter
+                                .run(check_condition(
+                                    condition,
+                                    auth_sigs,
+                                    cfg.clone(),
+                                    request_id,
+                                    bls_root_pubkey,
+                                    endpoint_version,
+                                    current_action_ipfs_id,
+                                    ipfs_cache.clone(),
+                                    http_client.clone(),
+                                    rpc_cache,
+                                ))
+                                .inspect(move |result| {
+                                    explain::record_result(
+                                        condition,
+                                        &result.as_ref().map(|r| r.result).map_err(Clone::clone),
+                                    )
+                                }),
+                        )
+                        .boxed(),
+                    );
+                }
+                UnifiedAccessControlConditionItem::Operator(operator) => {
+                    operators.push(operator.operator);
+                }
+                UnifiedAccessControlConditionItem::Group(group) => {
+                    operands.push(
+                        explain::at_index(
+                            index,
+                            check_condition_group(
+                                group,
+                                auth_sigs,
+                                cfg.clone(),
+                                request_id,
+                                bls_root_pubkey,
+                                endpoint_version,
+                                current_action_ipfs_id,
+                                ipfs_cache.clone(),
+                                http_client.clone(),
+                                rpc_cache,
+                                limiter,
+                            ),
+                        )
+                        .boxed(),
+                    );
+                }
+            }
         }
-    }
 
-    Ok(results
-        .last()
-        .expect_or_err("Could not get last result from results vector")?
-        .to_owned())
+        boolean::evaluate(operands, operators).await
+    })
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -175,6 +175,7 @@ async fn check_condition(
     current_action_ipfs_id: Option<&String>,
     ipfs_cache: Cache<String, Arc<String>>,
     http_client: reqwest::Client,
+    rpc_cache: &RpcResultCache,
 ) -> Result<UnifiedConditionCheckResult> {
     // do the check depending on the condition type
     let auth_sig;
@@ -197,6 +198,7 @@ async fn check_condition(
                 current_action_ipfs_id,
                 ipfs_cache,
                 http_client,
+                rpc_cache,
             )
             .await?
         }
@@ -213,6 +215,7 @@ async fn check_condition(
                 &auth_sig,
                 bls_root_pubkey,
                 current_action_ipfs_id,
+                rpc_cache,
             )
             .await?
         }
@@ -229,6 +232,7 @@ async fn check_condition(
                 &auth_sig,
                 bls_root_pubkey,
                 current_action_ipfs_id,
+                rpc_cache,
             )
             .await?
         }
@@ -241,9 +245,28 @@ async fn check_condition(
                 bls_root_pubkey,
                 current_action_ipfs_id,
                 http_client,
+                rpc_cache,
             )
             .await?
         }
+        other => {
+            let evaluator = evaluator::evaluator_for(other).ok_or_else(|| {
+                validation_err_code(
+                    "No evaluator registered for condition",
+                    EC::NodeInvalidUnifiedConditionType,
+                    None,
+                )
+            })?;
+            let ctx = ConditionContext {
+                auth_sigs,
+                bls_root_pubkey,
+                current_action_ipfs_id,
+                http_client,
+            };
+            let (result, evaluator_auth_sig) = evaluator::check_condition(evaluator, &ctx).await?;
+            auth_sig = evaluator_auth_sig;
+            result
+        }
     };
 
     Ok(UnifiedConditionCheckResult {
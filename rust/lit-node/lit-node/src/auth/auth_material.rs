@@ -6,6 +6,7 @@ use super::{
         validate_and_extract_wallet_sig, validate_session_sig,
     },
     validators::wallet_sig::{validate_siwe_message, validate_wallet_sig_by_chain},
+    validators::webauthn::validate_passkey_auth_sig,
 };
 use crate::{
     error::{EC, Result, conversion_err, unexpected_err, validation_err, validation_err_code},
@@ -318,6 +319,7 @@ impl JsonAuthSigExtendedRef<'_> {
 
         if matches!(self.0.auth_material_type, AuthMaterialType::WalletSig)
             || matches!(self.0.auth_material_type, AuthMaterialType::ContractSig)
+            || matches!(self.0.auth_material_type, AuthMaterialType::PasskeySig)
         {
             // Try to infer the chain from the address by doing hex decode.
             let user_address = self
@@ -347,6 +349,7 @@ impl JsonAuthSigExtendedRef<'_> {
             AuthMaterialType::WalletSig => Ok(self.0.address.clone()),
             AuthMaterialType::BLSNetworkSig => Ok(self.0.address.clone()),
             AuthMaterialType::ContractSig => Ok(self.0.address.clone()),
+            AuthMaterialType::PasskeySig => Ok(self.0.address.clone()),
             AuthMaterialType::SessionSig => {
                 Ok(extract_wallet_sig(self.0, bls_root_pubkey).await?.address)
             }
@@ -359,6 +362,7 @@ impl JsonAuthSigExtendedRef<'_> {
             AuthMaterialType::BLSNetworkSig => Ok(self.0.clone()),
             AuthMaterialType::WalletSig => Ok(self.0.clone()),
             AuthMaterialType::ContractSig => Ok(self.0.clone()),
+            AuthMaterialType::PasskeySig => Ok(self.0.clone()),
             AuthMaterialType::SessionSig => extract_wallet_sig(self.0, bls_root_pubkey).await,
         }
     }
@@ -393,6 +397,10 @@ impl JsonAuthSigExtendedRef<'_> {
                 validate_eip1271_signature(self.0, chain).await?;
                 self.0.clone()
             }
+            AuthMaterialType::PasskeySig => {
+                validate_passkey_auth_sig(self.0, cfg)?;
+                self.0.clone()
+            }
             AuthMaterialType::SessionSig => {
                 // TODO: This is only here for backwards compatibility. Once the new implementation on the
                 // SDK has been stabilized, we should only use validate_session_sig.
@@ -441,6 +449,7 @@ impl JsonAuthSigExtendedRef<'_> {
             AuthMaterialType::WalletSig => Ok(vec![]),
             AuthMaterialType::BLSNetworkSig => Ok(vec![]),
             AuthMaterialType::ContractSig => Ok(vec![]),
+            AuthMaterialType::PasskeySig => Ok(vec![]),
             AuthMaterialType::SessionSig => extract_requested_resources_from_session_sig(self.0),
         }
     }
@@ -789,6 +798,7 @@ mod multiple_auth_sigs_tests {
             expiration: "2024-01-01T00:00:00Z".to_string(),
             node_address: "localhost:7470".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
         })
         .unwrap();
 
@@ -848,6 +858,7 @@ mod multiple_auth_sigs_tests {
                             expiration: "2024-01-01T00:00:00Z".to_string(),
                             node_address: "localhost:7470".to_string(),
                             max_price: U256::MAX,
+                            delegations: vec![],
                         })
                         .unwrap(),
                         "address".into(),
@@ -892,6 +903,7 @@ mod multiple_auth_sigs_tests {
                             expiration: "2024-01-01T00:00:00Z".to_string(),
                             node_address: "localhost:7470".to_string(),
                             max_price: U256::MAX,
+                            delegations: vec![],
                         })
                         .unwrap(),
                         "address".into(),
@@ -936,6 +948,7 @@ mod multiple_auth_sigs_tests {
                             expiration: "2024-01-01T00:00:00Z".to_string(),
                             node_address: "localhost:7470".to_string(),
                             max_price: U256::MAX,
+                            delegations: vec![],
                         })
                         .unwrap(),
                         "address".into(),
@@ -980,6 +993,7 @@ mod multiple_auth_sigs_tests {
                             expiration: "2024-01-01T00:00:00Z".to_string(),
                             node_address: "localhost:7470".to_string(),
                             max_price: U256::MAX,
+                            delegations: vec![],
                         })
                         .unwrap(),
                         "address".into(),
@@ -1024,6 +1038,7 @@ mod multiple_auth_sigs_tests {
                             expiration: "2024-01-01T00:00:00Z".to_string(),
                             node_address: "localhost:7470".to_string(),
                             max_price: U256::MAX,
+                            delegations: vec![],
                         })
                         .unwrap(),
                         "address".into(),
@@ -0,0 +1,297 @@
+use crate::auth::session_sigs::check_ed25519_auth_sig;
+use crate::error::{EC, Result, parser_err_code, validation_err_code};
+use crate::models::auth::{SessionKeyDelegation, SessionKeySignedMessageV2};
+use chrono::{DateTime, FixedOffset, Utc};
+use lit_node_core::LitResourceAbilityRequest;
+
+/// Bounds the work a single session sig can make us do.
+const MAX_DELEGATION_CHAIN_LENGTH: usize = 8;
+
+/// Verifies the chain of re-delegations from the session key that the capability auth sigs
+/// were issued to, down to `session_pubkey`.
+///
+/// Each session key in the chain may only hand on a subset of what it was delegated, for no
+/// longer than it was delegated for. Returns the session keys of the chain, starting with the
+/// one the capabilities were issued to and ending with `session_pubkey`.
+pub(crate) fn verify_delegation_chain(
+    session_pubkey: &str,
+    session_key_signed_message: &SessionKeySignedMessageV2,
+) -> Result<Vec<String>> {
+    let delegations = &session_key_signed_message.delegations;
+    if delegations.is_empty() {
+        return Ok(vec![session_pubkey.to_string()]);
+    }
+    if delegations.len() > MAX_DELEGATION_CHAIN_LENGTH {
+        return Err(invalid_delegation(format!(
+            "Delegation chain has {} links but at most {} are allowed",
+            delegations.len(),
+            MAX_DELEGATION_CHAIN_LENGTH
+        )));
+    }
+
+    let now = Utc::now();
+    let mut session_keys = Vec::with_capacity(delegations.len() + 1);
+    let mut parent: Option<(SessionKeyDelegation, DateTime<FixedOffset>)> = None;
+    for delegation_auth_sig in delegations {
+        // The delegating session key signs the delegation, just like it would sign a session sig
+        check_ed25519_auth_sig(delegation_auth_sig)?;
+        let delegation: SessionKeyDelegation =
+            serde_json::from_str(&delegation_auth_sig.signed_message).map_err(|e| {
+                parser_err_code(
+                    e,
+                    EC::NodeInvalidSessionKeyDelegation,
+                    Some("Error parsing session key delegation".into()),
+                )
+            })?;
+        if !same_session_key(&delegation.delegator, &delegation_auth_sig.address) {
+            return Err(invalid_delegation(format!(
+                "Delegation from {} is signed by {}",
+                delegation.delegator, delegation_auth_sig.address
+            )));
+        }
+
+        let expiration = parse_time(&delegation.expiration)?;
+        if expiration < now {
+            return Err(invalid_delegation(format!(
+                "Delegation from {} expired at {}",
+                delegation.delegator, expiration
+            )));
+        }
+
+        match &parent {
+            None => session_keys.push(delegation.delegator.clone()),
+            Some((parent, parent_expiration)) => {
+                if !same_session_key(&parent.delegatee, &delegation.delegator) {
+                    return Err(invalid_delegation(format!(
+                        "Delegation chain is broken: {} delegated to {}, not {}",
+                        parent.delegator, parent.delegatee, delegation.delegator
+                    )));
+                }
+                check_attenuation(
+                    &parent.resource_ability_requests,
+                    *parent_expiration,
+                    &delegation.resource_ability_requests,
+                    expiration,
+                )?;
+            }
+        }
+
+        session_keys.push(delegation.delegatee.clone());
+        parent = Some((delegation, expiration));
+    }
+
+    // The session sig itself is the last link of the chain
+    let (last, last_expiration) = parent.expect("delegation chain is not empty");
+    if !same_session_key(&last.delegatee, session_pubkey) {
+        return Err(invalid_delegation(format!(
+            "Delegation chain ends at {}, not at session key {}",
+            last.delegatee, session_pubkey
+        )));
+    }
+    check_attenuation(
+        &last.resource_ability_requests,
+        last_expiration,
+        &session_key_signed_message.resource_ability_requests,
+        parse_time(&session_key_signed_message.expiration)?,
+    )?;
+
+    Ok(session_keys)
+}
+
+/// Fails unless the child delegation is a subset of the parent one.
+fn check_attenuation(
+    parent_requests: &[LitResourceAbilityRequest],
+    parent_expiration: DateTime<FixedOffset>,
+    child_requests: &[LitResourceAbilityRequest],
+    child_expiration: DateTime<FixedOffset>,
+) -> Result<()> {
+    if child_expiration > parent_expiration {
+        return Err(invalid_delegation(format!(
+            "Delegation expiring at {} outlives its parent expiring at {}",
+            child_expiration, parent_expiration
+        )));
+    }
+
+    for child_request in child_requests {
+        let covered = parent_requests
+            .iter()
+            .any(|parent_request| covers(parent_request, child_request));
+        if !covered {
+            return Err(invalid_delegation(format!(
+                "Delegation widens its parent with {} on {}://{}",
+                child_request.ability,
+                child_request.resource.resource_prefix,
+                child_request.resource.resource
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `parent` grants at least what `child` asks for.
+fn covers(parent: &LitResourceAbilityRequest, child: &LitResourceAbilityRequest) -> bool {
+    parent.ability == child.ability
+        && parent.resource.resource_prefix == child.resource.resource_prefix
+        && (parent.resource.resource == "*" || parent.resource.resource == child.resource.resource)
+}
+
+fn same_session_key(a: &str, b: &str) -> bool {
+    a.trim_start_matches("0x")
+        .eq_ignore_ascii_case(b.trim_start_matches("0x"))
+}
+
+fn parse_time(time: &str) -> Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(time).map_err(|e| {
+        parser_err_code(
+            e,
+            EC::NodeInvalidSessionKeyDelegation,
+            Some("Could not parse expiration of session key delegation".into()),
+        )
+    })
+}
+
+fn invalid_delegation(msg: String) -> crate::error::Error {
+    validation_err_code(msg, EC::NodeInvalidSessionKeyDelegation, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_delegation_chain;
+    use crate::error::EC;
+    use crate::models::auth::{SessionKeyDelegation, SessionKeySignedMessageV2};
+    use crate::utils::encoding;
+    use chrono::{Duration, Utc};
+    use ed25519_dalek::{Signer, SigningKey};
+    use ethers::types::U256;
+    use lit_node_core::{
+        JsonAuthSig, LitAbility, LitResourceAbilityRequest, LitResourceAbilityRequestResource,
+    };
+    use rand::rngs::OsRng;
+
+    fn request(resource: &str) -> LitResourceAbilityRequest {
+        LitResourceAbilityRequest {
+            resource: LitResourceAbilityRequestResource {
+                resource: resource.to_string(),
+                resource_prefix: "lit-accesscontrolcondition".to_string(),
+            },
+            ability: LitAbility::AccessControlConditionDecryption.to_string(),
+        }
+    }
+
+    fn session_key(signing_key: &SigningKey) -> String {
+        encoding::bytes_to_hex(signing_key.verifying_key().to_bytes())
+    }
+
+    fn delegate(
+        delegator: &SigningKey,
+        delegatee: &SigningKey,
+        resource: &str,
+        expires_in: Duration,
+    ) -> JsonAuthSig {
+        let signed_message = serde_json::to_string(&SessionKeyDelegation {
+            delegator: session_key(delegator),
+            delegatee: session_key(delegatee),
+            resource_ability_requests: vec![request(resource)],
+            issued_at: Utc::now().to_rfc3339(),
+            expiration: (Utc::now() + expires_in).to_rfc3339(),
+        })
+        .unwrap();
+        JsonAuthSig::new(
+            delegator.sign(signed_message.as_bytes()).to_string(),
+            "litSessionSignViaNacl".to_string(),
+            signed_message,
+            session_key(delegator),
+            Some("ed25519".to_string()),
+        )
+    }
+
+    fn signed_message(
+        session_key: String,
+        delegations: Vec<JsonAuthSig>,
+    ) -> SessionKeySignedMessageV2 {
+        SessionKeySignedMessageV2 {
+            session_key,
+            resource_ability_requests: vec![request("123")],
+            capabilities: vec![],
+            issued_at: Utc::now().to_rfc3339(),
+            expiration: (Utc::now() + Duration::minutes(10)).to_rfc3339(),
+            node_address: "localhost:7470".to_string(),
+            max_price: U256::MAX,
+            delegations,
+        }
+    }
+
+    #[test]
+    fn test_verify_delegation_chain() {
+        let root = SigningKey::generate(&mut OsRng);
+        let backend = SigningKey::generate(&mut OsRng);
+        let worker = SigningKey::generate(&mut OsRng);
+
+        let chain = vec![
+            delegate(&root, &backend, "*", Duration::hours(1)),
+            delegate(&backend, &worker, "123", Duration::minutes(30)),
+        ];
+        let session_keys = verify_delegation_chain(
+            &session_key(&worker),
+            &signed_message(session_key(&worker), chain),
+        )
+        .unwrap();
+        assert_eq!(
+            session_keys,
+            vec![
+                session_key(&root),
+                session_key(&backend),
+                session_key(&worker)
+            ]
+        );
+
+        // without delegations the session key is its own root
+        let session_keys = verify_delegation_chain(
+            &session_key(&root),
+            &signed_message(session_key(&root), vec![]),
+        )
+        .unwrap();
+        assert_eq!(session_keys, vec![session_key(&root)]);
+    }
+
+    #[test]
+    fn test_verify_delegation_chain_rejects_widening() {
+        let root = SigningKey::generate(&mut OsRng);
+        let backend = SigningKey::generate(&mut OsRng);
+        let worker = SigningKey::generate(&mut OsRng);
+
+        // wider resource than the parent
+        let chain = vec![
+            delegate(&root, &backend, "123", Duration::hours(1)),
+            delegate(&backend, &worker, "*", Duration::minutes(30)),
+        ];
+        let err = verify_delegation_chain(
+            &session_key(&worker),
+            &signed_message(session_key(&worker), chain),
+        )
+        .unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidSessionKeyDelegation, true));
+
+        // outlives the parent
+        let chain = vec![
+            delegate(&root, &backend, "*", Duration::minutes(30)),
+            delegate(&backend, &worker, "123", Duration::hours(1)),
+        ];
+        let err = verify_delegation_chain(
+            &session_key(&worker),
+            &signed_message(session_key(&worker), chain),
+        )
+        .unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidSessionKeyDelegation, true));
+
+        // skips a link
+        let chain = vec![delegate(&root, &backend, "*", Duration::hours(1))];
+        let err = verify_delegation_chain(
+            &session_key(&worker),
+            &signed_message(session_key(&worker), chain),
+        )
+        .unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidSessionKeyDelegation, true));
+    }
+}
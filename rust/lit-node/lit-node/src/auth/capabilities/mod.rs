@@ -1,2 +1,3 @@
+pub mod delegation;
 pub mod recap;
 pub mod session_capability_object;
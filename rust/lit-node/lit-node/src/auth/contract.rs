@@ -1,12 +1,12 @@
 use crate::error::{EC, Result, blockchain_err_code, validation_err_code};
 use encoding::hex_to_bytes;
-use ethers::middleware::SignerMiddleware;
-use ethers::signers::LocalWallet;
-use ethers::{contract::abigen, types::Bytes};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest, U256};
+use ethers::{abi::Token, contract::abigen};
 
 use lit_core::error::Unexpected;
 use lit_core::utils::binary::bytes_to_hex;
-use rand_core::OsRng;
 use sha2::Digest;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -17,6 +17,24 @@ use lit_node_core::{JsonAuthSig, constants::AUTH_SIG_DERIVED_VIA_CONTRACT_SIG_SH
 
 const VALID_SIGNATURE_WORD: &str = "1626ba7e";
 
+/// Suffix marking an ERC-6492 signature of an account that may not be deployed yet.
+const ERC6492_MAGIC_SUFFIX: [u8; 32] = [
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+];
+
+/// Creation code of the ERC-6492 `ValidateSigOffchain` contract, whose constructor returns whether
+/// a signature is valid so it can be run with an `eth_call` without being deployed.
+const ERC6492_VALIDATOR_CREATION_CODE: &str =
+    include_str!("../../config/contracts/ERC6492/ValidateSigOffchain.hex");
+
+abigen!(
+    EIP1271,
+    r#"[
+            function isValidSignature(bytes32 _hash, bytes calldata _signature) external override view returns (bytes4)
+        ]"#,
+);
+
 /// Validate a signature that is meant to be validated by a smart contract per EIP-1271.
 pub async fn validate_eip1271_signature(
     auth_sig: &JsonAuthSig,
@@ -33,17 +51,6 @@ pub async fn validate_eip1271_signature(
         .clone()
         .expect_or_err_code(EC::NodeBlockchainChainUnknown, "Empty chain value")?;
 
-    abigen!(
-        EIP1271,
-        r#"[
-                function isValidSignature(bytes32 _hash, bytes calldata _signature) external override view returns (bytes4)
-            ]"#,
-    );
-    let wallet = LocalWallet::new(&mut OsRng);
-    let provider = ENDPOINT_MANAGER.get_provider(&c)?;
-    let client = SignerMiddleware::new(provider.clone(), wallet.clone());
-    let contract = EIP1271::new(presented_address, Arc::new(client.clone()));
-
     let sig = hex_to_bytes(&auth_sig.sig)?;
 
     // Since the node hashes the passed `auth_sig.signed_message` "before" calling the `isValidSignatue()` this prevents the attacker to pull the signed hash from chain since they would need to pass the original unhashed message which should not be available on-chain under normal circumstances
@@ -54,31 +61,198 @@ pub async fn validate_eip1271_signature(
     };
     debug!("_hash: {:?}", hashed_message);
 
+    validate_smart_account_signature(presented_address, hashed_message, &sig, &c).await
+}
+
+/// Validate a signature of a smart account per EIP-1271. ERC-6492 signatures are checked with the
+/// deployless validator, which deploys the account first if it isn't deployed yet.
+pub async fn validate_smart_account_signature(
+    account: Address,
+    hash: [u8; 32],
+    signature: &[u8],
+    chain: &str,
+) -> Result<()> {
+    let provider = ENDPOINT_MANAGER.get_provider(chain)?;
+
+    if signature.ends_with(&ERC6492_MAGIC_SUFFIX) {
+        if !call_erc6492_validator(&provider, account, hash, signature).await? {
+            return Err(validation_err_code(
+                "ERC-6492 Authsig failed",
+                EC::NodeContractAuthsigUnauthorized,
+                Some(format!(
+                    "Authsig failed for account {:?}.  We sent params isValidSig({:?}, {}, {})",
+                    account,
+                    account,
+                    hex::encode(hash),
+                    bytes_to_hex(signature)
+                )),
+            ));
+        }
+        return Ok(());
+    }
+
+    let contract = EIP1271::new(account, provider.clone());
+    let returned = call_is_valid_signature(&contract, hash, signature.to_vec(), chain).await?;
+
+    let valid_result_bytes = hex_to_bytes(VALID_SIGNATURE_WORD)?;
+    if !returned.starts_with(&valid_result_bytes) {
+        return Err(validation_err_code(
+            "EIP1271 Authsig failed",
+            EC::NodeContractAuthsigUnauthorized,
+            Some(format!(
+                "Authsig failed for contract {:?}.  Return value was {}.  We sent params isValidSignature({}, {})",
+                account,
+                &bytes_to_hex(&returned),
+                hex::encode(hash),
+                bytes_to_hex(signature)
+            )),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether a signature that doesn't recover to `account` may still be a smart account signature,
+/// i.e. it's wrapped per ERC-6492 or `account` has code.
+pub(crate) async fn may_be_smart_account_signature(
+    account: Address,
+    signature: &[u8],
+    chain: &str,
+) -> Result<bool> {
+    if signature.ends_with(&ERC6492_MAGIC_SUFFIX) {
+        return Ok(true);
+    }
+
+    let provider = ENDPOINT_MANAGER.get_provider(chain)?;
+    let code = provider.get_code(account, None).await.map_err(|e| {
+        blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
+    })?;
+    Ok(!code.is_empty())
+}
+
+/// Checks that a SIWE message signed by a smart account was signed for the chain the account is
+/// verified on, so a signature checked against an account on one chain can't be replayed on
+/// another where the same address is controlled by someone else.
+pub(crate) async fn check_siwe_chain_id(siwe_chain_id: u64, chain: &str) -> Result<()> {
+    let provider = ENDPOINT_MANAGER.get_provider(chain)?;
+    let chain_id = provider.get_chainid().await.map_err(|e| {
+        blockchain_err_code(e, EC::NodeRpcError, Some("Error making RPC Call".into()))
+    })?;
+    if chain_id != U256::from(siwe_chain_id) {
+        return Err(validation_err_code(
+            format!(
+                "The SIWE message is for chain id {siwe_chain_id} but the signature is verified on {chain} (chain id {chain_id})"
+            ),
+            EC::NodeSIWEMessageError,
+            None,
+        ));
+    }
+    Ok(())
+}
+
+async fn call_is_valid_signature(
+    contract: &EIP1271<Provider<Http>>,
+    hash: [u8; 32],
+    signature: Vec<u8>,
+    chain: &str,
+) -> Result<Vec<u8>> {
     let is_valid_signature = contract
-        .is_valid_signature(hashed_message, sig.into())
+        .is_valid_signature(hash, signature.into())
         .call()
-        .await.map_err(|e| {
+        .await
+        .map_err(|e| {
             blockchain_err_code(
                 e,
                 EC::NodeBlockchainError,
-                Some(format!("Execution Reverted- Call to contract's {} isValidSignature function failed on chain {}", &auth_sig.address, c))
+                Some(format!("Execution Reverted- Call to contract's {:?} isValidSignature function failed on chain {}", contract.address(), chain))
             )
         })?;
-    let valid_result_bytes: Bytes = hex_to_bytes(VALID_SIGNATURE_WORD)?.into();
-    let is_valid_bytes: Bytes = is_valid_signature.into();
-    if is_valid_bytes != valid_result_bytes {
-        return Err(validation_err_code(
-            "EIP1271 Authsig failed",
-            EC::NodeContractAuthsigUnauthorized,
+    Ok(is_valid_signature.to_vec())
+}
+
+/// Runs the ERC-6492 deployless validator in an `eth_call`, so a counterfactual account is only
+/// deployed within the call. Returns whether the signature is valid.
+async fn call_erc6492_validator(
+    provider: &Arc<Provider<Http>>,
+    account: Address,
+    hash: [u8; 32],
+    signature: &[u8],
+) -> Result<bool> {
+    let tx: TypedTransaction = TransactionRequest::new()
+        .data(erc6492_validator_calldata(account, hash, signature)?)
+        .into();
+    let returned = provider.call(&tx, None).await.map_err(|e| {
+        blockchain_err_code(
+            e,
+            EC::NodeBlockchainError,
             Some(format!(
-                "Authsig failed for contract {}.  Return value was {}.  We sent params isValidSignature({:?}, {})",
-                &auth_sig.address,
-                &bytes_to_hex(is_valid_signature.as_ref()),
-                format!("{}", hex::encode(hashed_message)),
-                &auth_sig.sig
+                "Execution Reverted- ERC-6492 validation of {account:?} failed.  The account could not be deployed or its isValidSignature function reverted"
             )),
-        ));
+        )
+    })?;
+
+    match returned.as_ref() {
+        [1] => Ok(true),
+        [0] => Ok(false),
+        _ => Err(blockchain_err_code(
+            format!(
+                "Unexpected ERC-6492 validator result: {}",
+                bytes_to_hex(&returned)
+            ),
+            EC::NodeRpcError,
+            None,
+        )),
     }
+}
 
-    Ok(())
+/// The validator's creation code followed by its constructor arguments.
+fn erc6492_validator_calldata(account: Address, hash: [u8; 32], signature: &[u8]) -> Result<Bytes> {
+    let mut calldata = hex_to_bytes(ERC6492_VALIDATOR_CREATION_CODE.trim())?;
+    calldata.extend(ethers::abi::encode(&[
+        Token::Address(account),
+        Token::FixedBytes(hash.to_vec()),
+        Token::Bytes(signature.to_vec()),
+    ]));
+    Ok(calldata.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ERC6492_VALIDATOR_CREATION_CODE, erc6492_validator_calldata};
+    use ethers::abi::{ParamType, Token, decode};
+    use ethers::types::Address;
+
+    #[test]
+    fn test_erc6492_validator_calldata() {
+        let account = Address::repeat_byte(0x11);
+        let signature = vec![4, 5, 6];
+        let calldata = erc6492_validator_calldata(account, [7; 32], &signature).unwrap();
+
+        let creation_code = hex::decode(
+            ERC6492_VALIDATOR_CREATION_CODE
+                .trim()
+                .trim_start_matches("0x"),
+        )
+        .unwrap();
+        let (code, args) = calldata.split_at(creation_code.len());
+        assert_eq!(code, creation_code.as_slice());
+
+        let tokens = decode(
+            &[
+                ParamType::Address,
+                ParamType::FixedBytes(32),
+                ParamType::Bytes,
+            ],
+            args,
+        )
+        .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Address(account),
+                Token::FixedBytes(vec![7; 32]),
+                Token::Bytes(signature),
+            ]
+        );
+    }
 }
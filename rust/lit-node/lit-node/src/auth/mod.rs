@@ -2,5 +2,6 @@ pub mod auth_material;
 pub mod capabilities;
 pub mod contract;
 pub mod resources;
+pub mod revocation;
 pub mod session_sigs;
 pub mod validators;
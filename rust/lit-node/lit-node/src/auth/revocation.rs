@@ -0,0 +1,776 @@
+//! Revocation of session keys.
+//!
+//! The wallet or PKP that delegated to a session key can revoke it before its session sigs
+//! expire, by signing a SIWE message whose URI names the session key and whose resources name
+//! the subnet, so a revocation can't be replayed on another network. Smart account issuers are
+//! verified per EIP-1271 on the chain the request names.
+//!
+//! A node only persists revocations of session keys it has validated session sigs for, and keeps
+//! them until the capability that delegated to the session key expires, so neither what is stored
+//! nor for how long is up to the client. Revocations of session keys the node hasn't seen yet are
+//! held in memory and persisted if the session key shows up before they are evicted. The number
+//! of persisted revocations is capped across all issuers, and each issuer can only revoke a
+//! limited number of session keys per hour, since the endpoint is free.
+//!
+//! A node that persists a revocation forwards it to its peers, which do the same the first time
+//! they persist it. Nodes that miss the gossip, e.g. because they were down, pull the live
+//! revocations from their peers on startup and whenever the epoch changes.
+
+use crate::auth::contract::{
+    check_siwe_chain_id, may_be_smart_account_signature, validate_smart_account_signature,
+};
+use crate::error::{EC, Result, conversion_err, parser_err_code, validation_err_code};
+use crate::siwe_db::db::{
+    db_get_session_revocation_requests, db_get_session_revocations, db_insert_session_revocation,
+};
+use crate::siwe_db::utils::MAX_TIMESTAMP_VALIDITY_DAYS;
+use crate::tss::common::tss_state::TssState;
+use crate::utils::encoding;
+use chrono::{Duration, Utc};
+use ethers::types::{Address, H256, Signature};
+use lit_core::config::LitConfig;
+use lit_node_common::config::LitNodeConfig;
+use lit_node_core::JsonAuthSig;
+use lit_node_core::request::JsonSessionRevocationRequest;
+use lit_node_core::response::GenericResponse;
+use moka::future::Cache;
+use siwe::Message;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tracing::{Instrument, debug, debug_span, error, info, warn};
+
+pub(crate) const REVOKE_SESSION_KEY_PATH: &str = "/web/session_sig/revoke/v2";
+pub(crate) const SESSION_REVOCATIONS_PATH: &str = "/web/session_sig/revocations/v2";
+
+/// The URI of a revocation is this prefix followed by the revoked session key.
+pub(crate) const REVOCATION_URI_PREFIX: &str = "lit:revoke:";
+/// A revocation is only valid on the subnet named by a resource with this prefix.
+pub(crate) const SUBNET_RESOURCE_PREFIX: &str = "lit-subnet:";
+
+/// How many session keys a single issuer can revoke per hour.
+const MAX_REVOCATIONS_PER_ISSUER_PER_HOUR: u32 = 60;
+
+/// How many issuers are rate limited at once. Further issuers are turned away until the hour ends.
+const MAX_RATE_LIMITED_ISSUERS: usize = 100_000;
+
+/// How many live revocations a node persists across all issuers.
+const MAX_REVOCATIONS: usize = 100_000;
+
+/// How many revocations of session keys the node hasn't seen yet are held in memory.
+const MAX_PENDING_REVOCATIONS: u64 = 10_000;
+
+/// How many session keys the node remembers validating session sigs for.
+const MAX_SEEN_SESSIONS: u64 = 1_000_000;
+
+/// How often the sync worker checks whether the epoch changed.
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Revoked session keys and their issuers, mapped to when the revocation expires.
+static REVOKED_SESSIONS: LazyLock<RwLock<HashMap<(String, String), i64>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// The number of revocations accepted from each issuer, and the hour they were counted in.
+static ISSUER_REVOCATION_COUNTS: LazyLock<Mutex<HashMap<String, (i64, u32)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Session keys the node validated session sigs for and their issuers, mapped to when the
+/// capability that delegated to them expires.
+static SEEN_SESSIONS: LazyLock<Cache<(String, String), i64>> = LazyLock::new(|| {
+    Cache::builder()
+        .max_capacity(MAX_SEEN_SESSIONS)
+        .time_to_live(max_validity())
+        .build()
+});
+
+/// Revocations of session keys the node hasn't seen yet, by session key and issuer.
+static PENDING_REVOCATIONS: LazyLock<Cache<(String, String), JsonSessionRevocationRequest>> =
+    LazyLock::new(|| {
+        Cache::builder()
+            .max_capacity(MAX_PENDING_REVOCATIONS)
+            .time_to_live(max_validity())
+            .build()
+    });
+
+/// A session key revoked by the wallet or PKP that delegated to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevokedSession {
+    /// The hex encoded session public key, without a `0x` prefix.
+    pub session_key: String,
+    /// The lowercase address of the wallet or PKP that revoked the session key.
+    pub issuer: String,
+    /// Unix timestamp after which the revoked session sigs are expired anyway.
+    pub expires_at: i64,
+}
+
+/// A revocation whose signature has been verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Revocation {
+    /// The hex encoded session public key, without a `0x` prefix.
+    pub session_key: String,
+    /// The lowercase address of the wallet or PKP that revoked the session key.
+    pub issuer: String,
+}
+
+/// Verifies that a revocation is meant for `subnet_id` and signed by its issuer, and returns
+/// what it revokes.
+pub(crate) async fn verify_revocation(
+    request: &JsonSessionRevocationRequest,
+    subnet_id: &str,
+) -> Result<Revocation> {
+    let auth_sig = &request.auth_sig;
+    let message: Message = auth_sig.signed_message.parse().map_err(|e| {
+        parser_err_code(
+            e,
+            EC::NodeInvalidSessionRevocation,
+            Some("Error parsing the session revocation".into()),
+        )
+    })?;
+
+    let session_key = message
+        .uri
+        .as_str()
+        .strip_prefix(REVOCATION_URI_PREFIX)
+        .ok_or_else(|| {
+            validation_err_code(
+                format!(
+                    "The URI of a session revocation must be {}<session key>",
+                    REVOCATION_URI_PREFIX
+                ),
+                EC::NodeInvalidSessionRevocation,
+                None,
+            )
+        })?;
+    let subnet_resource = format!("{}{}", SUBNET_RESOURCE_PREFIX, subnet_id);
+    if !message
+        .resources
+        .iter()
+        .any(|resource| resource.as_str().eq_ignore_ascii_case(&subnet_resource))
+    {
+        return Err(validation_err_code(
+            format!("Session revocation is not for subnet {}", subnet_id),
+            EC::NodeInvalidSessionRevocation,
+            None,
+        ));
+    }
+
+    let issuer = Address::from(message.address);
+    let presented_issuer = Address::from_str(&auth_sig.address)
+        .map_err(|e| validation_err_code(e, EC::NodeAuthSigAddressConversionError, None))?;
+    if issuer != presented_issuer {
+        return Err(validation_err_code(
+            "The address in the session revocation does not match the address in the auth sig",
+            EC::NodeInvalidSessionRevocation,
+            None,
+        ));
+    }
+    verify_issuer_signature(&message, issuer, auth_sig, request.chain.as_deref()).await?;
+
+    Ok(Revocation {
+        session_key: normalize_session_key(session_key),
+        issuer: format!("{:#x}", issuer),
+    })
+}
+
+/// Accepts a revocation sent by a client or forwarded by a peer. Returns whether it was
+/// persisted and should be gossiped.
+pub(crate) async fn revoke(
+    revocation: Revocation,
+    request: &JsonSessionRevocationRequest,
+    port: u16,
+) -> Result<bool> {
+    if is_revoked(&revocation.session_key, &revocation.issuer) {
+        return Ok(false);
+    }
+
+    check_rate_limit(&revocation.issuer)?;
+    accept(revocation, request, port).await
+}
+
+/// Notes that `issuer` delegated to `session_key` until `expires_at`, so that it can revoke the
+/// session key, and persists a revocation of it that arrived before the session key was seen.
+pub(crate) async fn record_seen_session(
+    session_key: &str,
+    issuer: &str,
+    expires_at: i64,
+    port: u16,
+) -> Result<()> {
+    let key = (normalize_session_key(session_key), normalize_issuer(issuer));
+    let max_expiration = (Utc::now() + Duration::days(MAX_TIMESTAMP_VALIDITY_DAYS)).timestamp();
+    let mut expires_at = expires_at.min(max_expiration);
+    match SEEN_SESSIONS.get(&key).await {
+        Some(seen_expires_at) if seen_expires_at >= expires_at => expires_at = seen_expires_at,
+        _ => SEEN_SESSIONS.insert(key.clone(), expires_at).await,
+    }
+
+    if let Some(request) = PENDING_REVOCATIONS.remove(&key).await {
+        let (session_key, issuer) = key;
+        record(
+            RevokedSession {
+                session_key,
+                issuer,
+                expires_at,
+            },
+            &request,
+            port,
+        )?;
+    }
+    Ok(())
+}
+
+/// When the capability that delegated to a session key expires. Capabilities without an
+/// expiration are taken to last as long as any session sig can.
+pub(crate) fn capability_expiration(capability: &JsonAuthSig) -> i64 {
+    let max_expiration = (Utc::now() + Duration::days(MAX_TIMESTAMP_VALIDITY_DAYS)).timestamp();
+    capability
+        .signed_message
+        .parse::<Message>()
+        .ok()
+        .and_then(|message| message.expiration_time)
+        .map(|expiration| expiration.as_ref().unix_timestamp())
+        .map_or(max_expiration, |expiration| expiration.min(max_expiration))
+}
+
+/// Returns the signed requests of the live revocations, for peers to catch up from.
+pub(crate) fn live_revocation_requests(port: u16) -> Result<Vec<JsonSessionRevocationRequest>> {
+    db_get_session_revocation_requests(port, Utc::now().timestamp())?
+        .iter()
+        .map(|request| serde_json::from_str(request).map_err(|e| conversion_err(e, None)))
+        .collect()
+}
+
+/// Loads the persisted revocations that are still live. Called once on startup.
+pub fn load(port: u16) -> Result<()> {
+    for revoked in db_get_session_revocations(port, Utc::now().timestamp())? {
+        insert(revoked)?;
+    }
+    Ok(())
+}
+
+/// Fails if `issuer` revoked `session_key`.
+pub(crate) fn check_not_revoked(session_key: &str, issuer: &str) -> Result<()> {
+    let issuer = normalize_issuer(issuer);
+    if is_revoked(&normalize_session_key(session_key), &issuer) {
+        return Err(validation_err_code(
+            format!("Session key {} was revoked by {}", session_key, issuer),
+            EC::NodeSessionSigRevoked,
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Forwards a newly learned revocation to the other nodes.
+pub(crate) async fn forward_to_peers(
+    request: JsonSessionRevocationRequest,
+    tss_state: Arc<TssState>,
+    cfg: Arc<LitConfig>,
+    http_client: reqwest::Client,
+) {
+    let peers = tss_state
+        .peer_state
+        .peers()
+        .active_peers()
+        .all_peers_except(&tss_state.addr);
+    let prefix = cfg.http_prefix_when_talking_to_other_nodes();
+
+    for peer in peers.0 {
+        let url = format!(
+            "{}{}{}",
+            prefix, peer.socket_address, REVOKE_SESSION_KEY_PATH
+        );
+        let res = http_client
+            .post(&url)
+            .json(&request)
+            .send()
+            .instrument(debug_span!("forward_session_revocation"))
+            .await;
+        if let Err(e) = res {
+            warn!(
+                "Failed to forward session revocation to {}: {:?}",
+                peer.socket_address, e
+            );
+        }
+    }
+}
+
+/// Pulls the live revocations from the other nodes and records the ones this node missed.
+/// Returns whether all the peers could be synced from.
+pub(crate) async fn sync_from_peers(
+    tss_state: &TssState,
+    cfg: &LitConfig,
+    http_client: &reqwest::Client,
+    port: u16,
+) -> bool {
+    let peers = tss_state
+        .peer_state
+        .peers()
+        .active_peers()
+        .all_peers_except(&tss_state.addr);
+    let prefix = cfg.http_prefix_when_talking_to_other_nodes();
+    let subnet_id = match cfg.subnet_id() {
+        Ok(subnet_id) => subnet_id,
+        Err(e) => {
+            error!("Error getting subnet id from config: {}", e);
+            return false;
+        }
+    };
+    let subnet_id = subnet_id.as_str();
+
+    let mut synced = true;
+    for peer in peers.0 {
+        let url = format!(
+            "{}{}{}",
+            prefix, peer.socket_address, SESSION_REVOCATIONS_PATH
+        );
+        let res = match http_client
+            .get(&url)
+            .send()
+            .instrument(debug_span!("sync_session_revocations"))
+            .await
+        {
+            Ok(res) => {
+                res.json::<GenericResponse<Vec<JsonSessionRevocationRequest>>>()
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        let requests = match res {
+            Ok(GenericResponse {
+                data: Some(requests),
+                ..
+            }) => requests,
+            Ok(res) => {
+                warn!(
+                    "Failed to sync session revocations from {}: {:?}",
+                    peer.socket_address, res.error
+                );
+                synced = false;
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to sync session revocations from {}: {:?}",
+                    peer.socket_address, e
+                );
+                synced = false;
+                continue;
+            }
+        };
+
+        let mut learned = 0;
+        for request in requests {
+            // peers aren't trusted, so every revocation must still be signed by its issuer
+            let revocation = match verify_revocation(&request, subnet_id).await {
+                Ok(revocation) => revocation,
+                Err(e) => {
+                    debug!(
+                        "Skipping session revocation synced from {}: {:?}",
+                        peer.socket_address, e
+                    );
+                    continue;
+                }
+            };
+            if is_revoked(&revocation.session_key, &revocation.issuer) {
+                continue;
+            }
+            match accept(revocation, &request, port).await {
+                Ok(true) => learned += 1,
+                Ok(false) => {}
+                Err(e) => warn!("Failed to record synced session revocation: {:?}", e),
+            }
+        }
+        if learned > 0 {
+            info!(
+                "Learned {} session revocations from {}",
+                learned, peer.socket_address
+            );
+        }
+    }
+
+    synced
+}
+
+/// Catches up on the revocations gossiped while this node wasn't listening, once on startup
+/// and again whenever the epoch changes.
+pub async fn sync_worker(
+    mut quit_rx: mpsc::Receiver<bool>,
+    tss_state: Arc<TssState>,
+    http_client: reqwest::Client,
+) {
+    info!("Starting: session revocation sync");
+
+    let mut synced_epoch = None;
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = quit_rx.recv() => {
+                info!("Stopped: session revocation sync");
+                break;
+            }
+            _ = interval.tick() => {
+                // Continue below.
+            }
+        }
+
+        let epoch = tss_state.peer_state.epoch();
+        if synced_epoch == Some(epoch) {
+            continue;
+        }
+
+        let cfg = tss_state.lit_config.load_full();
+        let port = match cfg.external_port() {
+            Ok(port) => port,
+            Err(e) => {
+                error!("Error getting external port from config: {}", e);
+                continue;
+            }
+        };
+        // retried on the next tick until every peer answered
+        if sync_from_peers(&tss_state, &cfg, &http_client, port).await {
+            synced_epoch = Some(epoch);
+        }
+    }
+}
+
+/// Persists a revocation of a session key this node has seen, or holds it until the node sees
+/// the session key. Returns whether it was persisted.
+async fn accept(
+    revocation: Revocation,
+    request: &JsonSessionRevocationRequest,
+    port: u16,
+) -> Result<bool> {
+    let key = (revocation.session_key, revocation.issuer);
+    match SEEN_SESSIONS.get(&key).await {
+        Some(expires_at) => {
+            let (session_key, issuer) = key;
+            record(
+                RevokedSession {
+                    session_key,
+                    issuer,
+                    expires_at,
+                },
+                request,
+                port,
+            )?;
+            Ok(true)
+        }
+        None => {
+            PENDING_REVOCATIONS.insert(key, request.clone()).await;
+            Ok(false)
+        }
+    }
+}
+
+fn record(
+    revoked: RevokedSession,
+    request: &JsonSessionRevocationRequest,
+    port: u16,
+) -> Result<()> {
+    let request = serde_json::to_string(request).map_err(|e| conversion_err(e, None))?;
+    insert(revoked.clone())?;
+    db_insert_session_revocation(port, &revoked, &request)
+}
+
+/// Verifies the issuer's EIP-191 signature of a revocation, falling back to EIP-1271 for smart
+/// account issuers when the request names their chain.
+async fn verify_issuer_signature(
+    message: &Message,
+    issuer: Address,
+    auth_sig: &JsonAuthSig,
+    chain: Option<&str>,
+) -> Result<()> {
+    let sig = encoding::hex_to_bytes(&auth_sig.sig).map_err(|e| {
+        parser_err_code(
+            e,
+            EC::NodeInvalidSessionRevocation,
+            Some("Error parsing the signature".into()),
+        )
+    })?;
+    let hash = message.eip191_hash().map_err(|e| {
+        parser_err_code(
+            e,
+            EC::NodeInvalidSessionRevocation,
+            Some("Error hashing the session revocation".into()),
+        )
+    })?;
+
+    let eoa_err = match Signature::try_from(sig.as_slice()) {
+        Ok(signature) => match signature.verify(H256::from(hash), issuer) {
+            Ok(()) => return Ok(()),
+            Err(e) => validation_err_code(
+                e,
+                EC::NodeInvalidSessionRevocation,
+                Some("Session revocation is not signed by its issuer".into()),
+            ),
+        },
+        Err(e) => parser_err_code(
+            e,
+            EC::NodeInvalidSessionRevocation,
+            Some("Error parsing the signature".into()),
+        ),
+    };
+    let Some(chain) = chain else {
+        return Err(eoa_err);
+    };
+    if !may_be_smart_account_signature(issuer, &sig, chain).await? {
+        return Err(eoa_err);
+    }
+    check_siwe_chain_id(message.chain_id, chain).await?;
+    validate_smart_account_signature(issuer, hash, &sig, chain).await
+}
+
+/// Fails if `issuer` already revoked too many session keys this hour.
+fn check_rate_limit(issuer: &str) -> Result<()> {
+    let hour = Utc::now().timestamp() / 3600;
+    let mut counts = ISSUER_REVOCATION_COUNTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    counts.retain(|_, (counted_in, _)| *counted_in == hour);
+
+    if !counts.contains_key(issuer) && counts.len() >= MAX_RATE_LIMITED_ISSUERS {
+        return Err(validation_err_code(
+            "Too many issuers revoked session keys this hour",
+            EC::NodeSessionRevocationRateLimited,
+            None,
+        ));
+    }
+    let (_, count) = counts.entry(issuer.to_string()).or_insert((hour, 0));
+    if *count >= MAX_REVOCATIONS_PER_ISSUER_PER_HOUR {
+        return Err(validation_err_code(
+            format!(
+                "{} already revoked {} session keys this hour",
+                issuer, MAX_REVOCATIONS_PER_ISSUER_PER_HOUR
+            ),
+            EC::NodeSessionRevocationRateLimited,
+            None,
+        ));
+    }
+    *count += 1;
+    Ok(())
+}
+
+/// Adds a revocation to the in-memory list, failing if the list is full.
+fn insert(revoked: RevokedSession) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let mut revoked_sessions = REVOKED_SESSIONS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    revoked_sessions.retain(|_, expires_at| *expires_at > now);
+
+    let key = (revoked.session_key, revoked.issuer);
+    if !revoked_sessions.contains_key(&key) && revoked_sessions.len() >= MAX_REVOCATIONS {
+        return Err(validation_err_code(
+            format!(
+                "The node already holds {} session revocations",
+                MAX_REVOCATIONS
+            ),
+            EC::NodeSessionRevocationRateLimited,
+            None,
+        ));
+    }
+    revoked_sessions.insert(key, revoked.expires_at);
+    Ok(())
+}
+
+fn is_revoked(session_key: &str, issuer: &str) -> bool {
+    let revoked_sessions = REVOKED_SESSIONS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    revoked_sessions
+        .get(&(session_key.to_string(), issuer.to_string()))
+        .is_some_and(|expires_at| *expires_at > Utc::now().timestamp())
+}
+
+fn max_validity() -> std::time::Duration {
+    std::time::Duration::from_secs(MAX_TIMESTAMP_VALIDITY_DAYS as u64 * 24 * 60 * 60)
+}
+
+fn normalize_session_key(session_key: &str) -> String {
+    session_key.trim_start_matches("0x").to_lowercase()
+}
+
+fn normalize_issuer(issuer: &str) -> String {
+    match Address::from_str(issuer) {
+        Ok(issuer) => format!("{:#x}", issuer),
+        Err(_) => issuer.to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MAX_REVOCATIONS_PER_ISSUER_PER_HOUR, PENDING_REVOCATIONS, REVOCATION_URI_PREFIX,
+        RevokedSession, SEEN_SESSIONS, SUBNET_RESOURCE_PREFIX, capability_expiration,
+        check_not_revoked, check_rate_limit, insert, revoke, verify_revocation,
+    };
+    use crate::error::EC;
+    use chrono::{Duration, Utc};
+    use ethers::prelude::rand::rngs::OsRng as EthersOsRng;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::H256;
+    use lit_node_core::JsonAuthSig;
+    use lit_node_core::request::JsonSessionRevocationRequest;
+    use siwe::Message;
+
+    const SUBNET_ID: &str = "c0ffee254729296a45a3885639ac7e10f9d54979";
+
+    fn revocation_message(wallet: &LocalWallet, uri: &str, subnet_id: &str) -> Message {
+        Message {
+            domain: "localhost:3000".parse().unwrap(),
+            address: wallet.address().into(),
+            statement: Some("Revoke a Lit Protocol session key".into()),
+            uri: uri.parse().unwrap(),
+            version: siwe::Version::V1,
+            chain_id: 1,
+            nonce: "JIsknRumpxsM9pqmc".into(),
+            issued_at: Utc::now().to_rfc3339().parse().unwrap(),
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: vec![
+                format!("{}{}", SUBNET_RESOURCE_PREFIX, subnet_id)
+                    .parse()
+                    .unwrap(),
+            ],
+        }
+    }
+
+    fn revocation_request(wallet: &LocalWallet, message: &Message) -> JsonSessionRevocationRequest {
+        let sig = wallet
+            .sign_hash(H256::from(message.eip191_hash().unwrap()))
+            .unwrap();
+        JsonSessionRevocationRequest {
+            auth_sig: JsonAuthSig::new(
+                sig.to_string(),
+                "web3.eth.personal.sign".to_string(),
+                message.to_string(),
+                format!("{:#x}", wallet.address()),
+                None,
+            ),
+            chain: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_revocation() {
+        let wallet = LocalWallet::new(&mut EthersOsRng);
+        let uri = format!("{}0xABCDEF", REVOCATION_URI_PREFIX);
+        let request = revocation_request(&wallet, &revocation_message(&wallet, &uri, SUBNET_ID));
+
+        let revocation = verify_revocation(&request, SUBNET_ID).await.unwrap();
+        assert_eq!(revocation.session_key, "abcdef");
+        assert_eq!(revocation.issuer, format!("{:#x}", wallet.address()));
+
+        // only the issuer can revoke its session keys
+        let mut forged = request.clone();
+        forged.auth_sig.address = format!("{:#x}", LocalWallet::new(&mut EthersOsRng).address());
+        let err = verify_revocation(&forged, SUBNET_ID).await.unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidSessionRevocation, true));
+
+        // a revocation for another network can't be replayed on this one
+        let err = verify_revocation(&request, "deadbeef254729296a45a3885639ac7e10f9d549")
+            .await
+            .unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidSessionRevocation, true));
+
+        // nor can a session delegation be passed off as a revocation
+        let delegation = revocation_request(
+            &wallet,
+            &revocation_message(&wallet, "lit:session:abcdef", SUBNET_ID),
+        );
+        let err = verify_revocation(&delegation, SUBNET_ID).await.unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidSessionRevocation, true));
+    }
+
+    #[tokio::test]
+    async fn test_revocations_of_unseen_session_keys_are_held() {
+        let wallet = LocalWallet::new(&mut EthersOsRng);
+        let uri = format!("{}0123456789", REVOCATION_URI_PREFIX);
+        let request = revocation_request(&wallet, &revocation_message(&wallet, &uri, SUBNET_ID));
+        let revocation = verify_revocation(&request, SUBNET_ID).await.unwrap();
+        let key = (revocation.session_key.clone(), revocation.issuer.clone());
+
+        // nothing is persisted, so the port is never used
+        assert!(!revoke(revocation, &request, 0).await.unwrap());
+        assert!(SEEN_SESSIONS.get(&key).await.is_none());
+        assert!(PENDING_REVOCATIONS.get(&key).await.is_some());
+        assert!(check_not_revoked(&key.0, &key.1).is_ok());
+    }
+
+    #[test]
+    fn test_capability_expiration() {
+        let wallet = LocalWallet::new(&mut EthersOsRng);
+        let mut message = revocation_message(&wallet, "lit:session:abcdef", SUBNET_ID);
+        let capability = |message: &Message| {
+            JsonAuthSig::new(
+                "".to_string(),
+                "web3.eth.personal.sign".to_string(),
+                message.to_string(),
+                format!("{:#x}", wallet.address()),
+                None,
+            )
+        };
+        let max_expiration = (Utc::now() + Duration::days(30)).timestamp();
+
+        assert!(capability_expiration(&capability(&message)) >= max_expiration);
+
+        let expiration = Utc::now() + Duration::hours(1);
+        message.expiration_time = Some(expiration.to_rfc3339().parse().unwrap());
+        assert_eq!(
+            capability_expiration(&capability(&message)),
+            expiration.timestamp()
+        );
+
+        // no capability outlives the longest a session sig may be valid for
+        message.expiration_time = Some(
+            (Utc::now() + Duration::days(365))
+                .to_rfc3339()
+                .parse()
+                .unwrap(),
+        );
+        assert!(capability_expiration(&capability(&message)) <= max_expiration + 1);
+    }
+
+    #[test]
+    fn test_check_not_revoked() {
+        let issuer = "0x00000000000000000000000000000000000000aa";
+        insert(RevokedSession {
+            session_key: "0123".to_string(),
+            issuer: issuer.to_string(),
+            expires_at: Utc::now().timestamp() + 60,
+        })
+        .unwrap();
+        insert(RevokedSession {
+            session_key: "4567".to_string(),
+            issuer: issuer.to_string(),
+            expires_at: Utc::now().timestamp() - 60,
+        })
+        .unwrap();
+
+        let err =
+            check_not_revoked("0123", &issuer.to_uppercase().replace("0X", "0x")).unwrap_err();
+        assert!(err.is_code(EC::NodeSessionSigRevoked, true));
+        // revoked by someone else
+        assert!(check_not_revoked("0123", "0x00000000000000000000000000000000000000bb").is_ok());
+        // revocation expired along with the session
+        assert!(check_not_revoked("4567", issuer).is_ok());
+    }
+
+    #[test]
+    fn test_check_rate_limit() {
+        let issuer = "0x00000000000000000000000000000000000000cc";
+        for _ in 0..MAX_REVOCATIONS_PER_ISSUER_PER_HOUR {
+            assert!(check_rate_limit(issuer).is_ok());
+        }
+
+        let err = check_rate_limit(issuer).unwrap_err();
+        assert!(err.is_code(EC::NodeSessionRevocationRateLimited, true));
+        // other issuers are unaffected
+        assert!(check_rate_limit("0x00000000000000000000000000000000000000dd").is_ok());
+    }
+}
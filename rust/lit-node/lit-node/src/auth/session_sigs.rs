@@ -10,7 +10,9 @@ use crate::utils::encoding;
 use chrono::{DateTime, Utc};
 use lit_api_core::config::LitApiConfig;
 
+use super::capabilities::delegation::verify_delegation_chain;
 use super::resources::{get_resource_prefix_id_from_type, parse_resource_and_prefix};
+use super::revocation::{capability_expiration, check_not_revoked, record_seen_session};
 use super::validators::auth_sig::SessionSigAuthSigValidator;
 use super::validators::siwe::SiweValidator;
 use lit_node_core::{AuthMaterialType, JsonAuthSig, LitResource, LitResourceAbility};
@@ -49,14 +51,22 @@ pub async fn extract_wallet_sig(
 ) -> Result<JsonAuthSig> {
     // Parse session key signed message
     let session_key_signed_message = parse_signed_message(&session_sig.signed_message)?;
+    let session_keys = verify_delegation_chain(
+        &session_key_signed_message.session_key,
+        &session_key_signed_message,
+    )?;
 
     let valid_inner_auth_sig = validate_capabilities_array_basic(
-        &session_key_signed_message.session_key,
+        &session_keys[0],
         &session_key_signed_message.capabilities,
         bls_root_pubkey,
     )
     .await?;
 
+    for session_key in &session_keys {
+        check_not_revoked(session_key, &valid_inner_auth_sig.address)?;
+    }
+
     Ok(valid_inner_auth_sig.to_owned())
 }
 
@@ -85,6 +95,8 @@ pub fn validate_and_extract_wallet_sig(session_sig: &JsonAuthSig) -> Result<Json
             .add_source_to_details()
         })?;
 
+    let session_keys = verify_delegation_chain(&session_sig.address, &session_key_signed_message)?;
+
     // parse it and make sure it signed the right key
     let signed_message = wallet_sig
         .signed_message
@@ -98,7 +110,7 @@ pub fn validate_and_extract_wallet_sig(session_sig: &JsonAuthSig) -> Result<Json
             .add_source_to_details()
         })?;
     // confirm that the signed session key matches the one we verified above
-    let formatted_ed25519_pubkey = format!("lit:session:{}", session_sig.address);
+    let formatted_ed25519_pubkey = format!("lit:session:{}", session_keys[0]);
     if signed_message.uri != formatted_ed25519_pubkey {
         return Err(parser_err_code(
             "Signed session key does not match the one we verified above",
@@ -108,6 +120,10 @@ pub fn validate_and_extract_wallet_sig(session_sig: &JsonAuthSig) -> Result<Json
         .add_source_to_details());
     }
 
+    for session_key in &session_keys {
+        check_not_revoked(session_key, &wallet_sig.address)?;
+    }
+
     Ok(wallet_sig.to_owned())
 }
 
@@ -157,11 +173,15 @@ pub(crate) async fn validate_session_sig(
         .add_source_to_details());
     }
 
+    // Walk any re-delegations back to the session key the capabilities were issued to
+    let session_keys = verify_delegation_chain(&session_pubkey, &session_key_signed_message)?;
+
     // Lastly, check that the resource id is authed in the capabilities
     let valid_auth_sig = validate_capabilities_array(
-        &session_pubkey,
+        &session_keys[0],
         &session_key_signed_message.capabilities,
         requested_lit_resource_ability,
+        chain,
         bls_root_pubkey,
     )
     .await
@@ -174,6 +194,14 @@ pub(crate) async fn validate_session_sig(
         .add_msg_to_details()
     })?;
 
+    // Let the issuer revoke the session keys it delegated to, and reject them, along with
+    // everything they delegated to, if it has
+    let expires_at = capability_expiration(valid_auth_sig);
+    for session_key in &session_keys {
+        record_seen_session(session_key, &valid_auth_sig.address, expires_at, port).await?;
+        check_not_revoked(session_key, &valid_auth_sig.address)?;
+    }
+
     Ok(valid_auth_sig.to_owned())
 }
 
@@ -316,6 +344,7 @@ async fn validate_capabilities_array<'a>(
     session_pubkey: &str,
     capabilities: &'a Vec<JsonAuthSig>,
     requested_lit_resource_ability: &LitResourceAbility,
+    chain: &Option<String>,
     bls_root_pubkey: &str,
 ) -> Result<&'a JsonAuthSig> {
     if capabilities.is_empty() {
@@ -337,6 +366,7 @@ async fn validate_capabilities_array<'a>(
                 inner_auth_sig,
                 session_pubkey,
                 requested_lit_resource_ability,
+                chain,
                 bls_root_pubkey,
             )
             .await;
@@ -380,7 +410,7 @@ async fn validate_capabilities_array_basic<'a>(
         let validation_res = match inner_auth_sig.auth_material_type {
             AuthMaterialType::WalletSig => {
                 auth_sig_validator
-                    .validate_auth_sig_basic(inner_auth_sig, session_pubkey)
+                    .validate_auth_sig_basic(inner_auth_sig, session_pubkey, &None)
                     .await
             }
             AuthMaterialType::BLSNetworkSig => {
@@ -388,9 +418,14 @@ async fn validate_capabilities_array_basic<'a>(
                     .validate_bls_auth_sig_basic(inner_auth_sig, session_pubkey, bls_root_pubkey)
                     .await
             }
+            AuthMaterialType::PasskeySig => {
+                auth_sig_validator
+                    .validate_passkey_auth_sig_basic(inner_auth_sig, session_pubkey)
+                    .await
+            }
             _ => {
                 return Err(validation_err_code(
-                    "The auth sig material type is not supported when calling validate_capabilities_array_basic() within session sig validation.  Only WalletSig and BLSNetworkSig are supported.",
+                    "The auth sig material type is not supported when calling validate_capabilities_array_basic() within session sig validation.  Only WalletSig, BLSNetworkSig and PasskeySig are supported.",
                     EC::NodeSIWEMessageError,
                     None,
                 ));
@@ -451,7 +486,8 @@ mod validate_session_sig_tests {
     use rand::rngs::OsRng;
     use siwe::Message;
 
-    use crate::auth::session_sigs::validate_session_sig;
+    use crate::auth::session_sigs::{extract_wallet_sig, validate_session_sig};
+    use crate::auth::validators::webauthn::tests::{passkey_auth_sig, test_passkey_address};
     use crate::error::EC;
     use crate::models::auth::SessionKeySignedMessageV2;
     use crate::tests::common::{get_test_config, get_test_config_with_key};
@@ -585,6 +621,7 @@ mod validate_session_sig_tests {
             expiration: "2021-01-01T00:00:00Z".to_string(),
             node_address: "abc".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
         };
 
         // serialize to JSON string
@@ -643,6 +680,7 @@ mod validate_session_sig_tests {
             expiration: "2021-01-01T00:00:00Z".to_string(),
             node_address: "abc".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
         };
 
         // serialize to JSON string
@@ -703,6 +741,7 @@ mod validate_session_sig_tests {
             expiration: expiration.to_string(),
             node_address: "abc".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
         };
 
         // serialize to JSON string
@@ -760,6 +799,7 @@ mod validate_session_sig_tests {
             expiration: expiration.to_string(),
             node_address: "localhost:7470".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
         };
 
         // serialize to JSON string
@@ -858,6 +898,7 @@ mod validate_session_sig_tests {
             expiration,
             node_address: "localhost:7470".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
         };
 
         // serialize to JSON string
@@ -894,6 +935,123 @@ mod validate_session_sig_tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_validate_passkey_capability_success() {
+        let lit_config = get_test_config_with_key(Some("node".into()));
+
+        // Generate ed25519 keypair for signing.
+        let mut csprng = OsRng {};
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut csprng);
+        let verifying_key = signing_key.verifying_key();
+        let session_pub_key = encoding::bytes_to_hex(verifying_key.to_bytes());
+
+        // Generate a passkey for signing the SIWE message.
+        let passkey = p256::SecretKey::random(&mut OsRng);
+
+        let lit_resource = AccessControlConditionResource::new(
+            "524a697a410a417fb95a9f52d57cba5fa7c87b3acd3b408cf14560fa52691251".into(),
+        );
+
+        let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.fZ").to_string();
+        let expiration = (Utc::now() + Duration::days(1))
+            .format("%Y-%m-%dT%H:%M:%S%.fZ")
+            .to_string();
+
+        let siwe_message = Message {
+            domain: "localhost:7470".parse().unwrap(),
+            address: test_passkey_address(&passkey).into(),
+            statement: Some(r#"Some custom statement. I further authorize the stated URI to perform the following actions on my behalf: (1) '*': '*' for 'lit-accesscontrolcondition://524a697a410a417fb95a9f52d57cba5fa7c87b3acd3b408cf14560fa52691251'."#.into()),
+            uri: format!("lit:session:{}", session_pub_key).parse().unwrap(),
+            version: siwe::Version::V1,
+            chain_id: 1,
+            nonce: "JIsknRumpxsM9pqmc".into(),
+            issued_at: now.parse().unwrap(),
+            expiration_time: Some(expiration.parse().unwrap()),
+            not_before: None,
+            request_id: None,
+            resources: vec!["urn:recap:eyJhdHQiOnsibGl0LWFjY2Vzc2NvbnRyb2xjb25kaXRpb246Ly81MjRhNjk3YTQxMGE0MTdmYjk1YTlmNTJkNTdjYmE1ZmE3Yzg3YjNhY2QzYjQwOGNmMTQ1NjBmYTUyNjkxMjUxIjp7IiovKiI6W3t9XX19LCJwcmYiOltdfQo=".parse().unwrap()],
+        };
+
+        // Sign SIWE message with the passkey.
+        let auth_sig = passkey_auth_sig(
+            &passkey,
+            &siwe_message.to_string(),
+            "localhost",
+            "https://localhost",
+        );
+
+        let session_key_signed_message = SessionKeySignedMessageV2 {
+            session_key: session_pub_key.clone(),
+            resource_ability_requests: vec![LitResourceAbilityRequest {
+                resource: LitResourceAbilityRequestResource {
+                    resource: lit_resource.get_resource_id().to_owned(),
+                    resource_prefix: lit_resource.get_resource_prefix().to_string(),
+                },
+                ability: LitAbility::AccessControlConditionDecryption.to_string(),
+            }],
+            capabilities: vec![auth_sig.clone()],
+            issued_at: now,
+            expiration,
+            node_address: "localhost:7470".to_string(),
+            max_price: U256::MAX,
+            delegations: vec![],
+        };
+
+        // serialize to JSON string
+        let message = serde_json::to_string(&session_key_signed_message).unwrap();
+        let signature: Signature = signing_key.sign(message.as_bytes());
+
+        let session_sig = JsonAuthSig::new(
+            signature.to_string(),
+            "ghi".to_string(),
+            message,
+            session_pub_key.clone(),
+            None,
+        );
+
+        let requested_lit_resource_ability = lit_resource.decrypt_ability();
+
+        let valid_auth_sig = validate_session_sig(
+            &session_sig,
+            &requested_lit_resource_ability,
+            &None,
+            &lit_config,
+            &"".to_string(),
+        )
+        .await
+        .expect("Failed to validate passkey signed session sig");
+        assert_eq!(valid_auth_sig, auth_sig);
+
+        let wallet_sig = extract_wallet_sig(&session_sig, "")
+            .await
+            .expect("Failed to extract passkey auth sig");
+        assert_eq!(wallet_sig, auth_sig);
+
+        // A passkey assertion made on another site is not accepted.
+        let foreign_auth_sig = passkey_auth_sig(
+            &passkey,
+            &siwe_message.to_string(),
+            "localhost",
+            "https://example.com",
+        );
+        let foreign_session_key_signed_message = SessionKeySignedMessageV2 {
+            capabilities: vec![foreign_auth_sig],
+            ..session_key_signed_message
+        };
+        let message = serde_json::to_string(&foreign_session_key_signed_message).unwrap();
+        let signature: Signature = signing_key.sign(message.as_bytes());
+        let foreign_session_sig = JsonAuthSig::new(
+            signature.to_string(),
+            "ghi".to_string(),
+            message,
+            session_pub_key,
+            None,
+        );
+
+        let validate = extract_wallet_sig(&foreign_session_sig, "").await;
+        assert!(validate.is_err());
+    }
 }
 
 #[cfg(test)]
@@ -929,6 +1087,7 @@ mod extract_requested_resources_tests {
                         expiration: "2024-01-01T00:00:00Z".to_string(),
                         node_address: "localhost:7470".to_string(),
                         max_price: U256::MAX,
+                        delegations: vec![],
                     }).unwrap(),
                     "pub_key".into(),
                     None,
@@ -953,6 +1112,7 @@ mod extract_requested_resources_tests {
                         expiration: "2024-01-01T00:00:00Z".to_string(),
                         node_address: "localhost:7470".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
                     }).unwrap(),
                     "pub_key".into(),
                     None,
@@ -981,6 +1141,7 @@ mod extract_requested_resources_tests {
                         expiration: "2024-01-01T00:00:00Z".to_string(),
                         node_address: "localhost:7470".to_string(),
             max_price: U256::MAX,
+            delegations: vec![],
                     }).unwrap(),
                     "pub_key".into(),
                     None,
@@ -5,11 +5,13 @@ use lit_node_core::{JsonAuthSig, LitResourceAbility};
 #[async_trait::async_trait]
 pub(crate) trait SessionSigAuthSigValidator: Send + Sync {
     /// Basic validation of the auth sig without checking capabilities to perform
-    /// the requested resource ability.
+    /// the requested resource ability. Smart account signatures are only accepted
+    /// when `chain` is set.
     async fn validate_auth_sig_basic(
         &self,
         auth_sig: &JsonAuthSig,
         session_pubkey: &str,
+        chain: &Option<String>,
     ) -> Result<()>;
 
     /// Basic validation of the auth sig without checking capabilities to perform
@@ -21,6 +23,14 @@ pub(crate) trait SessionSigAuthSigValidator: Send + Sync {
         bls_root_pubkey: &str,
     ) -> Result<()>;
 
+    /// Basic validation of the auth sig without checking capabilities to perform
+    /// the requested resource ability. Works with passkey signatures.
+    async fn validate_passkey_auth_sig_basic(
+        &self,
+        auth_sig: &JsonAuthSig,
+        session_pubkey: &str,
+    ) -> Result<()>;
+
     /// Validation of the auth sig AND checking capabilities to perform
     /// the requested resource ability.
     async fn validate_auth_sig(
@@ -28,6 +38,7 @@ pub(crate) trait SessionSigAuthSigValidator: Send + Sync {
         auth_sig: &JsonAuthSig,
         session_pubkey: &str,
         requested_lit_resource_ability: &LitResourceAbility,
+        chain: &Option<String>,
         bls_root_pubkey: &str,
     ) -> Result<()>;
 }
@@ -44,6 +55,11 @@ pub(crate) trait CapabilityAuthSigValidator: Send + Sync {
         auth_sig: &JsonAuthSig,
         bls_root_pubkey: &str,
     ) -> Result<()>;
+
+    /// Basic validation of the auth sig without checking capabilities to perform
+    /// the requested resource ability. Works with passkey signatures.
+    async fn validate_capability_passkey_auth_sig(&self, auth_sig: &JsonAuthSig) -> Result<()>;
+
     async fn validate_capability_auth_sig(
         &self,
         auth_sig: &JsonAuthSig,
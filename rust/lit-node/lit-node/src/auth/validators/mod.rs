@@ -3,3 +3,4 @@ pub mod cosmos;
 pub mod siwe;
 pub mod solana;
 pub mod wallet_sig;
+pub mod webauthn;
@@ -9,6 +9,10 @@ use crate::{
         auth_material::siwe_hash_to_bls_session_hash,
         capabilities::recap::extract_and_verify_all_capabilities,
         capabilities::session_capability_object::SessionCapabilityObject,
+        contract::{
+            check_siwe_chain_id, may_be_smart_account_signature, validate_smart_account_signature,
+        },
+        validators::webauthn::validate_passkey_siwe_sig,
     },
     error::{EC, Result, parser_err_code, validation_err_code},
     utils::encoding,
@@ -26,6 +30,64 @@ impl SiweValidator {
 }
 
 impl SiweValidator {
+    /// Verifies a SIWE message signed by a smart account per EIP-1271 or ERC-6492.
+    async fn validate_smart_account_sig(
+        &self,
+        siwe_message: &Message,
+        sig: &[u8],
+        chain: &Option<String>,
+    ) -> Result<()> {
+        let chain = chain.as_deref().ok_or_else(|| {
+            validation_err_code(
+                "A chain is required to verify smart account signatures",
+                EC::NodeSIWEMessageError,
+                None,
+            )
+        })?;
+        let account = Address::from(siwe_message.address);
+        if !may_be_smart_account_signature(account, sig, chain).await? {
+            return Err(validation_err_code(
+                "The signer is not a smart account",
+                EC::NodeSIWEMessageError,
+                None,
+            ));
+        }
+        check_siwe_chain_id(siwe_message.chain_id, chain).await?;
+        if !siwe_message.valid_now() {
+            return Err(validation_err_code(
+                "The SIWE message is not valid now.  It has expired or is not yet valid",
+                EC::NodeSIWEMessageError,
+                None,
+            ));
+        }
+        let siwe_hash = siwe_message.eip191_hash().map_err(|e| {
+            parser_err_code(
+                e,
+                EC::NodeSIWEMessageError,
+                Some("Error hashing SIWE message".into()),
+            )
+        })?;
+
+        validate_smart_account_signature(account, siwe_hash, sig, chain).await
+    }
+
+    /// Parses the SIWE message of a passkey auth sig and verifies the passkey assertion over it.
+    fn validate_passkey_siwe_message(&self, auth_sig: &JsonAuthSig) -> Result<Message> {
+        let siwe_message = self.parse_siwe_message(&auth_sig.signed_message)?;
+
+        if !siwe_message.valid_now() {
+            return Err(validation_err_code(
+                "The SIWE message is not valid now.  It has expired or is not yet valid",
+                EC::NodeSIWEMessageError,
+                None,
+            ));
+        }
+
+        validate_passkey_siwe_sig(auth_sig, &siwe_message)?;
+
+        Ok(siwe_message)
+    }
+
     pub fn parse_siwe_message(&self, message: &str) -> Result<Message> {
         message.parse::<Message>().map_err(|e| {
             parser_err_code(
@@ -145,6 +207,12 @@ impl CapabilityAuthSigValidator for SiweValidator {
         Ok(())
     }
 
+    async fn validate_capability_passkey_auth_sig(&self, auth_sig: &JsonAuthSig) -> Result<()> {
+        self.validate_passkey_siwe_message(auth_sig)?;
+
+        Ok(())
+    }
+
     async fn validate_capability_auth_sig(
         &self,
         auth_sig: &JsonAuthSig,
@@ -158,10 +226,13 @@ impl CapabilityAuthSigValidator for SiweValidator {
                 self.validate_capability_bls_auth_sig(auth_sig, bls_root_pubkey)
                     .await?
             }
+            AuthMaterialType::PasskeySig => {
+                self.validate_capability_passkey_auth_sig(auth_sig).await?
+            }
             _ => {
                 return Err(validation_err_code(
                     format!(
-                        "The auth sig material type {:?} is not supported when calling validate_auth_sig() within session sig validation.  Only WalletSig and BLSNetworkSig are supported.",
+                        "The auth sig material type {:?} is not supported when calling validate_auth_sig() within session sig validation.  Only WalletSig, BLSNetworkSig and PasskeySig are supported.",
                         auth_sig.auth_material_type
                     ),
                     EC::NodeSIWEMessageError,
@@ -181,6 +252,7 @@ impl SessionSigAuthSigValidator for SiweValidator {
         &self,
         auth_sig: &JsonAuthSig,
         session_pubkey: &str,
+        chain: &Option<String>,
     ) -> Result<()> {
         // Parse the SIWE message.
         let siwe_message = self.parse_siwe_message(&auth_sig.signed_message)?;
@@ -202,12 +274,21 @@ impl SessionSigAuthSigValidator for SiweValidator {
             )
             .await
         {
-            return Err(validation_err_code(
+            let e = validation_err_code(
                 e,
                 EC::NodeSIWEMessageError,
                 Some("Wallet signature of session key via SIWE is not valid".into()),
             )
-            .add_msg_to_details());
+            .add_msg_to_details();
+            self.validate_smart_account_sig(&siwe_message, sig_as_array, chain)
+                .await
+                .map_err(|smart_account_err| {
+                    debug!(
+                        "SIWE is not signed by a smart account either: {:?}",
+                        smart_account_err
+                    );
+                    e
+                })?;
         }
         // Validate that the session public key is signed in the SIWE message.
         let signed_uri = siwe_message.uri.to_string();
@@ -293,11 +374,34 @@ impl SessionSigAuthSigValidator for SiweValidator {
         Ok(())
     }
 
+    async fn validate_passkey_auth_sig_basic(
+        &self,
+        auth_sig: &JsonAuthSig,
+        session_pubkey: &str,
+    ) -> Result<()> {
+        let siwe_message = self.validate_passkey_siwe_message(auth_sig)?;
+
+        // Validate that the session public key is signed in the SIWE message.
+        let signed_uri = siwe_message.uri.to_string();
+        let correct_uri = format!("lit:session:{}", session_pubkey);
+        if signed_uri != correct_uri {
+            return Err(validation_err_code(
+                "The session pubkey in the auth sig is not signed in the passkey-signed SIWE message",
+                EC::NodeSIWEMessageError,
+                None,
+            )
+            .add_source_to_details());
+        }
+
+        Ok(())
+    }
+
     async fn validate_auth_sig(
         &self,
         auth_sig: &JsonAuthSig,
         session_pubkey: &str,
         requested_lit_resource_ability: &LitResourceAbility,
+        chain: &Option<String>,
         bls_root_pubkey: &str,
     ) -> Result<()> {
         // Parse the SIWE message.
@@ -305,17 +409,21 @@ impl SessionSigAuthSigValidator for SiweValidator {
 
         match auth_sig.auth_material_type {
             AuthMaterialType::WalletSig => {
-                self.validate_auth_sig_basic(auth_sig, session_pubkey)
+                self.validate_auth_sig_basic(auth_sig, session_pubkey, chain)
                     .await?
             }
             AuthMaterialType::BLSNetworkSig => {
                 self.validate_bls_auth_sig_basic(auth_sig, session_pubkey, bls_root_pubkey)
                     .await?
             }
+            AuthMaterialType::PasskeySig => {
+                self.validate_passkey_auth_sig_basic(auth_sig, session_pubkey)
+                    .await?
+            }
             _ => {
                 return Err(validation_err_code(
                     format!(
-                        "The auth sig material type {:?} is not supported when calling validate_auth_sig() within session sig validation.  Only WalletSig and BLSNetworkSig are supported.",
+                        "The auth sig material type {:?} is not supported when calling validate_auth_sig() within session sig validation.  Only WalletSig, BLSNetworkSig and PasskeySig are supported.",
                         auth_sig.auth_material_type
                     ),
                     EC::NodeSIWEMessageError,
@@ -394,6 +502,7 @@ mod tests {
                 &auth_sig,
                 "0xdeadbeef",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
@@ -440,6 +549,7 @@ mod tests {
                 &auth_sig,
                 "0xdeadbeef",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
@@ -481,6 +591,7 @@ mod tests {
                 &auth_sig,
                 "0xdeadbeef",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
@@ -535,6 +646,7 @@ mod tests {
                 &auth_sig,
                 "0xdeadbeef",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
@@ -587,6 +699,7 @@ mod tests {
                 &auth_sig,
                 "e76233cdd5483d674020cee626bdecfee6cf9d02b2bffa31b75b91c0ec04a09f",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
@@ -640,6 +753,7 @@ mod tests {
                 &auth_sig,
                 "e76233cdd5483d674020cee626bdecfee6cf9d02b2bffa31b75b91c0ec04a09f",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
@@ -699,6 +813,7 @@ mod tests {
                 &auth_sig,
                 "e76233cdd5483d674020cee626bdecfee6cf9d02b2bffa31b75b91c0ec04a09f",
                 &requested_lit_resource_ability,
+                &None,
                 &"".to_string(),
             )
             .await;
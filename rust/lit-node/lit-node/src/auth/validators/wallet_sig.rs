@@ -1,3 +1,6 @@
+use crate::auth::contract::{
+    check_siwe_chain_id, may_be_smart_account_signature, validate_smart_account_signature,
+};
 use crate::auth::validators::cosmos::validate_cosmos_auth_sig;
 use crate::auth::validators::solana::validate_solana_auth_sig;
 use crate::error::{
@@ -57,14 +60,81 @@ pub(crate) async fn validate_wallet_sig_by_chain(
                 }
                 Ok(())
             }
-            _ => validate_wallet_sig(auth_sig, enable_siwe_validation).await,
+            _ => validate_wallet_sig(auth_sig, Some(chain.as_str()), enable_siwe_validation).await,
         },
-        None => validate_wallet_sig(auth_sig, enable_siwe_validation).await,
+        None => validate_wallet_sig(auth_sig, None, enable_siwe_validation).await,
+    }
+}
+
+/// Validate EVM-compatible wallet signature. When a chain is known, signatures that don't
+/// recover to the address are checked against the address as a smart account.
+async fn validate_wallet_sig(
+    auth_sig: &JsonAuthSig,
+    chain: Option<&str>,
+    enable_siwe_validation: bool,
+) -> Result<()> {
+    let eoa_err = match validate_eoa_wallet_sig(auth_sig, enable_siwe_validation).await {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+    let Some(chain) = chain else {
+        return Err(eoa_err);
+    };
+
+    match validate_smart_account_wallet_sig(auth_sig, chain, enable_siwe_validation).await {
+        Ok(()) => Ok(()),
+        // the address is a contract that rejected the signature
+        Err(e) if e.is_code(EC::NodeContractAuthsigUnauthorized, true) => Err(e),
+        Err(e) => {
+            debug!(
+                "Wallet sig is not a valid smart account signature either: {:?}",
+                e
+            );
+            Err(eoa_err)
+        }
     }
 }
 
-/// Validate EVM-compatible wallet signature.
-async fn validate_wallet_sig(auth_sig: &JsonAuthSig, enable_siwe_validation: bool) -> Result<()> {
+/// Validate a SIWE message signed by a smart account per EIP-1271 or ERC-6492.
+async fn validate_smart_account_wallet_sig(
+    auth_sig: &JsonAuthSig,
+    chain: &str,
+    enable_siwe_validation: bool,
+) -> Result<()> {
+    let message = validate_siwe_message(auth_sig, enable_siwe_validation)?;
+
+    let presented_address = Address::from_str(&auth_sig.address)
+        .map_err(|e| validation_err_code(e, EC::NodeAuthSigAddressConversionError, None))?;
+    if Address::from(message.address) != presented_address {
+        return Err(validation_err_code(
+            "The address in the SIWE message does not match the address in the auth sig",
+            EC::NodeInvalidAuthSig,
+            None,
+        ));
+    }
+
+    let sig = encoding::hex_to_bytes(&auth_sig.sig)
+        .map_err(|e| parser_err_code(e, EC::NodeSIWESigConversionError, None))?;
+    if !may_be_smart_account_signature(presented_address, &sig, chain).await? {
+        return Err(validation_err_code(
+            "The signer is not a smart account",
+            EC::NodeInvalidAuthSig,
+            None,
+        ));
+    }
+    check_siwe_chain_id(message.chain_id, chain).await?;
+    let hash = message
+        .eip191_hash()
+        .map_err(|e| parser_err(e, Some("Error hashing SIWE message".into())))?;
+
+    validate_smart_account_signature(presented_address, hash, &sig, chain).await
+}
+
+/// Validate wallet signature of an externally owned account.
+async fn validate_eoa_wallet_sig(
+    auth_sig: &JsonAuthSig,
+    enable_siwe_validation: bool,
+) -> Result<()> {
     let sig = Signature::from_str(&auth_sig.sig).map_err(|e| {
         parser_err_code(
             e,
@@ -176,7 +246,7 @@ mod tests {
             None,
         );
 
-        let result = validate_wallet_sig(&auth_sig, true).await;
+        let result = validate_wallet_sig(&auth_sig, None, true).await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.is_code(EC::NodeAuthSigSignatureConversionError, false));
@@ -194,7 +264,7 @@ mod tests {
             None,
         );
 
-        let result = validate_wallet_sig(&auth_sig, true).await;
+        let result = validate_wallet_sig(&auth_sig, None, true).await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.is_code(EC::NodeAuthSigAddressConversionError, false));
@@ -211,7 +281,7 @@ mod tests {
             None,
         );
 
-        let result = validate_wallet_sig(&auth_sig, true).await;
+        let result = validate_wallet_sig(&auth_sig, None, true).await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.is_kind(Kind::Validation, false));
@@ -251,13 +321,13 @@ mod tests {
             None,
         );
 
-        let result = validate_wallet_sig(&auth_sig, true).await;
+        let result = validate_wallet_sig(&auth_sig, None, true).await;
         let err = result.unwrap_err();
         assert!(err.is_kind(Kind::Validation, false));
         assert!(err.to_string().contains("Session key expiration 2023-06-01T15:41:08.640Z is in the past beyond the grace period of 60 seconds"));
 
         // Try again, this time without SIWE validation
-        let result = validate_wallet_sig(&auth_sig, false).await;
+        let result = validate_wallet_sig(&auth_sig, None, false).await;
         assert!(result.is_ok());
     }
 
@@ -298,7 +368,7 @@ mod tests {
             None,
         );
 
-        let result = validate_wallet_sig(&auth_sig, true).await;
+        let result = validate_wallet_sig(&auth_sig, None, true).await;
         assert!(result.is_ok());
     }
 }
@@ -0,0 +1,296 @@
+use crate::auth::validators::wallet_sig::validate_siwe_message;
+use crate::error::{EC, Result, conversion_err_code, parser_err_code, validation_err_code};
+use crate::pkp::auth::webauthn::{
+    authenticate_credential_from_origins, get_challenge_from_credential, get_origin_from_credential,
+};
+use crate::utils::cose_keys::decode_cbor_cose_key;
+use crate::utils::encoding;
+use ethers::types::Address;
+use ethers::utils::keccak256;
+use lit_core::config::LitConfig;
+use lit_node_common::config::LitNodeConfig as _;
+use lit_node_core::JsonAuthSig;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use siwe::Message;
+use std::str::FromStr;
+use url::Url;
+use webauthn_rs_core::proto::{COSEKey, COSEKeyType};
+use webauthn_rs_proto::PublicKeyCredential;
+
+/// The `sig` of a passkey auth sig.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PasskeySig {
+    /// The WebAuthn assertion over the signed message.
+    credential: PublicKeyCredential,
+    /// The hex encoded CBOR COSE public key of the credential.
+    public_key: String,
+}
+
+/// Validate an auth sig made with a WebAuthn passkey.
+///
+/// The signed message is a SIWE message whose domain is the relying party id and whose address
+/// is derived from the passkey's public key. The assertion's challenge must be the SHA-256 hash
+/// of the signed message.
+pub(crate) fn validate_passkey_auth_sig(auth_sig: &JsonAuthSig, cfg: &LitConfig) -> Result<()> {
+    let enable_siwe_validation = matches!(cfg.enable_siwe_validation(), Ok(true));
+    let message = validate_siwe_message(auth_sig, enable_siwe_validation)?;
+    let passkey_sig = parse_passkey_sig(auth_sig)?;
+
+    verify_passkey_assertion(auth_sig, &message, passkey_sig, || {
+        cfg.webauthn_allowed_origins()
+    })
+}
+
+/// Validate a passkey signature of a session key or capability SIWE message, which are checked
+/// without the node config. The assertion's origin must be the relying party id or one of its
+/// subdomains instead.
+pub(crate) fn validate_passkey_siwe_sig(auth_sig: &JsonAuthSig, message: &Message) -> Result<()> {
+    let passkey_sig = parse_passkey_sig(auth_sig)?;
+
+    let origin = get_origin_from_credential(&passkey_sig.credential)?;
+    let rp_id = message.domain.host();
+    let is_rp_origin = origin
+        .host_str()
+        .is_some_and(|host| host == rp_id || host.ends_with(&format!(".{rp_id}")));
+    if !is_rp_origin {
+        return Err(validation_err_code(
+            format!("The passkey assertion origin {origin} is not on the relying party {rp_id}"),
+            EC::NodeInvalidAuthSig,
+            None,
+        ));
+    }
+
+    verify_passkey_assertion(auth_sig, message, passkey_sig, || Ok(vec![origin]))
+}
+
+fn parse_passkey_sig(auth_sig: &JsonAuthSig) -> Result<PasskeySig> {
+    serde_json::from_str::<PasskeySig>(&auth_sig.sig).map_err(|e| {
+        parser_err_code(
+            e,
+            EC::NodeAuthSigSignatureConversionError,
+            Some("Error parsing the passkey assertion".into()),
+        )
+    })
+}
+
+fn verify_passkey_assertion(
+    auth_sig: &JsonAuthSig,
+    message: &Message,
+    passkey_sig: PasskeySig,
+    allowed_origins: impl FnOnce() -> Result<Vec<Url>>,
+) -> Result<()> {
+    let challenge = get_challenge_from_credential(&passkey_sig.credential)?;
+    if challenge.0 != Sha256::digest(auth_sig.signed_message.as_bytes()).to_vec() {
+        return Err(validation_err_code(
+            "The passkey assertion challenge is not the hash of the signed message",
+            EC::NodeInvalidWebAuthnChallenge,
+            None,
+        ));
+    }
+
+    let public_key = encoding::hex_to_bytes(&passkey_sig.public_key)
+        .map_err(|e| conversion_err_code(e, EC::NodeAuthSigAddressConversionError, None))?;
+    let (cose_key, _) = decode_cbor_cose_key(public_key.into())?;
+
+    let presented_address = Address::from_str(&auth_sig.address)
+        .map_err(|e| validation_err_code(e, EC::NodeAuthSigAddressConversionError, None))?;
+    let passkey_address = passkey_address(&cose_key)?;
+    if presented_address != passkey_address || Address::from(message.address) != passkey_address {
+        return Err(validation_err_code(
+            "The address is not derived from the passkey public key",
+            EC::NodeInvalidAuthSig,
+            None,
+        ));
+    }
+
+    let user_verified = authenticate_credential_from_origins(
+        allowed_origins()?,
+        &passkey_sig.credential,
+        cose_key,
+        message.domain.host(),
+        challenge,
+    )?;
+    if !user_verified {
+        return Err(validation_err_code(
+            "The passkey did not verify the user",
+            EC::NodeInvalidAuthSig,
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the address of a passkey, derived from its public key the same way as an
+/// Ethereum address.
+pub(crate) fn passkey_address(cose_key: &COSEKey) -> Result<Address> {
+    match &cose_key.key {
+        COSEKeyType::EC_EC2(key) => {
+            let hash = keccak256([key.x.0.as_slice(), key.y.0.as_slice()].concat());
+            Ok(Address::from_slice(&hash[12..]))
+        }
+        _ => Err(validation_err_code(
+            "Only EC2 passkeys are supported",
+            EC::NodeInvalidAuthSig,
+            None,
+        )),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{passkey_address, validate_passkey_auth_sig};
+    use crate::error::EC;
+    use crate::utils::cose_keys::decode_cbor_cose_key;
+    use crate::utils::encoding;
+    use ciborium::value::Value;
+    use ethers::types::Address;
+    use ethers::utils::keccak256;
+    use lit_node_core::constants::AUTH_SIG_DERIVED_VIA_PASSKEY_SIG;
+    use lit_node_core::{AuthMaterialType, JsonAuthSig};
+    use p256::SecretKey;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::{Signature, SigningKey};
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+    use rand::rngs::OsRng;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    fn cose_key(secret_key: &SecretKey) -> Vec<u8> {
+        let point = secret_key.public_key().to_encoded_point(false);
+        let cose_key = Value::Map(vec![
+            (Value::from(1), Value::from(2)),
+            (Value::from(3), Value::from(-7)),
+            (Value::from(-1), Value::from(1)),
+            (Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+            (Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&cose_key, &mut bytes).unwrap();
+        bytes
+    }
+
+    /// Returns the address of the passkey with the given secret key.
+    pub(crate) fn test_passkey_address(secret_key: &SecretKey) -> Address {
+        let (cose_key, _) = decode_cbor_cose_key(cose_key(secret_key).into()).unwrap();
+        passkey_address(&cose_key).unwrap()
+    }
+
+    /// Signs `signed_message` with a passkey assertion made on `origin` for the relying party
+    /// `rp_id`, the way a browser would.
+    pub(crate) fn passkey_auth_sig(
+        secret_key: &SecretKey,
+        signed_message: &str,
+        rp_id: &str,
+        origin: &str,
+    ) -> JsonAuthSig {
+        let b64 = data_encoding::BASE64URL_NOPAD;
+
+        // The user present, user verified and backup eligible flags, and a zero counter.
+        let mut authenticator_data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        authenticator_data.push(0x0D);
+        authenticator_data.extend_from_slice(&0u32.to_be_bytes());
+
+        let client_data_json = json!({
+            "type": "webauthn.get",
+            "challenge": b64.encode(&Sha256::digest(signed_message.as_bytes())),
+            "origin": origin,
+            "crossOrigin": false,
+        })
+        .to_string();
+
+        let signing_key = SigningKey::from(secret_key);
+        let signature: Signature = signing_key.sign(
+            &[
+                authenticator_data.as_slice(),
+                Sha256::digest(client_data_json.as_bytes()).as_slice(),
+            ]
+            .concat(),
+        );
+
+        let credential = json!({
+            "id": "AQID",
+            "rawId": "AQID",
+            "response": {
+                "authenticatorData": b64.encode(&authenticator_data),
+                "clientDataJSON": b64.encode(client_data_json.as_bytes()),
+                "signature": b64.encode(signature.to_der().as_bytes()),
+                "userHandle": null,
+            },
+            "extensions": {},
+            "type": "public-key",
+        });
+        let sig = json!({
+            "credential": credential,
+            "publicKey": encoding::bytes_to_hex(cose_key(secret_key)),
+        });
+
+        JsonAuthSig::new_with_type(
+            sig.to_string(),
+            AUTH_SIG_DERIVED_VIA_PASSKEY_SIG.to_string(),
+            signed_message.to_string(),
+            format!("{:?}", test_passkey_address(secret_key)),
+            None,
+            AuthMaterialType::PasskeySig,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_passkey_address() {
+        let secret_key = SecretKey::random(&mut OsRng);
+        let (cose_key, _) = decode_cbor_cose_key(cose_key(&secret_key).into()).unwrap();
+
+        let point = secret_key.public_key().to_encoded_point(false);
+        let expected = keccak256(&point.as_bytes()[1..]);
+        assert_eq!(
+            passkey_address(&cose_key).unwrap().as_bytes(),
+            &expected[12..]
+        );
+    }
+
+    #[test]
+    fn test_challenge_must_be_hash_of_signed_message() {
+        let secret_key = SecretKey::random(&mut OsRng);
+        let credential = json!({
+            "id": "AQID",
+            "rawId": "AQID",
+            "response": {
+                "authenticatorData": "AQID",
+                "clientDataJSON": data_encoding::BASE64URL_NOPAD.encode(
+                    json!({
+                        "type": "webauthn.get",
+                        "challenge": "AQID",
+                        "origin": "https://localhost",
+                    })
+                    .to_string()
+                    .as_bytes()
+                ),
+                "signature": "AQID",
+                "userHandle": null,
+            },
+            "extensions": {},
+            "type": "public-key",
+        });
+        let sig = json!({
+            "credential": credential,
+            "publicKey": encoding::bytes_to_hex(cose_key(&secret_key)),
+        });
+        let auth_sig = JsonAuthSig::new(
+            sig.to_string(),
+            AUTH_SIG_DERIVED_VIA_PASSKEY_SIG.to_string(),
+            "localhost wants you to sign in with your Ethereum account:\n0x0000000000000000000000000000000000000000\n\n\nURI: https://localhost/login\nVersion: 1\nChain ID: 1\nNonce: JIsknRumpxsM9pqmc\nIssued At: 2023-05-01T15:41:08.640Z".to_string(),
+            "0x0000000000000000000000000000000000000000".to_string(),
+            None,
+        );
+        let cfg = lit_core::config::LitConfigBuilder::default()
+            .set_default("lit.env", "dev")
+            .build()
+            .unwrap();
+
+        let err = validate_passkey_auth_sig(&auth_sig, &cfg).unwrap_err();
+        assert!(err.is_code(EC::NodeInvalidWebAuthnChallenge, true));
+    }
+}
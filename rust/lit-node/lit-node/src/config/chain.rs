@@ -486,7 +486,7 @@ impl ChainDataConfigManager {
             })
             .collect::<Vec<LitActionPriceConfig>>();
 
-        Ok(configs)
+        Ok(with_default_storage_price(configs))
     }
 
     async fn set_peers_and_epoch_data_from_chain_by_realm(
@@ -956,3 +956,23 @@ impl ChainDataConfigManager {
         }
     }
 }
+
+/// Price feeds deployed before storage was priced have no config for it, which would fail every
+/// storage write, so they get the price a fresh deployment starts out with.
+fn with_default_storage_price(mut configs: Vec<LitActionPriceConfig>) -> Vec<LitActionPriceConfig> {
+    let has_storage_price = configs
+        .iter()
+        .any(|c| c.price_component == LitActionPriceComponent::StorageBytes);
+    let base_amount = configs
+        .iter()
+        .find(|c| c.price_component == LitActionPriceComponent::BaseAmount)
+        .map(|c| c.price);
+    if let (false, Some(base_amount)) = (has_storage_price, base_amount) {
+        configs.push(LitActionPriceConfig {
+            price_component: LitActionPriceComponent::StorageBytes,
+            price_measurement: NodePriceMeasurement::PerMegabyte,
+            price: base_amount / 10,
+        });
+    }
+    configs
+}
@@ -1,13 +1,14 @@
 use crate::auth::auth_material::AuthSigItemExtendedRef;
 use crate::error::unexpected_err;
 use crate::models::auth::SessionKeySignedMessageV2;
-use crate::models::{AllowlistCache, AuthContextCache};
+use crate::models::{AllowlistCache, AuthContext, AuthContextCache};
 use crate::payment::delegated_usage::DelegatedUsageDB;
 use crate::payment::selection::get_payment_method;
 use crate::payment::{payed_endpoint::PayedEndpoint, payment_tracker::PaymentTracker};
 use crate::pkp::auth::AuthMethodScope;
-use crate::pkp::utils::{claim_key, sign};
+use crate::pkp::utils::{claim_key, musig2_nonce, musig2_sign, sign, sign_batch};
 use crate::tss::common::tss_state::TssState;
+use crate::tss::musig2::MuSig2NonceCache;
 use crate::utils::web::get_auth_context;
 use lit_node_common::config::LitNodeConfig;
 
@@ -16,14 +17,24 @@ use crate::utils::web::pubkey_to_token_id;
 use crate::utils::web::{
     get_auth_context_from_session_sigs, get_bls_root_pubkey, get_signed_message,
 };
+use ethers::types::I256;
 use lit_api_core::error::ApiError;
-use lit_core::config::ReloadableLitConfig;
+use lit_core::config::{LitConfig, ReloadableLitConfig};
 use lit_node_common::client_state::ClientState;
+use lit_node_core::request::JsonPKPBatchSigningRequest;
 use lit_node_core::request::JsonPKPClaimKeyRequest;
+use lit_node_core::request::JsonPKPMuSig2NonceRequest;
+use lit_node_core::request::JsonPKPMuSig2SigningRequest;
 use lit_node_core::request::JsonPKPSigningRequest;
 use lit_node_core::response::GenericResponse;
+use lit_node_core::response::JsonPKPBatchSigningResponse;
+use lit_node_core::response::JsonPKPMuSig2NonceResponse;
+use lit_node_core::response::JsonPKPMuSig2SigningResponse;
 use lit_node_core::response::JsonPKPSigningResponse;
-use lit_node_core::{AuthSigItem, EndpointVersion, PKPNFTResource, constants::CHAIN_ETHEREUM};
+use lit_node_core::{
+    AuthMethod, AuthSigItem, CurveType, EndpointVersion, JsonAuthSig, PKPNFTResource,
+    constants::CHAIN_ETHEREUM,
+};
 use rocket::State;
 use rocket::http::Status;
 use rocket::response::status;
@@ -33,6 +44,8 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
+const MAX_BATCH_SIGNING_MESSAGES: usize = 100;
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn pkp_sign(
     remote_addr: SocketAddr,
@@ -54,17 +67,411 @@ pub(crate) async fn pkp_sign(
 
     let mut timing: BTreeMap<String, Duration> = BTreeMap::new();
     let request_start = std::time::Instant::now();
+
+    let (auth_sig, auth_context, bls_root_pubkey) = match authorize_pkp_signing(
+        "pkp_sign",
+        tss_state,
+        auth_context_cache,
+        delegation_usage_db,
+        &cfg,
+        &json_pkp_signing_request.pubkey,
+        &json_pkp_signing_request.auth_sig,
+        &json_pkp_signing_request.auth_methods,
+        json_pkp_signing_request.signing_scheme.curve_type(),
+        json_pkp_signing_request.epoch,
+        1,
+        &client_session,
+        payment_tracker,
+        endpoint_version,
+        http_client,
+        &mut timing,
+    )
+    .await
+    {
+        Ok(authorized) => authorized,
+        Err(response) => return response,
+    };
     let before = std::time::Instant::now();
 
-    let token_id = match pubkey_to_token_id(&json_pkp_signing_request.pubkey) {
-        Ok(token_id) => token_id,
+    let epoch = match json_pkp_signing_request.epoch {
+        0 => None,
+        i => Some(i),
+    };
+    trace!(
+        "PKP Signing scheme: {}",
+        json_pkp_signing_request.signing_scheme
+    );
+
+    let result = sign(
+        cfg.as_ref(),
+        &json_pkp_signing_request.to_sign,
+        json_pkp_signing_request.pubkey.clone(),
+        request_id.clone(),
+        None, // Only the first one as we only allow running a single Lit Action now for session creation
+        Some(auth_sig.clone()), // This works with EOA wallets as well as we're passing SessionSig/AuthSig here
+        auth_context,
+        Some(tss_state.as_ref().clone()),
+        &[AuthMethodScope::SignAnything as usize],
+        epoch,
+        &bls_root_pubkey,
+        &json_pkp_signing_request.node_set,
+        json_pkp_signing_request.signing_scheme,
+        json_pkp_signing_request.derivation_path.as_ref(),
+        json_pkp_signing_request.proof_of_possession,
+    )
+    .await
+    .map_err(|e| unexpected_err(e, Some("Error signing with the PKP".to_string())));
+    timing.insert("sign".to_string(), before.elapsed());
+
+    let result = match result {
+        Ok(result) => client_session.json_encrypt_response_status(JsonPKPSigningResponse {
+            success: true,
+            signed_data: json_pkp_signing_request.to_sign.clone(),
+            signature_share: result,
+        }),
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("unable to get signature share", e.handle());
+        }
+    };
+
+    timing.insert("total".to_string(), request_start.elapsed());
+
+    debug!("POST /web/pkp/sign timing: {:?}", timing);
+
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_sign_batch(
+    remote_addr: SocketAddr,
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<AuthContextCache>>,
+    delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
+    cfg: &State<ReloadableLitConfig>,
+    allowlist_cache: &State<Arc<AllowlistCache>>,
+    client_state: &Arc<ClientState>,
+    json_pkp_batch_signing_request: JsonPKPBatchSigningRequest,
+    client_session: Arc<ClientSession>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    endpoint_version: EndpointVersion,
+    request_id: String,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    trace!(
+        "pkp sign batch, request: {:?}",
+        json_pkp_batch_signing_request
+    );
+    let cfg = cfg.load_full();
+
+    let mut timing: BTreeMap<String, Duration> = BTreeMap::new();
+    let request_start = std::time::Instant::now();
+
+    let message_count = json_pkp_batch_signing_request.messages.len();
+    if message_count == 0 || message_count > MAX_BATCH_SIGNING_MESSAGES {
+        return client_session.json_encrypt_err_and_code(
+            &format!(
+                "A batch must have between 1 and {} messages",
+                MAX_BATCH_SIGNING_MESSAGES
+            ),
+            "invalid_batch_size",
+            Status::BadRequest,
+        );
+    }
+
+    // a PKP is a key on a single curve, and the same PKP on another curve is a different key
+    let messages = json_pkp_batch_signing_request.messages_with_schemes();
+    let first_scheme = messages[0].1;
+    let curve_type = first_scheme.curve_type();
+    if let Some((_, scheme)) = messages
+        .iter()
+        .find(|(_, scheme)| scheme.curve_type() != curve_type)
+    {
+        return client_session.json_encrypt_err_and_code(
+            &format!(
+                "A batch can't mix the curves of {} and {}",
+                first_scheme, scheme
+            ),
+            "mixed_batch_curves",
+            Status::BadRequest,
+        );
+    }
+
+    // the whole batch is authorized and paid for at once, before any message is signed
+    let (auth_sig, auth_context, bls_root_pubkey) = match authorize_pkp_signing(
+        "pkp_sign_batch",
+        tss_state,
+        auth_context_cache,
+        delegation_usage_db,
+        &cfg,
+        &json_pkp_batch_signing_request.pubkey,
+        &json_pkp_batch_signing_request.auth_sig,
+        &json_pkp_batch_signing_request.auth_methods,
+        curve_type,
+        json_pkp_batch_signing_request.epoch,
+        message_count,
+        &client_session,
+        payment_tracker,
+        endpoint_version,
+        http_client,
+        &mut timing,
+    )
+    .await
+    {
+        Ok(authorized) => authorized,
+        Err(response) => return response,
+    };
+    let before = std::time::Instant::now();
+
+    let epoch = match json_pkp_batch_signing_request.epoch {
+        0 => None,
+        i => Some(i),
+    };
+
+    let result = sign_batch(
+        cfg.as_ref(),
+        &messages,
+        json_pkp_batch_signing_request.pubkey.clone(),
+        request_id.clone(),
+        None,
+        Some(auth_sig.clone()),
+        auth_context,
+        Some(tss_state.as_ref().clone()),
+        &[AuthMethodScope::SignAnything as usize],
+        epoch,
+        &bls_root_pubkey,
+        &json_pkp_batch_signing_request.node_set,
+        json_pkp_batch_signing_request.derivation_path.as_ref(),
+    )
+    .await
+    .map_err(|e| unexpected_err(e, Some("Error batch signing with the PKP".to_string())));
+    timing.insert("sign".to_string(), before.elapsed());
+
+    let result = match result {
+        Ok(result) => client_session.json_encrypt_response_status(JsonPKPBatchSigningResponse {
+            success: true,
+            signed_data: json_pkp_batch_signing_request
+                .messages
+                .into_iter()
+                .map(|m| m.to_sign)
+                .collect(),
+            signature_shares: result,
+        }),
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("unable to get signature shares", e.handle());
+        }
+    };
+
+    timing.insert("total".to_string(), request_start.elapsed());
+
+    debug!("POST /web/pkp/sign/batch timing: {:?}", timing);
+
+    result
+}
+
+#[doc = "The first phase of a MuSig2 signature with the PKP, which is paid for like any other signature."]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_musig2_nonce(
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<AuthContextCache>>,
+    delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
+    cfg: &State<ReloadableLitConfig>,
+    nonce_cache: &State<Arc<MuSig2NonceCache>>,
+    json_musig2_nonce_request: JsonPKPMuSig2NonceRequest,
+    client_session: Arc<ClientSession>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    endpoint_version: EndpointVersion,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    trace!("pkp musig2 nonce, request: {:?}", json_musig2_nonce_request);
+    let cfg = cfg.load_full();
+
+    let mut timing: BTreeMap<String, Duration> = BTreeMap::new();
+    let request_start = std::time::Instant::now();
+
+    let (auth_sig, auth_context, bls_root_pubkey) = match authorize_pkp_signing(
+        "pkp_musig2_nonce",
+        tss_state,
+        auth_context_cache,
+        delegation_usage_db,
+        &cfg,
+        &json_musig2_nonce_request.pubkey,
+        &json_musig2_nonce_request.auth_sig,
+        &json_musig2_nonce_request.auth_methods,
+        CurveType::K256,
+        json_musig2_nonce_request.epoch,
+        1,
+        &client_session,
+        payment_tracker,
+        endpoint_version,
+        http_client,
+        &mut timing,
+    )
+    .await
+    {
+        Ok(authorized) => authorized,
+        Err(response) => return response,
+    };
+    let before = std::time::Instant::now();
+
+    let result = musig2_nonce(
+        cfg.as_ref(),
+        tss_state.inner().clone(),
+        nonce_cache,
+        &json_musig2_nonce_request.pubkey,
+        &json_musig2_nonce_request.session_id,
+        Some(auth_sig),
+        auth_context,
+        &[AuthMethodScope::SignAnything as usize],
+        &bls_root_pubkey,
+    )
+    .await;
+    timing.insert("nonce".to_string(), before.elapsed());
+
+    let result = match result {
+        Ok((peer_id, public_nonce)) => {
+            client_session.json_encrypt_response_status(JsonPKPMuSig2NonceResponse {
+                success: true,
+                peer_id: peer_id.to_string(),
+                public_nonce,
+            })
+        }
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("unable to create a MuSig2 nonce", e.handle());
+        }
+    };
+
+    timing.insert("total".to_string(), request_start.elapsed());
+
+    debug!("POST /web/pkp/musig2/nonce timing: {:?}", timing);
+
+    result
+}
+
+#[doc = "The second phase of a MuSig2 signature with the PKP, which was paid for in the first."]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_musig2_sign(
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<AuthContextCache>>,
+    delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
+    cfg: &State<ReloadableLitConfig>,
+    nonce_cache: &State<Arc<MuSig2NonceCache>>,
+    json_musig2_signing_request: JsonPKPMuSig2SigningRequest,
+    client_session: Arc<ClientSession>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    endpoint_version: EndpointVersion,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    trace!(
+        "pkp musig2 sign, request: {:?}",
+        json_musig2_signing_request
+    );
+    let cfg = cfg.load_full();
+
+    let mut timing: BTreeMap<String, Duration> = BTreeMap::new();
+    let request_start = std::time::Instant::now();
+
+    let (auth_sig, auth_context, bls_root_pubkey) = match authorize_pkp_signing(
+        "pkp_musig2_sign",
+        tss_state,
+        auth_context_cache,
+        delegation_usage_db,
+        &cfg,
+        &json_musig2_signing_request.pubkey,
+        &json_musig2_signing_request.auth_sig,
+        &json_musig2_signing_request.auth_methods,
+        CurveType::K256,
+        json_musig2_signing_request.epoch,
+        0,
+        &client_session,
+        payment_tracker,
+        endpoint_version,
+        http_client,
+        &mut timing,
+    )
+    .await
+    {
+        Ok(authorized) => authorized,
+        Err(response) => return response,
+    };
+    let before = std::time::Instant::now();
+
+    let epoch = match json_musig2_signing_request.epoch {
+        0 => None,
+        i => Some(i),
+    };
+
+    let result = musig2_sign(
+        cfg.as_ref(),
+        tss_state.inner().clone(),
+        nonce_cache,
+        &json_musig2_signing_request,
+        Some(auth_sig),
+        auth_context,
+        &[AuthMethodScope::SignAnything as usize],
+        epoch,
+        &bls_root_pubkey,
+    )
+    .await;
+    timing.insert("sign".to_string(), before.elapsed());
+
+    let result = match result {
+        Ok((peer_id, partial_signature_share)) => {
+            client_session.json_encrypt_response_status(JsonPKPMuSig2SigningResponse {
+                success: true,
+                signed_data: json_musig2_signing_request.to_sign.clone(),
+                peer_id: peer_id.to_string(),
+                partial_signature_share,
+            })
+        }
         Err(e) => {
             return client_session.json_encrypt_err_custom_response(
-                "can't get token id from public key",
+                "unable to get a MuSig2 signature share",
                 e.handle(),
             );
         }
     };
+
+    timing.insert("total".to_string(), request_start.elapsed());
+
+    debug!("POST /web/pkp/musig2/sign timing: {:?}", timing);
+
+    result
+}
+
+#[doc = "Validates the auth sig and the payment of a PKP signing request for `message_count` messages, returning the auth sig, auth context and BLS root key to sign with.  Nothing is paid for zero messages, which is the second phase of a signature paid for in the first."]
+#[allow(clippy::too_many_arguments)]
+async fn authorize_pkp_signing(
+    endpoint_name: &str,
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<AuthContextCache>>,
+    delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
+    cfg: &Arc<LitConfig>,
+    pubkey: &str,
+    auth_sig_item: &AuthSigItem,
+    auth_methods: &Option<Vec<AuthMethod>>,
+    curve_type: CurveType,
+    epoch: u64,
+    message_count: usize,
+    client_session: &Arc<ClientSession>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    endpoint_version: EndpointVersion,
+    http_client: &State<reqwest::Client>,
+    timing: &mut BTreeMap<String, Duration>,
+) -> std::result::Result<(JsonAuthSig, AuthContext, String), status::Custom<Value>> {
+    let before = std::time::Instant::now();
+
+    let token_id = match pubkey_to_token_id(pubkey) {
+        Ok(token_id) => token_id,
+        Err(e) => {
+            return Err(client_session.json_encrypt_err_custom_response(
+                "can't get token id from public key",
+                e.handle(),
+            ));
+        }
+    };
     let resource = PKPNFTResource::new(token_id);
     let resource_ability = resource.signing_ability();
 
@@ -72,13 +479,13 @@ pub(crate) async fn pkp_sign(
     let bls_root_pubkey = match get_bls_root_pubkey(tss_state).await {
         Ok(bls_root_pubkey) => bls_root_pubkey,
         Err(e) => {
-            return client_session
-                .json_encrypt_err_custom_response("No bls root key exists", e.handle());
+            return Err(client_session
+                .json_encrypt_err_custom_response("No bls root key exists", e.handle()));
         }
     };
 
     let validated_address = {
-        match AuthSigItemExtendedRef(&json_pkp_signing_request.auth_sig)
+        match AuthSigItemExtendedRef(auth_sig_item)
             .validate_and_get_user_address(
                 &resource_ability,
                 &Some(CHAIN_ETHEREUM.to_string()),
@@ -89,8 +496,8 @@ pub(crate) async fn pkp_sign(
             .await
         {
             Err(e) => {
-                return client_session
-                    .json_encrypt_err_custom_response("invalid user address", e.handle());
+                return Err(client_session
+                    .json_encrypt_err_custom_response("invalid user address", e.handle()));
             }
             Ok(resp) => resp,
         }
@@ -100,29 +507,31 @@ pub(crate) async fn pkp_sign(
 
     // Handle payment depending on the version
 
-    if cfg.enable_payment().unwrap_or(true) {
+    if message_count > 0 && cfg.enable_payment().unwrap_or(true) {
         let delegation_usage_db = match delegation_usage_db {
             Some(db) => db,
             None => {
                 let msg = format!(
                     "Delegation db is not provided to {}, version {}",
-                    "pkp_sign", endpoint_version as u8
+                    endpoint_name, endpoint_version as u8
                 );
-                return client_session.json_encrypt_err_and_code(
+                return Err(client_session.json_encrypt_err_and_code(
                     &msg,
                     "delegation_usage_db_not_provided",
                     Status::PaymentRequired,
-                );
+                ));
             }
         };
 
         let before = std::time::Instant::now();
-        let single_auth_sig = match &json_pkp_signing_request.auth_sig {
+        let single_auth_sig = match auth_sig_item {
             AuthSigItem::Single(single_auth_sig) => single_auth_sig,
             AuthSigItem::Multiple(_) => {
                 let err_msg = "MultiAuthSig not supported for payment";
                 error!("{}", err_msg);
-                return client_session.json_encrypt_err_response(err_msg, Status::PaymentRequired);
+                return Err(
+                    client_session.json_encrypt_err_response(err_msg, Status::PaymentRequired)
+                );
             }
         };
 
@@ -133,36 +542,36 @@ pub(crate) async fn pkp_sign(
                     let err_msg = "Parsing SessionKeySignedMessageV2 failed. \
                         The sessionSig is incorrectly formatted";
                     error!("{}", err_msg);
-                    return client_session
-                        .json_encrypt_err_response(err_msg, Status::PaymentRequired);
+                    return Err(
+                        client_session.json_encrypt_err_response(err_msg, Status::PaymentRequired)
+                    );
                 }
             };
 
         let user_address = match validated_address.evm_address() {
             Ok(address) => address,
             Err(e) => {
-                return client_session.json_encrypt_err_custom_response(
+                return Err(client_session.json_encrypt_err_custom_response(
                     "can't convert address to an evm address",
                     e.handle(),
-                );
+                ));
             }
         };
 
         let peers = tss_state.peer_state.peers();
 
-        let curve_type = json_pkp_signing_request.signing_scheme.curve_type();
         let threshold = match tss_state
             .get_threshold_using_current_epoch_realm_peers_for_curve(
                 &peers,
                 curve_type,
-                Some(json_pkp_signing_request.epoch),
+                Some(epoch),
             )
             .await
         {
             Ok(t) => t,
             Err(e) => {
-                return client_session
-                    .json_encrypt_err_custom_response("unable to get threshold", e.handle());
+                return Err(client_session
+                    .json_encrypt_err_custom_response("unable to get threshold", e.handle()));
             }
         };
 
@@ -179,13 +588,29 @@ pub(crate) async fn pkp_sign(
         )
         .await;
 
-        let pending_payment = match payment_method {
+        let mut pending_payment = match payment_method {
             Ok(payment) => payment,
             Err(e) => {
-                return client_session
-                    .json_encrypt_err_custom_response("can't get payment method", e.handle());
+                return Err(client_session
+                    .json_encrypt_err_custom_response("can't get payment method", e.handle()));
             }
         };
+
+        // a batch is priced per message
+        if message_count > 1 {
+            pending_payment.price = pending_payment
+                .price
+                .saturating_mul(I256::from(message_count as u64));
+            if pending_payment.price > pending_payment.spending_limit {
+                let err_msg = format!(
+                    "The price of signing {} messages exceeds the spending limit",
+                    message_count
+                );
+                return Err(
+                    client_session.json_encrypt_err_response(&err_msg, Status::PaymentRequired)
+                );
+            }
+        }
         timing.insert("verify the payment".to_string(), before.elapsed());
 
         let before = std::time::Instant::now();
@@ -198,14 +623,14 @@ pub(crate) async fn pkp_sign(
     // check for single or multiple auth sigs and do the session key
     // capability check.  set the wallet that provided the capabilities as the
     // main auth sig wallet.
-    let auth_sig = match &json_pkp_signing_request.auth_sig {
+    let auth_sig = match auth_sig_item {
         AuthSigItem::Single(single_auth_sig) => single_auth_sig.clone(),
         AuthSigItem::Multiple(_) => {
-            return client_session.json_encrypt_err_and_code(
+            return Err(client_session.json_encrypt_err_and_code(
                 "Multiple auth sigs not supported by Lit Actions",
                 "unsupported_auth_sig",
                 Status::BadRequest,
-            );
+            ));
         }
     };
 
@@ -215,7 +640,7 @@ pub(crate) async fn pkp_sign(
         EndpointVersion::Initial => {
             let auth_context = get_auth_context(
                 Some(auth_sig.clone()),
-                json_pkp_signing_request.auth_methods.clone(),
+                auth_methods.clone(),
                 None,
                 Some(auth_context_cache),
                 false,
@@ -230,8 +655,8 @@ pub(crate) async fn pkp_sign(
             match auth_context {
                 Ok(auth_context) => auth_context,
                 Err(e) => {
-                    return client_session
-                        .json_encrypt_err_custom_response("invalid auth context", e.handle());
+                    return Err(client_session
+                        .json_encrypt_err_custom_response("invalid auth context", e.handle()));
                 }
             }
         }
@@ -240,11 +665,11 @@ pub(crate) async fn pkp_sign(
             let signed_message = match msg {
                 Ok(signed_message) => signed_message,
                 Err(err_msg) => {
-                    return client_session.json_encrypt_err_and_code(
+                    return Err(client_session.json_encrypt_err_and_code(
                         &err_msg,
                         "unsupported_auth_sig",
                         Status::BadRequest,
-                    );
+                    ));
                 }
             };
 
@@ -253,10 +678,10 @@ pub(crate) async fn pkp_sign(
                     Ok(resolved_auth_context) => resolved_auth_context,
                     Err(e) => {
                         error!("Error parsing AuthContext from sessionSig");
-                        return client_session.json_encrypt_err_custom_response(
+                        return Err(client_session.json_encrypt_err_custom_response(
                             "can't parse auth context from session signature",
                             e.handle(),
-                        );
+                        ));
                     }
                 };
 
@@ -283,10 +708,10 @@ pub(crate) async fn pkp_sign(
                     match new_auth_context {
                         Ok(new_auth_context) => new_auth_context,
                         Err(e) => {
-                            return client_session.json_encrypt_err_custom_response(
+                            return Err(client_session.json_encrypt_err_custom_response(
                                 "can't create an auth context from the EOA auth-sig",
                                 e.handle(),
-                            );
+                            ));
                         }
                     }
                 }
@@ -295,54 +720,9 @@ pub(crate) async fn pkp_sign(
     };
 
     timing.insert("auth context".to_string(), before.elapsed());
-    let before = std::time::Instant::now();
     trace!("Got auth context");
 
-    let epoch = match json_pkp_signing_request.epoch {
-        0 => None,
-        i => Some(i),
-    };
-    trace!(
-        "PKP Signing scheme: {}",
-        json_pkp_signing_request.signing_scheme
-    );
-
-    let result = sign(
-        cfg.as_ref(),
-        &json_pkp_signing_request.to_sign,
-        json_pkp_signing_request.pubkey.clone(),
-        request_id.clone(),
-        None, // Only the first one as we only allow running a single Lit Action now for session creation
-        Some(auth_sig.clone()), // This works with EOA wallets as well as we're passing SessionSig/AuthSig here
-        auth_context,
-        Some(tss_state.as_ref().clone()),
-        &[AuthMethodScope::SignAnything as usize],
-        epoch,
-        &bls_root_pubkey,
-        &json_pkp_signing_request.node_set,
-        json_pkp_signing_request.signing_scheme,
-    )
-    .await
-    .map_err(|e| unexpected_err(e, Some("Error signing with the PKP".to_string())));
-    timing.insert("sign".to_string(), before.elapsed());
-
-    let result = match result {
-        Ok(result) => client_session.json_encrypt_response_status(JsonPKPSigningResponse {
-            success: true,
-            signed_data: json_pkp_signing_request.to_sign.clone(),
-            signature_share: result,
-        }),
-        Err(e) => {
-            return client_session
-                .json_encrypt_err_custom_response("unable to get signature share", e.handle());
-        }
-    };
-
-    timing.insert("total".to_string(), request_start.elapsed());
-
-    debug!("POST /web/pkp/sign timing: {:?}", timing);
-
-    result
+    Ok((auth_sig, auth_context, bls_root_pubkey))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1,4 +1,7 @@
+use crate::access_control::cache::RpcResultCache;
 use crate::auth::auth_material::JsonAuthSigExtended;
+#[cfg(feature = "lit-actions")]
+use crate::client_session::ClientSession;
 use crate::client_session::ClientSessionHandler;
 use crate::endpoints::{admin, pkp, web_client};
 use crate::functions::ActionStore;
@@ -7,17 +10,22 @@ use crate::payment::delegated_usage::DelegatedUsageDB;
 use crate::payment::{payed_endpoint::PayedEndpoint, payment_tracker::PaymentTracker};
 use crate::peers::grpc_client_pool::GrpcClientPool;
 use crate::tss::common::{restore::restore_state::RestoreState, tss_state::TssState};
+use crate::tss::musig2::MuSig2NonceCache;
 use crate::utils::rocket::guards::RequestHeaders;
 use crate::utils::web::with_timeout;
 use lit_api_core::context::{Tracer, Tracing};
 use lit_api_core::error::ApiError;
+#[cfg(feature = "lit-actions")]
+use lit_api_core::http::rocket::helper::stream::JsonEventStream;
 use lit_core::config::ReloadableLitConfig;
 use lit_node_common::client_state::ClientState;
-use lit_node_core::request::EncryptionSignRequest;
+use lit_node_core::request::{EncryptionSignRequest, JsonSessionRevocationRequest};
 use lit_node_core::response::GenericResponse;
 use lit_node_core::{EndpointVersion, request};
 use lit_sdk::EncryptedPayload;
 use moka::future::Cache;
+#[cfg(feature = "lit-actions")]
+use rocket::futures::{Stream, stream};
 use rocket::response::status;
 use rocket::serde::json::{Json, Value, serde_json::json};
 use rocket::{Route, State};
@@ -33,9 +41,23 @@ pub(crate) fn routes() -> Vec<Route> {
         admin_set_blinders,
         sign_session_key,
         encryption_sign,
+        explain_access_control_conditions,
         pkp_sign,
+        pkp_sign_batch,
+        pkp_musig2_nonce,
+        pkp_musig2_sign,
         execute_function,
+        execute_function_stream,
         get_job_status,
+        create_action_schedule,
+        get_action_schedules,
+        delete_action_schedule,
+        get_action_schedule_jobs,
+        put_action_secret,
+        get_action_secret_names,
+        delete_action_secret,
+        revoke_session_key,
+        get_session_revocations,
     ]
 }
 
@@ -53,6 +75,7 @@ pub(crate) async fn sign_session_key(
     delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
     ipfs_cache: &State<Cache<String, Arc<String>>>,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
     cfg: &State<ReloadableLitConfig>,
     client_state: &State<Arc<ClientState>>,
     payment_tracker: &State<Arc<PaymentTracker>>,
@@ -100,6 +123,7 @@ pub(crate) async fn sign_session_key(
                 payment_tracker,
                 tracing.correlation_id().to_owned(),
                 http_client,
+                rpc_cache,
             )
             .await
         },
@@ -127,6 +151,7 @@ pub(crate) async fn encryption_sign(
     cfg: &State<ReloadableLitConfig>,
     client_state: &State<Arc<ClientState>>,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
     encryption_sign_request: Json<EncryptedPayload<EncryptionSignRequest>>,
     tracing: Tracing,
 ) -> status::Custom<Value> {
@@ -178,6 +203,7 @@ pub(crate) async fn encryption_sign(
                 EndpointVersion::V2,
                 tracing.correlation_id().to_owned(),
                 http_client,
+                rpc_cache,
             )
             .await
         },
@@ -189,6 +215,67 @@ pub(crate) async fn encryption_sign(
     call_result
 }
 
+#[allow(clippy::too_many_arguments)]
+#[post(
+    "/web/access_control/explain/v2",
+    format = "json",
+    data = "<explain_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/access_control/explain/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+pub(crate) async fn explain_access_control_conditions(
+    session: &State<Arc<TssState>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+    http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
+    explain_request: Json<EncryptedPayload<EncryptionSignRequest>>,
+    tracing: Tracing,
+) -> status::Custom<Value> {
+    payment_tracker.register_usage(&PayedEndpoint::ExplainAccessControl);
+
+    let (explain_request, client_session) =
+        match client_state.json_decrypt_to_session(&explain_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                let msg = GenericResponse::err_and_data_json("can't decrypt".to_string(), handle.1);
+                payment_tracker.deregister_usage(&PayedEndpoint::ExplainAccessControl);
+                return status::Custom(handle.0, json!(msg));
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    let call_result = with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::explain_access_control_conditions(
+                session,
+                delegation_usage_db,
+                ipfs_cache,
+                cfg,
+                explain_request,
+                client_session,
+                payment_tracker,
+                EndpointVersion::V2,
+                tracing.correlation_id().to_owned(),
+                http_client,
+                rpc_cache,
+            )
+            .await
+        },
+    )
+    .await;
+
+    payment_tracker.deregister_usage(&PayedEndpoint::ExplainAccessControl);
+
+    call_result
+}
+
 #[cfg(feature = "lit-actions")]
 #[post("/web/execute/v2", format = "json", data = "<json_execution_request>")]
 #[instrument(level = "debug", name = "POST /web/execute/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
@@ -200,6 +287,7 @@ pub(crate) async fn execute_function(
     delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
     ipfs_cache: &State<Cache<String, Arc<String>>>,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
     payment_tracker: &State<Arc<PaymentTracker>>,
     cfg: &State<ReloadableLitConfig>,
     allowlist_cache: &State<Arc<models::AllowlistCache>>,
@@ -253,6 +341,8 @@ pub(crate) async fn execute_function(
                 tracing.correlation_id().to_owned(),
                 action_store,
                 http_client,
+                rpc_cache,
+                None,
             )
             .await
         },
@@ -264,6 +354,152 @@ pub(crate) async fn execute_function(
     call_result
 }
 
+#[cfg(feature = "lit-actions")]
+const STREAM_CHUNK_BUFFER: usize = 16;
+
+/// Like `/web/execute/v2`, but streams the chunks passed to `Lit.Actions.streamChunk` as
+/// `chunk` server-sent events while the action runs, followed by a single `result` event
+/// holding the status and body `/web/execute/v2` would have responded with.
+#[cfg(feature = "lit-actions")]
+#[post(
+    "/web/execute/stream/v2",
+    format = "json",
+    data = "<json_execution_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/execute/stream/v2", skip_all, fields(correlation_id = tracing.correlation_id()))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn execute_function_stream<'r>(
+    remote_addr: SocketAddr,
+    tss_state: &'r State<Arc<TssState>>,
+    auth_context_cache: &'r State<Arc<models::AuthContextCache>>,
+    delegation_usage_db: &'r State<Arc<DelegatedUsageDB>>,
+    ipfs_cache: &'r State<Cache<String, Arc<String>>>,
+    http_client: &'r State<reqwest::Client>,
+    rpc_cache: &'r State<RpcResultCache>,
+    payment_tracker: &'r State<Arc<PaymentTracker>>,
+    cfg: &'r State<ReloadableLitConfig>,
+    allowlist_cache: &'r State<Arc<models::AllowlistCache>>,
+    client_state: &'r State<Arc<ClientState>>,
+    client_grpc_connections: &'r State<GrpcClientPool<tonic::transport::Channel>>,
+    json_execution_request: Json<EncryptedPayload<request::JsonExecutionRequest>>,
+    tracing: Tracing,
+    request_headers: RequestHeaders<'r>,
+    action_store: &'r State<ActionStore>,
+) -> Result<
+    JsonEventStream<impl Stream<Item = (&'static str, Value)> + Send + 'r>,
+    status::Custom<Value>,
+> {
+    let (json_execution_request, client_session) =
+        match client_state.json_decrypt_to_session(&json_execution_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                return Err(status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                ));
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    // Deregistered when the stream is dropped, also if the client disconnects midway
+    let usage = payment_tracker.register_usage_guard(PayedEndpoint::LitAction);
+
+    let actions_config = tss_state.chain_data_config_manager.get_actions_config();
+    let correlation_id = tracing.correlation_id().to_owned();
+    let (chunk_sender, chunk_receiver) = tokio::sync::mpsc::channel(STREAM_CHUNK_BUFFER);
+
+    let execution = {
+        let client_session = client_session.clone();
+        async move {
+            let _usage = usage;
+
+            // Payment is settled by the execution even if the client disconnected, exactly as
+            // for non-streamed executions
+            with_timeout(
+                &cfg.load_full(),
+                Some(actions_config.timeout_ms),
+                Some(client_session.clone()),
+                web_client::execute_function(
+                    remote_addr,
+                    tss_state,
+                    auth_context_cache,
+                    Some(delegation_usage_db),
+                    cfg,
+                    client_grpc_connections,
+                    allowlist_cache,
+                    ipfs_cache,
+                    client_state,
+                    json_execution_request,
+                    client_session,
+                    request_headers,
+                    payment_tracker,
+                    EndpointVersion::V2,
+                    correlation_id,
+                    action_store,
+                    http_client,
+                    rpc_cache,
+                    Some(chunk_sender),
+                ),
+            )
+            .await
+        }
+    };
+
+    let events = stream::unfold(
+        (Box::pin(execution), None, false, chunk_receiver),
+        move |(mut execution, mut result, mut done, mut chunk_receiver)| {
+            let client_session = client_session.clone();
+            async move {
+                loop {
+                    tokio::select! {
+                        biased;
+                        chunk = chunk_receiver.recv() => {
+                            let event = match chunk {
+                                Some(chunk) => chunk_event(&client_session, chunk),
+                                // The sender is dropped with the action client, i.e. once the
+                                // execution is done and every chunk has been sent
+                                None => match result.take() {
+                                    Some(result) => result_event(result),
+                                    None if !done => {
+                                        done = true;
+                                        result_event((&mut execution).await)
+                                    }
+                                    None => return None,
+                                },
+                            };
+                            return Some((event, (execution, result, done, chunk_receiver)));
+                        }
+                        call_result = &mut execution, if !done => {
+                            done = true;
+                            result = Some(call_result);
+                        }
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(JsonEventStream(events))
+}
+
+#[cfg(feature = "lit-actions")]
+fn chunk_event(client_session: &ClientSession, chunk: String) -> (&'static str, Value) {
+    (
+        "chunk",
+        json!(client_session.json_encrypt_response(GenericResponse::ok(chunk))),
+    )
+}
+
+#[cfg(feature = "lit-actions")]
+fn result_event(result: status::Custom<Value>) -> (&'static str, Value) {
+    let status::Custom(status, body) = result;
+    ("result", json!({ "status": status.code, "body": body }))
+}
+
 #[cfg(feature = "lit-actions")]
 #[post("/web/job_status/v2", format = "json", data = "<job_status_request>")]
 #[instrument(level = "debug", name = "POST /web/job_status/v2", skip_all, ret)]
@@ -309,31 +545,384 @@ pub(crate) async fn get_job_status(
     .await
 }
 
+#[cfg(feature = "lit-actions")]
+#[post("/web/schedule/v2", format = "json", data = "<schedule_request>")]
+#[instrument(level = "debug", name = "POST /web/schedule/v2", skip_all, ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn create_action_schedule(
+    schedule_request: Json<EncryptedPayload<models::JsonActionScheduleRequest>>,
+    allowlist_cache: &State<Arc<models::AllowlistCache>>,
+    tss_state: &State<Arc<TssState>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+) -> status::Custom<Value> {
+    // creating a schedule is billed as a Lit Action, so it shares its capacity
+    payment_tracker.register_usage(&PayedEndpoint::LitAction);
+
+    let (schedule_request, client_session) =
+        match client_state.json_decrypt_to_session(&schedule_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                payment_tracker.deregister_usage(&PayedEndpoint::LitAction);
+                return status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                );
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    let call_result = with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::create_action_schedule(
+                schedule_request,
+                client_session,
+                allowlist_cache,
+                tss_state,
+                delegation_usage_db,
+                payment_tracker,
+                cfg,
+            )
+            .await
+        },
+    )
+    .await;
+
+    payment_tracker.deregister_usage(&PayedEndpoint::LitAction);
+
+    call_result
+}
+
+#[cfg(feature = "lit-actions")]
+#[post("/web/schedule/list/v2", format = "json", data = "<schedules_request>")]
+#[instrument(level = "debug", name = "POST /web/schedule/list/v2", skip_all, ret)]
+pub(crate) async fn get_action_schedules(
+    schedules_request: Json<EncryptedPayload<models::JsonActionSchedulesRequest>>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+) -> status::Custom<Value> {
+    let (schedules_request, client_session) =
+        match client_state.json_decrypt_to_session(&schedules_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                return status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                );
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::get_action_schedules(schedules_request, client_session, tss_state, cfg)
+                .await
+        },
+    )
+    .await
+}
+
+#[cfg(feature = "lit-actions")]
 #[post(
-    "/web/pkp/sign/v2",
+    "/web/schedule/delete/v2",
     format = "json",
-    data = "<json_pkp_signing_request>"
+    data = "<schedule_request>"
 )]
-#[instrument(level = "debug", name = "POST /web/pkp/sign/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+#[instrument(level = "debug", name = "POST /web/schedule/delete/v2", skip_all, ret)]
+pub(crate) async fn delete_action_schedule(
+    schedule_request: Json<EncryptedPayload<models::JsonActionScheduleIdRequest>>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+) -> status::Custom<Value> {
+    let (schedule_request, client_session) =
+        match client_state.json_decrypt_to_session(&schedule_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                return status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                );
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::delete_action_schedule(schedule_request, client_session, tss_state, cfg)
+                .await
+        },
+    )
+    .await
+}
+
+#[cfg(feature = "lit-actions")]
+#[post("/web/schedule/jobs/v2", format = "json", data = "<schedule_request>")]
+#[instrument(level = "debug", name = "POST /web/schedule/jobs/v2", skip_all, ret)]
+pub(crate) async fn get_action_schedule_jobs(
+    schedule_request: Json<EncryptedPayload<models::JsonActionScheduleIdRequest>>,
+    action_store: &State<ActionStore>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+) -> status::Custom<Value> {
+    let (schedule_request, client_session) =
+        match client_state.json_decrypt_to_session(&schedule_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                return status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                );
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::get_action_schedule_jobs(
+                schedule_request,
+                client_session,
+                action_store,
+                tss_state,
+                cfg,
+            )
+            .await
+        },
+    )
+    .await
+}
+
+#[cfg(feature = "lit-actions")]
+#[post("/web/secrets/v2", format = "json", data = "<secret_request>")]
+#[instrument(level = "debug", name = "POST /web/secrets/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
 #[allow(clippy::too_many_arguments)]
-pub(crate) async fn pkp_sign(
-    remote_addr: SocketAddr,
+pub(crate) async fn put_action_secret(
+    secret_request: Json<EncryptedPayload<models::JsonActionSecretRequest>>,
     tss_state: &State<Arc<TssState>>,
-    auth_context_cache: &State<Arc<models::AuthContextCache>>,
-    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
     payment_tracker: &State<Arc<PaymentTracker>>,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    http_client: &State<reqwest::Client>,
     cfg: &State<ReloadableLitConfig>,
-    allowlist_cache: &State<Arc<models::AllowlistCache>>,
     client_state: &State<Arc<ClientState>>,
-    json_pkp_signing_request: Json<EncryptedPayload<request::JsonPKPSigningRequest>>,
     tracing: Tracing,
-    http_client: &State<reqwest::Client>,
 ) -> status::Custom<Value> {
-    payment_tracker.register_usage(&PayedEndpoint::PkpSign);
+    // storing a secret is billed as a Lit Action, so it shares its capacity
+    payment_tracker.register_usage(&PayedEndpoint::LitAction);
 
-    let (json_pkp_signing_request, client_session) =
-        match client_state.json_decrypt_to_session(&json_pkp_signing_request) {
-            Ok(json_pkp_signing_request) => json_pkp_signing_request,
+    let (secret_request, client_session) =
+        match client_state.json_decrypt_to_session(&secret_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                let msg = GenericResponse::err_and_data_json("can't decrypt".to_string(), handle.1);
+                payment_tracker.deregister_usage(&PayedEndpoint::LitAction);
+                return status::Custom(handle.0, json!(msg));
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    let call_result = with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::put_action_secret(
+                secret_request,
+                client_session,
+                tss_state,
+                delegation_usage_db,
+                payment_tracker,
+                ipfs_cache,
+                http_client,
+                cfg,
+                tracing.correlation_id().to_owned(),
+            )
+            .await
+        },
+    )
+    .await;
+
+    payment_tracker.deregister_usage(&PayedEndpoint::LitAction);
+
+    call_result
+}
+
+#[cfg(feature = "lit-actions")]
+#[post("/web/secrets/list/v2", format = "json", data = "<secrets_request>")]
+#[instrument(level = "debug", name = "POST /web/secrets/list/v2", skip_all, ret)]
+pub(crate) async fn get_action_secret_names(
+    secrets_request: Json<EncryptedPayload<models::JsonActionSecretsRequest>>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+) -> status::Custom<Value> {
+    let (secrets_request, client_session) =
+        match client_state.json_decrypt_to_session(&secrets_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                return status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                );
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::get_action_secret_names(secrets_request, client_session, tss_state, cfg)
+                .await
+        },
+    )
+    .await
+}
+
+#[cfg(feature = "lit-actions")]
+#[post("/web/secrets/delete/v2", format = "json", data = "<secret_request>")]
+#[instrument(level = "debug", name = "POST /web/secrets/delete/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn delete_action_secret(
+    secret_request: Json<EncryptedPayload<models::JsonActionSecretNameRequest>>,
+    tss_state: &State<Arc<TssState>>,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    http_client: &State<reqwest::Client>,
+    cfg: &State<ReloadableLitConfig>,
+    client_state: &State<Arc<ClientState>>,
+    tracing: Tracing,
+) -> status::Custom<Value> {
+    let (secret_request, client_session) =
+        match client_state.json_decrypt_to_session(&secret_request) {
+            Ok(request) => request,
+            Err(e) => {
+                let handle = e.handle();
+                return status::Custom(
+                    handle.0,
+                    json!(GenericResponse::err_and_data_json(
+                        "can't decrypt".to_string(),
+                        handle.1
+                    )),
+                );
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            web_client::delete_action_secret(
+                secret_request,
+                client_session,
+                tss_state,
+                ipfs_cache,
+                http_client,
+                cfg,
+                tracing.correlation_id().to_owned(),
+            )
+            .await
+        },
+    )
+    .await
+}
+
+#[post(
+    "/web/session_sig/revoke/v2",
+    format = "json",
+    data = "<revocation_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/session_sig/revoke/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+pub(crate) async fn revoke_session_key(
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+    http_client: &State<reqwest::Client>,
+    revocation_request: Json<JsonSessionRevocationRequest>,
+    tracing: Tracing,
+) -> status::Custom<Value> {
+    with_timeout(&cfg.load_full(), None, None, async move {
+        web_client::revoke_session_key(revocation_request.into_inner(), tss_state, cfg, http_client)
+            .await
+    })
+    .await
+}
+
+#[get("/web/session_sig/revocations/v2")]
+#[instrument(level = "debug", name = "GET /web/session_sig/revocations/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+pub(crate) async fn get_session_revocations(
+    cfg: &State<ReloadableLitConfig>,
+    tracing: Tracing,
+) -> status::Custom<Value> {
+    with_timeout(&cfg.load_full(), None, None, async move {
+        web_client::get_session_revocations(cfg).await
+    })
+    .await
+}
+
+#[post(
+    "/web/pkp/sign/v2",
+    format = "json",
+    data = "<json_pkp_signing_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/pkp/sign/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_sign(
+    remote_addr: SocketAddr,
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<models::AuthContextCache>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    cfg: &State<ReloadableLitConfig>,
+    allowlist_cache: &State<Arc<models::AllowlistCache>>,
+    client_state: &State<Arc<ClientState>>,
+    json_pkp_signing_request: Json<EncryptedPayload<request::JsonPKPSigningRequest>>,
+    tracing: Tracing,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    payment_tracker.register_usage(&PayedEndpoint::PkpSign);
+
+    let (json_pkp_signing_request, client_session) =
+        match client_state.json_decrypt_to_session(&json_pkp_signing_request) {
+            Ok(json_pkp_signing_request) => json_pkp_signing_request,
             Err(e) => {
                 let handle = e.handle();
                 let msg = GenericResponse::err_and_data_json("can't decrypt".to_string(), handle.1);
@@ -372,6 +961,187 @@ pub(crate) async fn pkp_sign(
     call_result
 }
 
+#[post(
+    "/web/pkp/sign/batch/v2",
+    format = "json",
+    data = "<json_pkp_batch_signing_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/pkp/sign/batch/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_sign_batch(
+    remote_addr: SocketAddr,
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<models::AuthContextCache>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    cfg: &State<ReloadableLitConfig>,
+    allowlist_cache: &State<Arc<models::AllowlistCache>>,
+    client_state: &State<Arc<ClientState>>,
+    json_pkp_batch_signing_request: Json<EncryptedPayload<request::JsonPKPBatchSigningRequest>>,
+    tracing: Tracing,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    payment_tracker.register_usage(&PayedEndpoint::PkpSign);
+
+    let (json_pkp_batch_signing_request, client_session) =
+        match client_state.json_decrypt_to_session(&json_pkp_batch_signing_request) {
+            Ok(json_pkp_batch_signing_request) => json_pkp_batch_signing_request,
+            Err(e) => {
+                let handle = e.handle();
+                let msg = GenericResponse::err_and_data_json("can't decrypt".to_string(), handle.1);
+                return status::Custom(handle.0, json!(msg));
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    let call_result = with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            pkp::pkp_sign_batch(
+                remote_addr,
+                tss_state,
+                auth_context_cache,
+                Some(delegation_usage_db),
+                cfg,
+                allowlist_cache,
+                client_state,
+                json_pkp_batch_signing_request,
+                client_session,
+                payment_tracker,
+                EndpointVersion::V2,
+                tracing.correlation_id().to_owned(),
+                http_client,
+            )
+            .await
+        },
+    )
+    .await;
+
+    payment_tracker.deregister_usage(&PayedEndpoint::PkpSign);
+
+    call_result
+}
+
+#[post(
+    "/web/pkp/musig2/nonce/v2",
+    format = "json",
+    data = "<json_musig2_nonce_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/pkp/musig2/nonce/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_musig2_nonce(
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<models::AuthContextCache>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    cfg: &State<ReloadableLitConfig>,
+    nonce_cache: &State<Arc<MuSig2NonceCache>>,
+    client_state: &State<Arc<ClientState>>,
+    json_musig2_nonce_request: Json<EncryptedPayload<request::JsonPKPMuSig2NonceRequest>>,
+    tracing: Tracing,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    payment_tracker.register_usage(&PayedEndpoint::PkpSign);
+
+    let (json_musig2_nonce_request, client_session) =
+        match client_state.json_decrypt_to_session(&json_musig2_nonce_request) {
+            Ok(json_musig2_nonce_request) => json_musig2_nonce_request,
+            Err(e) => {
+                let handle = e.handle();
+                let msg = GenericResponse::err_and_data_json("can't decrypt".to_string(), handle.1);
+                return status::Custom(handle.0, json!(msg));
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    let call_result = with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            pkp::pkp_musig2_nonce(
+                tss_state,
+                auth_context_cache,
+                Some(delegation_usage_db),
+                cfg,
+                nonce_cache,
+                json_musig2_nonce_request,
+                client_session,
+                payment_tracker,
+                EndpointVersion::V2,
+                http_client,
+            )
+            .await
+        },
+    )
+    .await;
+
+    payment_tracker.deregister_usage(&PayedEndpoint::PkpSign);
+
+    call_result
+}
+
+#[post(
+    "/web/pkp/musig2/sign/v2",
+    format = "json",
+    data = "<json_musig2_signing_request>"
+)]
+#[instrument(level = "debug", name = "POST /web/pkp/musig2/sign/v2", skip_all, fields(correlation_id = tracing.correlation_id()), ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn pkp_musig2_sign(
+    tss_state: &State<Arc<TssState>>,
+    auth_context_cache: &State<Arc<models::AuthContextCache>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    cfg: &State<ReloadableLitConfig>,
+    nonce_cache: &State<Arc<MuSig2NonceCache>>,
+    client_state: &State<Arc<ClientState>>,
+    json_musig2_signing_request: Json<EncryptedPayload<request::JsonPKPMuSig2SigningRequest>>,
+    tracing: Tracing,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    payment_tracker.register_usage(&PayedEndpoint::PkpSign);
+
+    let (json_musig2_signing_request, client_session) =
+        match client_state.json_decrypt_to_session(&json_musig2_signing_request) {
+            Ok(json_musig2_signing_request) => json_musig2_signing_request,
+            Err(e) => {
+                let handle = e.handle();
+                let msg = GenericResponse::err_and_data_json("can't decrypt".to_string(), handle.1);
+                return status::Custom(handle.0, json!(msg));
+            }
+        };
+    let client_session = Arc::new(client_session);
+
+    let call_result = with_timeout(
+        &cfg.load_full(),
+        None,
+        Some(client_session.clone()),
+        async move {
+            pkp::pkp_musig2_sign(
+                tss_state,
+                auth_context_cache,
+                Some(delegation_usage_db),
+                cfg,
+                nonce_cache,
+                json_musig2_signing_request,
+                client_session,
+                payment_tracker,
+                EndpointVersion::V2,
+                http_client,
+            )
+            .await
+        },
+    )
+    .await;
+
+    payment_tracker.deregister_usage(&PayedEndpoint::PkpSign);
+
+    call_result
+}
+
 #[post("/web/admin/get_blinders/v2", format = "json", data = "<auth>")]
 #[instrument(
     level = "trace",
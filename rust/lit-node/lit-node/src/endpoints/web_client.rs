@@ -1,13 +1,15 @@
+use crate::access_control::cache::RpcResultCache;
 use crate::auth::auth_material::{
     AuthSigItemExtendedRef, ValidatedAddress, siwe_hash_to_bls_session_hash,
 };
+use crate::auth::revocation;
 use crate::client_session::ClientSession;
 use crate::error::{
     EC, connect_err_code, conversion_err, memory_limit_err_code, timeout_err_code, unexpected_err,
     unexpected_err_code, validation_err_code,
 };
 use crate::error::{parser_err, parser_err_code};
-use crate::functions::{ActionStore, JobId, action_client};
+use crate::functions::{ActionStore, JobId, action_client, schedule, secrets};
 use crate::models::auth::SessionKeySignedMessageV2;
 use crate::models::{self, RequestConditions};
 use crate::payment::delegated_usage::DelegatedUsageDB;
@@ -41,12 +43,16 @@ use lit_core::config::{LitConfig, ReloadableLitConfig};
 use lit_node_common::{client_state::ClientState, config::LitNodeConfig};
 use lit_node_core::CurveType;
 use lit_node_core::SigningScheme;
-use lit_node_core::request::{EncryptionSignRequest, JsonExecutionRequest};
-use lit_node_core::response::{EncryptionSignResponse, GenericResponse};
+use lit_node_core::request::{
+    EncryptionSignRequest, JsonExecutionRequest, JsonSessionRevocationRequest,
+};
+use lit_node_core::response::{
+    EncryptionSignResponse, GenericResponse, JsonAccessControlExplainResponse,
+};
 use lit_node_core::{
     AccessControlConditionItem, AccessControlConditionResource, AuthSigItem,
-    EVMContractConditionItem, EndpointVersion, LitActionResource, LitResource, LitResourceAbility,
-    SolRpcConditionItem, UnifiedAccessControlConditionItem,
+    EVMContractConditionItem, EndpointVersion, LitActionPriceComponent, LitActionResource,
+    LitResource, LitResourceAbility, SolRpcConditionItem, UnifiedAccessControlConditionItem,
     constants::{CHAIN_ETHEREUM, LIT_RESOURCE_KEY_RAC, LIT_RESOURCE_PREFIX_RAC},
     request,
     request::JsonSDKHandshakeRequest,
@@ -58,7 +64,7 @@ use rocket::http::Status;
 use rocket::response::status;
 use rocket::serde::json::{Json, Value, serde_json::json};
 use siwe_recap::Capability;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::net::SocketAddr;
 use std::str::FromStr;
@@ -90,6 +96,7 @@ pub(crate) async fn encryption_sign(
     endpoint_version: EndpointVersion,
     request_id: String,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
 ) -> status::Custom<Value> {
     let request_start = std::time::Instant::now();
 
@@ -273,8 +280,10 @@ pub(crate) async fn encryption_sign(
         &bls_root_pubkey,
         &endpoint_version,
         None,
+        &encryption_sign_request.block_numbers,
         ipfs_cache,
         http_client,
+        rpc_cache,
     )
     .await;
     timing.insert(
@@ -332,6 +341,178 @@ pub(crate) async fn encryption_sign(
     })
 }
 
+/// Evaluates the access control conditions of a decryption request and returns a
+/// per-condition trace instead of a signature share.  Billed as `explain_access_control`
+/// once the conditions have been evaluated.
+#[instrument(
+    level = "debug",
+    name = "POST /web/access_control/explain",
+    skip_all,
+    ret
+)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn explain_access_control_conditions(
+    session: &Arc<TssState>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    cfg: &ReloadableLitConfig,
+    explain_request: EncryptionSignRequest,
+    client_session: Arc<ClientSession>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    endpoint_version: EndpointVersion,
+    request_id: String,
+    http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
+) -> status::Custom<Value> {
+    trace!(
+        "explain_access_control_conditions, request: {:?}",
+        explain_request
+    );
+
+    let cfg = cfg.load_full();
+
+    if let Err(e) = check_condition_count(
+        &explain_request.access_control_conditions,
+        &explain_request.evm_contract_conditions,
+        &explain_request.sol_rpc_conditions,
+        &explain_request.unified_access_control_conditions,
+    ) {
+        return client_session
+            .json_encrypt_err_custom_response("failed condition count check", e.handle());
+    }
+
+    let hashed_access_control_conditions = match hash_access_control_conditions(RequestConditions {
+        access_control_conditions: explain_request.access_control_conditions.clone(),
+        evm_contract_conditions: explain_request.evm_contract_conditions.clone(),
+        sol_rpc_conditions: explain_request.sol_rpc_conditions.clone(),
+        unified_access_control_conditions: explain_request
+            .unified_access_control_conditions
+            .clone(),
+    }) {
+        Ok(hashed_access_control_conditions) => hashed_access_control_conditions,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("failed control conditions", e.handle());
+        }
+    };
+    let lit_acc_resource = AccessControlConditionResource::new(format!(
+        "{}/{}",
+        hashed_access_control_conditions, explain_request.data_to_encrypt_hash
+    ));
+
+    let bls_root_pubkey = match get_bls_root_pubkey(session).await {
+        Ok(bls_root_pubkey) => bls_root_pubkey,
+        Err(e) => {
+            return client_session.json_encrypt_err_custom_response("no bls root key", e.handle());
+        }
+    };
+    let validated_address = match AuthSigItemExtendedRef(&explain_request.auth_sig)
+        .validate_and_get_user_address(
+            &lit_acc_resource.decrypt_ability(),
+            &explain_request.chain.clone(),
+            &cfg,
+            &bls_root_pubkey,
+            &endpoint_version,
+        )
+        .await
+    {
+        Ok(validated_address) => validated_address,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("couldn't validate user address", e.handle());
+        }
+    };
+
+    let pending_payment = if cfg.enable_payment().unwrap_or(true) {
+        let single_auth_sig = match &explain_request.auth_sig {
+            AuthSigItem::Single(single_auth_sig) => single_auth_sig,
+            AuthSigItem::Multiple(_) => {
+                return client_session.json_encrypt_err_response(
+                    "MultiAuthSig not supported for payment",
+                    Status::PaymentRequired,
+                );
+            }
+        };
+        let signed_message: SessionKeySignedMessageV2 = match serde_json::from_str(
+            &single_auth_sig.signed_message,
+        ) {
+            Ok(signed_message) => signed_message,
+            Err(_) => {
+                return client_session.json_encrypt_err_response(
+                        "Parsing SessionKeySignedMessageV2 failed. The sessionSig is incorrectly formatted.",
+                        Status::PaymentRequired,
+                    );
+            }
+        };
+        let user_address = match validated_address.evm_address() {
+            Ok(address) => address,
+            Err(e) => {
+                return client_session
+                    .json_encrypt_err_custom_response("invalid evm address", e.handle());
+            }
+        };
+
+        let pending_payment = match get_payment_method(
+            &user_address,
+            PayedEndpoint::ExplainAccessControl,
+            session.get_threshold().await,
+            signed_message.max_price,
+            Some(signed_message),
+            payment_tracker,
+            delegation_usage_db,
+            &bls_root_pubkey,
+            &cfg,
+        )
+        .await
+        {
+            Ok(payment) => payment,
+            Err(e) => {
+                return client_session
+                    .json_encrypt_err_custom_response("unable to get payment method", e.handle());
+            }
+        };
+        Some(pending_payment)
+    } else {
+        None
+    };
+
+    let (check_result, trace) =
+        access_control::explain::with_trace(check_multiple_access_control_conditions(
+            &explain_request.auth_sig,
+            &explain_request.access_control_conditions,
+            &explain_request.evm_contract_conditions,
+            &explain_request.sol_rpc_conditions,
+            &explain_request.unified_access_control_conditions,
+            cfg,
+            &lit_acc_resource.decrypt_ability(),
+            &explain_request.chain,
+            request_id,
+            &bls_root_pubkey,
+            &endpoint_version,
+            None,
+            &explain_request.block_numbers,
+            ipfs_cache,
+            http_client,
+            rpc_cache,
+        ))
+        .await;
+
+    let (result, error) = match check_result {
+        Ok(check_result) => (check_result.result, None),
+        Err(e) => (false, Some(e.to_string())),
+    };
+
+    if let Some(pending_payment) = pending_payment {
+        payment_tracker.batches().add(pending_payment).await;
+    }
+
+    client_session.json_encrypt_response_status(JsonAccessControlExplainResponse {
+        result,
+        error,
+        trace,
+    })
+}
+
 /*
 curl --header "Content-Type: application/json" \
   --request POST \
@@ -511,6 +692,61 @@ pub async fn handshake(
     )
 }
 
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn revoke_session_key(
+    revocation_request: JsonSessionRevocationRequest,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+    http_client: &State<reqwest::Client>,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+    let port = match cfg.external_port() {
+        Ok(port) => port,
+        Err(e) => return e.handle(),
+    };
+
+    let subnet_id = match cfg.subnet_id() {
+        Ok(subnet_id) => subnet_id,
+        Err(e) => return e.handle(),
+    };
+
+    let revocation = match revocation::verify_revocation(&revocation_request, &subnet_id).await {
+        Ok(revocation) => revocation,
+        Err(e) => return e.handle(),
+    };
+    let is_new = match revocation::revoke(revocation, &revocation_request, port).await {
+        Ok(is_new) => is_new,
+        Err(e) => return e.handle(),
+    };
+
+    // gossip each persisted revocation once; nodes that already know it stop the spread
+    if is_new {
+        tokio::spawn(revocation::forward_to_peers(
+            revocation_request,
+            tss_state.inner().clone(),
+            cfg,
+            http_client.inner().clone(),
+        ));
+    }
+
+    status::Custom(Status::Ok, json!(GenericResponse::ok(is_new)))
+}
+
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn get_session_revocations(
+    cfg: &State<ReloadableLitConfig>,
+) -> status::Custom<Value> {
+    let port = match cfg.load_full().external_port() {
+        Ok(port) => port,
+        Err(e) => return e.handle(),
+    };
+
+    match revocation::live_revocation_requests(port) {
+        Ok(requests) => status::Custom(Status::Ok, json!(GenericResponse::ok(requests))),
+        Err(e) => e.handle(),
+    }
+}
+
 #[cfg(feature = "lit-actions")]
 #[instrument(level = "debug", skip_all, ret)]
 pub(crate) async fn get_job_status(
@@ -601,6 +837,516 @@ pub(crate) async fn get_job_status(
     }
 }
 
+/// Validates the auth sig of a request about action schedules or secrets, returning the
+/// user's address.
+#[cfg(feature = "lit-actions")]
+async fn validate_action_user(
+    auth_sig: &AuthSigItem,
+    ipfs_id: &str,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &LitConfig,
+) -> error::Result<String> {
+    validate_action_user_address(auth_sig, ipfs_id, tss_state, cfg)
+        .await
+        .map(|resp| resp.address_str().to_lowercase())
+}
+
+async fn validate_action_user_address(
+    auth_sig: &AuthSigItem,
+    ipfs_id: &str,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &LitConfig,
+) -> error::Result<ValidatedAddress> {
+    let bls_root_pubkey = get_bls_root_pubkey(tss_state).await?;
+    let lit_action_resource = LitActionResource::new(ipfs_id.to_string());
+
+    AuthSigItemExtendedRef(auth_sig)
+        .validate_and_get_user_address(
+            &lit_action_resource.execution_ability(),
+            &Some(CHAIN_ETHEREUM.to_string()),
+            cfg,
+            &bls_root_pubkey,
+            &EndpointVersion::V2,
+        )
+        .await
+}
+
+/// Checks that the user is the secrets owner declared by the code of the action.
+#[cfg(feature = "lit-actions")]
+async fn authorize_secrets_owner(
+    ipfs_id: &str,
+    user_address: &str,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    http_client: &State<reqwest::Client>,
+    cfg: &LitConfig,
+) -> error::Result<()> {
+    let code = get_ipfs_file(
+        &ipfs_id.to_string(),
+        cfg,
+        moka::future::Cache::clone(ipfs_cache),
+        reqwest::Client::clone(http_client),
+    )
+    .await?;
+    secrets::authorize(ipfs_id, &code, user_address)
+}
+
+/// Returns a schedule of the user. Schedules of others are reported as not found.
+#[cfg(feature = "lit-actions")]
+fn get_owned_schedule(
+    port: u16,
+    schedule_id: &str,
+    user_address: &str,
+) -> error::Result<schedule::ActionSchedule> {
+    match schedule::get(port, schedule_id)? {
+        Some(schedule) if schedule.owner == user_address => Ok(schedule),
+        _ => Err(validation_err_code(
+            format!("Schedule with ID {schedule_id} not found"),
+            EC::NodeScheduleNotFound,
+            None,
+        )),
+    }
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn create_action_schedule(
+    schedule_request: models::JsonActionScheduleRequest,
+    client_session: Arc<ClientSession>,
+    allowlist_cache: &State<Arc<models::AllowlistCache>>,
+    tss_state: &State<Arc<TssState>>,
+    delegation_usage_db: &State<Arc<DelegatedUsageDB>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    cfg: &State<ReloadableLitConfig>,
+) -> status::Custom<Value> {
+    use crate::utils::web::check_allowlist;
+    use ethers::utils::keccak256;
+
+    let cfg = cfg.load_full();
+    let ipfs_id = &schedule_request.ipfs_id;
+
+    let user_address =
+        match validate_action_user(&schedule_request.auth_sig, ipfs_id, tss_state, &cfg).await {
+            Ok(user_address) => user_address,
+            Err(e) => {
+                return client_session.json_encrypt_err_custom_response(
+                    "couldn't validate user address",
+                    e.handle(),
+                );
+            }
+        };
+
+    // Every run is enqueued as an async job
+    if !tss_state
+        .chain_data_config_manager
+        .get_actions_config()
+        .async_actions_enabled
+    {
+        return client_session.json_encrypt_err_and_code(
+            "Scheduled Lit Actions need async Lit Actions to be enabled",
+            "unsupported_invocation",
+            Status::BadRequest,
+        );
+    }
+
+    if matches!(cfg.enable_actions_allowlist(), Ok(true)) {
+        let allowlist_entry_id = keccak256(format!("LIT_ACTION_{}", ipfs_id).as_bytes());
+        match check_allowlist(allowlist_cache, &allowlist_entry_id, &cfg).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return client_session.json_encrypt_err_custom_response(
+                    "lit action not allowed",
+                    validation_err_code("Action not allowed", EC::NodeActionNotAllowed, None)
+                        .handle(),
+                );
+            }
+            Err(e) => {
+                return client_session
+                    .json_encrypt_err_custom_response("lit action not allowed", e.handle());
+            }
+        }
+    }
+
+    // Runs are paid for by a payer that delegated to the owner in the Payment DB, who also pays
+    // for creating the schedule like for the base amount of a Lit Action
+    let dynamic_payment = schedule::delegated_dynamic_payment(
+        &user_address,
+        tss_state,
+        payment_tracker,
+        delegation_usage_db,
+        &cfg,
+    )
+    .await
+    .and_then(|mut dynamic_payment| {
+        dynamic_payment.add(LitActionPriceComponent::BaseAmount, 1)?;
+        Ok(dynamic_payment)
+    });
+    let dynamic_payment = match dynamic_payment {
+        Ok(dynamic_payment) => dynamic_payment,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("no payment delegation", e.handle());
+        }
+    };
+
+    let port = match cfg.external_port() {
+        Ok(port) => port,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("no port", unexpected_err(e, None).handle());
+        }
+    };
+
+    let created = schedule::ActionSchedule::new(
+        &user_address,
+        schedule_request.ipfs_id,
+        schedule_request.js_params,
+        schedule_request.trigger,
+        chrono::Utc::now().timestamp(),
+    )
+    .and_then(|action_schedule| {
+        schedule::create(port, &action_schedule)?;
+        Ok(action_schedule)
+    });
+
+    match created {
+        Ok(action_schedule) => {
+            info!("Created action schedule {}", action_schedule.id);
+            if dynamic_payment.payment_enabled {
+                let pending_payment = dynamic_payment.to_pending_payment();
+                payment_tracker.batches().add(pending_payment).await;
+            }
+            client_session.json_encrypt_response_status(action_schedule)
+        }
+        Err(e) => client_session.json_encrypt_err_custom_response("invalid schedule", e.handle()),
+    }
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn get_action_schedules(
+    schedules_request: models::JsonActionSchedulesRequest,
+    client_session: Arc<ClientSession>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+
+    let schedules = match validate_action_user(&schedules_request.auth_sig, "", tss_state, &cfg)
+        .await
+    {
+        Ok(user_address) => cfg
+            .external_port()
+            .map_err(|e| unexpected_err(e, None))
+            .and_then(|port| schedule::list(port, &user_address)),
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("couldn't validate user address", e.handle());
+        }
+    };
+
+    match schedules {
+        Ok(schedules) => client_session.json_encrypt_response_status(schedules),
+        Err(e) => {
+            client_session.json_encrypt_err_custom_response("error getting schedules", e.handle())
+        }
+    }
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn delete_action_schedule(
+    schedule_request: models::JsonActionScheduleIdRequest,
+    client_session: Arc<ClientSession>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+    let schedule_id = &schedule_request.schedule_id;
+
+    let deleted = match validate_action_user(&schedule_request.auth_sig, "", tss_state, &cfg).await
+    {
+        Ok(user_address) => cfg
+            .external_port()
+            .map_err(|e| unexpected_err(e, None))
+            .and_then(|port| {
+                get_owned_schedule(port, schedule_id, &user_address)?;
+                schedule::delete(port, schedule_id)
+            }),
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("couldn't validate user address", e.handle());
+        }
+    };
+
+    match deleted {
+        Ok(deleted) => {
+            info!("Deleted action schedule {schedule_id}");
+            client_session.json_encrypt_response_status(deleted)
+        }
+        Err(e) => {
+            client_session.json_encrypt_err_custom_response("error deleting schedule", e.handle())
+        }
+    }
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn get_action_schedule_jobs(
+    schedule_request: models::JsonActionScheduleIdRequest,
+    client_session: Arc<ClientSession>,
+    action_store: &State<ActionStore>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+    let schedule_id = &schedule_request.schedule_id;
+
+    let jobs = match validate_action_user(&schedule_request.auth_sig, "", tss_state, &cfg).await {
+        Ok(user_address) => cfg
+            .external_port()
+            .map_err(|e| unexpected_err(e, None))
+            .and_then(|port| {
+                get_owned_schedule(port, schedule_id, &user_address)?;
+                schedule::jobs(port, schedule_id)
+            }),
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("couldn't validate user address", e.handle());
+        }
+    };
+
+    let jobs = match jobs {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("error getting schedule jobs", e.handle());
+        }
+    };
+
+    let mut store = action_store.inner().clone();
+    let mut response = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let status = match &job.job_id {
+            Some(job_id) => match store.get_job(job_id).await {
+                Ok(found) => found.map(|found| found.status),
+                Err(e) => {
+                    return client_session.json_encrypt_err_custom_response(
+                        "error getting job status",
+                        unexpected_err(e, None).handle(),
+                    );
+                }
+            },
+            None => None,
+        };
+        response.push(models::JsonScheduledJobResponse { job, status });
+    }
+
+    client_session.json_encrypt_response_status(response)
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn put_action_secret(
+    secret_request: models::JsonActionSecretRequest,
+    client_session: Arc<ClientSession>,
+    tss_state: &State<Arc<TssState>>,
+    delegation_usage_db: Option<&State<Arc<DelegatedUsageDB>>>,
+    payment_tracker: &State<Arc<PaymentTracker>>,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    http_client: &State<reqwest::Client>,
+    cfg: &State<ReloadableLitConfig>,
+    request_id: String,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+    let ipfs_id = &secret_request.ipfs_id;
+    let name = &secret_request.name;
+
+    let validated_address = match validate_action_user_address(
+        &secret_request.auth_sig,
+        ipfs_id,
+        tss_state,
+        &cfg,
+    )
+    .await
+    {
+        Ok(validated_address) => validated_address,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("couldn't validate user address", e.handle());
+        }
+    };
+    let user_address = validated_address.address_str().to_lowercase();
+
+    let port = match cfg.external_port() {
+        Ok(port) => port,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("no port", unexpected_err(e, None).handle());
+        }
+    };
+
+    let checked = match authorize_secrets_owner(
+        ipfs_id,
+        &user_address,
+        ipfs_cache,
+        http_client,
+        &cfg,
+    )
+    .await
+    {
+        Ok(()) => secrets::validate(ipfs_id, name, &secret_request.secret)
+            .and_then(|_| secrets::check_limits(port, ipfs_id, name, &user_address)),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = checked {
+        return client_session.json_encrypt_err_custom_response("invalid secret", e.handle());
+    }
+
+    // Storing a secret is billed like the base amount of a Lit Action
+    let bls_root_pubkey = match get_bls_root_pubkey(tss_state).await {
+        Ok(bls_root_pubkey) => bls_root_pubkey,
+        Err(e) => {
+            return client_session.json_encrypt_err_custom_response("no bls root key", e.handle());
+        }
+    };
+    let dynamic_payment = get_lit_action_dynamic_payment(
+        &cfg,
+        Some(secret_request.auth_sig.clone()),
+        delegation_usage_db,
+        EndpointVersion::V2,
+        &client_session,
+        payment_tracker,
+        &bls_root_pubkey,
+        tss_state,
+        &validated_address,
+    )
+    .await
+    .and_then(|mut dynamic_payment| {
+        dynamic_payment.add(LitActionPriceComponent::BaseAmount, 1)?;
+        Ok(dynamic_payment)
+    });
+    match dynamic_payment {
+        Ok(dynamic_payment) => {
+            if dynamic_payment.payment_enabled {
+                let pending_payment = dynamic_payment.to_pending_payment();
+                payment_tracker.batches().add(pending_payment).await;
+            }
+        }
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("unable to get payment method", e.handle());
+        }
+    }
+
+    let write = ("put", ipfs_id, name, &secret_request.secret);
+    let stored = match secrets::agree_on_write(
+        tss_state,
+        &request_id,
+        &secret_request.node_set,
+        write,
+    )
+    .await
+    {
+        Ok(()) => secrets::put(port, ipfs_id, name, &user_address, &secret_request.secret),
+        Err(e) => Err(e),
+    };
+
+    match stored {
+        Ok(()) => {
+            info!("Stored secret {name} of action {ipfs_id}");
+            client_session.json_encrypt_response_status(name.clone())
+        }
+        Err(e) => client_session.json_encrypt_err_custom_response("invalid secret", e.handle()),
+    }
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+pub(crate) async fn get_action_secret_names(
+    secrets_request: models::JsonActionSecretsRequest,
+    client_session: Arc<ClientSession>,
+    tss_state: &State<Arc<TssState>>,
+    cfg: &State<ReloadableLitConfig>,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+    let ipfs_id = &secrets_request.ipfs_id;
+
+    let names = match validate_action_user(&secrets_request.auth_sig, ipfs_id, tss_state, &cfg)
+        .await
+    {
+        Ok(user_address) => cfg
+            .external_port()
+            .map_err(|e| unexpected_err(e, None))
+            .and_then(|port| secrets::names(port, ipfs_id, &user_address)),
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("couldn't validate user address", e.handle());
+        }
+    };
+
+    match names {
+        Ok(names) => client_session.json_encrypt_response_status(names),
+        Err(e) => {
+            client_session.json_encrypt_err_custom_response("error getting secrets", e.handle())
+        }
+    }
+}
+
+#[cfg(feature = "lit-actions")]
+#[instrument(level = "debug", skip_all, ret)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn delete_action_secret(
+    secret_request: models::JsonActionSecretNameRequest,
+    client_session: Arc<ClientSession>,
+    tss_state: &State<Arc<TssState>>,
+    ipfs_cache: &State<Cache<String, Arc<String>>>,
+    http_client: &State<reqwest::Client>,
+    cfg: &State<ReloadableLitConfig>,
+    request_id: String,
+) -> status::Custom<Value> {
+    let cfg = cfg.load_full();
+    let ipfs_id = &secret_request.ipfs_id;
+    let name = &secret_request.name;
+
+    let user_address =
+        match validate_action_user(&secret_request.auth_sig, ipfs_id, tss_state, &cfg).await {
+            Ok(user_address) => user_address,
+            Err(e) => {
+                return client_session.json_encrypt_err_custom_response(
+                    "couldn't validate user address",
+                    e.handle(),
+                );
+            }
+        };
+
+    let port = match cfg.external_port() {
+        Ok(port) => port,
+        Err(e) => {
+            return client_session
+                .json_encrypt_err_custom_response("no port", unexpected_err(e, None).handle());
+        }
+    };
+
+    let deleted = async {
+        authorize_secrets_owner(ipfs_id, &user_address, ipfs_cache, http_client, &cfg).await?;
+        let write = ("delete", ipfs_id, name);
+        secrets::agree_on_write(tss_state, &request_id, &secret_request.node_set, write).await?;
+        secrets::delete(port, ipfs_id, name, &user_address)
+    }
+    .await;
+
+    match deleted {
+        Ok(deleted) => {
+            info!("Deleted secret {name} of action {ipfs_id}");
+            client_session.json_encrypt_response_status(deleted)
+        }
+        Err(e) => {
+            client_session.json_encrypt_err_custom_response("error deleting secret", e.handle())
+        }
+    }
+}
+
 #[cfg(feature = "lit-actions")]
 #[instrument(level = "debug", name = "POST /web/execute", skip_all, ret)]
 #[allow(clippy::too_many_arguments)]
@@ -622,6 +1368,8 @@ pub(crate) async fn execute_function(
     request_id: String,
     action_store: &State<ActionStore>,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
+    chunk_sender: Option<tokio::sync::mpsc::Sender<String>>,
 ) -> status::Custom<Value> {
     use crate::utils::web::check_allowlist;
     use ethers::utils::keccak256;
@@ -639,6 +1387,15 @@ pub(crate) async fn execute_function(
 
     let cfg = cfg.load_full();
 
+    // An async job outlives the request, so there is nothing to stream its chunks to
+    if chunk_sender.is_some() && json_execution_request.is_async() {
+        return client_session.json_encrypt_err_and_code(
+            "Async Lit Actions can't stream their response",
+            "unsupported_invocation",
+            Status::BadRequest,
+        );
+    }
+
     // get the derived IPFS ID so that we can auth the user against it
     let before = std::time::Instant::now();
     // determine if the user passed code or an ipfs hash
@@ -895,11 +1652,13 @@ pub(crate) async fn execute_function(
 
     // TODO compare max_price to current cost of the operation.
 
+    let egress_policy = action_client::egress_policy(&cfg);
     let deno_execution_env = models::DenoExecutionEnv {
         tss_state: Some(tss_state.as_ref().clone()),
         cfg,
         ipfs_cache: Some(moka::future::Cache::clone(ipfs_cache)),
         http_client: Some(reqwest::Client::clone(http_client)),
+        rpc_cache: Some(RpcResultCache::clone(rpc_cache)),
     };
 
     let http_headers = {
@@ -925,6 +1684,7 @@ pub(crate) async fn execute_function(
         .auth_sig(Some(auth_sig.clone()))
         .request_id(request_id.clone())
         .http_headers(http_headers)
+        .egress_policy(egress_policy)
         .timeout_ms(actions_config.timeout_ms)
         .memory_limit_mb(actions_config.memory_limit_mb as u32)
         .max_code_length(actions_config.max_code_length as usize)
@@ -937,6 +1697,9 @@ pub(crate) async fn execute_function(
         .epoch(epoch)
         .endpoint_version(endpoint_version)
         .node_set(json_execution_request.node_set.clone())
+        .deterministic(json_execution_request.deterministic)
+        .trace(json_execution_request.trace)
+        .chunk_sender(chunk_sender)
         .dynamic_payment(dynamic_payment)
         .client_grpc_channels((*grpc_client_pool).clone())
         .build()
@@ -984,37 +1747,54 @@ pub(crate) async fn execute_function(
         };
     }
 
-    let execution_result = client.execute_js(execution_options).await;
-    timing.insert("js execution".to_string(), before.elapsed());
+    // The request is dropped if the client disconnects, e.g. while reading a streamed response,
+    // so the execution and its payment run in a task of their own that finishes either way
+    let payment_tracker = payment_tracker.inner().clone();
+    let execution = tokio::spawn(async move {
+        let execution_result = client.execute_js(execution_options).await;
 
-    // apply to the pending payment
-    if client.dynamic_payment.payment_enabled {
-        timing.insert("verify the payment".to_string(), before.elapsed());
-        let before = std::time::Instant::now();
-        let pending_payment = client.dynamic_payment.to_pending_payment();
-        payment_tracker.batches().add(pending_payment).await;
-        timing.insert("register pending payment".to_string(), before.elapsed());
-    }
+        // apply to the pending payment
+        if client.dynamic_payment.payment_enabled {
+            let pending_payment = client.dynamic_payment.to_pending_payment();
+            payment_tracker.batches().add(pending_payment).await;
+        }
+
+        (client, execution_result)
+    });
+    let (client, execution_result) = match execution.await {
+        Ok(execution) => execution,
+        Err(e) => {
+            return client_session.json_encrypt_err_custom_response(
+                "error executing lit action",
+                unexpected_err_code(e, EC::NodeJsExecutionError, None).handle(),
+            );
+        }
+    };
+    timing.insert("js execution and payment".to_string(), before.elapsed());
 
     let execution_state = match execution_result {
         Ok(state) => state,
         Err(err) => {
             error!("error in Js comms result: {err:?}");
             let logs = client.logs();
+            let trace = client.trace();
             match err.kind() {
                 lit_api_core::error::Kind::Timeout => {
                     let handle =
                         timeout_err_code(err, EC::NodeJsTimeoutError, None).handle_with_logs(logs);
+                    let handle = with_trace(handle, trace);
                     return client_session.json_encrypt_err_custom_response("timeout", handle);
                 }
                 lit_api_core::error::Kind::MemoryLimit => {
                     let handle = memory_limit_err_code(err, EC::NodeJsMemoryLimitError, None)
                         .handle_with_logs(logs);
+                    let handle = with_trace(handle, trace);
                     return client_session.json_encrypt_err_custom_response("memory limit", handle);
                 }
                 lit_api_core::error::Kind::Connect => {
                     let handle = connect_err_code(err, EC::NodeJsConnectionError, None)
                         .handle_with_logs(logs);
+                    let handle = with_trace(handle, trace);
                     return client_session.json_encrypt_err_custom_response("connect", handle);
                 }
                 _ => {}
@@ -1032,6 +1812,7 @@ pub(crate) async fn execute_function(
                 Some("Error executing JS".into()),
             )
             .handle_with_logs(logs);
+            let handle = with_trace(handle, trace);
             return client_session.json_encrypt_err_custom_response("error executing js", handle);
         }
     };
@@ -1049,9 +1830,23 @@ pub(crate) async fn execute_function(
         response: execution_state.response,
         logs: execution_state.logs,
         payment_detail: Some(client.dynamic_payment.items),
+        deterministic_report: execution_state.deterministic_report,
+        trace: execution_state.trace,
     })
 }
 
+// Attaches the trace of a failed execution to the error response, like its logs
+#[cfg(feature = "lit-actions")]
+fn with_trace(
+    mut handle: status::Custom<Value>,
+    trace: Option<&lit_node_core::response::ExecutionTrace>,
+) -> status::Custom<Value> {
+    if let Some(trace) = trace {
+        handle.1["trace"] = json!(trace);
+    }
+    handle
+}
+
 #[allow(clippy::too_many_arguments)]
 #[doc = "Get the dynamic payment for a lit action.    This is used to determine if the lit action should be paid for.    If the lit action is not paid for, the lit action will fail.    If the lit action is paid for, the lit action will be executed. "]
 pub async fn get_lit_action_dynamic_payment(
@@ -1173,7 +1968,7 @@ pub async fn get_lit_action_dynamic_payment(
     }
 }
 
-async fn get_price_multiplier(
+pub(crate) async fn get_price_multiplier(
     tss_state: &Arc<TssState>,
     pending_price: I256,
 ) -> error::Result<u64> {
@@ -1235,6 +2030,7 @@ pub(crate) async fn sign_session_key(
     payment_tracker: &State<Arc<PaymentTracker>>,
     request_id: String,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &State<RpcResultCache>,
 ) -> status::Custom<Value> {
     use crate::{
         error::validation_err,
@@ -1645,6 +2441,7 @@ pub(crate) async fn sign_session_key(
                 cfg: cfg.clone(),
                 ipfs_cache: Some(moka::future::Cache::clone(ipfs_cache)),
                 http_client: Some(reqwest::Client::clone(http_client)),
+                rpc_cache: Some(RpcResultCache::clone(rpc_cache)),
             };
 
             trace!("spawning js execution task");
@@ -1695,6 +2492,7 @@ pub(crate) async fn sign_session_key(
                 .auth_context(auth_context.clone())
                 .request_id(request_id)
                 .http_headers(http_headers)
+                .egress_policy(action_client::egress_policy(&cfg))
                 .epoch(epoch)
                 .endpoint_version(endpoint_version)
                 .build()
@@ -2125,9 +2923,23 @@ pub async fn check_multiple_access_control_conditions(
     bls_root_pubkey: &String,
     endpoint_version: &EndpointVersion,
     current_action_ipfs_id: Option<&String>,
+    default_block_numbers: &HashMap<String, u64>,
     ipfs_cache: &State<Cache<String, Arc<String>>>,
     http_client: &State<reqwest::Client>,
+    rpc_cache: &RpcResultCache,
 ) -> error::Result<models::UnifiedConditionCheckResult> {
+    // EVM conditions that don't pin a block are evaluated at the request's block for their chain
+    let access_control_conditions = access_control::block::with_default_blocks(
+        access_control_conditions,
+        default_block_numbers,
+    );
+    let evm_contract_conditions =
+        access_control::block::with_default_blocks(evm_contract_conditions, default_block_numbers);
+    let unified_access_control_conditions = access_control::block::with_default_blocks(
+        unified_access_control_conditions,
+        default_block_numbers,
+    );
+
     if let Some(access_control_conditions) = &access_control_conditions {
         let auth_sig = access_control::get_ethereum_auth_sig(auth_sig_item)?;
 
@@ -2143,6 +2955,7 @@ pub async fn check_multiple_access_control_conditions(
             current_action_ipfs_id,
             moka::future::Cache::clone(ipfs_cache),
             reqwest::Client::clone(http_client),
+            rpc_cache,
         )
         .await?;
         return Ok(models::UnifiedConditionCheckResult {
@@ -2161,6 +2974,7 @@ pub async fn check_multiple_access_control_conditions(
             bls_root_pubkey,
             endpoint_version,
             current_action_ipfs_id,
+            rpc_cache,
         )
         .await?;
 
@@ -2174,8 +2988,10 @@ pub async fn check_multiple_access_control_conditions(
         let result = access_control::sol_rpc::check_access_control_conditions(
             sol_rpc_conditions,
             auth_sig,
+            &cfg,
             bls_root_pubkey,
             current_action_ipfs_id,
+            rpc_cache,
         )
         .await?;
         return Ok(models::UnifiedConditionCheckResult {
@@ -2195,6 +3011,7 @@ pub async fn check_multiple_access_control_conditions(
             current_action_ipfs_id,
             moka::future::Cache::clone(ipfs_cache),
             reqwest::Client::clone(http_client),
+            rpc_cache,
         )
         .await;
     }
@@ -144,6 +144,18 @@ pub(crate) enum EC {
     /// Invalid Capability object in SIWE resource ReCap
     #[code(kind = Validation, http_status = 400)]
     NodeSIWECapabilityInvalid,
+    /// Session key was revoked by the wallet or PKP that delegated to it
+    #[code(kind = Validation, http_status = 401)]
+    NodeSessionSigRevoked,
+    /// Session revocation message is malformed or not signed by its issuer
+    #[code(kind = Validation, http_status = 400)]
+    NodeInvalidSessionRevocation,
+    /// The issuer revoked too many session keys recently
+    #[code(kind = Validation, http_status = 429)]
+    NodeSessionRevocationRateLimited,
+    /// Session key delegation chain is malformed, broken or widens the delegated capabilities
+    #[code(kind = Validation, http_status = 401)]
+    NodeInvalidSessionKeyDelegation,
     /// Invalid target action of Capability object in SIWE resource ReCap
     #[code(kind = Parser, http_status = 400)]
     NodeSIWECapabilityActionInvalid,
@@ -204,6 +216,18 @@ pub(crate) enum EC {
     /// Cosmos invalid condition
     #[code(kind = Validation, http_status = 400)]
     NodeCosmosInvalidCondition,
+    /// Bitcoin invalid condition
+    #[code(kind = Validation, http_status = 400)]
+    NodeBitcoinInvalidCondition,
+    /// Bitcoin response body error
+    #[code(kind = Unexpected, http_status = 502)]
+    NodeBitcoinResponseError,
+    /// Block number or tag passed with an access control condition is invalid
+    #[code(kind = Validation, http_status = 400)]
+    NodeAccessControlInvalidBlock,
+    /// Block passed with an access control condition is too far behind the chain head for its state to be available
+    #[code(kind = Validation, http_status = 400)]
+    NodeAccessControlBlockTooOld,
     /// Mismatched number of parameters in Params & Condition
     #[code(kind = Validation, http_status = 400)]
     NodeMismatchParameters,
@@ -370,6 +394,18 @@ pub(crate) enum EC {
     // The peer has provided incorrect information for key share validation
     #[code(kind = Validation, http_status = 400)]
     IncorrectInfoForKeyShareValidation,
+    /// Schedule not found
+    #[code(kind = Validation, http_status = 404)]
+    NodeScheduleNotFound,
+    /// The schedule is invalid, or its owner already has too many
+    #[code(kind = Validation, http_status = 400)]
+    NodeInvalidSchedule,
+    /// The secret isn't bound to its action, or is owned by another user
+    #[code(kind = Validation, http_status = 400)]
+    NodeInvalidSecret,
+    /// The MuSig2 session has no nonce on this node, or its co-signers or nonces are invalid
+    #[code(kind = Validation, http_status = 400)]
+    NodeInvalidMuSig2Session,
 }
 
 generate_pkg_constructors!(PKG_NAME, pub(crate), EC);
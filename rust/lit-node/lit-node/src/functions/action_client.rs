@@ -9,7 +9,8 @@ use std::error::Error as _;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use super::{ActionJob, ActionStore, JobId};
+use super::{ActionJob, ActionStore, JobId, secrets, storage};
+use crate::access_control::cache::RpcResultCache;
 use crate::access_control::rpc_url;
 use crate::error::{connect_err, conversion_err, memory_limit_err, timeout_err, unexpected_err};
 use crate::models::{self, RequestConditions, UnifiedConditionCheckResult};
@@ -44,11 +45,16 @@ use lit_core::config::LitConfig;
 use lit_core::error::Unexpected;
 use lit_core::utils::binary::bytes_to_hex;
 use moka::future::Cache;
+use prost::Message as _;
 use serde::{Deserialize, Serialize};
 use tokio::time::Duration;
 use tracing::{debug, instrument};
 
-use lit_node_common::config::LitNodeConfig as _;
+use lit_node_common::config::{
+    CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS_DEFAULT, CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES_DEFAULT,
+    CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS_DEFAULT,
+    CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE_DEFAULT, LitNodeConfig as _,
+};
 use lit_node_core::{
     AccessControlConditionResource, AuthSigItem, BeHex, CompressedBytes, CurveType,
     EndpointVersion, JsonAuthSig, LitActionPriceComponent, LitResource, NodeSet, PeerId,
@@ -66,10 +72,14 @@ const DEFAULT_MAX_CONSOLE_LOG_LENGTH: usize = 1024 * 100; // 100KB
 const DEFAULT_MAX_CONTRACT_CALL_COUNT: u32 = 30;
 const DEFAULT_MAX_FETCH_COUNT: u32 = 50;
 const DEFAULT_MAX_RESPONSE_LENGTH: usize = 1024 * 100; // 100KB
+const DEFAULT_MAX_STREAMED_RESPONSE_LENGTH: usize = 1024 * 1024 * 10; // 10MB
 const DEFAULT_MAX_SIGN_COUNT: u32 = 10; // 10 signature requests per action execution
 const DEFAULT_MAX_BROADCAST_AND_COLLECT_COUNT: u32 = 30;
 const DEFAULT_MAX_CALL_DEPTH: u32 = 5;
+const DEFAULT_MAX_MODULE_COUNT: u32 = 50;
+const DEFAULT_MAX_STORAGE_BYTES: usize = 1024 * 1024; // 1MB per namespace
 const DEFAULT_MAX_RETRIES: u32 = 3;
+const MAX_TRACE_ENTRIES: usize = 1_000; // per list, to bound the size of the response
 
 #[derive(Debug, Default, Clone, Builder, Serialize, Deserialize)]
 pub struct Client {
@@ -93,6 +103,19 @@ pub struct Client {
     endpoint_version: EndpointVersion,
     #[builder(default, setter(into))]
     node_set: Vec<NodeSet>,
+    #[builder(default)]
+    deterministic: bool,
+    // Records the ops invoked by the action in ExecutionState::trace
+    #[builder(default)]
+    trace: bool,
+    // Receives the chunks streamed by Lit.Actions.streamChunk, if the client asked for a stream
+    #[builder(default, setter(into))]
+    #[serde(skip)]
+    chunk_sender: Option<tokio::sync::mpsc::Sender<String>>,
+    // The node operator's limits on fetch(), unset to allow any host
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    pub(crate) egress_policy: Option<EgressPolicy>,
 
     // Limits
     #[builder(default = "DEFAULT_TIMEOUT_MS")]
@@ -106,6 +129,8 @@ pub struct Client {
     max_code_length: usize,
     #[builder(default = "DEFAULT_MAX_RESPONSE_LENGTH")]
     max_response_length: usize,
+    #[builder(default = "DEFAULT_MAX_STREAMED_RESPONSE_LENGTH")]
+    max_streamed_response_length: usize,
     #[builder(default = "DEFAULT_MAX_CONSOLE_LOG_LENGTH")]
     max_console_log_length: usize,
     #[builder(default = "DEFAULT_MAX_FETCH_COUNT")]
@@ -118,6 +143,10 @@ pub struct Client {
     max_broadcast_and_collect_count: u32,
     #[builder(default = "DEFAULT_MAX_CALL_DEPTH")]
     max_call_depth: u32,
+    #[builder(default = "DEFAULT_MAX_MODULE_COUNT")]
+    max_module_count: u32,
+    #[builder(default = "DEFAULT_MAX_STORAGE_BYTES")]
+    max_storage_bytes: usize,
     #[builder(default = "DEFAULT_MAX_RETRIES")]
     max_retries: u32,
 
@@ -138,6 +167,8 @@ pub struct ExecutionState {
     pub response: String,
     pub logs: String,
     #[serde(skip)]
+    pub streamed_response_length: usize,
+    #[serde(skip)]
     pub fetch_count: u32,
     #[serde(skip)]
     pub sign_count: u32,
@@ -151,6 +182,23 @@ pub struct ExecutionState {
     pub broadcast_and_collect_count: u32,
     #[serde(skip)]
     pub ops_count: u32,
+    #[serde(skip)]
+    pub module_count: u32,
+    #[serde(skip)]
+    pub determinism: Option<Determinism>,
+    #[serde(skip)]
+    pub fetch_records: Vec<FetchRecord>,
+    pub deterministic_report: Option<response::DeterministicExecutionReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<response::ExecutionTrace>,
+}
+
+/// A fetch response as observed by JS in deterministic mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FetchRecord {
+    pub url: String,
+    pub status: u32,
+    pub body_sha256: Vec<u8>,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -178,6 +226,38 @@ impl From<String> for ExecutionOptions {
     }
 }
 
+/// The node operator's limits on fetch() from Lit Actions, enforced by the runtime on top of
+/// the hosts declared by each action.
+pub(crate) fn egress_policy(cfg: &LitConfig) -> EgressPolicy {
+    let list = |s: &str| s.split(',').map(str::to_string).collect::<Vec<_>>();
+    EgressPolicy {
+        denied_hosts: cfg
+            .actions_egress_denied_hosts()
+            .unwrap_or_else(|_| list(CFG_KEY_ACTIONS_EGRESS_DENIED_HOSTS_DEFAULT)),
+        denied_ip_ranges: cfg
+            .actions_egress_denied_ip_ranges()
+            .unwrap_or_else(|_| list(CFG_KEY_ACTIONS_EGRESS_DENIED_IP_RANGES_DEFAULT)),
+        max_response_size: u64::try_from(
+            cfg.actions_egress_max_response_size()
+                .unwrap_or(CFG_KEY_ACTIONS_EGRESS_MAX_RESPONSE_SIZE_DEFAULT),
+        )
+        .ok(),
+        fetch_timeout: u64::try_from(
+            cfg.actions_egress_fetch_timeout_ms()
+                .unwrap_or(CFG_KEY_ACTIONS_EGRESS_FETCH_TIMEOUT_MS_DEFAULT),
+        )
+        .ok(),
+    }
+}
+
+// Milliseconds since epoch, as used in execution traces
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
 impl Client {
     pub fn new(socket_path: impl Into<PathBuf>) -> Self {
         ClientBuilder::default()
@@ -210,6 +290,10 @@ impl Client {
         &self.state.logs
     }
 
+    pub fn trace(&self) -> Option<&response::ExecutionTrace> {
+        self.state.trace.as_ref()
+    }
+
     pub fn authorized_address(&self) -> Option<String> {
         self.auth_context
             .auth_sig_address
@@ -242,6 +326,13 @@ impl Client {
         bail!("No HTTP cache found");
     }
 
+    fn rpc_cache(&self) -> Result<RpcResultCache> {
+        if let Some(rpc_cache) = self.js_env.rpc_cache.clone() {
+            return Ok(rpc_cache);
+        }
+        bail!("No RPC result cache found");
+    }
+
     fn metadata(&self) -> Result<MetadataMap> {
         let mut md = MetadataMap::new();
         md.insert(
@@ -269,16 +360,17 @@ impl Client {
         opts: impl Into<ExecutionOptions>,
         store: &ActionStore,
     ) -> Result<JobId> {
-        store
-            .clone()
-            .submit_job(ActionJob::new(
-                Client {
-                    timeout_ms: self.async_timeout_ms,
-                    ..self.clone()
-                },
-                opts,
-            ))
-            .await
+        store.clone().submit_job(self.async_job(opts)).await
+    }
+
+    pub fn async_job(&self, opts: impl Into<ExecutionOptions>) -> ActionJob {
+        ActionJob::new(
+            Client {
+                timeout_ms: self.async_timeout_ms,
+                ..self.clone()
+            },
+            opts,
+        )
     }
 
     #[instrument(level = "debug", skip_all, ret)]
@@ -287,11 +379,27 @@ impl Client {
         opts: impl Into<ExecutionOptions>,
     ) -> Result<ExecutionState, crate::error::Error> {
         self.reset_state();
+        if self.trace {
+            self.state.trace = Some(response::ExecutionTrace {
+                started_at: now_ms(),
+                ..Default::default()
+            });
+        }
         self.dynamic_payment
             .add(LitActionPriceComponent::BaseAmount, 1)?;
         let opts = opts.into();
         let timeout = self.client_timeout();
 
+        if self.deterministic {
+            let determinism = self.agree_on_determinism().await.map_err(|e| {
+                unexpected_err(
+                    e,
+                    Some("Could not agree on inputs for deterministic execution".to_string()),
+                )
+            })?;
+            self.state.determinism = Some(determinism);
+        }
+
         let auth_context = {
             let mut ctx = self.auth_context.clone();
             if let Some(id) = &opts.action_ipfs_id {
@@ -300,13 +408,34 @@ impl Client {
             ctx
         };
 
+        let result = self
+            .execute_js_with_retries(&opts, &auth_context, timeout)
+            .await;
+
+        // Streamed chunks were sent to the client even if the action failed afterwards
+        if self.state.streamed_response_length > 0 {
+            self.dynamic_payment.add_quantity(
+                LitActionPriceComponent::ResponseLength,
+                self.state.streamed_response_length as u64,
+            )?;
+        }
+
+        result
+    }
+
+    async fn execute_js_with_retries(
+        &mut self,
+        opts: &ExecutionOptions,
+        auth_context: &models::AuthContext,
+        timeout: Duration,
+    ) -> Result<ExecutionState, crate::error::Error> {
         // Hand-roll retry loop as crates like tokio-retry or again don't play well with &mut self
         let mut retry = 0;
         loop {
             let execution = Box::pin(self.execute_js_inner(
                 opts.code.clone(),
                 opts.globals.clone(),
-                &auth_context,
+                auth_context,
                 0,
             ));
             let execution_result =
@@ -319,7 +448,21 @@ impl Client {
                         )
                     })?;
             match execution_result {
-                Ok(state) => return Ok(state),
+                Ok(mut state) => {
+                    if let Some(determinism) = state.determinism.clone() {
+                        let report = self
+                            .compare_fetch_records(determinism, &state.fetch_records)
+                            .await
+                            .map_err(|e| {
+                                unexpected_err(
+                                    e,
+                                    Some("Could not compare fetch responses".to_string()),
+                                )
+                            })?;
+                        state.deterministic_report = Some(report);
+                    }
+                    return Ok(state);
+                }
                 Err(e) => {
                     let last_error = if let Some(status) = e.downcast_ref::<Status>() {
                         let msg = status.message();
@@ -431,6 +574,8 @@ impl Client {
                     http_headers: self.http_headers.clone(),
                     timeout: Some(self.timeout_ms),
                     memory_limit: Some(self.memory_limit_mb),
+                    determinism: self.state.determinism.clone(),
+                    egress_policy: self.egress_policy.clone(),
                 }
                 .into(),
             )
@@ -450,6 +595,7 @@ impl Client {
                 }
                 // Handle op requests
                 Some(op) => {
+                    let op_trace = self.start_op_trace(&op, call_depth);
                     let resp = self
                         .handle_op(op, auth_context, call_depth)
                         .await
@@ -459,6 +605,7 @@ impl Client {
                             }
                             .into()
                         });
+                    self.finish_op_trace(op_trace, &resp);
                     outbound_tx
                         .send_async(resp)
                         .await
@@ -495,6 +642,30 @@ impl Client {
                 self.state.response = response;
                 SetResponseResponse {}.into()
             }
+            UnionResponse::StreamChunk(StreamChunkRequest { chunk }) => {
+                self.state.streamed_response_length += chunk.len();
+                if self.state.streamed_response_length > self.max_streamed_response_length {
+                    bail!(
+                        "Streamed response is too long. Max length is {} bytes",
+                        self.max_streamed_response_length
+                    );
+                }
+                // The chunks are charged once for their total length after the execution, as
+                // long as the payer can still afford it
+                self.dynamic_payment.check_quantity(
+                    LitActionPriceComponent::ResponseLength,
+                    self.state.streamed_response_length as u64,
+                )?;
+                // Chunks are only forwarded if the client asked for a streamed response
+                if let Some(chunk_sender) = &self.chunk_sender {
+                    // Applies backpressure to the action while the client is slow to read
+                    chunk_sender
+                        .send(chunk)
+                        .await
+                        .context("Client stopped reading the streamed response")?;
+                }
+                StreamChunkResponse {}.into()
+            }
             UnionResponse::Print(PrintRequest { message }) => {
                 if self.state.logs.len() + message.len() > self.max_console_log_length {
                     bail!(
@@ -519,6 +690,102 @@ impl Client {
                 }
                 .into()
             }
+            UnionResponse::RecordFetch(RecordFetchRequest {
+                url,
+                status,
+                body_sha256,
+            }) => {
+                if self.state.determinism.is_none() {
+                    bail!("Fetch responses are only recorded in deterministic mode");
+                }
+                self.state.fetch_records.push(FetchRecord {
+                    url,
+                    status,
+                    body_sha256,
+                });
+                RecordFetchResponse {}.into()
+            }
+            UnionResponse::StorageGet(StorageGetRequest {
+                key,
+                pkp_public_key,
+            }) => {
+                storage::validate_key(&key)?;
+                let namespace = storage::namespace(action_ipfs_id.as_deref(), &pkp_public_key)?;
+                self.increment_broad_and_collect_counter()?;
+                let value = self.read_storage(&namespace, &key).await?;
+                StorageGetResponse { value }.into()
+            }
+            UnionResponse::StorageSet(StorageSetRequest {
+                key,
+                value,
+                pkp_public_key,
+            }) => {
+                storage::validate_key(&key)?;
+                let namespace = storage::namespace(action_ipfs_id.as_deref(), &pkp_public_key)?;
+                let port = self.lit_config().external_port()?;
+                let size = key.len() + value.len();
+                storage::check_quota(port, &namespace, &key, size, self.max_storage_bytes)?;
+                self.pay_quantity(LitActionPriceComponent::StorageBytes, size as u64)
+                    .await?;
+                self.increment_broad_and_collect_counter()?;
+                self.agree_on_storage_write(&namespace, &key, Some(&value))
+                    .await?;
+
+                let (tss_state, _) = self.tss_state_and_txn_prefix()?;
+                let network_pubkey = get_bls_root_pubkey(&tss_state).await?;
+                let network_pubkey =
+                    blsful::PublicKey::<Bls12381G2Impl>::try_from(&hex::decode(&network_pubkey)?)?;
+                storage::set(
+                    port,
+                    &network_pubkey,
+                    &namespace,
+                    &key,
+                    &value,
+                    self.max_storage_bytes,
+                )?;
+                StorageSetResponse {}.into()
+            }
+            UnionResponse::StorageDelete(StorageDeleteRequest {
+                key,
+                pkp_public_key,
+            }) => {
+                storage::validate_key(&key)?;
+                let namespace = storage::namespace(action_ipfs_id.as_deref(), &pkp_public_key)?;
+                self.increment_broad_and_collect_counter()?;
+                self.agree_on_storage_write(&namespace, &key, None).await?;
+
+                let deleted =
+                    storage::delete(self.lit_config().external_port()?, &namespace, &key)?;
+                StorageDeleteResponse { deleted }.into()
+            }
+            UnionResponse::GetSecret(GetSecretRequest { name }) => {
+                secrets::validate_name(&name)?;
+                let Some(ipfs_id) = action_ipfs_id.as_deref().filter(|id| !id.is_empty()) else {
+                    bail!("Secrets are only available to actions with an IPFS CID");
+                };
+                let Some(secret) =
+                    secrets::get(self.lit_config().external_port()?, ipfs_id, &name)?
+                else {
+                    return Ok(GetSecretResponse { value: None }.into());
+                };
+                // Checked when the secret was stored, but it's what keeps other actions out
+                if !secrets::is_bound_to(&secret.unified_access_control_conditions, ipfs_id) {
+                    bail!("Secret {} isn't bound to this action", name);
+                }
+
+                self.increment_broad_and_collect_counter()?;
+                self.pay(LitActionPriceComponent::Broadcasts, 1).await?;
+                self.pay(LitActionPriceComponent::Decrypts, 1).await?;
+
+                let value = self
+                    .decrypt_and_combine(
+                        &secret.unified_access_control_conditions,
+                        &secret.ciphertext,
+                        &secret.data_to_encrypt_hash,
+                    )
+                    .await?;
+                GetSecretResponse { value: Some(value) }.into()
+            }
             UnionResponse::PkpPermissionsGetPermitted(PkpPermissionsGetPermittedRequest {
                 method,
                 token_id,
@@ -833,6 +1100,65 @@ impl Client {
                 }
                 .into()
             }
+            UnionResponse::GetModule(GetModuleRequest { cid }) => {
+                self.state.module_count += 1;
+                if self.state.module_count > self.max_module_count {
+                    bail!(
+                        "You may not import more than {} modules per session and you have attempted to exceed that limit.",
+                        self.max_module_count
+                    );
+                }
+
+                // get_ipfs_file verifies that the module hashes to its CID
+                let code = crate::utils::web::get_ipfs_file(
+                    &cid,
+                    self.lit_config(),
+                    self.ipfs_cache()?,
+                    self.http_cache()?,
+                )
+                .await?;
+                if code.len() > self.max_code_length {
+                    bail!(
+                        "Module {} is too large ({} bytes). Max length is {} bytes.",
+                        cid,
+                        code.len(),
+                        self.max_code_length,
+                    );
+                }
+
+                GetModuleResponse {
+                    code: code.to_string(),
+                }
+                .into()
+            }
+            UnionResponse::GetWasmModule(GetWasmModuleRequest { cid }) => {
+                self.state.module_count += 1;
+                if self.state.module_count > self.max_module_count {
+                    bail!(
+                        "You may not import more than {} modules per session and you have attempted to exceed that limit.",
+                        self.max_module_count
+                    );
+                }
+
+                // get_ipfs_binary_file verifies that the binary hashes to its CID
+                let code = crate::utils::web::get_ipfs_binary_file(
+                    &cid,
+                    self.lit_config(),
+                    self.ipfs_cache()?,
+                    self.http_cache()?,
+                )
+                .await?;
+                if code.len() > self.max_code_length {
+                    bail!(
+                        "WebAssembly module {} is too large ({} bytes). Max length is {} bytes.",
+                        cid,
+                        code.len(),
+                        self.max_code_length,
+                    );
+                }
+
+                GetWasmModuleResponse { code }.into()
+            }
             UnionResponse::BroadcastAndCollect(BroadcastAndCollectRequest { name, value }) => {
                 self.pay(LitActionPriceComponent::Broadcasts, 1).await?;
 
@@ -863,10 +1189,7 @@ impl Client {
                 self.pay(LitActionPriceComponent::Broadcasts, 1).await?;
                 self.pay(LitActionPriceComponent::Decrypts, 1).await?;
 
-                let (tss_state, txn_prefix) = self.tss_state_and_txn_prefix()?;
                 let json_auth_sig = self.parse_json_authsig_helper(auth_sig)?;
-                let txn_prefix = format!("{}_{}", txn_prefix, generate_hash(ciphertext.clone()));
-                let tss_state = Arc::new(tss_state);
 
                 let conditions: Vec<UnifiedAccessControlConditionItem> =
                     serde_json::from_slice(&access_control_conditions)?;
@@ -886,62 +1209,10 @@ impl Client {
                     );
                 }
 
-                let identity_parameter = get_identity_param(&conditions, &data_to_encrypt_hash)?;
-
-                // Load the BLS secret key share as a blsful key for signing.
-                let cipher_state = match tss_state.get_cipher_state(SigningScheme::Bls12381) {
-                    Ok(cipher_state) => cipher_state,
-                    Err(e) => {
-                        bail!("Couldn't get BLS ciper state: {:?}", e);
-                    }
-                };
-
-                // Sign the identity parameter using the blsful secret key share.
-                let (signature_share, share_id) =
-                    match cipher_state.sign(&identity_parameter, self.epoch).await {
-                        Ok(signature_share) => signature_share,
-                        Err(e) => {
-                            bail!("Couldn't sign the identity parameter: {:?}", e);
-                        }
-                    };
-
-                let cm = CommsManager::new(&tss_state, 0, &txn_prefix, "0", &self.node_set).await?;
-                let mut shares = cm
-                    .broadcast_and_collect::<SignatureShare<Bls12381G2Impl>, SignatureShare<Bls12381G2Impl>>(
-                        signature_share,
-                    )
+                let result = self
+                    .decrypt_and_combine(&conditions, &ciphertext, &data_to_encrypt_hash)
                     .await?;
 
-                shares.push((PeerId::ONE, signature_share)); // lazy - it's not zero, but we don't seem to care!
-
-                let network_pubkey = get_bls_root_pubkey(&tss_state).await?;
-                let network_pubkey = blsful::PublicKey::try_from(&hex::decode(&network_pubkey)?)?;
-
-                let serialized_decryption_shares =
-                    shares.iter().map(|(_, share)| *share).collect::<Vec<_>>();
-                let ciphertext = serde_bare::from_slice(&base64_decode(&ciphertext))?;
-
-                let decrypted = lit_sdk::encryption::verify_and_decrypt_with_signatures_shares(
-                    &network_pubkey,
-                    &identity_parameter,
-                    &ciphertext,
-                    &serialized_decryption_shares,
-                );
-
-                let decrypted = match decrypted {
-                    Ok(decrypted) => decrypted,
-                    Err(e) => {
-                        bail!("Failed to decrypt and combine: {:?}", e);
-                    }
-                };
-
-                let result = match std::str::from_utf8(&decrypted) {
-                    Ok(result) => result.to_string(),
-                    Err(e) => {
-                        bail!("Failed to convert decrypted bytes to string.")
-                    }
-                };
-
                 DecryptAndCombineResponse { result }.into()
             }
             UnionResponse::DecryptToSingleNode(DecryptToSingleNodeRequest {
@@ -994,7 +1265,7 @@ impl Client {
                 let cipher_state = match tss_state.get_cipher_state(SigningScheme::Bls12381) {
                     Ok(cipher_state) => cipher_state,
                     Err(e) => {
-                        bail!("Couldn't get BLS ciper state: {:?}", e);
+                        bail!("Couldn't get BLS cipher state: {:?}", e);
                     }
                 };
 
@@ -1394,7 +1665,24 @@ impl Client {
                 }
                 .into()
             }
-            UnionResponse::UpdateResourceUsage(UpdateResourceUsageRequest { tick, used_kb }) => {
+            UnionResponse::UpdateResourceUsage(UpdateResourceUsageRequest {
+                tick,
+                used_kb,
+                used_heap_size,
+                external_memory,
+            }) => {
+                if let Some(trace) = self.state.trace.as_mut() {
+                    if trace.heap_samples.len() < MAX_TRACE_ENTRIES {
+                        trace.heap_samples.push(response::HeapSample {
+                            sampled_at: now_ms(),
+                            used_heap_size,
+                            external_memory,
+                        });
+                    } else {
+                        trace.truncated = true;
+                    }
+                }
+
                 // For now, we'll just return a success response
                 let r = self
                     .dynamic_payment
@@ -1544,6 +1832,61 @@ impl Client {
         })
     }
 
+    /// Starts tracing an op invoked by the action, if the client asked for a trace. Returns the
+    /// position of the op in the trace, so that ops of child actions come after their caller.
+    fn start_op_trace(
+        &mut self,
+        op: &UnionResponse,
+        call_depth: u32,
+    ) -> Option<(usize, response::OpTrace, usize)> {
+        // Sampled by lit_actions rather than invoked by the action
+        if matches!(op, UnionResponse::UpdateResourceUsage(_)) {
+            return None;
+        }
+        let trace = self.state.trace.as_mut()?;
+        if trace.ops.len() >= MAX_TRACE_ENTRIES {
+            trace.truncated = true;
+            return None;
+        }
+
+        // e.g. "Sign" for UnionResponse::Sign(SignRequest { .. })
+        let op_name = format!("{op:?}");
+        let op_name = op_name.split('(').next().unwrap_or_default().to_string();
+        let op_trace = response::OpTrace {
+            op: op_name,
+            call_depth,
+            started_at: now_ms(),
+            request_size: op.encoded_len(),
+            ..Default::default()
+        };
+        Some((trace.ops.len(), op_trace, self.dynamic_payment.items.len()))
+    }
+
+    fn finish_op_trace(
+        &mut self,
+        op_trace: Option<(usize, response::OpTrace, usize)>,
+        resp: &ExecuteJsRequest,
+    ) {
+        let (Some((position, mut op_trace, charges_from)), Some(trace)) =
+            (op_trace, self.state.trace.as_mut())
+        else {
+            return;
+        };
+
+        op_trace.ended_at = now_ms();
+        op_trace.response_size = resp.encoded_len();
+        op_trace.charges = self
+            .dynamic_payment
+            .items
+            .get(charges_from..)
+            .unwrap_or_default()
+            .to_vec();
+        if let Some(UnionRequest::ReportError(ErrorResponse { error })) = &resp.union {
+            op_trace.error = Some(error.clone());
+        }
+        trace.ops.insert(position.min(trace.ops.len()), op_trace);
+    }
+
     async fn pay(&mut self, price_component: LitActionPriceComponent, price: u64) -> Result<()> {
         if let Err(e) = self.dynamic_payment.add(price_component, price) {
             bail!(e);
@@ -1551,6 +1894,17 @@ impl Client {
         Ok(())
     }
 
+    async fn pay_quantity(
+        &mut self,
+        price_component: LitActionPriceComponent,
+        quantity: u64,
+    ) -> Result<()> {
+        if let Err(e) = self.dynamic_payment.add_quantity(price_component, quantity) {
+            bail!(e);
+        }
+        Ok(())
+    }
+
     fn parse_json_authsig_helper(&self, auth_sig: Option<Vec<u8>>) -> Result<JsonAuthSig> {
         match auth_sig {
             Some(auth_sig) => match serde_json::from_slice(&auth_sig)? {
@@ -1566,6 +1920,320 @@ impl Client {
         }
     }
 
+    /// Derives the seed from the network's BLS signature over the request id, which no client
+    /// can predict, and takes the median of all nodes' clocks as the timestamp so that a skewed
+    /// clock can't move it far.
+    async fn agree_on_determinism(&self) -> Result<Determinism> {
+        let (tss_state, txn_prefix) = self.tss_state_and_txn_prefix()?;
+        if txn_prefix.is_empty() {
+            bail!("Deterministic execution requires a request id");
+        }
+        let tss_state = Arc::new(tss_state);
+
+        let cipher_state = match tss_state.get_cipher_state(SigningScheme::Bls12381) {
+            Ok(cipher_state) => cipher_state,
+            Err(e) => {
+                bail!("Couldn't get BLS cipher state: {:?}", e);
+            }
+        };
+        let seed_message = format!("lit_deterministic_seed_{}", txn_prefix);
+        let (signature_share, _) =
+            match cipher_state.sign(seed_message.as_bytes(), self.epoch).await {
+                Ok(signature_share) => signature_share,
+                Err(e) => {
+                    bail!("Couldn't sign the deterministic seed: {:?}", e);
+                }
+            };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_millis() as u64;
+        let txn_prefix = format!("{}_deterministic_clock", txn_prefix);
+        let cm = CommsManager::new(&tss_state, 0, &txn_prefix, "0", &self.node_set).await?;
+        let (mut clocks, mut shares): (Vec<u64>, Vec<SignatureShare<Bls12381G2Impl>>) = cm
+            .broadcast_and_collect::<
+                (u64, SignatureShare<Bls12381G2Impl>),
+                (u64, SignatureShare<Bls12381G2Impl>),
+            >((now, signature_share))
+            .await?
+            .into_iter()
+            .map(|(_, inputs)| inputs)
+            .unzip();
+        clocks.push(now);
+        clocks.sort_unstable();
+        shares.push(signature_share);
+
+        // BLS signatures are unique, so every node combines the same one from any threshold of
+        // valid shares.  Invalid shares are dropped so a malicious node can neither bias the seed
+        // nor keep the others from agreeing on it.
+        let network_pubkey = get_bls_root_pubkey(&tss_state).await?;
+        let network_pubkey =
+            blsful::PublicKey::<Bls12381G2Impl>::try_from(&hex::decode(&network_pubkey)?)?;
+        let signature = cipher_state
+            .combine_signature_shares(&shares, seed_message.as_bytes(), self.epoch)
+            .await?;
+        signature.verify(&network_pubkey, seed_message.as_bytes())?;
+        let seed =
+            u64::from_be_bytes(keccak256(signature.as_raw_value().to_bytes())[..8].try_into()?);
+
+        Ok(Determinism {
+            seed,
+            timestamp: clocks[clocks.len() / 2],
+        })
+    }
+
+    /// Decrypts a ciphertext that was encrypted to the network by combining the decryption
+    /// shares of all nodes. Callers must have checked the access control conditions first.
+    async fn decrypt_and_combine(
+        &self,
+        conditions: &[UnifiedAccessControlConditionItem],
+        ciphertext: &str,
+        data_to_encrypt_hash: &str,
+    ) -> Result<String> {
+        let (tss_state, txn_prefix) = self.tss_state_and_txn_prefix()?;
+        let txn_prefix = format!("{}_{}", txn_prefix, generate_hash(ciphertext));
+        let tss_state = Arc::new(tss_state);
+
+        let identity_parameter = get_identity_param(conditions, data_to_encrypt_hash)?;
+
+        // Load the BLS secret key share as a blsful key for signing.
+        let cipher_state = match tss_state.get_cipher_state(SigningScheme::Bls12381) {
+            Ok(cipher_state) => cipher_state,
+            Err(e) => {
+                bail!("Couldn't get BLS cipher state: {:?}", e);
+            }
+        };
+
+        // Sign the identity parameter using the blsful secret key share.
+        let (signature_share, share_id) =
+            match cipher_state.sign(&identity_parameter, self.epoch).await {
+                Ok(signature_share) => signature_share,
+                Err(e) => {
+                    bail!("Couldn't sign the identity parameter: {:?}", e);
+                }
+            };
+
+        let cm = CommsManager::new(&tss_state, 0, &txn_prefix, "0", &self.node_set).await?;
+        let mut shares = cm
+            .broadcast_and_collect::<SignatureShare<Bls12381G2Impl>, SignatureShare<Bls12381G2Impl>>(
+                signature_share,
+            )
+            .await?;
+
+        shares.push((PeerId::ONE, signature_share)); // lazy - it's not zero, but we don't seem to care!
+
+        let network_pubkey = get_bls_root_pubkey(&tss_state).await?;
+        let network_pubkey = blsful::PublicKey::try_from(&hex::decode(&network_pubkey)?)?;
+
+        let serialized_decryption_shares =
+            shares.iter().map(|(_, share)| *share).collect::<Vec<_>>();
+        let ciphertext = serde_bare::from_slice(&base64_decode(ciphertext))?;
+
+        let decrypted = lit_sdk::encryption::verify_and_decrypt_with_signatures_shares(
+            &network_pubkey,
+            &identity_parameter,
+            &ciphertext,
+            &serialized_decryption_shares,
+        );
+
+        let decrypted = match decrypted {
+            Ok(decrypted) => decrypted,
+            Err(e) => {
+                bail!("Failed to decrypt and combine: {:?}", e);
+            }
+        };
+
+        match std::str::from_utf8(&decrypted) {
+            Ok(result) => Ok(result.to_string()),
+            Err(e) => {
+                bail!("Failed to convert decrypted bytes to string.")
+            }
+        }
+    }
+
+    /// Reads a value from the storage of a threshold of nodes. Every node signs the value's
+    /// identity with its BLS key share and sends it along with its ciphertext, so each node can
+    /// decrypt all of them and only a value held by a threshold of nodes is returned.
+    ///
+    /// Values aren't copied to nodes that join the network after they were written, so a value
+    /// stays readable only while a threshold of nodes hold it. Reads therefore copy an agreed
+    /// value to nodes missing it, and fail rather than report no value when some nodes hold one
+    /// that fewer than a threshold agree on.
+    async fn read_storage(&self, namespace: &str, key: &str) -> Result<Option<String>> {
+        let port = self.lit_config().external_port()?;
+        let ciphertext = storage::get(port, namespace, key)?;
+        let identity = storage::identity(namespace, key)?;
+
+        let (tss_state, txn_prefix) = self.tss_state_and_txn_prefix()?;
+        let txn_prefix = format!(
+            "{}_storage_read_{}",
+            txn_prefix, self.state.broadcast_and_collect_count
+        );
+        let tss_state = Arc::new(tss_state);
+
+        let cipher_state = match tss_state.get_cipher_state(SigningScheme::Bls12381) {
+            Ok(cipher_state) => cipher_state,
+            Err(e) => {
+                bail!("Couldn't get BLS cipher state: {:?}", e);
+            }
+        };
+        let (signature_share, _) = match cipher_state.sign(&identity, self.epoch).await {
+            Ok(signature_share) => signature_share,
+            Err(e) => {
+                bail!("Couldn't sign the storage identity: {:?}", e);
+            }
+        };
+
+        let cm = CommsManager::new(&tss_state, 0, &txn_prefix, "0", &self.node_set).await?;
+        let (mut shares, mut ciphertexts): (
+            Vec<SignatureShare<Bls12381G2Impl>>,
+            Vec<Option<Vec<u8>>>,
+        ) = cm
+            .broadcast_and_collect::<
+                (SignatureShare<Bls12381G2Impl>, Option<Vec<u8>>),
+                (SignatureShare<Bls12381G2Impl>, Option<Vec<u8>>),
+            >((signature_share, ciphertext.clone()))
+            .await?
+            .into_iter()
+            .map(|(_, inputs)| inputs)
+            .unzip();
+        shares.push(signature_share);
+        ciphertexts.push(ciphertext.clone());
+
+        let network_pubkey = get_bls_root_pubkey(&tss_state).await?;
+        let network_pubkey =
+            blsful::PublicKey::<Bls12381G2Impl>::try_from(&hex::decode(&network_pubkey)?)?;
+        let signature = cipher_state
+            .combine_signature_shares(&shares, &identity, self.epoch)
+            .await?;
+
+        // Values that don't decrypt count as no agreement rather than failing the read.
+        let decrypt = |ciphertext: &[u8]| {
+            storage::decrypt(&network_pubkey, &signature, namespace, key, ciphertext)
+        };
+        let mut values: HashMap<Option<String>, usize> = HashMap::new();
+        for ciphertext in ciphertexts {
+            let value = match ciphertext {
+                Some(ciphertext) => match decrypt(&ciphertext) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        warn!("Skipping undecryptable storage value: {:?}", e);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            *values.entry(value).or_default() += 1;
+        }
+
+        let threshold = tss_state.get_threshold().await;
+        let held = values.keys().any(Option::is_some);
+        let value = match values.into_iter().find(|(_, count)| *count >= threshold) {
+            Some((None, _)) if held => bail!(
+                "Fewer than {} nodes hold the stored value of {}, it was written before too many of the current nodes joined",
+                threshold,
+                key
+            ),
+            Some((value, _)) => value,
+            None => bail!(
+                "Fewer than {} nodes agree on the stored value of {}",
+                threshold,
+                key
+            ),
+        };
+
+        if let Some(value) = &value {
+            let own_value = ciphertext
+                .as_deref()
+                .map(decrypt)
+                .and_then(|value| value.ok());
+            if own_value.as_ref() != Some(value) {
+                if let Err(e) = storage::set(
+                    port,
+                    &network_pubkey,
+                    namespace,
+                    key,
+                    value,
+                    self.max_storage_bytes,
+                ) {
+                    warn!("Couldn't copy the agreed storage value of {}: {:?}", key, e);
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Only lets a storage write through once a threshold of nodes is about to make the same
+    /// write, so that nodes which ran the action differently don't end up with diverging values.
+    async fn agree_on_storage_write(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        let write_digest = bytes_to_hex(keccak256(serde_json::to_vec(&(namespace, key, value))?));
+
+        let (tss_state, txn_prefix) = self.tss_state_and_txn_prefix()?;
+        let txn_prefix = format!(
+            "{}_storage_{}",
+            txn_prefix, self.state.broadcast_and_collect_count
+        );
+        let tss_state = Arc::new(tss_state);
+        let cm = CommsManager::new(&tss_state, 0, &txn_prefix, "0", &self.node_set).await?;
+        let agreeing = 1 + cm
+            .broadcast_and_collect::<String, String>(write_digest.clone())
+            .await?
+            .into_iter()
+            .filter(|(_, digest)| *digest == write_digest)
+            .count();
+
+        let threshold = tss_state.get_threshold().await;
+        if agreeing < threshold {
+            bail!(
+                "Only {} nodes agreed on writing {} to storage, but {} are required",
+                agreeing,
+                key,
+                threshold
+            );
+        }
+        Ok(())
+    }
+
+    /// Exchanges a digest of the recorded fetch responses with the other nodes.
+    async fn compare_fetch_records(
+        &self,
+        determinism: Determinism,
+        fetch_records: &[FetchRecord],
+    ) -> Result<response::DeterministicExecutionReport> {
+        let fetch_digest = bytes_to_hex(keccak256(serde_json::to_vec(fetch_records)?));
+
+        let (tss_state, txn_prefix) = self.tss_state_and_txn_prefix()?;
+        let txn_prefix = format!("{}_deterministic_fetches", txn_prefix);
+        let cm =
+            CommsManager::new(&Arc::new(tss_state), 0, &txn_prefix, "0", &self.node_set).await?;
+        let divergent_peers: Vec<String> = cm
+            .broadcast_and_collect::<String, String>(fetch_digest.clone())
+            .await?
+            .into_iter()
+            .filter(|(_, digest)| *digest != fetch_digest)
+            .map(|(peer_id, _)| peer_id.to_string())
+            .collect();
+        if !divergent_peers.is_empty() {
+            warn!(
+                "Fetch responses of request {} diverge from peers {:?}",
+                self.request_id(),
+                divergent_peers
+            );
+        }
+
+        Ok(response::DeterministicExecutionReport {
+            seed: determinism.seed,
+            timestamp: determinism.timestamp,
+            fetch_digest,
+            divergent_peers,
+        })
+    }
+
     fn increment_broad_and_collect_counter(&mut self) -> Result<()> {
         self.state.broadcast_and_collect_count += 1;
         if self.state.broadcast_and_collect_count > self.max_broadcast_and_collect_count {
@@ -1656,6 +2324,8 @@ impl Client {
             &bls_root_pubkey,
             &self.node_set,
             signing_scheme,
+            None,
+            false,
         )
         .await
         .map_err(|e| anyhow::anyhow!(format!("Failed to sign: {:?}", e)))?;
@@ -1739,6 +2409,7 @@ impl Client {
             action_ipfs_id.as_ref(),
             self.ipfs_cache()?,
             self.http_cache()?,
+            &self.rpc_cache()?,
         )
         .await
         .map_err(|e| anyhow::anyhow!(format!("Error checking access control conditions: {:?}", e)))
@@ -6,6 +6,7 @@ use std::future::Future;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::str::FromStr as _;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
@@ -17,8 +18,9 @@ use apalis_sql::{
 };
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-use super::action_client::{Client, ExecutionOptions, ExecutionState};
+use super::action_client::{Client, ExecutionOptions, ExecutionState, egress_policy};
 use crate::models::DenoExecutionEnv;
+use crate::payment::payment_tracker::PaymentTracker;
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JobId(TaskId);
@@ -70,6 +72,8 @@ impl From<State> for JobStatus {
 pub struct ActionJob {
     client: Client,
     opts: ExecutionOptions,
+    #[serde(default)]
+    settles_payment: bool,
 }
 
 impl ActionJob {
@@ -77,6 +81,16 @@ impl ActionJob {
         Self {
             client,
             opts: opts.into(),
+            settles_payment: false,
+        }
+    }
+
+    /// Makes the worker settle the job's payment once it ran, for jobs without a request
+    /// around to do it, e.g. the runs of a schedule
+    pub fn settling_payment(self) -> Self {
+        Self {
+            settles_payment: true,
+            ..self
         }
     }
 
@@ -88,6 +102,8 @@ impl ActionJob {
         &mut self,
         env: DenoExecutionEnv,
     ) -> Result<ExecutionState, crate::error::Error> {
+        // Not persisted with the job, so taken from the config of the node running it
+        self.client.egress_policy = Some(egress_policy(&env.cfg));
         self.client.js_env = env;
         self.run().await
     }
@@ -221,18 +237,33 @@ pub struct ActionWorker {
 }
 
 impl ActionWorker {
-    pub fn new(store: ActionStore, env: DenoExecutionEnv) -> Self {
+    pub fn new(
+        store: ActionStore,
+        env: DenoExecutionEnv,
+        payment_tracker: Option<Arc<PaymentTracker>>,
+    ) -> Self {
         async fn run_job(
             mut job: ActionJob,
             env: Data<DenoExecutionEnv>,
+            payment_tracker: Data<Option<Arc<PaymentTracker>>>,
         ) -> Result<ExecutionState, crate::error::Error> {
-            job.run_with_env(env.deref().clone()).await
+            let result = job.run_with_env(env.deref().clone()).await;
+
+            if let Some(payment_tracker) = payment_tracker.deref() {
+                if job.settles_payment && job.client.dynamic_payment.payment_enabled {
+                    let pending_payment = job.client.dynamic_payment.to_pending_payment();
+                    payment_tracker.batches().add(pending_payment).await;
+                }
+            }
+
+            result
         }
 
         let monitor = Monitor::new().register({
             WorkerBuilder::new("action-worker")
                 .layer(TraceLayer::new())
                 .data(env)
+                .data(payment_tracker)
                 .backend(store.into_inner())
                 .build_fn(run_job)
         });
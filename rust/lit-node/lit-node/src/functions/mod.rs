@@ -1,6 +1,9 @@
 pub mod action_client;
 mod aes;
 mod job;
+pub mod schedule;
+pub mod secrets;
+mod storage;
 
 pub use job::*;
 
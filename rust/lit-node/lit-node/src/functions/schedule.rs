@@ -0,0 +1,703 @@
+//! Scheduled and event-triggered Lit Actions.
+//!
+//! A schedule runs an action from IPFS whenever its trigger fires, either on a cron expression
+//! or when a contract emits an event on a chain the node has an RPC endpoint for. Every node
+//! evaluates the schedules it holds on its own and enqueues each run as an async action job.
+//! The request ID of a run is derived from what triggered it, so that the nodes running it can
+//! find each other when the action broadcasts or signs.
+//!
+//! There's no client around to present a session sig when a schedule fires, so runs are paid
+//! for by a payer that delegated to the schedule's owner in the Payment DB.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use ethers::providers::Middleware as _;
+use ethers::types::{Address, Bytes, Filter, H256, Log};
+use ethers::utils::keccak256;
+use futures::StreamExt as _;
+use lit_blockchain::resolver::rpc::{ENDPOINT_MANAGER, RpcHealthcheckPoller as _};
+use lit_core::config::LitConfig;
+use lit_core::error::Unexpected as _;
+use lit_core::utils::binary::bytes_to_hex;
+use lit_node_common::config::LitNodeConfig as _;
+use lit_node_core::EndpointVersion;
+use saffron::Cron;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use super::action_client::{ClientBuilder, ExecutionOptions, egress_policy};
+use super::{ActionStore, JobId};
+use crate::endpoints::web_client::get_price_multiplier;
+use crate::error::{
+    EC, Result, blockchain_err_code, conversion_err, unexpected_err, validation_err_code,
+};
+use crate::models::{AuthContext, DenoExecutionEnv};
+use crate::payment::delegated_usage::DelegatedUsageDB;
+use crate::payment::dynamic::DynamicPayment;
+use crate::payment::payed_endpoint::PayedEndpoint;
+use crate::payment::payment_tracker::PaymentTracker;
+use crate::payment::selection::get_delegated_payment_method;
+use crate::siwe_db::db::{
+    db_count_action_schedules, db_delete_action_schedule, db_get_action_schedule,
+    db_get_action_schedule_jobs, db_get_action_schedules, db_insert_action_schedule,
+    db_insert_action_schedule_job, db_set_action_schedule_cursor,
+};
+use crate::tss::common::tss_state::TssState;
+use crate::utils::web::get_ipfs_file;
+
+pub(crate) const MAX_SCHEDULES_PER_OWNER: usize = 20;
+// Every node evaluates every schedule on each tick, so their total is capped as well
+pub(crate) const MAX_SCHEDULES: usize = 10_000;
+// Schedules evaluated at once, mostly waiting on RPC calls for their events
+const MAX_CONCURRENT_EVALUATIONS: usize = 32;
+const MAX_JOB_HISTORY: usize = 100;
+// Events are only acted upon once they are this deep, so that all nodes see them and
+// reorgs are unlikely to drop them
+const CONFIRMATIONS: u64 = 5;
+// Keeps log queries within the limits of RPC providers
+const MAX_BLOCK_RANGE: u64 = 1_000;
+// Further events found in the same poll are left for the next one
+const MAX_JOBS_PER_POLL: usize = 10;
+// Cron ticks missed for longer, e.g. while the node was down, are skipped
+const MAX_CRON_CATCH_UP_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduleTrigger {
+    /// Fires on a cron expression, evaluated in UTC
+    Cron { expression: String },
+    /// Fires whenever a contract emits an event with the given topic, from the block the
+    /// schedule is registered at on
+    #[serde(rename_all = "camelCase")]
+    ChainEvent {
+        chain: String,
+        contract_address: Address,
+        topic: H256,
+        from_block: u64,
+    },
+}
+
+impl ScheduleTrigger {
+    fn validate(&self) -> Result<()> {
+        match self {
+            Self::Cron { expression } => parse_cron(expression).map(|_| ()),
+            Self::ChainEvent {
+                chain, from_block, ..
+            } => {
+                if *from_block == 0 {
+                    return Err(validation_err_code(
+                        "The fromBlock of an event schedule must be set",
+                        EC::NodeInvalidSchedule,
+                        None,
+                    ));
+                }
+                ENDPOINT_MANAGER
+                    .get_provider(chain)
+                    .map(|_| ())
+                    .map_err(|e| {
+                        validation_err_code(
+                            e,
+                            EC::NodeInvalidSchedule,
+                            Some(format!("Unsupported chain {chain}")),
+                        )
+                    })
+            }
+        }
+    }
+}
+
+/// What made a schedule fire. Actions see it as the `scheduleTrigger` global.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TriggerEvent {
+    #[serde(rename_all = "camelCase")]
+    Cron { scheduled_at: i64 },
+    #[serde(rename_all = "camelCase")]
+    ChainEvent {
+        block_number: u64,
+        transaction_hash: H256,
+        log_index: u64,
+        topics: Vec<H256>,
+        data: Bytes,
+    },
+}
+
+impl TriggerEvent {
+    fn from_log(log: Log) -> Option<Self> {
+        if log.removed == Some(true) {
+            return None;
+        }
+        Some(Self::ChainEvent {
+            block_number: log.block_number?.as_u64(),
+            transaction_hash: log.transaction_hash?,
+            log_index: log.log_index?.as_u64(),
+            topics: log.topics,
+            data: log.data,
+        })
+    }
+
+    fn request_id(&self, schedule_id: &str) -> String {
+        match self {
+            Self::Cron { scheduled_at } => format!("schedule-{schedule_id}-{scheduled_at}"),
+            Self::ChainEvent {
+                transaction_hash,
+                log_index,
+                ..
+            } => format!("schedule-{schedule_id}-{transaction_hash:#x}-{log_index}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionSchedule {
+    pub id: String,
+    pub owner: String,
+    pub ipfs_id: String,
+    pub js_params: Option<Value>,
+    pub trigger: ScheduleTrigger,
+    pub created_at: i64,
+}
+
+impl ActionSchedule {
+    /// The ID is derived from everything but the creation time, so that all nodes assign
+    /// the same one and the same schedule can't be created twice.
+    pub fn new(
+        owner: &str,
+        ipfs_id: String,
+        js_params: Option<Value>,
+        trigger: ScheduleTrigger,
+        created_at: i64,
+    ) -> Result<Self> {
+        trigger.validate()?;
+        if !matches!(js_params, None | Some(Value::Object(_))) {
+            return Err(validation_err_code(
+                "The jsParams of a schedule must be an object",
+                EC::NodeInvalidSchedule,
+                None,
+            ));
+        }
+
+        let owner = owner.to_lowercase();
+        let preimage = serde_json::to_vec(&(&owner, &ipfs_id, &js_params, &trigger))
+            .map_err(|e| conversion_err(e, None))?;
+
+        Ok(Self {
+            id: bytes_to_hex(keccak256(preimage)),
+            owner,
+            ipfs_id,
+            js_params,
+            trigger,
+            created_at,
+        })
+    }
+
+    fn globals(&self, event: &TriggerEvent) -> Value {
+        let mut globals = self.js_params.clone().unwrap_or_else(|| json!({}));
+        if let Value::Object(globals) = &mut globals {
+            globals.insert("scheduleTrigger".to_string(), json!(event));
+        }
+        globals
+    }
+}
+
+/// A run of a schedule, with either the job it enqueued or why it couldn't
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJob {
+    pub job_id: Option<JobId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub triggered_at: i64,
+    pub trigger: TriggerEvent,
+}
+
+pub(crate) fn create(port: u16, schedule: &ActionSchedule) -> Result<()> {
+    if db_count_action_schedules(port, Some(&schedule.owner))? >= MAX_SCHEDULES_PER_OWNER {
+        return Err(validation_err_code(
+            format!("An owner can't have more than {MAX_SCHEDULES_PER_OWNER} schedules"),
+            EC::NodeInvalidSchedule,
+            None,
+        ));
+    }
+    if db_count_action_schedules(port, None)? >= MAX_SCHEDULES {
+        return Err(validation_err_code(
+            format!("The network can't hold more than {MAX_SCHEDULES} schedules"),
+            EC::NodeInvalidSchedule,
+            None,
+        ));
+    }
+
+    // Cron schedules fire from their creation on, while event schedules start watching at
+    // their registration block, so that all nodes fire for the same events
+    let cursor = match schedule.trigger {
+        ScheduleTrigger::Cron { .. } => schedule.created_at,
+        ScheduleTrigger::ChainEvent { from_block, .. } => from_block.saturating_sub(1) as i64,
+    };
+    let serialized = serde_json::to_string(schedule).map_err(|e| conversion_err(e, None))?;
+    if !db_insert_action_schedule(port, &schedule.id, &schedule.owner, &serialized, cursor)? {
+        return Err(validation_err_code(
+            format!("Schedule {} already exists", schedule.id),
+            EC::NodeInvalidSchedule,
+            None,
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn get(port: u16, id: &str) -> Result<Option<ActionSchedule>> {
+    db_get_action_schedule(port, id)?
+        .map(|schedule| serde_json::from_str(&schedule).map_err(|e| conversion_err(e, None)))
+        .transpose()
+}
+
+pub(crate) fn list(port: u16, owner: &str) -> Result<Vec<ActionSchedule>> {
+    load(port, Some(owner)).map(|schedules| schedules.into_iter().map(|(s, _)| s).collect())
+}
+
+pub(crate) fn delete(port: u16, id: &str) -> Result<bool> {
+    db_delete_action_schedule(port, id)
+}
+
+/// Returns the jobs enqueued by a schedule, most recent first
+pub(crate) fn jobs(port: u16, id: &str) -> Result<Vec<ScheduledJob>> {
+    db_get_action_schedule_jobs(port, id)?
+        .iter()
+        .map(|job| serde_json::from_str(job).map_err(|e| conversion_err(e, None)))
+        .collect()
+}
+
+fn load(port: u16, owner: Option<&str>) -> Result<Vec<(ActionSchedule, i64)>> {
+    db_get_action_schedules(port, owner)?
+        .into_iter()
+        .map(|(schedule, cursor)| {
+            serde_json::from_str(&schedule)
+                .map(|schedule| (schedule, cursor))
+                .map_err(|e| conversion_err(e, None))
+        })
+        .collect()
+}
+
+fn parse_cron(expression: &str) -> Result<Cron> {
+    expression.parse::<Cron>().map_err(|_| {
+        validation_err_code(
+            format!("Invalid cron expression {expression:?}"),
+            EC::NodeInvalidSchedule,
+            None,
+        )
+    })
+}
+
+/// Returns the most recent tick of `cron` after `cursor`, up to `now`. Older ticks that were
+/// missed are skipped rather than fired all at once.
+fn last_cron_tick(cron: &Cron, cursor: i64, now: DateTime<Utc>) -> Option<i64> {
+    let mut tick =
+        DateTime::from_timestamp(cursor.max(now.timestamp() - MAX_CRON_CATCH_UP_SECS), 0)?;
+    let mut last = None;
+    while let Some(next) = cron
+        .next_after(tick)
+        .filter(|next| *next > tick && *next <= now)
+    {
+        last = Some(next.timestamp());
+        tick = next;
+    }
+    last
+}
+
+/// Returns the blocks to look for events in after the one at `cursor`, if any got confirmed.
+fn confirmed_block_range(cursor: u64, head: u64) -> Option<(u64, u64)> {
+    let confirmed = head.saturating_sub(CONFIRMATIONS);
+    (cursor < confirmed).then(|| (cursor + 1, confirmed.min(cursor + MAX_BLOCK_RANGE)))
+}
+
+/// Caps the events found in the blocks up to `to` at `MAX_JOBS_PER_POLL`, returning the ones
+/// to enqueue along with the new cursor. The cursor only moves past the blocks whose events
+/// are all enqueued, and the events of a single block are never split up, so that the next
+/// poll neither skips nor repeats any.
+fn take_events(mut events: Vec<TriggerEvent>, to: u64) -> (Vec<TriggerEvent>, u64) {
+    let block_number = |event: &TriggerEvent| match event {
+        TriggerEvent::ChainEvent { block_number, .. } => *block_number,
+        TriggerEvent::Cron { .. } => 0,
+    };
+    let Some(first_left) = events.get(MAX_JOBS_PER_POLL).map(block_number) else {
+        return (events, to);
+    };
+
+    let first_block = events.first().map(block_number).unwrap_or(first_left);
+    let cursor = match first_block < first_left {
+        true => first_left - 1,
+        // A single block has more events than a poll takes
+        false => first_left,
+    };
+    events.retain(|event| block_number(event) <= cursor);
+    (events, cursor)
+}
+
+/// Returns the events emitted after the block at `cursor`, along with the new cursor.
+async fn poll_chain_events(
+    chain: &str,
+    contract_address: Address,
+    topic: H256,
+    cursor: i64,
+) -> Result<(Vec<TriggerEvent>, i64)> {
+    let provider = ENDPOINT_MANAGER.get_provider(chain)?;
+    let head = provider
+        .get_block_number()
+        .await
+        .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, None))?
+        .as_u64();
+
+    let Some((from, to)) = confirmed_block_range(cursor as u64, head) else {
+        return Ok((vec![], cursor));
+    };
+
+    let filter = Filter::new()
+        .address(contract_address)
+        .topic0(topic)
+        .from_block(from)
+        .to_block(to);
+    let logs = provider
+        .get_logs(&filter)
+        .await
+        .map_err(|e| blockchain_err_code(e, EC::NodeRpcError, None))?;
+
+    let (events, cursor) = take_events(
+        logs.into_iter()
+            .filter_map(TriggerEvent::from_log)
+            .collect(),
+        to,
+    );
+    Ok((events, cursor as i64))
+}
+
+/// Enqueues action jobs for the schedules whose trigger fired.
+pub struct Scheduler {
+    store: ActionStore,
+    env: DenoExecutionEnv,
+    tss_state: Arc<TssState>,
+    payment_tracker: Arc<PaymentTracker>,
+    delegation_usage_db: Arc<DelegatedUsageDB>,
+}
+
+impl Scheduler {
+    pub fn new(
+        store: ActionStore,
+        env: DenoExecutionEnv,
+        tss_state: Arc<TssState>,
+        payment_tracker: Arc<PaymentTracker>,
+        delegation_usage_db: Arc<DelegatedUsageDB>,
+    ) -> Self {
+        Self {
+            store,
+            env,
+            tss_state,
+            payment_tracker,
+            delegation_usage_db,
+        }
+    }
+
+    pub async fn tick(&self) {
+        let port = match self.env.cfg.external_port() {
+            Ok(port) => port,
+            Err(e) => {
+                warn!("Error getting the port of the schedules db: {e:?}");
+                return;
+            }
+        };
+        let schedules = match load(port, None) {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                warn!("Error loading action schedules: {e:?}");
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        futures::stream::iter(schedules)
+            .for_each_concurrent(
+                MAX_CONCURRENT_EVALUATIONS,
+                |(schedule, cursor)| async move {
+                    if let Err(e) = self.evaluate(port, &schedule, cursor, now).await {
+                        warn!("Error evaluating action schedule {}: {e:?}", schedule.id);
+                    }
+                },
+            )
+            .await;
+    }
+
+    async fn evaluate(
+        &self,
+        port: u16,
+        schedule: &ActionSchedule,
+        cursor: i64,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let (events, next_cursor) = match &schedule.trigger {
+            ScheduleTrigger::Cron { expression } => {
+                match last_cron_tick(&parse_cron(expression)?, cursor, now) {
+                    Some(tick) => (vec![TriggerEvent::Cron { scheduled_at: tick }], tick),
+                    None => return Ok(()),
+                }
+            }
+            ScheduleTrigger::ChainEvent {
+                chain,
+                contract_address,
+                topic,
+                ..
+            } => poll_chain_events(chain, *contract_address, *topic, cursor).await?,
+        };
+
+        // Move on before enqueueing, so that a trigger never fires twice
+        db_set_action_schedule_cursor(port, &schedule.id, next_cursor)?;
+
+        for event in events {
+            let job = match self.enqueue(schedule, &event).await {
+                Ok(job_id) => {
+                    info!("Action schedule {} enqueued job {job_id}", schedule.id);
+                    ScheduledJob {
+                        job_id: Some(job_id),
+                        error: None,
+                        triggered_at: now.timestamp(),
+                        trigger: event,
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Error enqueueing a job for action schedule {}: {e:?}",
+                        schedule.id
+                    );
+                    ScheduledJob {
+                        job_id: None,
+                        error: Some(e.to_string()),
+                        triggered_at: now.timestamp(),
+                        trigger: event,
+                    }
+                }
+            };
+            let serialized = serde_json::to_string(&job).map_err(|e| conversion_err(e, None))?;
+            db_insert_action_schedule_job(
+                port,
+                &schedule.id,
+                job.triggered_at,
+                &serialized,
+                MAX_JOB_HISTORY,
+            )?;
+        }
+        Ok(())
+    }
+
+    async fn enqueue(&self, schedule: &ActionSchedule, event: &TriggerEvent) -> Result<JobId> {
+        let actions_config = self
+            .tss_state
+            .chain_data_config_manager
+            .get_actions_config();
+        if !actions_config.async_actions_enabled {
+            return Err(unexpected_err("Async Lit Actions are disabled", None));
+        }
+
+        let code = get_ipfs_file(
+            &schedule.ipfs_id,
+            &self.env.cfg,
+            self.env
+                .ipfs_cache
+                .clone()
+                .expect_or_err("No IPFS cache found")?,
+            self.env
+                .http_client
+                .clone()
+                .expect_or_err("No HTTP client found")?,
+        )
+        .await?;
+
+        let client = ClientBuilder::default()
+            .js_env(self.env.clone())
+            .auth_context(AuthContext {
+                auth_sig_address: Some(schedule.owner.clone()),
+                ..Default::default()
+            })
+            .request_id(event.request_id(&schedule.id))
+            .egress_policy(egress_policy(&self.env.cfg))
+            .memory_limit_mb(actions_config.memory_limit_mb as u32)
+            .max_code_length(actions_config.max_code_length as usize)
+            .max_fetch_count(actions_config.max_fetch_count as u32)
+            .max_sign_count(actions_config.max_sign_count as u32)
+            .max_contract_call_count(actions_config.max_contract_call_count as u32)
+            .max_broadcast_and_collect_count(actions_config.max_broadcast_and_collect_count as u32)
+            .max_call_depth(actions_config.max_call_depth as u32)
+            .max_retries(actions_config.max_retries as u32)
+            .endpoint_version(EndpointVersion::V2)
+            .dynamic_payment(self.dynamic_payment(&schedule.owner).await?)
+            .build()
+            .map_err(|e| unexpected_err(e, Some("Error building action client".into())))?;
+
+        let opts = ExecutionOptions {
+            code,
+            globals: Some(schedule.globals(event)),
+            action_ipfs_id: Some(schedule.ipfs_id.clone()),
+        };
+        // Nobody waits for the run to settle its payment, so the job does it itself
+        self.store
+            .clone()
+            .submit_job(client.async_job(opts).settling_payment())
+            .await
+            .map_err(|e| unexpected_err(e, Some("Error submitting action job".into())))
+    }
+
+    async fn dynamic_payment(&self, owner: &str) -> Result<DynamicPayment> {
+        delegated_dynamic_payment(
+            owner,
+            &self.tss_state,
+            &self.payment_tracker,
+            &self.delegation_usage_db,
+            &self.env.cfg,
+        )
+        .await
+    }
+}
+
+/// Returns the payment for a run of a schedule of `owner`, or for creating one, which is
+/// made by a payer that delegated to the owner in the Payment DB.
+pub(crate) async fn delegated_dynamic_payment(
+    owner: &str,
+    tss_state: &Arc<TssState>,
+    payment_tracker: &Arc<PaymentTracker>,
+    delegation_usage_db: &DelegatedUsageDB,
+    cfg: &LitConfig,
+) -> Result<DynamicPayment> {
+    if !cfg.enable_payment().unwrap_or(true) {
+        return Ok(DynamicPayment::default());
+    }
+
+    let owner = owner
+        .parse::<Address>()
+        .map_err(|e| conversion_err(e, None))?;
+    let pending_payment = get_delegated_payment_method(
+        &owner,
+        PayedEndpoint::LitAction,
+        tss_state.get_threshold().await,
+        payment_tracker,
+        delegation_usage_db,
+        cfg,
+    )
+    .await?;
+    let price_multiplier = get_price_multiplier(tss_state, pending_payment.price).await?;
+
+    DynamicPayment::load_from(
+        pending_payment.payer,
+        &tss_state.chain_data_config_manager,
+        price_multiplier,
+        pending_payment.spending_limit,
+        true,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap()
+    }
+
+    #[test]
+    fn test_last_cron_tick() {
+        let every_minute = parse_cron("* * * * *").unwrap();
+        assert_eq!(last_cron_tick(&every_minute, 0, at(30)), None);
+        assert_eq!(last_cron_tick(&every_minute, 0, at(60)), Some(60));
+        // Only the most recent of the missed ticks fires
+        assert_eq!(last_cron_tick(&every_minute, 0, at(200)), Some(180));
+        assert_eq!(last_cron_tick(&every_minute, 180, at(200)), None);
+
+        assert!(parse_cron("not a cron").is_err());
+    }
+
+    #[test]
+    fn test_confirmed_block_range() {
+        assert_eq!(confirmed_block_range(100, 100), None);
+        assert_eq!(confirmed_block_range(100, 105), None);
+        assert_eq!(confirmed_block_range(100, 110), Some((101, 105)));
+        assert_eq!(
+            confirmed_block_range(100, 100_000),
+            Some((101, 100 + MAX_BLOCK_RANGE))
+        );
+    }
+
+    #[test]
+    fn test_take_events() {
+        let event = |block_number: u64, log_index: u64| TriggerEvent::ChainEvent {
+            block_number,
+            transaction_hash: H256::zero(),
+            log_index,
+            topics: vec![],
+            data: Bytes::default(),
+        };
+
+        let few: Vec<_> = (0..3).map(|i| event(101 + i, 0)).collect();
+        assert_eq!(take_events(few.clone(), 200), (few, 200));
+
+        // The events of block 103 don't all fit, so it's left for the next poll
+        let many: Vec<_> = (0..MAX_JOBS_PER_POLL as u64)
+            .map(|i| event(101 + i / 4, i))
+            .chain([event(103, 10), event(104, 0)])
+            .collect();
+        let (taken, cursor) = take_events(many.clone(), 200);
+        assert_eq!(cursor, 102);
+        assert_eq!(taken, many[..8]);
+
+        // A block with more events than a poll takes is taken as a whole
+        let one_block: Vec<_> = (0..MAX_JOBS_PER_POLL as u64 + 2)
+            .map(|i| event(101, i))
+            .chain([event(102, 0)])
+            .collect();
+        let (taken, cursor) = take_events(one_block.clone(), 200);
+        assert_eq!(cursor, 101);
+        assert_eq!(taken, one_block[..MAX_JOBS_PER_POLL + 2]);
+    }
+
+    #[test]
+    fn test_schedule_id() {
+        let trigger = ScheduleTrigger::Cron {
+            expression: "0 * * * *".to_string(),
+        };
+        let schedule =
+            ActionSchedule::new("0xABC", "QmA".to_string(), None, trigger.clone(), 1).unwrap();
+        assert_eq!(schedule.owner, "0xabc");
+
+        // Same schedule created at another time, e.g. by another node
+        let same = ActionSchedule::new("0xabc", "QmA".to_string(), None, trigger.clone(), 2);
+        assert_eq!(same.unwrap().id, schedule.id);
+
+        let other = ActionSchedule::new("0xabc", "QmB".to_string(), None, trigger.clone(), 1);
+        assert_ne!(other.unwrap().id, schedule.id);
+
+        let invalid_params =
+            ActionSchedule::new("0xabc", "QmA".to_string(), Some(json!([1])), trigger, 1);
+        assert!(invalid_params.is_err());
+    }
+
+    #[test]
+    fn test_globals() {
+        let schedule = ActionSchedule::new(
+            "0xabc",
+            "QmA".to_string(),
+            Some(json!({ "foo": 1 })),
+            ScheduleTrigger::Cron {
+                expression: "0 * * * *".to_string(),
+            },
+            1,
+        )
+        .unwrap();
+        let event = TriggerEvent::Cron { scheduled_at: 3600 };
+
+        assert_eq!(
+            schedule.globals(&event),
+            json!({ "foo": 1, "scheduleTrigger": { "type": "cron", "scheduledAt": 3600 } })
+        );
+        assert_eq!(
+            event.request_id(&schedule.id),
+            format!("schedule-{}-3600", schedule.id)
+        );
+    }
+}
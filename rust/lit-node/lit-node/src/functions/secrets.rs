@@ -0,0 +1,261 @@
+//! Secrets of Lit Actions, kept by the nodes for an action's IPFS CID.
+//!
+//! An owner encrypts a secret to the network under a single condition that
+//! `:currentActionIpfsId` is the CID, and stores the ciphertext with every node under a name.
+//! When the action asks for the secret by name, the nodes decrypt it the same way as
+//! `decryptAndCombine`, and only hand the plaintext to the action.
+//!
+//! The secrets of an action are managed by the owner its code declares in its leading comments,
+//! e.g. `// @lit-secrets-owner 0x…`. As the CID is the hash of the code, only whoever published
+//! the action could have picked that owner, and every node derives the same one. Writes are
+//! only applied once a threshold of nodes agree on them, so the nodes hold the same secrets.
+
+use std::sync::Arc;
+
+use ethers::types::Address;
+use ethers::utils::keccak256;
+use lit_core::utils::binary::bytes_to_hex;
+use lit_node_core::{
+    ControlConditionItem, NodeSet, UnifiedAccessControlCondition, UnifiedAccessControlConditionItem,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EC, Error, Result, conversion_err, validation_err_code};
+use crate::p2p_comms::CommsManager;
+use crate::siwe_db::db::{
+    db_delete_action_secret, db_get_action_secret, db_get_action_secret_ipfs_ids,
+    db_get_action_secret_names, db_put_action_secret,
+};
+use crate::tss::common::tss_state::TssState;
+
+// Leading comment line of an action naming the address that manages its secrets
+const OWNER_TAG: &str = "@lit-secrets-owner";
+
+pub(crate) const MAX_NAME_LENGTH: usize = 256;
+pub(crate) const MAX_CIPHERTEXT_LENGTH: usize = 64 * 1024;
+pub(crate) const MAX_SECRETS_PER_ACTION: usize = 100;
+pub(crate) const MAX_ACTIONS_PER_OWNER: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionSecret {
+    pub ciphertext: String,
+    pub data_to_encrypt_hash: String,
+    pub unified_access_control_conditions: Vec<UnifiedAccessControlConditionItem>,
+}
+
+/// Checks that a secret can only be decrypted by the action with the given CID.
+pub(crate) fn validate(ipfs_id: &str, name: &str, secret: &ActionSecret) -> Result<()> {
+    validate_name(name)?;
+    if secret.ciphertext.is_empty() || secret.ciphertext.len() > MAX_CIPHERTEXT_LENGTH {
+        return Err(invalid_secret(format!(
+            "Secret ciphertexts must be between 1 and {MAX_CIPHERTEXT_LENGTH} bytes long"
+        )));
+    }
+    if !is_bound_to(&secret.unified_access_control_conditions, ipfs_id) {
+        return Err(invalid_secret(format!(
+            "Secrets must be encrypted under a single condition that :currentActionIpfsId is {ipfs_id}"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(invalid_secret(format!(
+            "Secret names must be between 1 and {MAX_NAME_LENGTH} bytes long"
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the conditions let no one but the action with the given CID decrypt.
+pub(crate) fn is_bound_to(conditions: &[UnifiedAccessControlConditionItem], ipfs_id: &str) -> bool {
+    match conditions {
+        [
+            ControlConditionItem::Condition(
+                UnifiedAccessControlCondition::JsonAccessControlCondition(condition),
+            ),
+        ] => {
+            !ipfs_id.is_empty()
+                && condition.contract_address.is_empty()
+                && condition.standard_contract_type.is_empty()
+                && condition.method.is_empty()
+                && condition.parameters == [":currentActionIpfsId"]
+                && condition.return_value_test.comparator == "="
+                && condition.return_value_test.value == ipfs_id
+        }
+        _ => false,
+    }
+}
+
+/// Returns the owner that the code of an action declares, if any.
+pub(crate) fn declared_owner(code: &str) -> Option<String> {
+    for line in code.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let comment = line.strip_prefix("//")?; // Only the leading comments are metadata
+        if let Some(owner) = comment.trim().strip_prefix(OWNER_TAG) {
+            let owner = owner.split_whitespace().next()?.parse::<Address>().ok()?;
+            return Some(format!("{owner:#x}"));
+        }
+    }
+    None
+}
+
+/// Checks that `user_address` is the owner declared by the code of the action.
+pub(crate) fn authorize(ipfs_id: &str, code: &str, user_address: &str) -> Result<()> {
+    match declared_owner(code) {
+        Some(owner) if owner == user_address => Ok(()),
+        Some(owner) => Err(invalid_secret(format!(
+            "Secrets of action {ipfs_id} can only be managed by its owner {owner}"
+        ))),
+        None => Err(invalid_secret(format!(
+            "Action {ipfs_id} has no secrets owner. Declare one with a leading `// {OWNER_TAG} 0x…` comment"
+        ))),
+    }
+}
+
+/// Fails if storing the secret would give the action or its owner too many secrets.
+pub(crate) fn check_limits(port: u16, ipfs_id: &str, name: &str, owner: &str) -> Result<()> {
+    let names = db_get_action_secret_names(port, ipfs_id, None)?;
+    if names.len() >= MAX_SECRETS_PER_ACTION && !names.iter().any(|n| n == name) {
+        return Err(invalid_secret(format!(
+            "An action can't have more than {MAX_SECRETS_PER_ACTION} secrets"
+        )));
+    }
+
+    let ipfs_ids = db_get_action_secret_ipfs_ids(port, owner)?;
+    if ipfs_ids.len() >= MAX_ACTIONS_PER_OWNER && !ipfs_ids.iter().any(|id| id == ipfs_id) {
+        return Err(invalid_secret(format!(
+            "An owner can't have secrets for more than {MAX_ACTIONS_PER_OWNER} actions"
+        )));
+    }
+    Ok(())
+}
+
+/// Only lets a write of a secret through once a threshold of the nodes that got the same
+/// request are about to make the same write.
+pub(crate) async fn agree_on_write(
+    tss_state: &Arc<TssState>,
+    request_id: &str,
+    node_set: &[NodeSet],
+    write: impl Serialize,
+) -> Result<()> {
+    if request_id.is_empty() {
+        return Err(invalid_secret(
+            "Writing a secret requires a request id".to_string(),
+        ));
+    }
+    let digest = serde_json::to_vec(&write).map_err(|e| conversion_err(e, None))?;
+    let digest = bytes_to_hex(keccak256(digest));
+
+    let txn_prefix = format!("action_secret_{request_id}");
+    let cm = CommsManager::new(tss_state, 0, &txn_prefix, "0", node_set).await?;
+    let agreeing = 1 + cm
+        .broadcast_and_collect::<String, String>(digest.clone())
+        .await?
+        .into_iter()
+        .filter(|(_, peer_digest)| *peer_digest == digest)
+        .count();
+
+    let threshold = tss_state.get_threshold().await;
+    if agreeing < threshold {
+        return Err(invalid_secret(format!(
+            "Only {agreeing} nodes agreed on writing the secret, but {threshold} are required"
+        )));
+    }
+    Ok(())
+}
+
+/// Stores a secret, replacing the one under the same name.
+pub(crate) fn put(
+    port: u16,
+    ipfs_id: &str,
+    name: &str,
+    owner: &str,
+    secret: &ActionSecret,
+) -> Result<()> {
+    check_limits(port, ipfs_id, name, owner)?;
+    let secret = serde_json::to_string(secret).map_err(|e| conversion_err(e, None))?;
+    db_put_action_secret(port, ipfs_id, name, owner, &secret)
+}
+
+pub(crate) fn get(port: u16, ipfs_id: &str, name: &str) -> Result<Option<ActionSecret>> {
+    db_get_action_secret(port, ipfs_id, name)?
+        .map(|secret| serde_json::from_str(&secret).map_err(|e| conversion_err(e, None)))
+        .transpose()
+}
+
+/// Returns the names of the secrets that `owner` stored for an action.
+pub(crate) fn names(port: u16, ipfs_id: &str, owner: &str) -> Result<Vec<String>> {
+    db_get_action_secret_names(port, ipfs_id, Some(owner))
+}
+
+/// Deletes a secret of `owner`. Returns whether there was one.
+pub(crate) fn delete(port: u16, ipfs_id: &str, name: &str, owner: &str) -> Result<bool> {
+    db_delete_action_secret(port, ipfs_id, name, owner)
+}
+
+fn invalid_secret(msg: String) -> Error {
+    validation_err_code(msg, EC::NodeInvalidSecret, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions(value: serde_json::Value) -> Vec<UnifiedAccessControlConditionItem> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn bound_to(ipfs_id: &str) -> serde_json::Value {
+        serde_json::json!([{
+            "conditionType": "evmBasic",
+            "contractAddress": "",
+            "standardContractType": "",
+            "chain": "ethereum",
+            "method": "",
+            "parameters": [":currentActionIpfsId"],
+            "returnValueTest": { "comparator": "=", "value": ipfs_id }
+        }])
+    }
+
+    #[test]
+    fn test_declared_owner() {
+        let owner = "0x00000000000000000000000000000000000000aB";
+        let code = format!("// My action\n\n// @lit-secrets-owner {owner}\nconsole.log(1);");
+        assert_eq!(
+            declared_owner(&code).as_deref(),
+            Some("0x00000000000000000000000000000000000000ab")
+        );
+        assert!(authorize("QmA", &code, "0x00000000000000000000000000000000000000ab").is_ok());
+        assert!(authorize("QmA", &code, "0x00000000000000000000000000000000000000ac").is_err());
+
+        // Only the leading comments are metadata
+        let late = format!("console.log(1);\n// @lit-secrets-owner {owner}");
+        assert_eq!(declared_owner(&late), None);
+        assert_eq!(declared_owner("// @lit-secrets-owner not-an-address"), None);
+        assert!(authorize("QmA", "console.log(1);", "0xab").is_err());
+    }
+
+    #[test]
+    fn test_is_bound_to() {
+        assert!(is_bound_to(&conditions(bound_to("QmA")), "QmA"));
+        assert!(!is_bound_to(&conditions(bound_to("QmA")), "QmB"));
+        assert!(!is_bound_to(&conditions(bound_to("")), ""));
+
+        // Any other condition would let someone else decrypt
+        let mut user_address = bound_to("QmA");
+        user_address[0]["parameters"] = serde_json::json!([":userAddress"]);
+        assert!(!is_bound_to(&conditions(user_address), "QmA"));
+
+        let or_condition = serde_json::json!([
+            bound_to("QmA")[0],
+            { "operator": "or" },
+            bound_to("QmB")[0],
+        ]);
+        assert!(!is_bound_to(&conditions(or_condition), "QmA"));
+    }
+}
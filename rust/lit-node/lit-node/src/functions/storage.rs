@@ -0,0 +1,198 @@
+//! Persistent key-value storage for Lit Actions.
+//!
+//! Each action gets its own namespace, made of its IPFS CID and optionally a PKP public key,
+//! so actions can neither read nor overwrite each other's values. Values are time-lock encrypted
+//! to the network's BLS key, which survives epoch changes, under an identity bound to their
+//! namespace and key so that rows can't be swapped around on disk. Decrypting a value takes
+//! the network's signature over that identity, so no single node can read it on its own.
+
+use anyhow::{Result, bail};
+use blsful::{Bls12381G2Impl, PublicKey, Signature, TimeCryptCiphertext};
+use ethers::utils::keccak256;
+use lit_core::utils::binary::bytes_to_hex;
+
+use crate::siwe_db::db::{
+    db_delete_action_storage, db_get_action_storage, db_get_action_storage_size,
+    db_put_action_storage,
+};
+
+pub(crate) const MAX_KEY_LENGTH: usize = 256;
+
+/// Returns the namespace of the storage of an action, optionally narrowed down to a PKP.
+pub(crate) fn namespace(action_ipfs_id: Option<&str>, pkp_public_key: &str) -> Result<String> {
+    let Some(action_ipfs_id) = action_ipfs_id.filter(|id| !id.is_empty()) else {
+        bail!("Storage is only available to actions with an IPFS CID");
+    };
+    let pkp_public_key = pkp_public_key.trim_start_matches("0x").to_lowercase();
+    Ok(match pkp_public_key.is_empty() {
+        true => action_ipfs_id.to_string(),
+        false => format!("{action_ipfs_id}/{pkp_public_key}"),
+    })
+}
+
+pub(crate) fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        bail!("Storage keys must be between 1 and {MAX_KEY_LENGTH} bytes long");
+    }
+    Ok(())
+}
+
+/// The identity a value is encrypted under, and which the network signs to decrypt it.
+pub(crate) fn identity(namespace: &str, key: &str) -> Result<Vec<u8>> {
+    let digest = bytes_to_hex(keccak256(serde_json::to_vec(&(namespace, key))?));
+    Ok(format!("lit-action-storage://{digest}").into_bytes())
+}
+
+/// Returns the encrypted value this node holds for a key.
+pub(crate) fn get(port: u16, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+    Ok(db_get_action_storage(port, namespace, key)?)
+}
+
+/// Fails if storing `size` bytes under a key would take the namespace over `max_bytes`.
+pub(crate) fn check_quota(
+    port: u16,
+    namespace: &str,
+    key: &str,
+    size: usize,
+    max_bytes: usize,
+) -> Result<()> {
+    let used = db_get_action_storage_size(port, namespace, key)? as usize;
+    if used + size > max_bytes {
+        bail!(
+            "Storage quota exceeded. Max size is {} bytes, of which {} are used by other keys",
+            max_bytes,
+            used
+        );
+    }
+    Ok(())
+}
+
+/// Stores a value, unless it would take the namespace over `max_bytes`.
+/// Returns the number of bytes the value counts towards the quota.
+pub(crate) fn set(
+    port: u16,
+    network_pubkey: &PublicKey<Bls12381G2Impl>,
+    namespace: &str,
+    key: &str,
+    value: &str,
+    max_bytes: usize,
+) -> Result<usize> {
+    let size = key.len() + value.len();
+    check_quota(port, namespace, key, size, max_bytes)?;
+
+    let ciphertext = encrypt(network_pubkey, namespace, key, value)?;
+    db_put_action_storage(port, namespace, key, &ciphertext, size as i64)?;
+    Ok(size)
+}
+
+pub(crate) fn delete(port: u16, namespace: &str, key: &str) -> Result<bool> {
+    Ok(db_delete_action_storage(port, namespace, key)?)
+}
+
+fn encrypt(
+    network_pubkey: &PublicKey<Bls12381G2Impl>,
+    namespace: &str,
+    key: &str,
+    value: &str,
+) -> Result<Vec<u8>> {
+    let identity = identity(namespace, key)?;
+    let ciphertext =
+        match lit_sdk::encryption::encrypt_time_lock(network_pubkey, value.as_bytes(), &identity) {
+            Ok(ciphertext) => ciphertext,
+            Err(e) => bail!("Failed to encrypt storage value: {:?}", e),
+        };
+    Ok(serde_bare::to_vec(&ciphertext)?)
+}
+
+/// Decrypts a value with the network's signature over its identity.
+pub(crate) fn decrypt(
+    network_pubkey: &PublicKey<Bls12381G2Impl>,
+    signature: &Signature<Bls12381G2Impl>,
+    namespace: &str,
+    key: &str,
+    ciphertext: &[u8],
+) -> Result<String> {
+    let identity = identity(namespace, key)?;
+    let ciphertext: TimeCryptCiphertext<Bls12381G2Impl> = serde_bare::from_slice(ciphertext)?;
+    let plaintext = match lit_sdk::encryption::verify_and_decrypt(
+        network_pubkey,
+        &identity,
+        &ciphertext,
+        signature,
+    ) {
+        Ok(plaintext) => plaintext,
+        Err(e) => bail!("Stored value for key {key} can't be decrypted: {:?}", e),
+    };
+    Ok(String::from_utf8(plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blsful::{SecretKey, SignatureSchemes};
+
+    #[test]
+    fn test_namespace() {
+        assert_eq!(namespace(Some("QmA"), "").unwrap(), "QmA");
+        assert_eq!(namespace(Some("QmA"), "0x04AB").unwrap(), "QmA/04ab");
+        assert!(namespace(None, "04ab").is_err());
+        assert!(namespace(Some(""), "").is_err());
+    }
+
+    #[test]
+    fn test_encryption_is_bound_to_key() {
+        let network_key = SecretKey::<Bls12381G2Impl>::new();
+        let network_pubkey = network_key.public_key();
+        let sign = |namespace: &str, key: &str| {
+            network_key
+                .sign(
+                    SignatureSchemes::ProofOfPossession,
+                    &identity(namespace, key).unwrap(),
+                )
+                .unwrap()
+        };
+        let ciphertext = encrypt(&network_pubkey, "QmA", "k1", "secret").unwrap();
+
+        assert_eq!(
+            decrypt(
+                &network_pubkey,
+                &sign("QmA", "k1"),
+                "QmA",
+                "k1",
+                &ciphertext
+            )
+            .unwrap(),
+            "secret"
+        );
+        assert!(
+            decrypt(
+                &network_pubkey,
+                &sign("QmA", "k2"),
+                "QmA",
+                "k2",
+                &ciphertext
+            )
+            .is_err()
+        );
+        assert!(
+            decrypt(
+                &network_pubkey,
+                &sign("QmB", "k1"),
+                "QmB",
+                "k1",
+                &ciphertext
+            )
+            .is_err()
+        );
+        assert!(
+            decrypt(
+                &network_pubkey,
+                &sign("QmA", "k2"),
+                "QmA",
+                "k1",
+                &ciphertext
+            )
+            .is_err()
+        );
+    }
+}
@@ -13,6 +13,7 @@ use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use super::action_client::{Client, ClientBuilder, ExecutionOptions, ExecutionState};
 use super::{ActionJob, ActionStore, ActionWorker, JobStatus};
+use crate::access_control::cache::RpcResultCache;
 use crate::models;
 use crate::utils::web::default_http_client;
 
@@ -320,6 +321,7 @@ async fn call_child(server: TestServer) {
         ),
         ipfs_cache: Some(ipfs_cache),
         http_client: Some(http_client),
+        rpc_cache: Some(RpcResultCache::new()),
         ..Default::default()
     };
 
@@ -579,7 +581,7 @@ async fn async_actions(server: TestServer) {
         tokio::time::sleep(Duration::from_secs(2)).await;
         Ok(())
     };
-    ActionWorker::new(store.clone(), Default::default())
+    ActionWorker::new(store.clone(), Default::default(), None)
         .start_with_shutdown(signal)
         .await
         .unwrap();
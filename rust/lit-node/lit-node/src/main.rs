@@ -5,17 +5,20 @@ extern crate dotenv;
 extern crate rocket;
 extern crate clap;
 
+use crate::access_control::cache::RpcResultCache;
 use crate::error::{EC, unexpected_err_code};
 use crate::models::AuthContextCacheExpiry;
 use crate::p2p_comms::web::chatter_server::launch_chatter_server;
 use crate::payment::delegated_usage::DelegatedUsageDB;
 use crate::peers::PeerState;
 use crate::tasks::chatter_sender::chatter_sender_worker;
+use crate::tasks::frost_nonce_manager::models::FrostNonceManager;
 use crate::tasks::fsm::fsm_worker::CounterBasedFSMWorkerMetadata;
 use crate::tasks::presign_manager::models::PresignManager;
 use crate::tss::common::{
     restore::RestoreState, traits::fsm_worker_metadata::FSMWorkerMetadata, tss_state,
 };
+use crate::tss::musig2::MuSig2NonceCache;
 use config::chain::ChainDataConfigManager;
 use error::{Result, unexpected_err};
 use ethers::types::U256;
@@ -203,6 +206,7 @@ pub fn main() {
     );
 
     siwe_db::db::db_initial_setup(port).expect("Initial SQLite db setup failed");
+    auth::revocation::load(port).expect("failed to load session revocations");
 
     // Since we use the same settings everywhere, we just create one and reuse it. From the docs.rs
     // The Client holds a connection pool internally, so it is advised that you create one and reuse it.
@@ -228,6 +232,7 @@ pub fn main() {
     )));
 
     let (ps_tx, ps_rx) = flume::unbounded();
+    let (frost_nonce_tx, frost_nonce_rx) = flume::unbounded();
 
     let peer_state = Arc::new(
         local_rt
@@ -237,6 +242,7 @@ pub fn main() {
                 Arc::new(cfg.clone()),
                 chain_data_manager.clone(),
                 ps_tx.clone(),
+                frost_nonce_tx.clone(),
                 peer_checker_tx.clone(),
             ))
             .expect("failed to create PeerState"),
@@ -272,10 +278,14 @@ pub fn main() {
         .max_capacity(1024 * 1024 * 1024)
         .build();
 
+    let rpc_cache = RpcResultCache::new();
+
     let allowlist_cache = Arc::new(models::AllowlistCache {
         entries: RwLock::new(HashMap::new()),
     });
 
+    let musig2_nonce_cache = Arc::new(MuSig2NonceCache::new());
+
     let restore_state = RestoreState::new();
     let restore_state = Arc::new(restore_state);
 
@@ -287,18 +297,28 @@ pub fn main() {
 
     let (quit_tx, quit_rx) = broadcast::channel(1);
     let quit_rx_ps = quit_tx.subscribe();
+    let quit_rx_frost_nonce = quit_tx.subscribe();
     let quit_rx_grpc_server = quit_tx.subscribe();
     let peer_state_clone = tss_state.peer_state.clone();
     let presign_cfg = cfg.clone();
     let (file_tx, file_rx) = channel(1);
     let tss_state_comms = tss_state.clone();
     let tss_state_ps = tss_state.clone();
+    let tss_state_frost_nonce = tss_state.clone();
+    let frost_nonce_cfg = cfg.clone();
     let fsm_worker_metadata_grpc = fsm_worker_metadata.clone();
 
     local_rt.spawn(async move {
         let mut presign_manager = PresignManager::new(ps_rx, ps_tx, tss_state_ps);
         presign_manager.listen(quit_rx_ps, presign_cfg).await;
     });
+    local_rt.spawn(async move {
+        let mut frost_nonce_manager =
+            FrostNonceManager::new(frost_nonce_rx, frost_nonce_tx, tss_state_frost_nonce);
+        frost_nonce_manager
+            .listen(quit_rx_frost_nonce, frost_nonce_cfg)
+            .await;
+    });
     local_rt.spawn(chatter_sender_worker(
         quit_rx,
         cfg.clone(),
@@ -331,10 +351,12 @@ pub fn main() {
         rx_round_manager,
         fsm_worker_metadata.clone(),
         payment_tracker.clone(),
+        delegation_usage_db.clone(),
         ipfs_cache.clone(),
         action_store.clone(),
         client_state.clone(),
         http_client.clone(),
+        rpc_cache.clone(),
         peer_checker_tx.clone(),
         peer_checker_rx,
     )
@@ -376,6 +398,7 @@ pub fn main() {
         let payment_tracker = payment_tracker.clone();
         let file_tx_clone = file_tx.clone();
         let ipfs_cache = ipfs_cache.clone();
+        let rpc_cache = rpc_cache.clone();
         let action_store = action_store.clone();
         let client_state = client_state.clone();
 
@@ -421,6 +444,7 @@ pub fn main() {
                 .manage(ipfs_cache)
                 .manage(action_store)
                 .manage(http_cache_clone)
+                .manage(rpc_cache)
                 .manage(GrpcClientPool::<tonic::transport::Channel>::new(
                     cfg.load_full(),
                 ))
@@ -431,7 +455,8 @@ pub fn main() {
                 .manage(tss_state)
                 .manage(client_state)
                 .manage(restore_state)
-                .manage(payment_tracker);
+                .manage(payment_tracker)
+                .manage(musig2_nonce_cache);
 
             l
         })
@@ -57,6 +57,34 @@ pub mod tss {
             }
         }
     }
+
+    pub enum FrostNonceMetrics {
+        Generate,
+        Store,
+        Use,
+    }
+
+    impl LitMetric for FrostNonceMetrics {
+        fn get_meter(&self) -> &str {
+            "lit.tss"
+        }
+        fn get_description(&self) -> &str {
+            ""
+        }
+        fn get_unit(&self) -> &str {
+            ""
+        }
+        fn get_namespace(&self) -> &str {
+            "frost_nonce"
+        }
+        fn get_name(&self) -> &str {
+            match self {
+                FrostNonceMetrics::Generate => "generate",
+                FrostNonceMetrics::Store => "store",
+                FrostNonceMetrics::Use => "use",
+            }
+        }
+    }
 }
 
 pub mod dkg {
@@ -125,3 +153,36 @@ pub mod complaint {
         }
     }
 }
+
+pub mod access_control {
+    use lit_observability::metrics::LitMetric;
+
+    // Attributes
+    pub const ATTRIBUTE_CHAIN: &str = "chain";
+
+    pub enum AccessControlCacheMetrics {
+        Hit,
+        Miss,
+    }
+
+    impl LitMetric for AccessControlCacheMetrics {
+        fn get_meter(&self) -> &str {
+            "lit.access_control"
+        }
+        fn get_description(&self) -> &str {
+            ""
+        }
+        fn get_unit(&self) -> &str {
+            ""
+        }
+        fn get_namespace(&self) -> &str {
+            "rpc_cache"
+        }
+        fn get_name(&self) -> &str {
+            match self {
+                AccessControlCacheMetrics::Hit => "hit",
+                AccessControlCacheMetrics::Miss => "miss",
+            }
+        }
+    }
+}
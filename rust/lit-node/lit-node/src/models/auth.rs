@@ -14,6 +14,22 @@ pub struct SessionKeySignedMessageV2 {
     pub node_address: String,
     #[serde(deserialize_with = "from_str_to_u256")]
     pub max_price: U256,
+    /// Re-delegations from the session key the capabilities were issued to, down to
+    /// `session_key`. Empty unless this session key was delegated to by another one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegations: Vec<JsonAuthSig>,
+}
+
+/// Signed by a session key to re-delegate an attenuated subset of its capabilities to
+/// another session key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionKeyDelegation {
+    pub delegator: String,
+    pub delegatee: String,
+    pub resource_ability_requests: Vec<LitResourceAbilityRequest>,
+    pub issued_at: String,
+    pub expiration: String,
 }
 
 fn from_str_to_u256<'de, D>(deserializer: D) -> Result<U256, D::Error>
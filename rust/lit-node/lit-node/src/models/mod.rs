@@ -1,6 +1,8 @@
 use ethers::types::{Address, U256};
 
 use crate::functions::action_client::ExecutionState;
+use crate::functions::schedule::{ScheduleTrigger, ScheduledJob};
+use crate::functions::secrets::ActionSecret;
 use crate::functions::{JobId, JobStatus};
 use iri_string::spec::UriSpec;
 use iri_string::types::RiString;
@@ -123,6 +125,7 @@ pub struct DenoExecutionEnv {
     pub cfg: Arc<LitConfig>,
     pub ipfs_cache: Option<Cache<String, Arc<String>>>,
     pub http_client: Option<reqwest::Client>,
+    pub rpc_cache: Option<crate::access_control::cache::RpcResultCache>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -184,6 +187,67 @@ pub enum JsonJobResult {
     },
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonActionScheduleRequest {
+    pub ipfs_id: String,
+    pub js_params: Option<Value>,
+    pub trigger: ScheduleTrigger,
+    pub auth_sig: AuthSigItem,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonActionSchedulesRequest {
+    pub auth_sig: AuthSigItem,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonActionScheduleIdRequest {
+    pub schedule_id: String,
+    pub auth_sig: AuthSigItem,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonActionSecretRequest {
+    pub ipfs_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub secret: ActionSecret,
+    pub auth_sig: AuthSigItem,
+    #[serde(default)]
+    pub node_set: Vec<NodeSet>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonActionSecretsRequest {
+    pub ipfs_id: String,
+    pub auth_sig: AuthSigItem,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonActionSecretNameRequest {
+    pub ipfs_id: String,
+    pub name: String,
+    pub auth_sig: AuthSigItem,
+    #[serde(default)]
+    pub node_set: Vec<NodeSet>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonScheduledJobResponse {
+    #[serde(flatten)]
+    pub job: ScheduledJob,
+    // The status of jobs that were cleaned up is no longer known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<JobStatus>,
+}
+
 fn default_epoch() -> u64 {
     0 // this will indicate to the nodes that a valid value isn't coming from the SDK.
 }
@@ -4,6 +4,9 @@ use crate::{config::chain::ChainDataConfigManager, error::unexpected_err_code};
 use ethers::types::{Address, I256};
 use lit_node_core::{DynamicPaymentItem, LitActionPriceComponent};
 use serde::{Deserialize, Serialize};
+
+const BYTES_PER_MEGABYTE: u128 = 1024 * 1024;
+
 // Notes:
 // - Per Node Sync, Runtime length, code length & response length are not evaluated.  They have not been removed as they line up with contract enums, and may be evaluated in the future.
 
@@ -102,45 +105,139 @@ impl DynamicPayment {
 
     #[doc = "Adds a single item to the dynamic payment struct, where the quantity is 1."]
     pub fn add(&mut self, component: LitActionPriceComponent, quantity: u64) -> Result<(), Error> {
+        let price = match self.next_price(component)? {
+            Some(price) => price,
+            None => return Ok(()),
+        };
+
+        trace!("Adding item to dynamic payment: {:?}", component);
+
+        self.items.push(DynamicPaymentItem {
+            component,
+            quantity,
+            price,
+        });
+        self.running_total += price;
+        Ok(())
+    }
+
+    #[doc = "Adds an item whose price scales with its quantity, which is counted in bytes for components priced per megabyte and in the unit of measurement otherwise."]
+    pub fn add_quantity(
+        &mut self,
+        component: LitActionPriceComponent,
+        quantity: u64,
+    ) -> Result<(), Error> {
+        let price = match self.price_of(component, quantity)? {
+            Some(price) => price,
+            None => return Ok(()),
+        };
+
+        trace!(
+            "Adding {} of item to dynamic payment: {:?}",
+            quantity, component
+        );
+
+        self.items.push(DynamicPaymentItem {
+            component,
+            quantity,
+            price,
+        });
+        self.running_total += price;
+        Ok(())
+    }
+
+    #[doc = "Checks that an item of the given component could be added without exceeding the spending limit, without adding it."]
+    pub fn check(&self, component: LitActionPriceComponent) -> Result<(), Error> {
+        self.next_price(component).map(|_| ())
+    }
+
+    #[doc = "Checks that `quantity` of the given component could be added without exceeding the spending limit, without adding it."]
+    pub fn check_quantity(
+        &self,
+        component: LitActionPriceComponent,
+        quantity: u64,
+    ) -> Result<(), Error> {
+        self.price_of(component, quantity).map(|_| ())
+    }
+
+    #[doc = "Returns the price of the next item of the given component, or None if payment is disabled."]
+    fn next_price(&self, component: LitActionPriceComponent) -> Result<Option<u64>, Error> {
         if !self.payment_enabled {
-            return Ok(());
+            return Ok(None);
         }
 
         debug!("Adding item to dynamic payment: {:?}", component);
         trace!("Dynamic payment configs: {:?}", self.configs);
-        let config = self.configs.iter().find(|c| c.price_component == component);
-        if let Some(config) = config {
-            let price = config.price * self.price_multiplier;
-            if (self.running_total + price) > self.spending_limit {
-                return Err(unexpected_err_code(
+        let config = self.config(component)?;
+        self.within_spending_limit(component, config.price * self.price_multiplier)
+            .map(Some)
+    }
+
+    #[doc = "Returns the price of `quantity` of the given component, rounded up, or None if payment is disabled."]
+    fn price_of(
+        &self,
+        component: LitActionPriceComponent,
+        quantity: u64,
+    ) -> Result<Option<u64>, Error> {
+        if !self.payment_enabled {
+            return Ok(None);
+        }
+
+        let config = self.config(component)?;
+        let unit_price = u128::from(config.price) * u128::from(self.price_multiplier);
+        let price = match config.price_measurement {
+            NodePriceMeasurement::PerMegabyte => {
+                (unit_price * u128::from(quantity)).div_ceil(BYTES_PER_MEGABYTE)
+            }
+            NodePriceMeasurement::PerSecond | NodePriceMeasurement::PerCount => {
+                unit_price * u128::from(quantity)
+            }
+        };
+        let price = u64::try_from(price).map_err(|_| {
+            unexpected_err_code(
+                format!(
+                    "Action aborted as the price of {} '{:?}' overflows.",
+                    quantity, component
+                ),
+                EC::PaymentFailed,
+                None,
+            )
+        })?;
+        self.within_spending_limit(component, price).map(Some)
+    }
+
+    fn config(&self, component: LitActionPriceComponent) -> Result<&LitActionPriceConfig, Error> {
+        self.configs
+            .iter()
+            .find(|c| c.price_component == component)
+            .ok_or_else(|| {
+                unexpected_err_code(
                     format!(
-                        "Action aborted as next execution of '{:?}' would exceed wallet balance.",
+                        "Action aborted as pricing component '{:?}' was not found.",
                         component
                     ),
                     EC::PaymentFailed,
                     None,
-                ));
-            }
+                )
+            })
+    }
 
-            trace!("Adding item to dynamic payment: {:?}", component);
-
-            self.items.push(DynamicPaymentItem {
-                component,
-                quantity,
-                price,
-            });
-            self.running_total += price;
-            Ok(())
-        } else {
-            Err(unexpected_err_code(
+    fn within_spending_limit(
+        &self,
+        component: LitActionPriceComponent,
+        price: u64,
+    ) -> Result<u64, Error> {
+        if (self.running_total + price) > self.spending_limit {
+            return Err(unexpected_err_code(
                 format!(
-                    "Action aborted as pricing component '{:?}' was not found.",
+                    "Action aborted as next execution of '{:?}' would exceed wallet balance.",
                     component
                 ),
                 EC::PaymentFailed,
                 None,
-            ))
+            ));
         }
+        Ok(price)
     }
 
     #[doc = "Adds multiple items to the dynamic payment struct, where the quantity is 1. This is used for operations that require multiple items to be added at once."]
@@ -9,6 +9,7 @@ pub enum PayedEndpoint {
     LitAction,
     PkpSign,
     SignSessionKey,
+    ExplainAccessControl,
 }
 
 impl FromStr for PayedEndpoint {
@@ -19,6 +20,7 @@ impl FromStr for PayedEndpoint {
             "lit_action" => Ok(PayedEndpoint::LitAction),
             "pkp_sign" => Ok(PayedEndpoint::PkpSign),
             "sign_session_key" => Ok(PayedEndpoint::SignSessionKey),
+            "explain_access_control" => Ok(PayedEndpoint::ExplainAccessControl),
             _ => Err(parser_err_code(
                 "",
                 EC::NodeSerializationError,
@@ -35,6 +37,7 @@ impl PayedEndpoint {
             PayedEndpoint::LitAction => "lit_action",
             PayedEndpoint::PkpSign => "pkp_sign",
             PayedEndpoint::SignSessionKey => "sign_session_key",
+            PayedEndpoint::ExplainAccessControl => "explain_access_control",
         }
     }
 
@@ -44,6 +47,7 @@ impl PayedEndpoint {
             U256::from(u8::from(&PayedEndpoint::LitAction)),
             U256::from(u8::from(&PayedEndpoint::PkpSign)),
             U256::from(u8::from(&PayedEndpoint::SignSessionKey)),
+            U256::from(u8::from(&PayedEndpoint::ExplainAccessControl)),
         ]
     }
 }
@@ -55,6 +59,7 @@ impl From<&PayedEndpoint> for u8 {
             PayedEndpoint::LitAction => 1,
             PayedEndpoint::PkpSign => 2,
             PayedEndpoint::SignSessionKey => 3,
+            PayedEndpoint::ExplainAccessControl => 4,
         }
     }
 }
@@ -35,6 +35,7 @@ pub struct PaymentDelegationAllowedScopes {
     pub lit_action: bool,
     pub pkp_sign: bool,
     pub sign_session_key: bool,
+    pub explain_access_control: bool,
 }
 
 #[derive(Debug)]
@@ -50,6 +51,7 @@ impl PaymentDelegationAllowedScopes {
             PayedEndpoint::LitAction => self.lit_action = true,
             PayedEndpoint::PkpSign => self.pkp_sign = true,
             PayedEndpoint::SignSessionKey => self.sign_session_key = true,
+            PayedEndpoint::ExplainAccessControl => self.explain_access_control = true,
         }
     }
 
@@ -59,6 +61,7 @@ impl PaymentDelegationAllowedScopes {
             PayedEndpoint::LitAction => self.lit_action,
             PayedEndpoint::PkpSign => self.pkp_sign,
             PayedEndpoint::SignSessionKey => self.sign_session_key,
+            PayedEndpoint::ExplainAccessControl => self.explain_access_control,
         }
     }
 }
@@ -1,6 +1,7 @@
 use crate::payment::{batches::Batches, payed_endpoint::PayedEndpoint};
 use crate::version::{DataVersionReader, DataVersionWriter};
 use sdd::AtomicShared;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Default, Copy, Clone)]
@@ -26,7 +27,8 @@ impl NodeCapacityConfig {
     pub fn get_op_capacity(&self, endpoint: &PayedEndpoint) -> u64 {
         match endpoint {
             PayedEndpoint::PkpSign => self.global_max_capacity / self.pkp_sign_max_concurrency,
-            PayedEndpoint::EncryptionSign => {
+            // explaining runs the same condition checks as a decryption, so it shares its capacity
+            PayedEndpoint::EncryptionSign | PayedEndpoint::ExplainAccessControl => {
                 self.global_max_capacity / self.enc_sign_max_concurrency
             }
             PayedEndpoint::LitAction => self.global_max_capacity / self.lit_action_max_concurrency,
@@ -65,6 +67,17 @@ impl PaymentTracker {
         );
     }
 
+    /// Registers usage for a request that may be dropped before it completes, e.g. when the
+    /// client disconnects from a streamed response. The usage is deregistered when the returned
+    /// guard is dropped.
+    pub fn register_usage_guard(self: &Arc<Self>, endpoint: PayedEndpoint) -> UsageGuard {
+        self.register_usage(&endpoint);
+        UsageGuard {
+            payment_tracker: self.clone(),
+            endpoint,
+        }
+    }
+
     pub fn get_usage_percentage(&self) -> u64 {
         let used_capacity = self.used_capacity.load(Ordering::SeqCst);
         trace!("Payment Tracker: Used capacity: {:?}", used_capacity);
@@ -89,3 +102,15 @@ impl PaymentTracker {
         DataVersionWriter::store(&self.node_capacity_config, config);
     }
 }
+
+/// Deregisters the usage of a request when dropped.
+pub struct UsageGuard {
+    payment_tracker: Arc<PaymentTracker>,
+    endpoint: PayedEndpoint,
+}
+
+impl Drop for UsageGuard {
+    fn drop(&mut self) {
+        self.payment_tracker.deregister_usage(&self.endpoint);
+    }
+}
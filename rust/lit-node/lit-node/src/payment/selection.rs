@@ -80,6 +80,53 @@ pub async fn get_payment_method(
     })
 }
 
+/// Resolves the payer of a request that isn't made by a client, like a scheduled Lit Action.
+/// Without a session sig to carry a delegation, only a payer that delegated to the user in the
+/// Payment DB can cover it.
+pub async fn get_delegated_payment_method(
+    user_address: &Address,
+    endpoint: PayedEndpoint,
+    threshold: usize,
+    payment_tracker: &Arc<PaymentTracker>,
+    delegation_usage_db: &DelegatedUsageDB,
+    cfg: &LitConfig,
+) -> Result<PendingPayment> {
+    let usage = payment_tracker.get_usage_percentage();
+    if usage >= 100 {
+        return Err(unexpected_err("Node usage is above 100%", None));
+    }
+
+    let endpoint_price = fetch_current_price(cfg, usage, &endpoint).await?;
+    let ledger = get_ledger_contract(cfg).await?;
+    let required_balance =
+        fetch_required_balance(cfg, &endpoint, &endpoint_price, threshold).await?;
+
+    match check_for_payment_db(
+        user_address,
+        None,
+        convert_price_to_i256(required_balance)?,
+        threshold,
+        payment_tracker,
+        delegation_usage_db,
+        &ledger,
+        cfg,
+    )
+    .await?
+    {
+        Some((payer, spending_limit)) => Ok(PendingPayment {
+            payer,
+            price: convert_price_to_i256(endpoint_price)?,
+            spending_limit,
+        }),
+        None => Err(generic_err_code(
+            format!("No payer in the Payment DB can cover {user_address:#x}"),
+            EC::PaymentFailed,
+            None,
+        )
+        .add_source_to_details()),
+    }
+}
+
 pub async fn check_payer_has_funds(
     ledger: &Ledger<Provider<Http>>,
     user_address: &Address,
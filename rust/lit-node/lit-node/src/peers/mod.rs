@@ -10,6 +10,7 @@ use crate::error::{EC, Result, unexpected_err, unexpected_err_code};
 use crate::models::PeerValidator;
 use crate::p2p_comms::web::chatter_server::chatter::chatter_service_client::ChatterServiceClient;
 use crate::peers::peer_state::models::SimplePeerCollection;
+use crate::tasks::frost_nonce_manager::models::FrostNonceMessage;
 use crate::tasks::peer_checker::PeerCheckerMessage;
 use crate::tasks::presign_manager::models::PresignMessage;
 use crate::tss::common::tss_state::TssState;
@@ -63,6 +64,7 @@ pub struct PeerState {
     pub wallet_keys: EthWalletKeys,
     pub lit_config: Arc<ReloadableLitConfig>,
     pub ps_tx: flume::Sender<PresignMessage>,
+    pub frost_nonce_tx: flume::Sender<FrostNonceMessage>,
     pub tss_state: Weak<TssState>,
     pub auto_join: bool,
     pub peer_checker_tx: flume::Sender<PeerCheckerMessage>,
@@ -75,6 +77,7 @@ impl PeerState {
         lit_config: Arc<ReloadableLitConfig>,
         chain_data_config_manager: Arc<ChainDataConfigManager>,
         ps_tx: flume::Sender<PresignMessage>,
+        frost_nonce_tx: flume::Sender<FrostNonceMessage>,
         peer_checker_tx: flume::Sender<PeerCheckerMessage>,
     ) -> Result<PeerState> {
         let cfg = lit_config.load_full();
@@ -145,6 +148,7 @@ impl PeerState {
             wallet_keys: attested_wallet_keys,
             lit_config,
             ps_tx,
+            frost_nonce_tx,
             client_grpc_channels: GrpcClientPool::new(cfg.clone()),
             tss_state: Weak::new(),
             auto_join: true,
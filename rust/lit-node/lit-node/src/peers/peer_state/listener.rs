@@ -1,6 +1,7 @@
 use super::super::PeerState;
 use super::models::NetworkState;
 use crate::error::{EC, Result, unexpected_err_code};
+use crate::tasks::frost_nonce_manager::models::FrostNonceMessage;
 use crate::tasks::presign_manager::models::PresignMessage;
 use ethers::providers::StreamExt;
 use lit_blockchain::contracts::staking::StakingEvents;
@@ -130,6 +131,7 @@ impl PeerState {
                                     StakingEvents::ClearOfflinePhaseDataFilter(data_type) => {
                                         debug!("ClearOfflinePhaseData event: {:?}", data_type);
                                         let _r = self.ps_tx.send_async(PresignMessage::Clear).await;
+                                        let _r = self.frost_nonce_tx.send_async(FrostNonceMessage::Clear).await;
                                     }
                                     _ => {}
 
@@ -9,3 +9,4 @@ pub const STYTCH_JWT_AUTH_FACTOR_EMAIL_OTP: u32 = 10;
 pub const STYTCH_JWT_AUTH_FACTOR_SMS_OTP: u32 = 11;
 pub const STYTCH_JWT_AUTH_FACTOR_WHATS_APP_OTP: u32 = 12;
 pub const STYTCH_JWT_AUTH_FACTOR_TOTP: u32 = 13;
+pub const OIDC_JWT_AUTH_METHOD_TYPE_ID: u32 = 14;
@@ -25,13 +25,14 @@ pub mod auth_method_verifier;
 pub mod constants;
 mod discord;
 mod google;
+pub mod oidc;
 pub mod stytch;
 pub mod wallet;
 pub mod webauthn;
 
 use self::constants::{
     APPLE_JWT_AUTH_METHOD_TYPE_ID, DISCORD_AUTH_METHOD_TYPE_ID, GOOGLE_AUTH_METHOD_TYPE_ID,
-    GOOGLE_JWT_AUTH_METHOD_TYPE_ID, STYTCH_JWT_AUTH_FACTOR_EMAIL_OTP,
+    GOOGLE_JWT_AUTH_METHOD_TYPE_ID, OIDC_JWT_AUTH_METHOD_TYPE_ID, STYTCH_JWT_AUTH_FACTOR_EMAIL_OTP,
     STYTCH_JWT_AUTH_FACTOR_SMS_OTP, STYTCH_JWT_AUTH_FACTOR_TOTP,
     STYTCH_JWT_AUTH_FACTOR_WHATS_APP_OTP, STYTCH_JWT_AUTH_METHOD_TYPE_ID,
     WALLET_AUTH_METHOD_TYPE_ID, WEBAUTHN_AUTH_METHOD_TYPE_ID,
@@ -40,6 +41,7 @@ use self::constants::{
 use self::apple::AppleJwtAuthMethodVerifier;
 use self::discord::DiscordAuthMethodVerifier;
 use self::google::{GoogleAuthMethodVerifier, GoogleJwtAuthMethodVerifier};
+use self::oidc::OidcJwtAuthMethodVerifier;
 use self::stytch::StytchJWTAuthMethodVerifier;
 use self::wallet::WalletAuthMethodVerifier;
 use self::webauthn::WebauthnAuthMethodVerifier;
@@ -155,6 +157,13 @@ pub async fn verify_auth_method(
                 .verify(&auth_method.access_token, http_client)
                 .await?
         }
+
+        OIDC_JWT_AUTH_METHOD_TYPE_ID => {
+            let verifier = OidcJwtAuthMethodVerifier { cfg: &config };
+            verifier
+                .verify(&auth_method.access_token, http_client)
+                .await?
+        }
         _ => {
             return Err(validation_err(
                 format!(
@@ -275,6 +284,13 @@ pub async fn verify_auth_method_for_claim(
                 .await?
         }
 
+        OIDC_JWT_AUTH_METHOD_TYPE_ID => {
+            let verifier = OidcJwtAuthMethodVerifier { cfg: &config };
+            verifier
+                .verify(&auth_method.access_token, http_client)
+                .await?
+        }
+
         _ => {
             return Err(validation_err(
                 format!(
@@ -302,6 +318,7 @@ pub fn serialize_auth_context_for_checking_against_contract_data(
         || auth_method.auth_method_type == STYTCH_JWT_AUTH_FACTOR_SMS_OTP
         || auth_method.auth_method_type == STYTCH_JWT_AUTH_FACTOR_WHATS_APP_OTP
         || auth_method.auth_method_type == STYTCH_JWT_AUTH_FACTOR_TOTP
+        || auth_method.auth_method_type == OIDC_JWT_AUTH_METHOD_TYPE_ID
     {
         let serialized = format!("{}:{}", auth_method.user_id, auth_method.app_id);
         trace!("Serializing auth context: {}", serialized);
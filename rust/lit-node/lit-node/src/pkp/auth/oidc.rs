@@ -0,0 +1,404 @@
+use crate::error::{
+    self, Result, Unexpected, conversion_err, parser_err, unexpected_err, validation_err,
+};
+use crate::models;
+use crate::pkp::auth::constants::OIDC_JWT_AUTH_METHOD_TYPE_ID;
+use chrono::Utc;
+use lit_core::config::LitConfig;
+use lit_node_common::config::LitNodeConfig;
+use moka::future::Cache;
+use rsa::PublicKey;
+use rsa::{BigUint, RsaPublicKey};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{Instrument, debug_span};
+
+use super::AuthMethodVerifier;
+
+/// How long a provider's JWKS is used before it is fetched again.
+const JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+/// How soon a JWKS may be fetched again when a token is signed by a key it doesn't contain.
+/// Keeps tokens with made up key ids from hammering the provider.
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const JWKS_CACHE_CAPACITY: u64 = 1_000;
+/// Clock skew tolerated when checking `exp` and `nbf`.
+const CLOCK_SKEW_SECS: i64 = 60;
+
+const JWT_RSA_ALG_VAL: &str = "RS256";
+
+static JWKS_CACHE: LazyLock<Cache<String, CachedJwks>> = LazyLock::new(|| {
+    Cache::builder()
+        .max_capacity(JWKS_CACHE_CAPACITY)
+        .time_to_live(JWKS_TTL)
+        .build()
+});
+
+#[derive(Clone)]
+struct CachedJwks {
+    keys: Arc<Vec<Value>>,
+    fetched_at: Instant,
+}
+
+/// An OpenID Connect provider whose ID tokens are accepted as an auth method.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcProvider {
+    /// The `iss` claim of the provider's tokens.
+    pub issuer: String,
+    /// The `aud` claim the tokens must be issued for.
+    pub audience: String,
+    /// Where the provider publishes its signing keys.
+    pub jwks_uri: String,
+}
+
+/// Verifies ID tokens of the OpenID Connect providers registered in the node config.
+///
+/// The auth method ID is derived from the token's `iss` and `sub`, so the same user of the
+/// same provider always maps to the same auth method.
+pub struct OidcJwtAuthMethodVerifier<'a> {
+    pub cfg: &'a LitConfig,
+}
+
+#[async_trait::async_trait]
+impl AuthMethodVerifier for OidcJwtAuthMethodVerifier<'_> {
+    async fn verify(
+        &self,
+        access_token: &str,
+        http_client: reqwest::Client,
+    ) -> error::Result<models::AuthMethodResponse> {
+        let providers = oidc_providers(self.cfg)?;
+        verify_oidc_jwt(access_token, &providers, http_client).await
+    }
+}
+
+pub fn oidc_providers(cfg: &LitConfig) -> Result<Vec<OidcProvider>> {
+    let providers = cfg
+        .oidc_providers()
+        .map_err(|e| unexpected_err(e, Some("Unable to get OIDC providers".into())))?;
+    serde_json::from_str(&providers)
+        .map_err(|e| parser_err(e, Some("Unable to parse OIDC providers config".into())))
+}
+
+pub async fn verify_oidc_jwt(
+    token: &str,
+    providers: &[OidcProvider],
+    http_client: reqwest::Client,
+) -> Result<models::AuthMethodResponse> {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return Err(validation_err("Invalid JWT parts length".to_string(), None));
+    }
+    let header_b64 = parts[0];
+    let payload_b64 = parts[1];
+    let signature_b64 = parts[2];
+
+    let header = decode_segment(header_b64)?;
+    let payload = decode_segment(payload_b64)?;
+    let signature = data_encoding::BASE64URL_NOPAD
+        .decode(signature_b64.as_bytes())
+        .map_err(|e| parser_err(e, Some("Unable to decode".into())))?;
+
+    if header["alg"] != JWT_RSA_ALG_VAL {
+        return Err(validation_err(
+            format!(
+                "Invalid JWT algorithm.  Only RS256 supported.  Alg returned is {}",
+                header["alg"]
+            ),
+            None,
+        ));
+    }
+
+    let issuer = claim_str(&payload, "iss")?;
+    let provider = providers
+        .iter()
+        .find(|provider| provider.issuer == issuer)
+        .expect_or_err(format!("OIDC issuer {} is not registered", issuer))
+        .map_err(|e| validation_err(e, None))?;
+
+    if !audience_matches(&payload["aud"], &provider.audience) {
+        return Err(validation_err(
+            format!(
+                "OIDC JWT audience {} does not match {}",
+                payload["aud"], provider.audience
+            ),
+            None,
+        ));
+    }
+
+    let now = Utc::now().timestamp();
+    let jwt_exp = payload["exp"]
+        .as_i64()
+        .expect_or_err("OIDC JWT missing exp")
+        .map_err(|e| validation_err(e, Some("Unable to convert to i64".into())))?;
+    if now > jwt_exp + CLOCK_SKEW_SECS {
+        trace!(
+            "JWT expired.  Expiration time: {} and current time: {}",
+            jwt_exp, now
+        );
+        return Err(validation_err("OIDC JWT expired".to_string(), None));
+    }
+    let nbf = payload.get("nbf").and_then(Value::as_i64);
+    if nbf.is_some_and(|nbf| now + CLOCK_SKEW_SECS < nbf) {
+        return Err(validation_err("OIDC JWT not yet valid".to_string(), None));
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(header_b64.as_bytes());
+    hasher.update(b".");
+    hasher.update(payload_b64.as_bytes());
+    let signed_message = hasher.finalize();
+
+    let public_key_id = header["kid"]
+        .as_str()
+        .expect_or_err("Invalid JWT. Missing kid")
+        .map_err(|e| validation_err(e, Some("Unable to convert to string".into())))?;
+    let keys = get_jwks(&provider.jwks_uri, public_key_id, http_client).await?;
+    let key = keys
+        .iter()
+        .find(|key| key["kid"] == public_key_id)
+        .expect_or_err(format!(
+            "No key with id {} in the JWKS of {}",
+            public_key_id, issuer
+        ))
+        .map_err(|e| validation_err(e, None))?;
+
+    let public_key = RsaPublicKey::new(jwk_component(key, "n")?, jwk_component(key, "e")?)
+        .map_err(|e| unexpected_err(e, Some("Unable to create public key".into())))?;
+    public_key
+        .verify(
+            rsa::padding::PaddingScheme::PKCS1v15Sign {
+                hash: Some(rsa::Hash::SHA2_256),
+            },
+            &signed_message,
+            &signature,
+        )
+        .map_err(|e| validation_err(e, Some("Invalid OIDC JWT signature".into())))?;
+
+    Ok(models::AuthMethodResponse {
+        user_id: claim_str(&payload, "sub")?.to_string(),
+        app_id: issuer.to_string(),
+        auth_method_type: OIDC_JWT_AUTH_METHOD_TYPE_ID,
+        last_retrieved_at: SystemTime::now(),
+        expiration: jwt_exp,
+        used_for_sign_session_key_request: false,
+    })
+}
+
+/// Returns the provider's signing keys. The keys are cached, and fetched again early when
+/// `kid` isn't among them since the provider has likely rotated its keys.
+async fn get_jwks(
+    jwks_uri: &str,
+    kid: &str,
+    http_client: reqwest::Client,
+) -> Result<Arc<Vec<Value>>> {
+    if let Some(cached) = JWKS_CACHE.get(jwks_uri).await {
+        let has_kid = cached.keys.iter().any(|key| key["kid"] == kid);
+        if has_kid || cached.fetched_at.elapsed() < JWKS_MIN_REFRESH_INTERVAL {
+            return Ok(cached.keys);
+        }
+        debug!("Key {} not in cached JWKS of {}, refreshing", kid, jwks_uri);
+    }
+
+    let keys = Arc::new(fetch_jwks(jwks_uri, http_client).await?);
+    JWKS_CACHE
+        .insert(
+            jwks_uri.to_string(),
+            CachedJwks {
+                keys: keys.clone(),
+                fetched_at: Instant::now(),
+            },
+        )
+        .await;
+    Ok(keys)
+}
+
+async fn fetch_jwks(jwks_uri: &str, http_client: reqwest::Client) -> Result<Vec<Value>> {
+    trace!("Downloading JWKS from {}", jwks_uri);
+    let resp = http_client
+        .get(jwks_uri)
+        .send()
+        .instrument(debug_span!("fetch_oidc_jwks"))
+        .await
+        .map_err(|e| unexpected_err(e, Some("Unable to send request for JWKS".into())))?;
+    let json: Value = resp
+        .json()
+        .await
+        .map_err(|e| conversion_err(e, Some("Unable to convert JWKS response to json".into())))?;
+
+    json["keys"]
+        .as_array()
+        .cloned()
+        .expect_or_err("JWKS has no keys")
+        .map_err(|e| conversion_err(e, None))
+}
+
+fn decode_segment(segment: &str) -> Result<Map<String, Value>> {
+    let decoded = data_encoding::BASE64URL_NOPAD
+        .decode(segment.as_bytes())
+        .map_err(|e| parser_err(e, Some("Unable to decode".into())))?;
+    let value: Value = serde_json::from_slice(&decoded)
+        .map_err(|e| conversion_err(e, Some("Unable to convert to value".into())))?;
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(validation_err("Invalid JWT segment".to_string(), None)),
+    }
+}
+
+fn claim_str<'a>(payload: &'a Map<String, Value>, claim: &str) -> Result<&'a str> {
+    payload
+        .get(claim)
+        .and_then(Value::as_str)
+        .expect_or_err(format!("Invalid JWT. Missing {}", claim))
+        .map_err(|e| validation_err(e, None))
+}
+
+/// `aud` may be a single audience or an array of them.
+fn audience_matches(aud: &Value, audience: &str) -> bool {
+    match aud {
+        Value::String(aud) => aud == audience,
+        Value::Array(auds) => auds.iter().any(|aud| aud == audience),
+        _ => false,
+    }
+}
+
+fn jwk_component(key: &Value, name: &str) -> Result<BigUint> {
+    let component = key[name]
+        .as_str()
+        .expect_or_err(format!("Invalid JWK. Missing {}", name))
+        .map_err(|e| validation_err(e, None))?;
+    let bytes = data_encoding::BASE64URL_NOPAD
+        .decode(component.as_bytes())
+        .map_err(|e| parser_err(e, Some("Unable to decode JWK".into())))?;
+    Ok(BigUint::from_bytes_be(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachedJwks, JWKS_CACHE, OidcProvider, verify_oidc_jwt};
+    use crate::pkp::auth::constants::OIDC_JWT_AUTH_METHOD_TYPE_ID;
+    use chrono::Utc;
+    use rsa::{PublicKeyParts, RsaPrivateKey};
+    use serde_json::{Value, json};
+    use sha2::{Digest, Sha256};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const AUDIENCE: &str = "lit-test-client";
+
+    fn b64(bytes: &[u8]) -> String {
+        data_encoding::BASE64URL_NOPAD.encode(bytes)
+    }
+
+    fn jwk(key: &RsaPrivateKey, kid: &str) -> Value {
+        json!({
+            "kty": "RSA",
+            "alg": "RS256",
+            "use": "sig",
+            "kid": kid,
+            "n": b64(&key.n().to_bytes_be()),
+            "e": b64(&key.e().to_bytes_be()),
+        })
+    }
+
+    fn sign_jwt(key: &RsaPrivateKey, kid: &str, claims: Value) -> String {
+        let header = b64(json!({ "alg": "RS256", "typ": "JWT", "kid": kid })
+            .to_string()
+            .as_bytes());
+        let payload = b64(claims.to_string().as_bytes());
+        let hashed = Sha256::digest(format!("{}.{}", header, payload).as_bytes());
+        let signature = key
+            .sign(
+                rsa::padding::PaddingScheme::PKCS1v15Sign {
+                    hash: Some(rsa::Hash::SHA2_256),
+                },
+                &hashed,
+            )
+            .unwrap();
+        format!("{}.{}.{}", header, payload, b64(&signature))
+    }
+
+    fn claims(issuer: &str, audience: &str) -> Value {
+        json!({
+            "iss": issuer,
+            "aud": audience,
+            "sub": "user-1234",
+            "exp": Utc::now().timestamp() + 600,
+        })
+    }
+
+    async fn serve_jwks(jwks: Value) -> (MockServer, OidcProvider) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/jwks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks))
+            .mount(&mock_server)
+            .await;
+        let provider = OidcProvider {
+            issuer: mock_server.uri(),
+            audience: AUDIENCE.to_string(),
+            jwks_uri: format!("{}/jwks", mock_server.uri()),
+        };
+        (mock_server, provider)
+    }
+
+    #[tokio::test]
+    async fn test_verify_oidc_jwt() {
+        let key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap();
+        let (_mock_server, provider) = serve_jwks(json!({ "keys": [jwk(&key, "key-1")] })).await;
+        let providers = vec![provider.clone()];
+
+        let token = sign_jwt(&key, "key-1", claims(&provider.issuer, AUDIENCE));
+        let response = verify_oidc_jwt(&token, &providers, reqwest::Client::new())
+            .await
+            .unwrap();
+        assert_eq!(response.user_id, "user-1234");
+        assert_eq!(response.app_id, provider.issuer);
+        assert_eq!(response.auth_method_type, OIDC_JWT_AUTH_METHOD_TYPE_ID);
+
+        let token = sign_jwt(&key, "key-1", claims(&provider.issuer, "another-client"));
+        assert!(
+            verify_oidc_jwt(&token, &providers, reqwest::Client::new())
+                .await
+                .is_err()
+        );
+
+        let token = sign_jwt(&key, "key-1", claims("https://unknown.example", AUDIENCE));
+        assert!(
+            verify_oidc_jwt(&token, &providers, reqwest::Client::new())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jwks_refreshed_on_key_rotation() {
+        let old_key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap();
+        let new_key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap();
+        let (_mock_server, provider) =
+            serve_jwks(json!({ "keys": [jwk(&new_key, "key-2")] })).await;
+        let providers = vec![provider.clone()];
+
+        // the node last saw the provider's keys before the rotation
+        JWKS_CACHE
+            .insert(
+                provider.jwks_uri.clone(),
+                CachedJwks {
+                    keys: Arc::new(vec![jwk(&old_key, "key-1")]),
+                    fetched_at: Instant::now() - Duration::from_secs(60),
+                },
+            )
+            .await;
+
+        let token = sign_jwt(&new_key, "key-2", claims(&provider.issuer, AUDIENCE));
+        assert!(
+            verify_oidc_jwt(&token, &providers, reqwest::Client::new())
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -5,6 +5,7 @@ use ethers::providers::{Http, Middleware, Provider};
 use ethers::types::Bytes;
 use lit_core::error::Unexpected;
 use std::time::SystemTime;
+use url::Url;
 use webauthn_rs_proto::PublicKeyCredential;
 
 use crate::error::validation_err;
@@ -25,7 +26,7 @@ use tracing::debug;
 use webauthn_rs_proto::{CollectedClientData, RegisteredExtensions};
 
 use webauthn_rs::prelude::{AuthenticationState, Base64UrlSafeData, ParsedAttestation};
-use webauthn_rs_core::proto::Credential;
+use webauthn_rs_core::proto::{COSEKey, Credential};
 
 use crate::error::{conversion_err, unexpected_err_code};
 use lit_node_common::config::LitNodeConfig;
@@ -198,18 +199,6 @@ async fn verify_credential(
     };
     debug!("cbor_encoded_cose_key: {:?}", cbor_encoded_cose_key);
 
-    let allowed_localhost = url::Url::parse("http://localhost:3000")
-        .map_err(|e| parser_err(e, Some("Unable to parse localhost URL".into())))?;
-
-    let webauthn_verification = webauthn_rs_core::core::WebauthnCore::new_unsafe_experts_only(
-        client_origin,
-        client_origin,
-        config.webauthn_allowed_origins()?,
-        None,
-        None,
-        None,
-    );
-
     // Decode credentialPublicKey CBOR to COSE
     let (cose_key, public_key_hex) = decode_cbor_cose_key(cbor_encoded_cose_key).map_err(|e| {
         parser_err(
@@ -218,8 +207,56 @@ async fn verify_credential(
         )
     })?;
 
+    let client_challenge = get_challenge_from_credential(credential)
+        .map_err(|e| parser_err(e, Some("Unable to get challenge from credential".into())))?;
+
+    authenticate_credential(
+        &config,
+        credential,
+        cose_key,
+        client_origin,
+        client_challenge,
+    )
+}
+
+/// Verifies a WebAuthn assertion against the credential's public key and the expected challenge.
+///
+/// Returns whether the user was verified by the authenticator.
+pub(crate) fn authenticate_credential(
+    config: &LitConfig,
+    credential: &PublicKeyCredential,
+    cose_key: COSEKey,
+    rp_id: &str,
+    challenge: Base64UrlSafeData,
+) -> Result<bool> {
+    authenticate_credential_from_origins(
+        config.webauthn_allowed_origins()?,
+        credential,
+        cose_key,
+        rp_id,
+        challenge,
+    )
+}
+
+/// Like `authenticate_credential`, but with the allowed origins given instead of read from the config.
+pub(crate) fn authenticate_credential_from_origins(
+    allowed_origins: Vec<Url>,
+    credential: &PublicKeyCredential,
+    cose_key: COSEKey,
+    rp_id: &str,
+    challenge: Base64UrlSafeData,
+) -> Result<bool> {
+    let webauthn_verification = webauthn_rs_core::core::WebauthnCore::new_unsafe_experts_only(
+        rp_id,
+        rp_id,
+        allowed_origins,
+        None,
+        None,
+        None,
+    );
+
     // Here we construct a Credential object from the credential public key
-    // obtained from the contract. This object will be used to verify against
+    // obtained from the contract or the auth sig. This object will be used to verify against
     // the assertion signature and payload from the client request. Authenticity
     // comes from verifying chain data (credential public key) against data provided
     // by the client (assertion signature and payload).
@@ -238,14 +275,11 @@ async fn verify_credential(
         attestation_format: webauthn_rs_core::AttestationFormat::None, // unused during verification.
     };
 
-    let client_challenge = get_challenge_from_credential(credential)
-        .map_err(|e| parser_err(e, Some("Unable to get challenge from credential".into())))?;
-
     let authentication_state = AuthenticationState {
         credentials: vec![restored_credential],
         policy: webauthn_rs_proto::UserVerificationPolicy::Preferred,
-        challenge: client_challenge,
-        appid: Some(client_origin.to_string()),
+        challenge,
+        appid: Some(rp_id.to_string()),
         allow_backup_eligible_upgrade: true,
     };
 
@@ -256,7 +290,9 @@ async fn verify_credential(
     Ok(auth_res.user_verified())
 }
 
-fn get_challenge_from_credential(credential: &PublicKeyCredential) -> Result<Base64UrlSafeData> {
+pub(crate) fn get_challenge_from_credential(
+    credential: &PublicKeyCredential,
+) -> Result<Base64UrlSafeData> {
     let client_data = serde_json::from_slice::<CollectedClientData>(
         credential.response.client_data_json.as_ref(),
     )
@@ -264,6 +300,14 @@ fn get_challenge_from_credential(credential: &PublicKeyCredential) -> Result<Bas
     Ok(client_data.challenge)
 }
 
+pub(crate) fn get_origin_from_credential(credential: &PublicKeyCredential) -> Result<Url> {
+    let client_data = serde_json::from_slice::<CollectedClientData>(
+        credential.response.client_data_json.as_ref(),
+    )
+    .map_err(|e| conversion_err(e, Some("Unable to parse client data JSON".into())))?;
+    Ok(client_data.origin)
+}
+
 #[allow(dead_code)]
 fn get_rp_id_from_credential(credential: &PublicKeyCredential) -> Result<String> {
     let client_data = serde_json::from_slice::<CollectedClientData>(
@@ -4,6 +4,7 @@ use crate::{
     peers::PeerState,
     pkp::auth::verify_auth_method_for_claim,
     tss::common::{storage::any_key_share_exists, tss_state::TssState},
+    tss::musig2::{MuSig2NonceCache, MuSig2State},
     utils::encoding::{self, ipfs_cid_to_bytes, string_to_eth_address, string_to_u256},
 };
 
@@ -24,10 +25,13 @@ use tracing::instrument;
 use super::auth::serialize_auth_context_for_checking_against_contract_data;
 use ethers::{signers::Signer, types::U256};
 use lit_blockchain::contracts::load_wallet;
+use lit_node_core::DerivationPath;
 use lit_node_core::NodeSet;
+use lit_node_core::PeerId;
 use lit_node_core::SignableOutput;
 use lit_node_core::SigningScheme;
 use lit_node_core::request::JsonPKPClaimKeyRequest;
+use lit_node_core::request::JsonPKPMuSig2SigningRequest;
 use lit_node_core::response::JsonPKPClaimKeyResponse;
 
 pub async fn pkp_permissions_is_permitted(
@@ -350,13 +354,262 @@ pub async fn sign(
     bls_root_pubkey: &String,
     node_set: &Vec<NodeSet>,
     signing_scheme: SigningScheme,
+    derivation_path: Option<&DerivationPath>,
+    proof_of_possession: bool,
 ) -> Result<SignableOutput> {
     trace!("sign() enter - signing_scheme: {}", signing_scheme);
-    // auth check
+    check_signing_auth(
+        cfg,
+        &pubkey,
+        lit_action_ipfs_id,
+        auth_sig,
+        auth_context,
+        required_scopes,
+        bls_root_pubkey,
+    )
+    .await?;
+
+    let tss_state = tss_state.expect_or_err("tss_state not set in RustJsComms")?;
+    let (tweak_preimage, root_pubkeys) =
+        signing_key_material(cfg, &tss_state, &pubkey, signing_scheme).await?;
+
+    let mut signing_state = tss_state.get_signing_state(signing_scheme)?;
+    if let Some(derivation_path) = derivation_path {
+        signing_state.set_derivation_path(derivation_path.clone())?;
+    }
+    if proof_of_possession {
+        signing_state.set_proof_of_possession()?;
+    }
+    trace!("sign() signing_scheme: {}", signing_scheme);
+    let public_key = encoding::hex_to_bytes(&pubkey)?;
+    let request_id = request_id.into_bytes();
+    let sign_start = std::time::Instant::now();
+
+    trace!("sign start: {:?}", sign_start);
+
+    let sign_result = signing_state
+        .sign_with_pubkey(
+            to_sign,
+            public_key,
+            root_pubkeys,
+            tweak_preimage,
+            request_id.clone(),
+            epoch,
+            node_set,
+        )
+        .await
+        .map_err(|e| unexpected_err_code(e, NodeUnknownError, Some("ECDSA signing failed".into())));
+
+    let sign_result = sign_result?;
+
+    Ok(sign_result)
+}
+
+#[doc = "Signs several messages with the same PKP, each with its own signing scheme.  The messages of each scheme are signed together, so the nodes exchange the data for all of them at once.  Returns the signature shares in the order of the messages."]
+#[allow(clippy::too_many_arguments)]
+#[instrument(
+    level = "debug",
+    skip(tss_state, cfg, messages, auth_sig, auth_context)
+)]
+pub async fn sign_batch(
+    cfg: &LitConfig,
+    messages: &[(Vec<u8>, SigningScheme)],
+    pubkey: String,
+    request_id: String,
+    lit_action_ipfs_id: Option<String>,
+    auth_sig: Option<JsonAuthSig>,
+    auth_context: AuthContext,
+    tss_state: Option<TssState>,
+    required_scopes: &[usize],
+    epoch: Option<u64>,
+    bls_root_pubkey: &String,
+    node_set: &Vec<NodeSet>,
+    derivation_path: Option<&DerivationPath>,
+) -> Result<Vec<SignableOutput>> {
+    check_signing_auth(
+        cfg,
+        &pubkey,
+        lit_action_ipfs_id,
+        auth_sig,
+        auth_context,
+        required_scopes,
+        bls_root_pubkey,
+    )
+    .await?;
+
+    let tss_state = tss_state.expect_or_err("tss_state not set in RustJsComms")?;
+    let public_key = encoding::hex_to_bytes(&pubkey)?;
+
+    // indices of the messages of each scheme, in the order the schemes first appear
+    let mut groups: Vec<(SigningScheme, Vec<usize>)> = Vec::new();
+    for (i, (_, signing_scheme)) in messages.iter().enumerate() {
+        match groups
+            .iter_mut()
+            .find(|(scheme, _)| scheme == signing_scheme)
+        {
+            Some((_, indices)) => indices.push(i),
+            None => groups.push((*signing_scheme, vec![i])),
+        }
+    }
+
+    let group_outputs =
+        futures::future::try_join_all(groups.iter().map(|(signing_scheme, indices)| {
+            let tss_state = &tss_state;
+            let public_key = public_key.clone();
+            // the same on every node, and distinct for each scheme of the batch
+            let request_id = format!("{}_{}", request_id, signing_scheme).into_bytes();
+            let to_sign = indices
+                .iter()
+                .map(|i| messages[*i].0.clone())
+                .collect::<Vec<_>>();
+            let pubkey = &pubkey;
+            async move {
+                let (tweak_preimage, root_pubkeys) =
+                    signing_key_material(cfg, tss_state, pubkey, *signing_scheme).await?;
+                let mut signing_state = tss_state.get_signing_state(*signing_scheme)?;
+                if let Some(derivation_path) = derivation_path {
+                    signing_state.set_derivation_path(derivation_path.clone())?;
+                }
+                signing_state
+                    .sign_batch_with_pubkey(
+                        &to_sign,
+                        public_key,
+                        root_pubkeys,
+                        tweak_preimage,
+                        request_id,
+                        epoch,
+                        node_set,
+                    )
+                    .await
+                    .map_err(|e| {
+                        unexpected_err_code(
+                            e,
+                            NodeUnknownError,
+                            Some(format!("Batch signing with {} failed", signing_scheme)),
+                        )
+                    })
+            }
+        }))
+        .await?;
+
+    let mut outputs = vec![None; messages.len()];
+    for ((_, indices), group_output) in groups.iter().zip(group_outputs) {
+        if group_output.len() != indices.len() {
+            return Err(unexpected_err(
+                "Batch signing returned the wrong number of signature shares",
+                None,
+            ));
+        }
+        for (i, output) in indices.iter().zip(group_output) {
+            outputs[*i] = Some(output);
+        }
+    }
+    outputs
+        .into_iter()
+        .map(|output| output.expect_or_err("Missing signature share in batch"))
+        .collect()
+}
+
+#[doc = "Commits this node to a nonce for the first phase of a MuSig2 signature with the PKP, returning its peer id and its part of the PKP's public nonce."]
+#[allow(clippy::too_many_arguments)]
+#[instrument(
+    level = "debug",
+    skip(tss_state, cfg, nonce_cache, auth_sig, auth_context)
+)]
+pub async fn musig2_nonce(
+    cfg: &LitConfig,
+    tss_state: Arc<TssState>,
+    nonce_cache: &MuSig2NonceCache,
+    pubkey: &str,
+    session_id: &str,
+    auth_sig: Option<JsonAuthSig>,
+    auth_context: AuthContext,
+    required_scopes: &[usize],
+    bls_root_pubkey: &String,
+) -> Result<(PeerId, Vec<u8>)> {
+    check_signing_auth(
+        cfg,
+        pubkey,
+        None,
+        auth_sig,
+        auth_context,
+        required_scopes,
+        bls_root_pubkey,
+    )
+    .await?;
+    // fail now rather than in the second phase if the PKP can't sign Schnorr signatures
+    signing_key_material(cfg, &tss_state, pubkey, SigningScheme::SchnorrK256Taproot).await?;
+
+    MuSig2State::new(tss_state)
+        .create_nonce(nonce_cache, pubkey, session_id)
+        .await
+}
+
+#[doc = "Signs the second phase of a MuSig2 signature with the nonce of the first, returning this node's peer id and its share of the PKP's partial signature."]
+#[allow(clippy::too_many_arguments)]
+#[instrument(
+    level = "debug",
+    skip(tss_state, cfg, nonce_cache, auth_sig, auth_context)
+)]
+pub async fn musig2_sign(
+    cfg: &LitConfig,
+    tss_state: Arc<TssState>,
+    nonce_cache: &MuSig2NonceCache,
+    request: &JsonPKPMuSig2SigningRequest,
+    auth_sig: Option<JsonAuthSig>,
+    auth_context: AuthContext,
+    required_scopes: &[usize],
+    epoch: Option<u64>,
+    bls_root_pubkey: &String,
+) -> Result<(PeerId, Vec<u8>)> {
+    check_signing_auth(
+        cfg,
+        &request.pubkey,
+        None,
+        auth_sig,
+        auth_context,
+        required_scopes,
+        bls_root_pubkey,
+    )
+    .await?;
+    let (tweak_preimage, root_pubkeys) = signing_key_material(
+        cfg,
+        &tss_state,
+        &request.pubkey,
+        SigningScheme::SchnorrK256Taproot,
+    )
+    .await?;
+
+    MuSig2State::new(tss_state)
+        .sign(
+            nonce_cache,
+            &request.pubkey,
+            &request.session_id,
+            &request.to_sign,
+            &request.aggregate_nonce,
+            &request.public_keys,
+            request.taproot_merkle_root.as_deref(),
+            root_pubkeys,
+            tweak_preimage,
+            epoch,
+            &request.node_set,
+        )
+        .await
+}
+
+async fn check_signing_auth(
+    cfg: &LitConfig,
+    pubkey: &str,
+    lit_action_ipfs_id: Option<String>,
+    auth_sig: Option<JsonAuthSig>,
+    auth_context: AuthContext,
+    required_scopes: &[usize],
+    bls_root_pubkey: &String,
+) -> Result<()> {
     let is_authed = crate::pkp::auth::check_pkp_auth(
         lit_action_ipfs_id,
-        auth_sig.clone(),
-        pubkey.clone(),
+        auth_sig,
+        pubkey.to_string(),
         auth_context,
         cfg,
         required_scopes,
@@ -374,9 +627,17 @@ pub async fn sign(
             None,
         ));
     }
+    Ok(())
+}
 
-    let tweak_preimage = get_tweak_preimage_from_pubkey(cfg, &pubkey).await;
-    let tss_state = tss_state.expect_or_err("tss_state not set in RustJsComms")?;
+#[doc = "Returns the tweak preimage and root pubkeys if the PKP is a HD key, or checks that this node has a key share for it that the signing scheme supports otherwise."]
+async fn signing_key_material(
+    cfg: &LitConfig,
+    tss_state: &TssState,
+    pubkey: &str,
+    signing_scheme: SigningScheme,
+) -> Result<(Option<Vec<u8>>, Option<Vec<String>>)> {
+    let tweak_preimage = get_tweak_preimage_from_pubkey(cfg, pubkey).await;
 
     // if this is a HD key, we need to get the root pubkeys, otherwise check the fs for the key share
     let (tweak_preimage, root_pubkeys) = match tweak_preimage {
@@ -389,7 +650,7 @@ pub async fn sign(
         Err(_) => {
             let staker_address = &tss_state.peer_state.hex_staker_address();
 
-            let result = any_key_share_exists(&pubkey, staker_address).await;
+            let result = any_key_share_exists(pubkey, staker_address).await;
             debug!("op_sign() any_key_share_exists() result: {:?}", &result);
 
             match result {
@@ -445,30 +706,7 @@ pub async fn sign(
         pubkey, tweak_preimage, root_pubkeys
     );
 
-    let mut signing_state = tss_state.get_signing_state(signing_scheme)?;
-    trace!("sign() signing_scheme: {}", signing_scheme);
-    let public_key = encoding::hex_to_bytes(&pubkey)?;
-    let request_id = request_id.into_bytes();
-    let sign_start = std::time::Instant::now();
-
-    trace!("sign start: {:?}", sign_start);
-
-    let sign_result = signing_state
-        .sign_with_pubkey(
-            to_sign,
-            public_key,
-            root_pubkeys,
-            tweak_preimage,
-            request_id.clone(),
-            epoch,
-            node_set,
-        )
-        .await
-        .map_err(|e| unexpected_err_code(e, NodeUnknownError, Some("ECDSA signing failed".into())));
-
-    let sign_result = sign_result?;
-
-    Ok(sign_result)
+    Ok((tweak_preimage, root_pubkeys))
 }
 
 #[instrument(level = "debug", skip(cfg))]
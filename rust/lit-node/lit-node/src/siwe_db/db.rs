@@ -1,11 +1,12 @@
 use std::time::Duration;
 
 use crate::{
+    auth::revocation::RevokedSession,
     error::{EC, Result, conversion_err_code, unexpected_err, unexpected_err_code},
     models::EthBlock,
 };
 use ethers::providers::Middleware;
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OptionalExtension as _, params};
 use tokio::sync::mpsc;
 use tracing::{Instrument, debug_span};
 
@@ -42,9 +43,446 @@ pub fn db_initial_setup(port: u16) -> Result<()> {
     )
     .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS
+            session_revocations(
+                session_key TEXT NOT NULL,
+                issuer TEXT NOT NULL,
+                expires_at INTEGER NOT NULL,
+                request TEXT NOT NULL,
+                PRIMARY KEY (session_key, issuer)
+            )",
+        [],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS
+            action_storage(
+                namespace TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value BLOB NOT NULL,
+                size INTEGER NOT NULL,
+                PRIMARY KEY (namespace, key)
+            )",
+        [],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS
+            action_schedules(
+                id TEXT PRIMARY KEY,
+                owner TEXT NOT NULL,
+                schedule TEXT NOT NULL,
+                cursor INTEGER NOT NULL
+            )",
+        [],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS
+            action_secrets(
+                ipfs_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                secret TEXT NOT NULL,
+                PRIMARY KEY (ipfs_id, name)
+            )",
+        [],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS
+            action_schedule_jobs(
+                schedule_id TEXT NOT NULL,
+                triggered_at INTEGER NOT NULL,
+                job TEXT NOT NULL
+            )",
+        [],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(())
+}
+
+/// Stores a session revocation along with the signed request it came from, so that it can be
+/// handed to peers that missed it.
+pub fn db_insert_session_revocation(
+    port: u16,
+    revoked: &RevokedSession,
+    request: &str,
+) -> Result<()> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO session_revocations(
+            session_key,
+            issuer,
+            expires_at,
+            request
+        ) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            revoked.session_key,
+            revoked.issuer,
+            revoked.expires_at,
+            request
+        ],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(())
+}
+
+/// Returns the session revocations that haven't expired yet, deleting the rest.
+pub fn db_get_session_revocations(port: u16, now: i64) -> Result<Vec<RevokedSession>> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "DELETE FROM session_revocations WHERE expires_at <= ?",
+        [now],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    let mut stmt = conn
+        .prepare("SELECT session_key, issuer, expires_at FROM session_revocations")
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let revocations = stmt
+        .query_map([], |row| {
+            Ok(RevokedSession {
+                session_key: row.get(0)?,
+                issuer: row.get(1)?,
+                expires_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    revocations
+        .collect::<std::result::Result<Vec<RevokedSession>, rusqlite::Error>>()
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Returns the signed requests of the session revocations that haven't expired yet.
+pub fn db_get_session_revocation_requests(port: u16, now: i64) -> Result<Vec<String>> {
+    let conn = db_conn(port)?;
+
+    let mut stmt = conn
+        .prepare("SELECT request FROM session_revocations WHERE expires_at > ?")
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let requests = stmt
+        .query_map([now], |row| row.get(0))
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    requests
+        .collect::<std::result::Result<Vec<String>, rusqlite::Error>>()
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+pub fn db_get_action_storage(port: u16, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+    let conn = db_conn(port)?;
+
+    conn.query_row(
+        "SELECT value FROM action_storage WHERE namespace = ?1 AND key = ?2",
+        params![namespace, key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Stores a value, where `size` is what it counts towards the quota of its namespace.
+pub fn db_put_action_storage(
+    port: u16,
+    namespace: &str,
+    key: &str,
+    value: &[u8],
+    size: i64,
+) -> Result<()> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO action_storage(
+            namespace,
+            key,
+            value,
+            size
+        ) VALUES (?1, ?2, ?3, ?4)",
+        params![namespace, key, value, size],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
     Ok(())
 }
 
+/// Deletes a value. Returns whether there was one.
+pub fn db_delete_action_storage(port: u16, namespace: &str, key: &str) -> Result<bool> {
+    let conn = db_conn(port)?;
+
+    let deleted = conn
+        .execute(
+            "DELETE FROM action_storage WHERE namespace = ?1 AND key = ?2",
+            params![namespace, key],
+        )
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(deleted > 0)
+}
+
+/// Returns the total size of the values in a namespace, except for the one under `key`.
+pub fn db_get_action_storage_size(port: u16, namespace: &str, except_key: &str) -> Result<i64> {
+    let conn = db_conn(port)?;
+
+    conn.query_row(
+        "SELECT COALESCE(SUM(size), 0) FROM action_storage WHERE namespace = ?1 AND key != ?2",
+        params![namespace, except_key],
+        |row| row.get(0),
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Stores a new schedule, where `cursor` tracks how far its trigger has been evaluated.
+/// Returns false if a schedule with the same ID already exists.
+pub fn db_insert_action_schedule(
+    port: u16,
+    id: &str,
+    owner: &str,
+    schedule: &str,
+    cursor: i64,
+) -> Result<bool> {
+    let conn = db_conn(port)?;
+
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO action_schedules(
+                id,
+                owner,
+                schedule,
+                cursor
+            ) VALUES (?1, ?2, ?3, ?4)",
+            params![id, owner, schedule, cursor],
+        )
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(inserted > 0)
+}
+
+/// Returns the schedules and their cursors, either all of them or only those of `owner`.
+pub fn db_get_action_schedules(port: u16, owner: Option<&str>) -> Result<Vec<(String, i64)>> {
+    let conn = db_conn(port)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT schedule, cursor FROM action_schedules
+            WHERE ?1 IS NULL OR owner = ?1
+            ORDER BY rowid",
+        )
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let schedules = stmt
+        .query_map([owner], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    schedules
+        .collect::<std::result::Result<Vec<(String, i64)>, rusqlite::Error>>()
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Counts the schedules, either all of them or only those of `owner`.
+pub fn db_count_action_schedules(port: u16, owner: Option<&str>) -> Result<usize> {
+    let conn = db_conn(port)?;
+
+    conn.query_row(
+        "SELECT COUNT(*) FROM action_schedules WHERE ?1 IS NULL OR owner = ?1",
+        [owner],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count as usize)
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+pub fn db_get_action_schedule(port: u16, id: &str) -> Result<Option<String>> {
+    let conn = db_conn(port)?;
+
+    conn.query_row(
+        "SELECT schedule FROM action_schedules WHERE id = ?1",
+        [id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+pub fn db_set_action_schedule_cursor(port: u16, id: &str, cursor: i64) -> Result<()> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "UPDATE action_schedules SET cursor = ?2 WHERE id = ?1",
+        params![id, cursor],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(())
+}
+
+/// Deletes a schedule along with its job history. Returns whether there was one.
+pub fn db_delete_action_schedule(port: u16, id: &str) -> Result<bool> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "DELETE FROM action_schedule_jobs WHERE schedule_id = ?1",
+        [id],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let deleted = conn
+        .execute("DELETE FROM action_schedules WHERE id = ?1", [id])
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(deleted > 0)
+}
+
+/// Records a job enqueued by a schedule, only keeping the `keep` most recent ones.
+pub fn db_insert_action_schedule_job(
+    port: u16,
+    schedule_id: &str,
+    triggered_at: i64,
+    job: &str,
+    keep: usize,
+) -> Result<()> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "INSERT INTO action_schedule_jobs(
+            schedule_id,
+            triggered_at,
+            job
+        ) VALUES (?1, ?2, ?3)",
+        params![schedule_id, triggered_at, job],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    conn.execute(
+        "DELETE FROM action_schedule_jobs WHERE schedule_id = ?1 AND rowid NOT IN (
+            SELECT rowid FROM action_schedule_jobs WHERE schedule_id = ?1
+            ORDER BY triggered_at DESC, rowid DESC LIMIT ?2
+        )",
+        params![schedule_id, keep as i64],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(())
+}
+
+/// Returns the jobs enqueued by a schedule, most recent first.
+pub fn db_get_action_schedule_jobs(port: u16, schedule_id: &str) -> Result<Vec<String>> {
+    let conn = db_conn(port)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT job FROM action_schedule_jobs WHERE schedule_id = ?1
+            ORDER BY triggered_at DESC, rowid DESC",
+        )
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let jobs = stmt
+        .query_map([schedule_id], |row| row.get(0))
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    jobs.collect::<std::result::Result<Vec<String>, rusqlite::Error>>()
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Stores a secret of an action, or replaces it if `owner` stored it.
+/// Returns false if the name is taken by another owner.
+pub fn db_put_action_secret(
+    port: u16,
+    ipfs_id: &str,
+    name: &str,
+    owner: &str,
+    secret: &str,
+) -> Result<()> {
+    let conn = db_conn(port)?;
+
+    conn.execute(
+        "INSERT INTO action_secrets(
+            ipfs_id,
+            name,
+            owner,
+            secret
+        ) VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT (ipfs_id, name) DO UPDATE SET owner = excluded.owner, secret = excluded.secret",
+        params![ipfs_id, name, owner, secret],
+    )
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(())
+}
+
+/// Returns the actions that `owner` stored secrets for.
+pub fn db_get_action_secret_ipfs_ids(port: u16, owner: &str) -> Result<Vec<String>> {
+    let conn = db_conn(port)?;
+
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT ipfs_id FROM action_secrets WHERE owner = ?1 ORDER BY ipfs_id")
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let ipfs_ids = stmt
+        .query_map(params![owner], |row| row.get(0))
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    ipfs_ids
+        .collect::<std::result::Result<Vec<String>, rusqlite::Error>>()
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+pub fn db_get_action_secret(port: u16, ipfs_id: &str, name: &str) -> Result<Option<String>> {
+    let conn = db_conn(port)?;
+
+    conn.query_row(
+        "SELECT secret FROM action_secrets WHERE ipfs_id = ?1 AND name = ?2",
+        params![ipfs_id, name],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Returns the names of the secrets of an action, either all of them or only those of `owner`.
+pub fn db_get_action_secret_names(
+    port: u16,
+    ipfs_id: &str,
+    owner: Option<&str>,
+) -> Result<Vec<String>> {
+    let conn = db_conn(port)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name FROM action_secrets
+            WHERE ipfs_id = ?1 AND (?2 IS NULL OR owner = ?2)
+            ORDER BY name",
+        )
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+    let names = stmt
+        .query_map(params![ipfs_id, owner], |row| row.get(0))
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    names
+        .collect::<std::result::Result<Vec<String>, rusqlite::Error>>()
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))
+}
+
+/// Deletes a secret of `owner`. Returns whether there was one.
+pub fn db_delete_action_secret(port: u16, ipfs_id: &str, name: &str, owner: &str) -> Result<bool> {
+    let conn = db_conn(port)?;
+
+    let deleted = conn
+        .execute(
+            "DELETE FROM action_secrets WHERE ipfs_id = ?1 AND name = ?2 AND owner = ?3",
+            params![ipfs_id, name, owner],
+        )
+        .map_err(|e| unexpected_err_code(e, EC::NodeSystemFault, None))?;
+
+    Ok(deleted > 0)
+}
+
 fn db_batch_write(conn: &mut Connection, block_records: &Vec<EthBlock>) -> Result<()> {
     let tx = conn
         .transaction()
@@ -200,11 +638,19 @@ mod siwe_db_tests {
     use std::process::Command;
     use tokio::sync::mpsc;
 
+    use crate::auth::revocation::RevokedSession;
     use crate::utils::web::default_http_client;
     use crate::{
         siwe_db::{
             db::{
-                EthBlock, db_batch_write, db_conn, db_initial_setup, fetch_and_store_block_info,
+                EthBlock, db_batch_write, db_conn, db_delete_action_schedule,
+                db_delete_action_secret, db_delete_action_storage, db_get_action_schedule,
+                db_get_action_schedule_jobs, db_get_action_schedules, db_get_action_secret,
+                db_get_action_secret_ipfs_ids, db_get_action_secret_names, db_get_action_storage,
+                db_get_action_storage_size, db_get_session_revocation_requests,
+                db_get_session_revocations, db_initial_setup, db_insert_action_schedule,
+                db_insert_action_schedule_job, db_insert_session_revocation, db_put_action_secret,
+                db_put_action_storage, db_set_action_schedule_cursor, fetch_and_store_block_info,
                 init_fill_db, retrieve_and_store_blockhash,
             },
             utils::MAX_TIMESTAMP_VALIDITY_DAYS,
@@ -516,6 +962,183 @@ mod siwe_db_tests {
     }
 
     // NOTE: We're using different ports for different tests to ensuring that deleting/updating a conn doesn't effect other tests
+    #[test]
+    fn test_session_revocations() {
+        // Pre clean-up
+        remove_db_files(5);
+
+        let port = 5;
+        assert!(db_initial_setup(port).is_ok());
+
+        let now = Utc::now().timestamp();
+        let live = RevokedSession {
+            session_key: "aa".to_string(),
+            issuer: "0x01".to_string(),
+            expires_at: now + 60,
+        };
+        let expired = RevokedSession {
+            session_key: "bb".to_string(),
+            issuer: "0x01".to_string(),
+            expires_at: now - 60,
+        };
+        assert!(db_insert_session_revocation(port, &live, "live").is_ok());
+        assert!(db_insert_session_revocation(port, &expired, "expired").is_ok());
+
+        // Only live revocations are handed to peers
+        assert_eq!(
+            db_get_session_revocation_requests(port, now).unwrap(),
+            vec!["live".to_string()]
+        );
+
+        // Expired revocations are dropped
+        assert_eq!(db_get_session_revocations(port, now).unwrap(), vec![live]);
+        let conn = db_conn(port).unwrap();
+        let num_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM session_revocations", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(num_rows, 1);
+
+        // Post clean-up
+        remove_db_files(5);
+    }
+
+    #[test]
+    fn test_action_storage() {
+        // Pre clean-up
+        remove_db_files(6);
+
+        let port = 6;
+        assert!(db_initial_setup(port).is_ok());
+
+        assert_eq!(db_get_action_storage(port, "QmA", "k1").unwrap(), None);
+        db_put_action_storage(port, "QmA", "k1", b"v1", 4).unwrap();
+        db_put_action_storage(port, "QmA", "k2", b"v2", 10).unwrap();
+        db_put_action_storage(port, "QmB", "k1", b"other", 100).unwrap();
+
+        // Namespaces are kept apart
+        assert_eq!(
+            db_get_action_storage(port, "QmA", "k1").unwrap(),
+            Some(b"v1".to_vec())
+        );
+        assert_eq!(db_get_action_storage_size(port, "QmA", "").unwrap(), 14);
+        assert_eq!(db_get_action_storage_size(port, "QmA", "k2").unwrap(), 4);
+
+        // Overwriting replaces the size
+        db_put_action_storage(port, "QmA", "k1", b"v1v1", 6).unwrap();
+        assert_eq!(db_get_action_storage_size(port, "QmA", "").unwrap(), 16);
+
+        assert!(db_delete_action_storage(port, "QmA", "k1").unwrap());
+        assert!(!db_delete_action_storage(port, "QmA", "k1").unwrap());
+        assert_eq!(db_get_action_storage(port, "QmA", "k1").unwrap(), None);
+        assert_eq!(
+            db_get_action_storage(port, "QmB", "k1").unwrap(),
+            Some(b"other".to_vec())
+        );
+
+        // Post clean-up
+        remove_db_files(6);
+    }
+
+    #[test]
+    fn test_action_schedules() {
+        // Pre clean-up
+        remove_db_files(7);
+
+        let port = 7;
+        assert!(db_initial_setup(port).is_ok());
+
+        assert!(db_insert_action_schedule(port, "s1", "0xa", "{\"id\":\"s1\"}", 0).unwrap());
+        assert!(db_insert_action_schedule(port, "s2", "0xb", "{\"id\":\"s2\"}", 10).unwrap());
+        // IDs are unique
+        assert!(!db_insert_action_schedule(port, "s1", "0xb", "{}", 0).unwrap());
+
+        assert_eq!(db_get_action_schedules(port, None).unwrap().len(), 2);
+        assert_eq!(
+            db_get_action_schedules(port, Some("0xb")).unwrap(),
+            vec![("{\"id\":\"s2\"}".to_string(), 10)]
+        );
+
+        db_set_action_schedule_cursor(port, "s1", 42).unwrap();
+        assert_eq!(
+            db_get_action_schedules(port, Some("0xa")).unwrap(),
+            vec![("{\"id\":\"s1\"}".to_string(), 42)]
+        );
+
+        // Only the most recent jobs are kept
+        for i in 0..5 {
+            db_insert_action_schedule_job(port, "s1", i, &format!("job{i}"), 3).unwrap();
+        }
+        db_insert_action_schedule_job(port, "s2", 0, "other", 3).unwrap();
+        assert_eq!(
+            db_get_action_schedule_jobs(port, "s1").unwrap(),
+            vec!["job4", "job3", "job2"]
+        );
+
+        assert!(db_delete_action_schedule(port, "s1").unwrap());
+        assert!(!db_delete_action_schedule(port, "s1").unwrap());
+        assert_eq!(db_get_action_schedule(port, "s1").unwrap(), None);
+        assert!(db_get_action_schedule_jobs(port, "s1").unwrap().is_empty());
+        assert_eq!(
+            db_get_action_schedule(port, "s2").unwrap(),
+            Some("{\"id\":\"s2\"}".to_string())
+        );
+        assert_eq!(
+            db_get_action_schedule_jobs(port, "s2").unwrap(),
+            vec!["other"]
+        );
+
+        // Post clean-up
+        remove_db_files(7);
+    }
+
+    #[test]
+    fn test_action_secrets() {
+        // Pre clean-up
+        remove_db_files(8);
+
+        let port = 8;
+        assert!(db_initial_setup(port).is_ok());
+
+        assert_eq!(db_get_action_secret(port, "QmA", "key").unwrap(), None);
+        db_put_action_secret(port, "QmA", "key", "0xa", "s1").unwrap();
+        db_put_action_secret(port, "QmA", "other", "0xa", "s2").unwrap();
+        db_put_action_secret(port, "QmB", "key", "0xb", "s3").unwrap();
+
+        db_put_action_secret(port, "QmA", "key", "0xa", "s1v2").unwrap();
+        assert_eq!(
+            db_get_action_secret(port, "QmA", "key").unwrap(),
+            Some("s1v2".to_string())
+        );
+
+        assert_eq!(
+            db_get_action_secret_names(port, "QmA", None).unwrap(),
+            vec!["key", "other"]
+        );
+        assert!(
+            db_get_action_secret_names(port, "QmA", Some("0xb"))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            db_get_action_secret_ipfs_ids(port, "0xa").unwrap(),
+            vec!["QmA"]
+        );
+
+        // Only the owner can delete a secret
+        assert!(!db_delete_action_secret(port, "QmA", "key", "0xb").unwrap());
+        assert!(db_delete_action_secret(port, "QmA", "key", "0xa").unwrap());
+        assert_eq!(db_get_action_secret(port, "QmA", "key").unwrap(), None);
+        assert_eq!(
+            db_get_action_secret(port, "QmB", "key").unwrap(),
+            Some("s3".to_string())
+        );
+
+        // Post clean-up
+        remove_db_files(8);
+    }
+
     fn remove_db_files(port: u16) {
         let _db_cleanup = Command::new("rm")
             .arg(format!("node_state/node_{}.db", port))
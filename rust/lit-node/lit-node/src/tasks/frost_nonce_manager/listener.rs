@@ -0,0 +1,556 @@
+use super::models::*;
+use crate::common::key_helper::KeyCache;
+use crate::error::{Result, unexpected_err};
+use crate::metrics;
+use crate::p2p_comms::CommsManager;
+use crate::peers::peer_state::models::SimplePeerCollection;
+use crate::tasks::presign_manager::models::{PeerGroupId, generate_hash};
+use crate::tss::common::storage::{
+    delete_frost_nonce, list_frost_nonces_on_disk, read_frost_nonce_from_disk,
+    write_frost_nonce_to_disk,
+};
+use crate::tss::frost::FrostState;
+use crate::version::DataVersionReader;
+use flume::Sender;
+use lit_core::config::ReloadableLitConfig;
+use lit_node_common::config::{CFG_KEY_SIGNING_ROUND_TIMEOUT_MS_DEFAULT, LitNodeConfig};
+use lit_node_core::{CurveType, SigningScheme};
+use std::time::Duration;
+use tracing::instrument;
+
+const NONCE_ROUND: &str = "0";
+
+const FROST_SIGNING_SCHEMES: [SigningScheme; 10] = [
+    SigningScheme::SchnorrEd25519Sha512,
+    SigningScheme::SchnorrK256Sha256,
+    SigningScheme::SchnorrP256Sha256,
+    SigningScheme::SchnorrP384Sha384,
+    SigningScheme::SchnorrRistretto25519Sha512,
+    SigningScheme::SchnorrEd448Shake256,
+    SigningScheme::SchnorrRedJubjubBlake2b512,
+    SigningScheme::SchnorrK256Taproot,
+    SigningScheme::SchnorrRedDecaf377Blake2b512,
+    SigningScheme::SchnorrkelSubstrate,
+];
+
+impl FrostNonceManager {
+    pub async fn listen(
+        &mut self,
+        mut quit_rx: tokio::sync::broadcast::Receiver<bool>,
+        config: ReloadableLitConfig,
+    ) {
+        info!("Loading FROST nonces and listening for requests to create new ones...");
+        self.set_chain_defaults();
+        self.epoch = self.tss_state.peer_state.epoch();
+        self.load_from_disk().await;
+
+        let cfg = config.load_full();
+        let timeout = cfg
+            .signing_round_timeout()
+            .unwrap_or(CFG_KEY_SIGNING_ROUND_TIMEOUT_MS_DEFAULT) as u64;
+        let mut heartbeat = tokio::time::interval(Duration::from_millis(2 * timeout));
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = quit_rx.recv() => {
+                    info!("Shutting down: tasks::frost_nonce_manager");
+                    break;
+                }
+                message = self.rx.recv_async() => {
+                    let message = match message {
+                        Ok(m) => m,
+                        Err(e) => {
+                            error!("Error receiving message: {}", e);
+                            continue;
+                        }
+                    };
+                    match message {
+                        FrostNonceMessage::RequestNonce(req, tx) => {
+                            let request_hash = req.hash();
+                            if req.peers.address_is_leader(request_hash, &self.tss_state.addr) {
+                                self.leader_nonce_request(req, tx).await;
+                            } else {
+                                self.get_nonce_key_from_leader(req, tx).await;
+                            }
+                        }
+                        FrostNonceMessage::FulfillNonceRequest(request_hash, leader_response, req, tx) => {
+                            if leader_response.generate_count > 0 {
+                                self.generate(request_hash, &req.peers, req.signing_scheme, leader_response.generate_count);
+                            }
+                            self.return_nonce_from_disk(req, leader_response, tx).await;
+                        }
+                        FrostNonceMessage::Store(nonces, signing_scheme, peer_group_id, epoch) => {
+                            self.store(nonces, signing_scheme, peer_group_id, epoch).await;
+                        }
+                        FrostNonceMessage::GenerationFailed(signing_scheme, peer_group_id) => {
+                            self.pool.generation_finished(signing_scheme, peer_group_id);
+                        }
+                        FrostNonceMessage::Clear => {
+                            self.clear().await;
+                        }
+                    }
+                }
+                _ = heartbeat.tick() => {
+                    self.set_chain_defaults();
+                    let epoch = self.tss_state.peer_state.epoch();
+                    if epoch != self.epoch {
+                        // nonce commitments are bound to the peer ids of the epoch they were made in
+                        info!("Epoch changed from {} to {}; dropping FROST nonces.", self.epoch, epoch);
+                        self.clear().await;
+                        self.epoch = epoch;
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_chain_defaults(&mut self) {
+        DataVersionReader::reader_unchecked(
+            &self.tss_state.chain_data_config_manager.generic_config,
+            |generic_chain_config| {
+                // Shares the watermarks of the presign pool
+                self.pool.min_nonces = generic_chain_config.min_presign_count;
+                self.pool.max_nonces = generic_chain_config.max_presign_count;
+            },
+        );
+    }
+
+    #[doc = "Picks the next nonces for the signing peers as their leader, and sends the storage key to every signing peer."]
+    #[instrument(level = "debug", skip_all, fields(txn_prefix = req.txn_prefix))]
+    async fn leader_nonce_request(
+        &mut self,
+        req: FrostNonceRequest,
+        tx: Sender<Result<Option<FrostNonce>>>,
+    ) {
+        let request_hash = req.hash();
+        let txn_prefix = TxnPrefix::GetNonce(request_hash).as_str();
+        let cm = match CommsManager::new_with_peers(
+            &self.tss_state,
+            &txn_prefix,
+            &req.peers,
+            NONCE_ROUND,
+        )
+        .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                return_error_to_requester(tx, e).await;
+                return;
+            }
+        };
+
+        let leader_response = self.pool.next_storage_key(&req.peers, req.signing_scheme);
+        debug!(
+            "Leader picked FROST nonces {:?} for request hash {}; generating {} more.",
+            leader_response.storage_key, request_hash, leader_response.generate_count
+        );
+
+        let local_tx = self.tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = cm.broadcast(leader_response.clone()).await {
+                error!("Error broadcasting FROST nonce key to peers: {}", e);
+                return_error_to_requester(tx, e).await;
+                return;
+            }
+            let message =
+                FrostNonceMessage::FulfillNonceRequest(request_hash, leader_response, req, tx);
+            if local_tx.send_async(message).await.is_err() {
+                error!("Error returning FROST nonce key.");
+            }
+        });
+    }
+
+    #[instrument(level = "debug", skip_all, fields(txn_prefix = req.txn_prefix))]
+    async fn get_nonce_key_from_leader(
+        &self,
+        req: FrostNonceRequest,
+        tx: Sender<Result<Option<FrostNonce>>>,
+    ) {
+        let request_hash = req.hash();
+        let txn_prefix = TxnPrefix::GetNonce(request_hash).as_str();
+        let cm = match CommsManager::new_with_peers(
+            &self.tss_state,
+            &txn_prefix,
+            &req.peers,
+            NONCE_ROUND,
+        )
+        .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                return_error_to_requester(tx, e).await;
+                return;
+            }
+        };
+        let leader = match req.peers.leader_for_active_peers(request_hash) {
+            Ok(peer) => SimplePeerCollection(vec![peer]),
+            Err(e) => {
+                return_error_to_requester(tx, e).await;
+                return;
+            }
+        };
+
+        let local_tx = self.tx.clone();
+        tokio::spawn(async move {
+            let leader_response = match cm.collect_from::<FrostNonceLeaderResponse>(&leader).await {
+                Ok(r) => r.into_iter().next().map(|(_, r)| r),
+                Err(e) => {
+                    return_error_to_requester(tx, e).await;
+                    return;
+                }
+            };
+            let Some(leader_response) = leader_response else {
+                return_error_to_requester(tx, unexpected_err("Invalid leader response.", None))
+                    .await;
+                return;
+            };
+            let message =
+                FrostNonceMessage::FulfillNonceRequest(request_hash, leader_response, req, tx);
+            if local_tx.send_async(message).await.is_err() {
+                error!("Error returning FROST nonce key.");
+            }
+        });
+    }
+
+    #[doc = "Reads the nonces that the leader picked from disk, and deletes them so that they are never used twice."]
+    #[instrument(level = "debug", skip_all)]
+    async fn return_nonce_from_disk(
+        &self,
+        req: FrostNonceRequest,
+        leader_response: FrostNonceLeaderResponse,
+        tx: Sender<Result<Option<FrostNonce>>>,
+    ) {
+        if leader_response.storage_key.is_empty() {
+            trace!("FROST nonce pool miss for {}", req.txn_prefix);
+            if tx.send_async(Ok(None)).await.is_err() {
+                error!("Error returning FROST nonce.");
+            }
+            return;
+        }
+
+        let curve_type = req.signing_scheme.curve_type();
+        let staker_address = self.tss_state.peer_state.hex_staker_address();
+        let epoch = self.epoch;
+        let realm_id = self.tss_state.peer_state.realm_id();
+        let key_cache = self.tss_state.key_cache.clone();
+        let storage_key = leader_response.storage_key;
+
+        tokio::spawn(async move {
+            let result = take_frost_nonce_from_disk(
+                curve_type,
+                &storage_key,
+                &staker_address,
+                epoch,
+                realm_id,
+                &key_cache,
+            )
+            .await
+            .map(Some);
+            if result.is_ok() {
+                metrics::counter::add_one(metrics::tss::FrostNonceMetrics::Use, &[]);
+            }
+            if tx.send_async(result).await.is_err() {
+                error!("Error returning FROST nonce.");
+            }
+        });
+    }
+
+    #[doc = "Generates nonces for `count` signatures with the peers, in a single round."]
+    fn generate(
+        &self,
+        txn_hash: u64,
+        peers: &SimplePeerCollection,
+        signing_scheme: SigningScheme,
+        count: u64,
+    ) {
+        let peer_group_id = peers.peer_group_id();
+        if !peers.contains_address(&self.tss_state.addr) {
+            return;
+        }
+
+        let frost_state = FrostState::new(self.tss_state.clone(), signing_scheme);
+        let txn_prefix = TxnPrefix::GenerateNonces(txn_hash, signing_scheme).as_str();
+        let peers = peers.clone();
+        let tx = self.tx.clone();
+        let epoch = self.epoch;
+        tokio::spawn(async move {
+            let start = std::time::Instant::now();
+            let message = match frost_state
+                .create_nonces_for_peers(&txn_prefix, &peers, count as usize)
+                .await
+            {
+                Ok(nonces) => {
+                    metrics::counter::add_one(metrics::tss::FrostNonceMetrics::Generate, &[]);
+                    debug!(
+                        "Generated {} {} nonces in {} ms.",
+                        nonces.len(),
+                        signing_scheme,
+                        start.elapsed().as_millis()
+                    );
+                    let nonces = nonces
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, (nonces, commitments))| {
+                            (
+                                nonce_storage_key(txn_hash, signing_scheme, i),
+                                FrostNonce::new(signing_scheme, nonces, commitments, &peers),
+                            )
+                        })
+                        .collect();
+                    FrostNonceMessage::Store(nonces, signing_scheme, peer_group_id, epoch)
+                }
+                Err(e) => {
+                    error!("Error generating {} nonces: {}", signing_scheme, e);
+                    FrostNonceMessage::GenerationFailed(signing_scheme, peer_group_id)
+                }
+            };
+            if let Err(e) = tx.send_async(message).await {
+                error!("Error sending generated FROST nonces: {}", e);
+            }
+        });
+    }
+
+    #[doc = "Stores generated nonces to disk, and adds those this node is the leader for to its list."]
+    #[instrument(level = "debug", skip_all)]
+    async fn store(
+        &mut self,
+        nonces: Vec<(NonceStorageKey, FrostNonce)>,
+        signing_scheme: SigningScheme,
+        peer_group_id: PeerGroupId,
+        epoch: u64,
+    ) {
+        self.pool.generation_finished(signing_scheme, peer_group_id);
+        if epoch != self.epoch {
+            debug!(
+                "Dropping {} nonces generated in epoch {}.",
+                signing_scheme, epoch
+            );
+            return;
+        }
+        let staker_address = self.tss_state.peer_state.hex_staker_address();
+        let realm_id = self.tss_state.peer_state.realm_id();
+
+        for (storage_key, nonce) in nonces {
+            if let Err(e) = write_frost_nonce_to_disk(
+                signing_scheme.curve_type(),
+                &storage_key,
+                &staker_address,
+                self.epoch,
+                realm_id,
+                &self.tss_state.key_cache,
+                &nonce,
+            )
+            .await
+            {
+                error!("Error writing FROST nonce to disk: {}", e);
+                continue;
+            }
+            metrics::counter::add_one(metrics::tss::FrostNonceMetrics::Store, &[]);
+            self.add_storage_key(storage_key, &nonce);
+        }
+    }
+
+    // every peer in the group stores the nonces, but only one of them hands out each key
+    fn add_storage_key(&mut self, storage_key: NonceStorageKey, nonce: &FrostNonce) {
+        self.pool.add_peer_group(nonce);
+
+        let peers = self.tss_state.peer_state.peers();
+        let Some(leader) = nonce
+            .staker_hashes
+            .get(generate_hash(&storage_key) as usize % nonce.staker_hashes.len().max(1))
+        else {
+            return;
+        };
+        let is_leader = peers
+            .0
+            .iter()
+            .any(|p| p.key_hash == *leader && p.socket_address == self.tss_state.addr);
+        if is_leader {
+            self.pool.push(storage_key, nonce);
+        }
+    }
+
+    #[doc = "Loads the nonces of the current epoch from disk, and deletes those of earlier epochs."]
+    async fn load_from_disk(&mut self) {
+        let staker_address = self.tss_state.peer_state.hex_staker_address();
+        let realm_id = self.tss_state.peer_state.realm_id();
+        let key_cache = self.tss_state.key_cache.clone();
+
+        for curve_type in frost_curve_types() {
+            let files = match list_frost_nonces_on_disk(curve_type, &staker_address).await {
+                Ok(files) => files,
+                Err(e) => {
+                    trace!("No {} FROST nonces loaded from disk: {}", curve_type, e);
+                    continue;
+                }
+            };
+            for file in files {
+                if file.epoch != self.epoch || file.realm_id != realm_id {
+                    if let Err(e) = delete_frost_nonce(
+                        curve_type,
+                        &file.pubkey,
+                        &staker_address,
+                        file.epoch,
+                        file.realm_id,
+                        &key_cache,
+                    )
+                    .await
+                    {
+                        error!("Error deleting stale FROST nonce: {}", e);
+                    }
+                    continue;
+                }
+                match read_frost_nonce_from_disk::<FrostNonce>(
+                    curve_type,
+                    &file.pubkey,
+                    &staker_address,
+                    file.epoch,
+                    realm_id,
+                    &key_cache,
+                )
+                .await
+                {
+                    Ok(nonce) => self.add_storage_key(file.pubkey, &nonce),
+                    Err(e) => error!("Error reading FROST nonce from disk: {}", e),
+                }
+            }
+        }
+    }
+
+    #[doc = "Clears the nonce lists, and deletes every nonce on disk."]
+    #[instrument(level = "debug", skip_all)]
+    async fn clear(&mut self) {
+        self.pool.clear();
+
+        // Followers hold nonces that aren't in any of our lists, so go by what's on disk.  This
+        // finishes before any newly generated nonces are stored.
+        let staker_address = self.tss_state.peer_state.hex_staker_address();
+        let key_cache = &self.tss_state.key_cache;
+        for curve_type in frost_curve_types() {
+            let Ok(files) = list_frost_nonces_on_disk(curve_type, &staker_address).await else {
+                continue;
+            };
+            for file in files {
+                if let Err(e) = delete_frost_nonce(
+                    curve_type,
+                    &file.pubkey,
+                    &staker_address,
+                    file.epoch,
+                    file.realm_id,
+                    key_cache,
+                )
+                .await
+                {
+                    error!("Error deleting FROST nonce: {:?}", e);
+                }
+            }
+        }
+        info!("Cleared FROST nonce lists and deleted the nonces on disk.");
+    }
+}
+
+// schemes on the same curve store their nonces in the same directory
+fn frost_curve_types() -> Vec<CurveType> {
+    let mut curve_types = Vec::new();
+    for signing_scheme in FROST_SIGNING_SCHEMES {
+        if !curve_types.contains(&signing_scheme.curve_type()) {
+            curve_types.push(signing_scheme.curve_type());
+        }
+    }
+    curve_types
+}
+
+// nonces are deleted as they are read, so that they are never used twice
+async fn take_frost_nonce_from_disk(
+    curve_type: CurveType,
+    storage_key: &str,
+    staker_address: &str,
+    epoch: u64,
+    realm_id: u64,
+    key_cache: &KeyCache,
+) -> Result<FrostNonce> {
+    let nonce = read_frost_nonce_from_disk::<FrostNonce>(
+        curve_type,
+        storage_key,
+        staker_address,
+        epoch,
+        realm_id,
+        key_cache,
+    )
+    .await?;
+    delete_frost_nonce(
+        curve_type,
+        storage_key,
+        staker_address,
+        epoch,
+        realm_id,
+        key_cache,
+    )
+    .await?;
+    Ok(nonce)
+}
+
+async fn return_error_to_requester(
+    tx: Sender<Result<Option<FrostNonce>>>,
+    error: crate::error::Error,
+) {
+    error!("Error getting FROST nonce: {}", error);
+    if tx.send_async(Err(error)).await.is_err() {
+        error!("Error returning FROST nonce error through channel.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::take_frost_nonce_from_disk;
+    use crate::common::key_helper::KeyCache;
+    use crate::tasks::frost_nonce_manager::models::tests::{nonce, peers};
+    use crate::tss::common::storage::write_frost_nonce_to_disk;
+    use lit_node_core::{CompressedHex, CurveType};
+
+    #[tokio::test]
+    async fn nonces_are_deleted_as_they_are_taken() {
+        let staker_address =
+            (k256::ProjectivePoint::GENERATOR * k256::Scalar::from(139u64)).to_compressed_hex();
+        let key_cache = KeyCache::default();
+        let nonce = nonce(&peers(&[1, 2, 3]));
+        let storage_key = "0123456789abcdef";
+
+        write_frost_nonce_to_disk(
+            CurveType::K256,
+            storage_key,
+            &staker_address,
+            1,
+            1,
+            &key_cache,
+            &nonce,
+        )
+        .await
+        .unwrap();
+
+        let taken = take_frost_nonce_from_disk(
+            CurveType::K256,
+            storage_key,
+            &staker_address,
+            1,
+            1,
+            &key_cache,
+        )
+        .await
+        .unwrap();
+        assert_eq!(taken.peer_group_id, nonce.peer_group_id);
+        assert_eq!(taken.staker_hashes, nonce.staker_hashes);
+
+        let taken_again = take_frost_nonce_from_disk(
+            CurveType::K256,
+            storage_key,
+            &staker_address,
+            1,
+            1,
+            &key_cache,
+        )
+        .await;
+        assert!(taken_again.is_err());
+    }
+}
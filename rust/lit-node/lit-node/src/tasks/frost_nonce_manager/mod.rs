@@ -0,0 +1,24 @@
+pub mod listener;
+pub mod models;
+
+use self::models::{FrostNonceManager, FrostNonceMessage, NoncePool};
+use crate::tss::common::tss_state::TssState;
+use std::sync::Arc;
+
+impl FrostNonceManager {
+    pub fn new(
+        rx: flume::Receiver<FrostNonceMessage>,
+        tx: flume::Sender<FrostNonceMessage>,
+        tss_state: Arc<TssState>,
+    ) -> Self {
+        info!("Creating new FROST nonce manager");
+
+        FrostNonceManager {
+            rx,
+            tx,
+            tss_state,
+            pool: NoncePool::new(0, 10),
+            epoch: 0,
+        }
+    }
+}
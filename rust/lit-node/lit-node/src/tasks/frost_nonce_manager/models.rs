@@ -0,0 +1,398 @@
+use crate::error::{Result, unexpected_err};
+use crate::peers::peer_state::models::SimplePeerCollection;
+use crate::tasks::presign_manager::models::{PeerGroupId, generate_hash};
+use crate::tss::common::tss_state::TssState;
+use flume::{Receiver, Sender};
+use lit_frost::{SigningCommitments, SigningNonces};
+use lit_node_core::PeerId;
+use lit_node_core::SigningScheme;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct FrostNonceManager {
+    pub rx: Receiver<FrostNonceMessage>,
+    pub tx: Sender<FrostNonceMessage>,
+    pub tss_state: Arc<TssState>,
+    pub pool: NoncePool,
+    // nonces are only valid in the epoch they were generated in
+    pub epoch: u64,
+}
+
+/// The nonces this node hands out as leader, and the peer groups it asked to generate more.
+#[derive(Debug, Default)]
+pub struct NoncePool {
+    pub min_nonces: u64,
+    pub max_nonces: u64,
+    // storage keys of the nonces this node hands out as leader, by scheme and peer group
+    pub nonce_lists: HashMap<SigningScheme, NonceListByGroup>,
+    // staker hashes of the peers that generated each group's nonces
+    pub peer_groups: HashMap<PeerGroupId, Vec<u64>>,
+    // groups that the leader asked to generate more nonces, until they are stored
+    pub generating: HashSet<(SigningScheme, PeerGroupId)>,
+}
+
+impl NoncePool {
+    pub fn new(min_nonces: u64, max_nonces: u64) -> Self {
+        NoncePool {
+            min_nonces,
+            max_nonces,
+            ..Default::default()
+        }
+    }
+
+    #[doc = "Takes the next nonces for the signing peers out of the pool, so that they are never handed out twice, and decides how many more the peers should generate.  The storage key is empty if the pool has no nonces for the signing peers."]
+    pub fn next_storage_key(
+        &mut self,
+        peers: &SimplePeerCollection,
+        signing_scheme: SigningScheme,
+    ) -> FrostNonceLeaderResponse {
+        let signing_group_id = peers.peer_group_id();
+        let keys = peers.peer_keys();
+        let list = self.nonce_lists.entry(signing_scheme).or_default();
+
+        // any group that every signing peer was part of will do
+        let group = list.iter_mut().find(|(peer_group_id, storage_keys)| {
+            !storage_keys.is_empty()
+                && self
+                    .peer_groups
+                    .get(*peer_group_id)
+                    .is_some_and(|staker_hashes| keys.iter().all(|k| staker_hashes.contains(k)))
+        });
+        let (storage_key, remaining) = match group {
+            Some((_, storage_keys)) => (
+                storage_keys.pop_front().unwrap_or_default(),
+                storage_keys.len() as u64,
+            ),
+            None => (String::new(), 0),
+        };
+
+        // the leader decides for every signing peer, so that they all join the same generation
+        let generate_count = match remaining <= self.min_nonces {
+            true => self.max_nonces.saturating_sub(remaining),
+            false => 0,
+        };
+        let generate_count = match generate_count > 0
+            && self.generating.insert((signing_scheme, signing_group_id))
+        {
+            true => generate_count,
+            false => 0,
+        };
+
+        FrostNonceLeaderResponse {
+            storage_key,
+            generate_count,
+        }
+    }
+
+    pub fn add_peer_group(&mut self, nonce: &FrostNonce) {
+        self.peer_groups
+            .entry(nonce.peer_group_id)
+            .or_insert_with(|| nonce.staker_hashes.clone());
+    }
+
+    pub fn push(&mut self, storage_key: NonceStorageKey, nonce: &FrostNonce) {
+        self.add_peer_group(nonce);
+        self.nonce_lists
+            .entry(nonce.signing_scheme)
+            .or_default()
+            .entry(nonce.peer_group_id)
+            .or_default()
+            .push_back(storage_key);
+    }
+
+    pub fn generation_finished(
+        &mut self,
+        signing_scheme: SigningScheme,
+        peer_group_id: PeerGroupId,
+    ) {
+        self.generating.remove(&(signing_scheme, peer_group_id));
+    }
+
+    pub fn clear(&mut self) {
+        self.nonce_lists.clear();
+        self.peer_groups.clear();
+        self.generating.clear();
+    }
+}
+
+/// Nonces that this node generated for one future FROST signature, along with the commitments
+/// that every peer in the group made to their own nonces for the same signature.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrostNonce {
+    pub signing_scheme: SigningScheme,
+    pub nonces: SigningNonces,
+    pub commitments: Vec<(PeerId, SigningCommitments)>,
+    pub peer_group_id: PeerGroupId,
+    pub staker_hashes: Vec<u64>,
+}
+
+impl FrostNonce {
+    pub fn new(
+        signing_scheme: SigningScheme,
+        nonces: SigningNonces,
+        commitments: Vec<(PeerId, SigningCommitments)>,
+        peers: &SimplePeerCollection,
+    ) -> Self {
+        FrostNonce {
+            signing_scheme,
+            nonces,
+            commitments,
+            peer_group_id: peers.peer_group_id(),
+            staker_hashes: peers.peer_keys(),
+        }
+    }
+
+    #[doc = "Returns the commitments of the signing peers, which must all have generated nonces for this signature.  Those of the other peers in the group are left out, and their nonces are never used."]
+    pub fn commitments_for_peers(
+        &self,
+        peers: &SimplePeerCollection,
+    ) -> Result<Vec<(PeerId, SigningCommitments)>> {
+        peers
+            .0
+            .iter()
+            .map(|peer| {
+                self.commitments
+                    .iter()
+                    .find(|(peer_id, _)| *peer_id == peer.peer_id)
+                    .cloned()
+                    .ok_or_else(|| {
+                        unexpected_err(
+                            format!("Peer {} has no commitments for these nonces", peer.peer_id),
+                            None,
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    pub fn own_commitments(&self, peer_id: PeerId) -> Result<SigningCommitments> {
+        self.commitments
+            .iter()
+            .find(|(id, _)| *id == peer_id)
+            .map(|(_, commitments)| commitments.clone())
+            .ok_or_else(|| unexpected_err("No commitments of our own for these nonces", None))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrostNonceRequest {
+    pub message_bytes: Vec<u8>,
+    pub txn_prefix: String,
+    pub peers: SimplePeerCollection,
+    pub signing_scheme: SigningScheme,
+}
+
+impl FrostNonceRequest {
+    // the same on every signing peer, to agree on a leader and a txn prefix
+    pub fn hash(&self) -> u64 {
+        generate_hash((&self.message_bytes, &self.txn_prefix, self.signing_scheme))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FrostNonceLeaderResponse {
+    // empty if the leader has no nonces for the signing peers
+    pub storage_key: NonceStorageKey,
+    // how many nonces the signing peers should pre-generate together, if any
+    pub generate_count: u64,
+}
+
+#[derive(Debug)]
+pub enum FrostNonceMessage {
+    RequestNonce(FrostNonceRequest, Sender<Result<Option<FrostNonce>>>),
+    FulfillNonceRequest(
+        u64,
+        FrostNonceLeaderResponse,
+        FrostNonceRequest,
+        Sender<Result<Option<FrostNonce>>>,
+    ),
+    Store(
+        Vec<(NonceStorageKey, FrostNonce)>,
+        SigningScheme,
+        PeerGroupId,
+        u64,
+    ),
+    GenerationFailed(SigningScheme, PeerGroupId),
+    Clear,
+}
+
+#[derive(Debug, Clone)]
+pub enum TxnPrefix {
+    GetNonce(u64),
+    GenerateNonces(u64, SigningScheme),
+}
+
+impl TxnPrefix {
+    pub fn as_str(&self) -> String {
+        match self {
+            Self::GetNonce(hash) => format!("GET_FROST_NONCE_{}", hash),
+            Self::GenerateNonces(hash, signing_scheme) => {
+                format!("GEN_FROST_NONCES_{}_{}", hash, signing_scheme)
+            }
+        }
+    }
+}
+
+#[doc = "Returns the pre-generated nonces to sign with, or `None` to run round 1 with the signing peers instead.  Neither a pool miss nor a failure to get nonces from the pool fails the signature."]
+pub fn pooled_nonce_or_round1(nonce: Result<Option<FrostNonce>>) -> Option<FrostNonce> {
+    nonce.unwrap_or_else(|e| {
+        warn!(
+            "Could not get pre-generated FROST nonces, running round 1 instead: {}",
+            e
+        );
+        None
+    })
+}
+
+pub type NonceStorageKey = String;
+pub type NonceListByGroup = HashMap<PeerGroupId, VecDeque<NonceStorageKey>>;
+
+// the same on every peer that generated the nonces, so they can be looked up by the leader's key
+pub fn nonce_storage_key(txn_hash: u64, signing_scheme: SigningScheme, index: usize) -> String {
+    format!(
+        "{:016x}",
+        generate_hash((txn_hash, signing_scheme, index as u64))
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::peers::peer_state::models::SimplePeer;
+    use crate::tss::common::signing_scheme::signing_scheme_to_frost_scheme;
+    use lit_frost::SigningShare;
+    use semver::Version;
+
+    const SIGNING_SCHEME: SigningScheme = SigningScheme::SchnorrK256Sha256;
+
+    pub(crate) fn peers(key_hashes: &[u64]) -> SimplePeerCollection {
+        SimplePeerCollection(
+            key_hashes
+                .iter()
+                .map(|key_hash| SimplePeer {
+                    socket_address: format!("127.0.0.1:{}", 7470 + key_hash),
+                    peer_id: PeerId::from_u8(*key_hash as u8),
+                    staker_address: Default::default(),
+                    key_hash: *key_hash,
+                    kicked: false,
+                    version: Version::new(1, 0, 0),
+                    realm_id: ethers::types::U256::from(1u64),
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn nonce(peers: &SimplePeerCollection) -> FrostNonce {
+        let scheme = signing_scheme_to_frost_scheme(SIGNING_SCHEME).unwrap();
+        let entropy = SigningShare {
+            scheme,
+            value: k256::Scalar::from(7u64).to_bytes().to_vec(),
+        };
+        let mut rng = rand::rngs::OsRng;
+        let mut nonces = None;
+        let commitments = peers
+            .0
+            .iter()
+            .map(|peer| {
+                let (n, c) = scheme.signing_round1(&entropy, &mut rng).unwrap();
+                nonces.get_or_insert(n);
+                (peer.peer_id, c)
+            })
+            .collect();
+        FrostNonce::new(SIGNING_SCHEME, nonces.unwrap(), commitments, peers)
+    }
+
+    fn pool(min_nonces: u64, max_nonces: u64, storage_keys: &[&str]) -> NoncePool {
+        let mut pool = NoncePool::new(min_nonces, max_nonces);
+        let nonce = nonce(&peers(&[1, 2, 3]));
+        for storage_key in storage_keys {
+            pool.push(storage_key.to_string(), &nonce);
+        }
+        pool
+    }
+
+    #[test]
+    fn nonces_are_handed_out_once() {
+        let mut pool = pool(0, 10, &["a", "b", "c"]);
+        let signing_peers = peers(&[1, 2, 3]);
+
+        let storage_keys = (0..3)
+            .map(|_| {
+                pool.next_storage_key(&signing_peers, SIGNING_SCHEME)
+                    .storage_key
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            storage_keys,
+            HashSet::from(["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+
+        let response = pool.next_storage_key(&signing_peers, SIGNING_SCHEME);
+        assert!(response.storage_key.is_empty());
+    }
+
+    #[test]
+    fn nonces_serve_signing_peers_that_generated_them() {
+        let mut pool = pool(0, 10, &["a"]);
+
+        // peer 4 didn't generate the nonces
+        let response = pool.next_storage_key(&peers(&[1, 4]), SIGNING_SCHEME);
+        assert!(response.storage_key.is_empty());
+        // the nonces are for another signing scheme
+        let response = pool.next_storage_key(&peers(&[1, 2]), SigningScheme::SchnorrEd25519Sha512);
+        assert!(response.storage_key.is_empty());
+        // a subset of the peers that generated them
+        let response = pool.next_storage_key(&peers(&[1, 3]), SIGNING_SCHEME);
+        assert_eq!(response.storage_key, "a");
+    }
+
+    #[test]
+    fn pool_refills_once_it_drops_to_min_nonces() {
+        let mut pool = pool(2, 5, &["a", "b", "c", "d"]);
+        let signing_peers = peers(&[1, 2, 3]);
+        let mut next = || pool.next_storage_key(&signing_peers, SIGNING_SCHEME);
+
+        // 3 left
+        assert_eq!(next().generate_count, 0);
+        // 2 left, so the peers generate up to the max
+        assert_eq!(next().generate_count, 3);
+        // 1 left, but the peers are already generating
+        assert_eq!(next().generate_count, 0);
+
+        pool.generation_finished(SIGNING_SCHEME, signing_peers.peer_group_id());
+        let response = pool.next_storage_key(&signing_peers, SIGNING_SCHEME);
+        assert_eq!(response.storage_key, "d");
+        assert_eq!(response.generate_count, 5);
+    }
+
+    #[test]
+    fn empty_pool_generates_max_nonces() {
+        let mut pool = NoncePool::new(2, 5);
+        let response = pool.next_storage_key(&peers(&[1, 2, 3]), SIGNING_SCHEME);
+        assert!(response.storage_key.is_empty());
+        assert_eq!(response.generate_count, 5);
+    }
+
+    #[test]
+    fn signing_falls_back_to_round1_without_pooled_nonces() {
+        let peers = peers(&[1, 2, 3]);
+        assert!(pooled_nonce_or_round1(Ok(Some(nonce(&peers)))).is_some());
+        // pool miss
+        assert!(pooled_nonce_or_round1(Ok(None)).is_none());
+        // e.g. the leader didn't respond
+        let error = unexpected_err("Could not receive response from FROST nonce manager", None);
+        assert!(pooled_nonce_or_round1(Err(error)).is_none());
+    }
+
+    #[test]
+    fn nonce_storage_keys_are_distinct() {
+        let keys = (0..100)
+            .map(|i| nonce_storage_key(42, SigningScheme::SchnorrEd25519Sha512, i))
+            .chain([nonce_storage_key(42, SigningScheme::SchnorrK256Taproot, 0)])
+            .collect::<HashSet<_>>();
+        assert_eq!(keys.len(), 101);
+        assert!(keys.iter().all(|k| k.len() == 16 && !k.contains('-')));
+    }
+}
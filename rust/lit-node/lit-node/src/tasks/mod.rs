@@ -1,14 +1,19 @@
 pub mod chatter_sender;
 pub mod endpoint_channels;
+pub mod frost_nonce_manager;
 pub mod fsm;
 mod payment;
 pub mod peer_checker;
 pub mod presign_manager;
 pub mod utils;
 
+use crate::access_control::cache::RpcResultCache;
+use crate::auth::revocation;
 use crate::error::Result;
+use crate::functions::schedule::Scheduler;
 use crate::functions::{ActionStore, ActionWorker};
 use crate::models::{AuthContextCache, DenoExecutionEnv};
+use crate::payment::delegated_usage::DelegatedUsageDB;
 use crate::payment::payment_tracker::PaymentTracker;
 use crate::peers::peer_reviewer::{PeerComplaint, PeerReviewer};
 use crate::siwe_db::db;
@@ -72,10 +77,12 @@ pub fn launch(
     rx_round_manager: flume::Receiver<RoundData>,
     fsm_worker_metadata: Arc<dyn FSMWorkerMetadata<LifecycleId = u64, ShadowLifecycleId = u64>>,
     payment_tracker: Arc<PaymentTracker>,
+    delegation_usage_db: Arc<DelegatedUsageDB>,
     ipfs_cache: Cache<String, Arc<String>>,
     action_store: ActionStore,
     client_state: Arc<ClientState>,
     http_client: reqwest::Client,
+    rpc_cache: RpcResultCache,
     peer_checker_tx: flume::Sender<PeerCheckerMessage>,
     peer_checker_rx: flume::Receiver<PeerCheckerMessage>,
 ) -> Result<Handle> {
@@ -227,6 +234,17 @@ pub fn launch(
                 info!("Epoch transitions disabled, not starting FSM");
             }
 
+            let tss_state_for_revocations = tss_state.clone();
+            let http_client_for_revocations = http_client.clone();
+            tasks.push(spawn(|quit_rx| async move {
+                revocation::sync_worker(
+                    quit_rx,
+                    tss_state_for_revocations,
+                    http_client_for_revocations,
+                )
+                .await;
+            }));
+
             let payment_config = tss_state.lit_config.clone();
             let peer_state_for_payment = tss_state.peer_state.clone();
             let payment_tracker_for_payment = payment_tracker.clone();
@@ -235,6 +253,8 @@ pub fn launch(
             }));
 
 
+            #[cfg(feature = "lit-actions")]
+            let payment_tracker_for_actions = payment_tracker.clone();
             let usage_config = tss_state.lit_config.clone();
             let peer_state_for_usage = tss_state.peer_state.clone();
             tasks.push(spawn(|quit_rx| async move {
@@ -262,9 +282,16 @@ pub fn launch(
 
             #[cfg(feature = "lit-actions")]
             {
-                let lit_config = cfg.clone();
+                let env = DenoExecutionEnv {
+                    tss_state: Some(tss_state.as_ref().clone()),
+                    cfg: cfg.clone(),
+                    ipfs_cache: Some(ipfs_cache),
+                    http_client: Some(http_client.clone()),
+                    rpc_cache: Some(rpc_cache),
+                };
                 let store = action_store.clone();
-                let http_cache_clone = http_client.clone();
+                let worker_env = env.clone();
+                let worker_payment_tracker = payment_tracker_for_actions.clone();
 
                 tasks.push(spawn(|mut quit_channel_rx| async move {
                     info!("Starting: action job workers");
@@ -275,21 +302,39 @@ pub fn launch(
                         Ok(())
                     };
 
-                    let worker = ActionWorker::new(
-                        store,
-                        DenoExecutionEnv {
-                            tss_state: Some(tss_state.as_ref().clone()),
-                            cfg: lit_config,
-                            ipfs_cache: Some(ipfs_cache),
-                            http_client: Some(http_cache_clone),
-                        },
-                    );
+                    let worker = ActionWorker::new(store, worker_env, Some(worker_payment_tracker));
 
                     if let Err(e) = worker.start_with_shutdown(signal).await {
                         error!("Error starting action job workers: {e:#}");
                     }
                 }));
 
+                let scheduler = Scheduler::new(
+                    action_store.clone(),
+                    env,
+                    tss_state.clone(),
+                    payment_tracker_for_actions,
+                    delegation_usage_db,
+                );
+                tasks.push(spawn(|mut quit_channel_rx| async move {
+                    info!("Starting: action scheduler");
+
+                    // Cron expressions have a resolution of a minute, so this catches every tick
+                    let mut interval = tokio::time::interval(Duration::from_secs(15));
+
+                    loop {
+                        tokio::select! {
+                            _ = quit_channel_rx.recv() => {
+                                info!("Stopped: action scheduler");
+                                break;
+                            }
+                            _ = interval.tick() => {
+                                scheduler.tick().await;
+                            }
+                        }
+                    }
+                }));
+
                 tasks.push(spawn(|mut quit_channel_rx| async move {
                     info!("Starting: action job janitor");
 
@@ -1,11 +1,18 @@
 pub mod models;
-use crate::error::{Result, unexpected_err};
+use crate::error::{EC, Result, unexpected_err, unexpected_err_code};
+use crate::peers::peer_state::models::SimplePeerCollection;
 use crate::tss::blsful::models::BlsState;
 use crate::tss::common::hd_keys::get_derived_keyshare;
 use crate::tss::common::key_share::KeyShare;
+use crate::tss::common::key_share_commitment::KeyShareCommitments;
+use crate::tss::common::storage::read_key_share_commitments_from_disk;
 use crate::tss::common::traits::signable::Signable;
 use crate::tss::common::{storage::read_key_share_from_disk, traits::cipherable::Cipherable};
-use blsful::{Pairing, SecretKeyShare, SignatureShare, vsss_rs::Share};
+use blsful::inner_types::{G1Projective, GroupEncoding};
+use blsful::{
+    Bls12381G2Impl, BlsSignaturePop, HashToPoint, Pairing, PublicKeyShare, SecretKeyShare,
+    Signature, SignatureShare, vsss_rs::Share,
+};
 use elliptic_curve::Group;
 use hd_keys_curves::HDDeriver;
 use lit_core::error::Unexpected;
@@ -13,7 +20,7 @@ use lit_core::utils::binary::bytes_to_hex;
 use lit_node_core::PeerId;
 use lit_node_core::{BlsSignedMessageShare, CurveType, NodeSet, SignableOutput, SigningScheme};
 use tracing::instrument;
-use vsss_rs::IdentifierPrimeField;
+use vsss_rs::{IdentifierPrimeField, ValueGroup};
 
 #[async_trait::async_trait]
 impl Cipherable for BlsState {
@@ -55,9 +62,72 @@ impl Cipherable for BlsState {
 
         Ok((sks, share_peer_id))
     }
+
+    #[instrument(level = "debug", skip(self, shares))]
+    async fn combine_signature_shares(
+        &self,
+        shares: &[SignatureShare<Bls12381G2Impl>],
+        message_bytes: &[u8],
+        epoch: Option<u64>,
+    ) -> Result<Signature<Bls12381G2Impl>> {
+        let dkg_state = self.state.get_dkg_state(CurveType::BLS)?;
+        let root_keys = dkg_state.root_keys().await;
+        if root_keys.is_empty() {
+            return Err(unexpected_err(
+                "No primary BLS key found!".to_string(),
+                None,
+            ));
+        }
+
+        let (epoch, peers) = self.resolve_epoch(epoch);
+        let peer_id = peers.peer_id_by_address(&self.state.addr)?;
+        let commitments =
+            read_key_share_commitments_from_disk::<KeyShareCommitments<G1Projective>>(
+                CurveType::BLS,
+                &root_keys[0],
+                &self.state.peer_state.hex_staker_address(),
+                &peer_id,
+                epoch,
+                self.state.peer_state.realm_id(),
+                &self.state.key_cache,
+            )
+            .await?;
+
+        let threshold = if epoch == self.state.peer_state.epoch() {
+            self.state.get_threshold().await
+        } else {
+            self.state
+                .get_threshold_using_current_epoch_realm_peers_for_curve(
+                    &peers,
+                    CurveType::BLS,
+                    Some(epoch),
+                )
+                .await?
+        };
+
+        combine_verified_shares(shares, &commitments, message_bytes, threshold)
+    }
 }
 #[async_trait::async_trait]
 impl Signable for BlsState {
+    fn set_proof_of_possession(&mut self) -> Result<()> {
+        match self.signing_scheme {
+            // only signatures with proof of possession can be aggregated over the same message
+            SigningScheme::Bls12381G1ProofOfPossession => {
+                self.proof_of_possession = true;
+                Ok(())
+            }
+            _ => Err(unexpected_err_code(
+                format!(
+                    "Proofs of possession are not supported by {}",
+                    self.signing_scheme
+                ),
+                EC::NodeSignatureNotSupported,
+                None,
+            )),
+        }
+    }
+
     async fn sign_with_pubkey(
         &mut self,
         message_bytes: &[u8],
@@ -116,7 +186,12 @@ impl Signable for BlsState {
             self.signing_scheme.id_sign_ctx(),
         );
         match self.signing_scheme {
-            SigningScheme::Bls12381G1ProofOfPossession => {
+            SigningScheme::Bls12381 | SigningScheme::Bls12381G1ProofOfPossession => {
+                // Ethereum consensus signs with proof of possession, Filecoin with the basic scheme
+                let bls_scheme = match self.signing_scheme {
+                    SigningScheme::Bls12381 => blsful::SignatureSchemes::Basic,
+                    _ => blsful::SignatureSchemes::ProofOfPossession,
+                };
                 let (sk, vk) = get_derived_keyshare::<blsful::inner_types::G1Projective>(
                     deriver,
                     &root_keys,
@@ -139,11 +214,15 @@ impl Signable for BlsState {
                         IdentifierPrimeField(sk),
                     ),
                 );
-                let signature_share: SignatureShare<blsful::Bls12381G2Impl> = secret_key_share
-                    .sign(blsful::SignatureSchemes::ProofOfPossession, message_bytes)
-                    .map_err(|e| {
-                        unexpected_err(e, Some("unable to generate signature".to_string()))
-                    })?;
+                let signature_share = if self.proof_of_possession {
+                    proof_of_possession_share(identifier, sk, vk, message_bytes)?
+                } else {
+                    secret_key_share
+                        .sign(bls_scheme, message_bytes)
+                        .map_err(|e| {
+                            unexpected_err(e, Some("unable to generate signature".to_string()))
+                        })?
+                };
                 let verifying_share = secret_key_share.public_key().map_err(|e| {
                     unexpected_err(e, Some("unable to generate verifying share".to_string()))
                 })?;
@@ -170,13 +249,79 @@ impl Signable for BlsState {
     }
 }
 
+#[doc = "A share of the proof of possession of a key, the same as `ProofOfPossession::new` computes with the whole secret key.  The message must be the public key."]
+fn proof_of_possession_share(
+    identifier: blsful::inner_types::Scalar,
+    secret_share: blsful::inner_types::Scalar,
+    public_key: blsful::inner_types::G1Projective,
+    message_bytes: &[u8],
+) -> Result<SignatureShare<blsful::Bls12381G2Impl>> {
+    let public_key = public_key.to_bytes();
+    if message_bytes != public_key.as_ref() {
+        return Err(unexpected_err(
+            "A proof of possession must sign the public key of the PKP",
+            None,
+        ));
+    }
+    let point = <blsful::Bls12381G2Impl as HashToPoint>::hash_to_point(
+        public_key,
+        <blsful::Bls12381G2Impl as BlsSignaturePop>::POP_DST,
+    ) * secret_share;
+    let share = <blsful::Bls12381G2Impl as Pairing>::SignatureShare::with_identifier_and_value(
+        IdentifierPrimeField(identifier),
+        ValueGroup(point),
+    );
+    Ok(SignatureShare::ProofOfPossession(share))
+}
+
+#[doc = "Combines the signature shares that verify against the verifying share the DKG commitments give for their identifier, counting each identifier once, as long as at least `threshold` of them do."]
+fn combine_verified_shares(
+    shares: &[SignatureShare<Bls12381G2Impl>],
+    commitments: &KeyShareCommitments<G1Projective>,
+    message_bytes: &[u8],
+    threshold: usize,
+) -> Result<Signature<Bls12381G2Impl>> {
+    let mut identifiers = Vec::with_capacity(shares.len());
+    let mut verified = Vec::with_capacity(shares.len());
+    for share in shares {
+        let identifier = share.as_raw_value().identifier().0;
+        if identifiers.contains(&identifier) {
+            warn!("Dropping duplicate signature share");
+            continue;
+        }
+        let verifying_share = PublicKeyShare(
+            <Bls12381G2Impl as Pairing>::PublicKeyShare::with_identifier_and_value(
+                IdentifierPrimeField(identifier),
+                ValueGroup(commitments.compute_key_share_commitment(&identifier)),
+            ),
+        );
+        match share.verify(&verifying_share, message_bytes) {
+            Ok(()) => {
+                identifiers.push(identifier);
+                verified.push(*share);
+            }
+            Err(e) => warn!("Dropping invalid signature share: {:?}", e),
+        }
+    }
+
+    if verified.len() < threshold {
+        return Err(unexpected_err(
+            format!(
+                "Only {} of {} signature shares are valid, {} are needed",
+                verified.len(),
+                shares.len(),
+                threshold
+            ),
+            None,
+        ));
+    }
+    Signature::from_shares(&verified)
+        .map_err(|e| unexpected_err(e, Some("Failed to combine signature shares".into())))
+}
+
 impl BlsState {
-    pub(crate) async fn get_keyshare(
-        &self,
-        pubkey: &str,
-        epoch: Option<u64>,
-    ) -> Result<(SecretKeyShare<blsful::Bls12381G2Impl>, PeerId)> {
-        let realm_id = self.state.peer_state.realm_id();
+    /// The epoch to use for a request for `epoch`, and the peers of that epoch.
+    fn resolve_epoch(&self, epoch: Option<u64>) -> (u64, SimplePeerCollection) {
         let self_epoch = self.state.peer_state.epoch();
 
         let epoch = match epoch {
@@ -193,12 +338,19 @@ impl BlsState {
             None => self_epoch,
         };
 
-        let (epoch, peers) = match self_epoch - epoch {
+        match self_epoch - epoch {
             0 => (epoch, self.state.peer_state.peers()),
             1 => (epoch, self.state.peer_state.peers_in_prior_epoch()),
             _ => (self_epoch, self.state.peer_state.peers()),
-        };
+        }
+    }
 
+    pub(crate) async fn get_keyshare(
+        &self,
+        pubkey: &str,
+        epoch: Option<u64>,
+    ) -> Result<(SecretKeyShare<blsful::Bls12381G2Impl>, PeerId)> {
+        let (epoch, peers) = self.resolve_epoch(epoch);
         let peer_id = peers.peer_id_by_address(&self.state.addr)?;
 
         let staker_address = &self.state.peer_state.hex_staker_address();
@@ -229,3 +381,139 @@ impl BlsState {
         Ok((secret_key_share, peer_id))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{combine_verified_shares, proof_of_possession_share};
+    use crate::tss::common::key_share_commitment::KeyShareCommitments;
+    use blsful::inner_types::{G1Projective, GroupEncoding, Scalar};
+    use blsful::vsss_rs::Share;
+    use blsful::{
+        Bls12381G2Impl, Pairing, PublicKey, SecretKey, SecretKeyShare, Signature, SignatureSchemes,
+        SignatureShare,
+    };
+    use vsss_rs::IdentifierPrimeField;
+
+    const MESSAGE: &[u8] = b"deterministic seed";
+
+    /// Shares of `secret` on a degree `coefficients.len()` polynomial, for identifiers 1..=5,
+    /// with the Feldman commitments a DKG would store for them.
+    fn split(
+        secret: Scalar,
+        coefficients: &[Scalar],
+    ) -> (
+        Vec<SecretKeyShare<Bls12381G2Impl>>,
+        KeyShareCommitments<G1Projective>,
+    ) {
+        let polynomial = [&[secret], coefficients].concat();
+        let shares = (1..=5u64)
+            .map(|id| {
+                let x = Scalar::from(id);
+                let value = polynomial
+                    .iter()
+                    .rev()
+                    .fold(Scalar::from(0u64), |acc, c| acc * x + c);
+                SecretKeyShare(
+                    <Bls12381G2Impl as Pairing>::SecretKeyShare::with_identifier_and_value(
+                        IdentifierPrimeField(x),
+                        IdentifierPrimeField(value),
+                    ),
+                )
+            })
+            .collect();
+        let commitments = KeyShareCommitments {
+            dkg_id: "test".to_string(),
+            commitments: polynomial
+                .iter()
+                .map(|c| G1Projective::GENERATOR * c)
+                .collect(),
+        };
+        (shares, commitments)
+    }
+
+    fn sign(
+        share: &SecretKeyShare<Bls12381G2Impl>,
+        message: &[u8],
+    ) -> SignatureShare<Bls12381G2Impl> {
+        share
+            .sign(SignatureSchemes::ProofOfPossession, message)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_combine_verified_shares_drops_invalid_shares() {
+        let secret = Scalar::from(42u64);
+        let (key_shares, commitments) = split(secret, &[Scalar::from(7u64), Scalar::from(11u64)]);
+
+        let mut shares = key_shares
+            .iter()
+            .map(|share| sign(share, MESSAGE))
+            .collect::<Vec<_>>();
+        shares[0] = sign(&key_shares[0], b"something else");
+        shares[1] = shares[2];
+
+        let signature = combine_verified_shares(&shares, &commitments, MESSAGE, 3).unwrap();
+        let public_key = SecretKey::<Bls12381G2Impl>(secret).public_key();
+        assert!(signature.verify(&public_key, MESSAGE).is_ok());
+    }
+
+    #[test]
+    fn test_combine_verified_shares_needs_threshold_valid_shares() {
+        let (key_shares, commitments) = split(
+            Scalar::from(42u64),
+            &[Scalar::from(7u64), Scalar::from(11u64)],
+        );
+
+        let mut shares = key_shares
+            .iter()
+            .map(|share| sign(share, MESSAGE))
+            .collect::<Vec<_>>();
+        shares[0] = sign(&key_shares[0], b"something else");
+        shares[1] = sign(&key_shares[1], b"something else");
+        shares[2] = shares[3];
+
+        assert!(combine_verified_shares(&shares, &commitments, MESSAGE, 3).is_err());
+    }
+
+    #[test]
+    fn test_proof_of_possession_shares_combine_to_proof_of_possession() {
+        let secret = Scalar::from(42u64);
+        let (key_shares, _) = split(secret, &[Scalar::from(7u64), Scalar::from(11u64)]);
+        let public_key = G1Projective::GENERATOR * secret;
+        let message = public_key.to_bytes();
+
+        let shares = key_shares
+            .iter()
+            .skip(1)
+            .map(|share| {
+                proof_of_possession_share(
+                    share.0.identifier().0,
+                    share.0.value().0,
+                    public_key,
+                    message.as_ref(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let expected = SecretKey::<Bls12381G2Impl>(secret)
+            .proof_of_possession()
+            .unwrap();
+        match Signature::from_shares(&shares).unwrap() {
+            Signature::ProofOfPossession(signature) => assert_eq!(signature, expected.0),
+            signature => panic!("expected a proof of possession, got {signature:?}"),
+        }
+        assert!(
+            expected
+                .verify(PublicKey::<Bls12381G2Impl>(public_key))
+                .is_ok()
+        );
+
+        // only the public key of the PKP can be signed this way
+        let share = &key_shares[0].0;
+        assert!(
+            proof_of_possession_share(share.identifier().0, share.value().0, public_key, MESSAGE)
+                .is_err()
+        );
+    }
+}
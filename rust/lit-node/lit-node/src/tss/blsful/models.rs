@@ -8,6 +8,8 @@ pub struct BlsState {
     pub state: Arc<TssState>,
     pub dkg_type: DkgType,
     pub signing_scheme: SigningScheme,
+    /// Signs the PKP's public key as a proof of possession, instead of the message
+    pub proof_of_possession: bool,
 }
 
 impl BlsState {
@@ -24,6 +26,7 @@ impl BlsState {
             state,
             signing_scheme,
             dkg_type,
+            proof_of_possession: false,
         }
     }
 }
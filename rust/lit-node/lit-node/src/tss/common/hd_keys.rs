@@ -8,6 +8,7 @@ use elliptic_curve::group::GroupEncoding;
 use hd_keys_curves::{HDDerivable, HDDeriver};
 use lit_node_core::CompressedBytes;
 use lit_node_core::CurveType;
+use lit_node_core::DerivationPath;
 use lit_node_core::PeerId;
 use tracing::instrument;
 
@@ -70,3 +71,29 @@ where
 
     Ok((derived_secret, derived_pubkey))
 }
+
+#[doc = "Derives the key share and public key of a child of the PKP at a BIP-32 style path.  The derivation is public, so every node adds the same tweak to its share of the PKP's key."]
+pub fn derive_child_keyshare<G>(
+    derivation_path: &DerivationPath,
+    curve_type: CurveType,
+    secret_share: G::Scalar,
+    public_key: G,
+) -> Result<(G::Scalar, G)>
+where
+    G: HDDerivable + GroupEncoding + Default + CompressedBytes,
+    G::Scalar: HDDeriver + CompressedBytes,
+{
+    let (child_secret, child_public_key) = derivation_path
+        .derive_key_share(
+            curve_type,
+            &secret_share.to_compressed(),
+            &public_key.to_compressed(),
+        )
+        .map_err(|e| unexpected_err(e, Some(format!("Could not derive {}", derivation_path))))?;
+
+    let child_secret = G::Scalar::from_compressed(&child_secret)
+        .ok_or_else(|| unexpected_err("Invalid derived secret share", None))?;
+    let child_public_key = G::from_compressed(&child_public_key)
+        .ok_or_else(|| unexpected_err("Invalid derived public key", None))?;
+    Ok((child_secret, child_public_key))
+}
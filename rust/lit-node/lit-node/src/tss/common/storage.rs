@@ -6,7 +6,9 @@ use crate::error::{EC, Error, Result, io_err, io_err_code, unexpected_err, unexp
 use async_std::path::PathBuf;
 use async_std::stream::StreamExt;
 use glob::glob;
-use lit_node_common::config::{key_commitment_path, presign_path, segmented_paths, typed_key_path};
+use lit_node_common::config::{
+    frost_nonce_path, key_commitment_path, presign_path, segmented_paths, typed_key_path,
+};
 use lit_node_core::CurveType;
 use lit_node_core::PeerId;
 use serde::{Serialize, de::DeserializeOwned};
@@ -17,6 +19,7 @@ pub enum StorageType {
     KeyShare(CurveType),
     Presign(CurveType),
     KeyShareCommitment(CurveType),
+    FrostNonce(CurveType),
 }
 
 impl StorageType {
@@ -33,6 +36,9 @@ impl StorageType {
                 Ok(presign_path(curve_type.as_str(), staker_address))
             }
             StorageType::KeyShareCommitment(_) => Ok(key_commitment_path(staker_address)),
+            StorageType::FrostNonce(curve_type) => {
+                Ok(frost_nonce_path(curve_type.as_str(), staker_address))
+            }
         }
     }
 
@@ -41,6 +47,7 @@ impl StorageType {
             StorageType::KeyShare(_) => "Key",
             StorageType::Presign(_) => "Presign",
             StorageType::KeyShareCommitment(_) => "KeyShareCommitment",
+            StorageType::FrostNonce(_) => "FrostNonce",
         }
     }
 }
@@ -51,6 +58,7 @@ impl From<StorageType> for CurveType {
             StorageType::KeyShare(curve_type) => curve_type,
             StorageType::Presign(curve_type) => curve_type,
             StorageType::KeyShareCommitment(curve_type) => curve_type,
+            StorageType::FrostNonce(curve_type) => curve_type,
         }
     }
 }
@@ -297,6 +305,99 @@ pub(crate) async fn delete_presign(
     delete_from_disk(path, key_cache).await
 }
 
+/**************** FROST NONCES ****************/
+
+#[doc = "Reads pre-generated FROST nonces from disk"]
+#[instrument(level = "debug", name = "read_frost_nonce_from_disk", skip(key_cache))]
+pub async fn read_frost_nonce_from_disk<T>(
+    curve_type: CurveType,
+    tag: &str,
+    staker_address: &str,
+    epoch: u64,
+    realm_id: u64,
+    key_cache: &KeyCache,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let storable_file = StorableFile {
+        storage_type: StorageType::FrostNonce(curve_type),
+        pubkey: tag.to_string(),
+        peer_id: PeerId::ONE,
+        epoch,
+        realm_id,
+    };
+    let path = storable_file.get_full_path(staker_address).await?;
+    do_read_from_disk(&path, key_cache, KeyCacheType::Protected).await
+}
+
+#[doc = "Writes pre-generated FROST nonces to disk"]
+#[instrument(level = "debug", name = "write_frost_nonce_to_disk", skip_all)]
+pub async fn write_frost_nonce_to_disk<T>(
+    curve_type: CurveType,
+    tag: &str,
+    staker_address: &str,
+    epoch: u64,
+    realm_id: u64,
+    key_cache: &KeyCache,
+    nonce: &T,
+) -> Result<()>
+where
+    T: Serialize + Sync,
+{
+    let storable_file = StorableFile {
+        storage_type: StorageType::FrostNonce(curve_type),
+        pubkey: tag.to_string(),
+        peer_id: PeerId::ONE,
+        epoch,
+        realm_id,
+    };
+    let path = storable_file.get_full_path(staker_address).await?;
+    do_write_to_disk(&path, key_cache, KeyCacheType::Protected, nonce).await
+}
+
+#[doc = "Delete pre-generated FROST nonces from disk."]
+#[instrument(level = "debug", name = "delete_frost_nonce", skip(key_cache))]
+pub(crate) async fn delete_frost_nonce(
+    curve_type: CurveType,
+    tag: &str,
+    staker_address: &str,
+    epoch: u64,
+    realm_id: u64,
+    key_cache: &KeyCache,
+) -> Result<()> {
+    let storable_file = StorableFile {
+        storage_type: StorageType::FrostNonce(curve_type),
+        pubkey: tag.to_string(),
+        peer_id: PeerId::ONE,
+        epoch,
+        realm_id,
+    };
+    let path = storable_file.get_full_path(staker_address).await?;
+    delete_from_disk(path, key_cache).await
+}
+
+#[doc = "Lists the pre-generated FROST nonces on disk, of any epoch"]
+#[instrument(level = "debug", name = "list_frost_nonces_on_disk")]
+pub(crate) async fn list_frost_nonces_on_disk(
+    curve_type: CurveType,
+    staker_address: &str,
+) -> Result<Vec<StorableFile>> {
+    let storage_type = StorageType::FrostNonce(curve_type);
+    let pattern = storage_type
+        .get_root_dir(staker_address)?
+        .join("**")
+        .join(format!(
+            "{}-H-{}-*.cbor",
+            storage_type.file_name_prefix(),
+            curve_type as u8,
+        ));
+    files_with_pattern(&pattern.display().to_string())?
+        .iter()
+        .map(StorableFile::try_from)
+        .collect()
+}
+
 /**************** BACKUP KEYS ****************/
 
 #[doc = "Reads a key share commitment from disk"]
@@ -615,6 +716,7 @@ impl TryFrom<&str> for StorableFile {
             ("Key-H-", "Key"),
             ("Presign-H-", "Presign"),
             ("KeyShareCommitment-H-", "KeyShareCommitment"),
+            ("FrostNonce-H-", "FrostNonce"),
         ];
 
         let (storage, file_name) = PREFIX_MAPPINGS
@@ -645,10 +747,11 @@ impl TryFrom<&str> for StorableFile {
             "Key" => StorageType::KeyShare(curve_type),
             "Presign" => StorageType::Presign(curve_type),
             "KeyShareCommitment" => StorageType::KeyShareCommitment(curve_type),
+            "FrostNonce" => StorageType::FrostNonce(curve_type),
             _ => {
                 return Err(unexpected_err(
                     format!(
-                        "{} is not a valid key file name. Expected 'Key', 'Presign', 'KeyShareCommitment'",
+                        "{} is not a valid key file name. Expected 'Key', 'Presign', 'KeyShareCommitment', 'FrostNonce'",
                         file_name
                     ),
                     None,
@@ -983,6 +1086,11 @@ mod test {
         let res_storable_file = StorableFile::try_from(&file_name).unwrap();
         assert_eq!(res_storable_file, storable_file);
 
+        storable_file.storage_type = StorageType::FrostNonce(CurveType::Ed25519);
+        let file_name = storable_file.file_name();
+        let res_storable_file = StorableFile::try_from(&file_name).unwrap();
+        assert_eq!(res_storable_file, storable_file);
+
         let file_name = storable_file.file_name();
         let res_storable_file = StorableFile::try_from(&file_name).unwrap();
         assert_eq!(res_storable_file, storable_file);
@@ -1,5 +1,5 @@
 use crate::error::Result; // EC , conversion_err_code
-use blsful::{Bls12381G2Impl, SignatureShare};
+use blsful::{Bls12381G2Impl, Signature, SignatureShare};
 use lit_node_core::PeerId;
 use std::fmt::Debug;
 
@@ -17,4 +17,14 @@ pub trait Cipherable: Debug + Send + Sync {
         public_key: &str,
         epoch: Option<u64>,
     ) -> Result<(SignatureShare<Bls12381G2Impl>, PeerId)>;
+
+    /// Combines shares of a signature over `message_bytes` by the primary key, as returned by
+    /// [`Cipherable::sign`] on each node.  Shares that don't verify against their node's
+    /// verifying share are dropped, and at least a threshold of valid shares is required.
+    async fn combine_signature_shares(
+        &self,
+        shares: &[SignatureShare<Bls12381G2Impl>],
+        message_bytes: &[u8],
+        epoch: Option<u64>,
+    ) -> Result<Signature<Bls12381G2Impl>>;
 }
@@ -1,5 +1,5 @@
-use crate::error::Result;
-use lit_node_core::{NodeSet, SignableOutput};
+use crate::error::{EC, Result, unexpected_err_code};
+use lit_node_core::{DerivationPath, NodeSet, SignableOutput};
 use std::fmt::Debug;
 
 #[async_trait::async_trait]
@@ -16,7 +16,62 @@ pub trait Signable: Debug + Send + Sync {
         nodeset: &[NodeSet],
     ) -> Result<SignableOutput>;
 
+    #[doc = "Signs several messages with the same public key, returning the outputs in the order of the messages.  Signers that exchange data with their peers for each signature should do so once for the whole batch; by default the messages are signed one after another."]
+    #[allow(clippy::too_many_arguments)]
+    async fn sign_batch_with_pubkey(
+        &mut self,
+        messages: &[Vec<u8>],
+        public_key: Vec<u8>,
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        request_id: Vec<u8>,
+        epoch: Option<u64>,
+        nodeset: &[NodeSet],
+    ) -> Result<Vec<SignableOutput>> {
+        let mut outputs = Vec::with_capacity(messages.len());
+        for (i, message_bytes) in messages.iter().enumerate() {
+            let output = self
+                .sign_with_pubkey(
+                    message_bytes,
+                    public_key.clone(),
+                    root_pubkeys.clone(),
+                    tweak_preimage.clone(),
+                    batch_item_request_id(&request_id, i),
+                    epoch,
+                    nodeset,
+                )
+                .await?;
+            outputs.push(output);
+        }
+        Ok(outputs)
+    }
+
+    #[doc = "Signs with the child of the key at a BIP-32 style path, instead of the key itself."]
+    fn set_derivation_path(&mut self, derivation_path: DerivationPath) -> Result<()> {
+        Err(unexpected_err_code(
+            format!("Can't sign with the child key at {}", derivation_path),
+            EC::NodeSignatureNotSupported,
+            Some("Derivation paths are only supported for Secp256k1 and Ed25519 keys".into()),
+        ))
+    }
+
+    #[doc = "Signs the public key of the key as a proof of possession of it, instead of the message."]
+    fn set_proof_of_possession(&mut self) -> Result<()> {
+        Err(unexpected_err_code(
+            "Can't prove possession of the key",
+            EC::NodeSignatureNotSupported,
+            Some("Proofs of possession are only supported for BLS keys".into()),
+        ))
+    }
+
     fn failed_message_share(&self) -> SignableOutput {
         SignableOutput::ecdsa_failed_message_share()
     }
 }
+
+#[doc = "The request id of one message of a batch, which is the same on every node."]
+pub fn batch_item_request_id(request_id: &[u8], index: usize) -> Vec<u8> {
+    let mut item_request_id = request_id.to_vec();
+    item_request_id.extend_from_slice(format!("_{}", index).as_bytes());
+    item_request_id
+}
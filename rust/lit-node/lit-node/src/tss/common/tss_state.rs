@@ -156,7 +156,7 @@ impl TssState {
             | SigningScheme::SchnorrkelSubstrate => {
                 Box::new(FrostState::new(state, signing_scheme)) as Box<dyn Signable>
             }
-            SigningScheme::Bls12381G1ProofOfPossession => {
+            SigningScheme::Bls12381 | SigningScheme::Bls12381G1ProofOfPossession => {
                 Box::new(BlsState::new(state, signing_scheme)) as Box<dyn Signable>
             }
             _ => {
@@ -1,8 +1,8 @@
-use crate::error::unexpected_err;
+use crate::error::{EC, unexpected_err, unexpected_err_code};
 use crate::metrics;
 use crate::p2p_comms::CommsManager;
 use crate::tasks::presign_manager::models::{Presign, PresignMessage, PresignRequest};
-use crate::tss::common::hd_keys::get_derived_keyshare;
+use crate::tss::common::hd_keys::{derive_child_keyshare, get_derived_keyshare};
 use crate::version::DataVersionReader;
 use crate::{
     error::Result,
@@ -16,11 +16,11 @@ use lit_fast_ecdsa::{
     ParticipantList, PreSignature, PreSignatureParams, PreSignatureParticipant, RoundPayload,
     SignatureShare,
 };
-use lit_node_core::{EcdsaSignedMessageShare, NodeSet, SignableOutput};
+use lit_node_core::{DerivationPath, EcdsaSignedMessageShare, NodeSet, SignableOutput};
 use std::ops::Add;
 use tracing::trace;
 
-use super::common::traits::signable::Signable;
+use super::common::traits::signable::{Signable, batch_item_request_id};
 use crate::tasks::utils::generate_hash;
 use crate::utils::traits::SignatureCurve;
 use elliptic_curve::generic_array::ArrayLength;
@@ -39,6 +39,8 @@ pub struct DamFastState {
     pub state: Arc<TssState>,
     pub dkg_type: DkgType,
     pub signing_scheme: SigningScheme,
+    // signs with the child key at this path, if any
+    pub derivation_path: Option<DerivationPath>,
 }
 
 impl DamFastState {
@@ -55,6 +57,7 @@ impl DamFastState {
             state,
             signing_scheme,
             dkg_type,
+            derivation_path: None,
         }
     }
 
@@ -70,31 +73,8 @@ impl DamFastState {
         C::Scalar: HDDeriver + From<PeerId> + CompressedBytes,
         <FieldBytesSize<C> as Add>::Output: ArrayLength<u8>,
     {
-        let self_peer = peers.peer_at_address(&self.state.addr)?;
-        let mut participants = Vec::with_capacity(peers.0.len());
-        let mut self_peer_ordinal = 0;
-        for (i, peer) in peers.0.iter().enumerate() {
-            if self_peer.peer_id == peer.peer_id {
-                self_peer_ordinal = i;
-            }
-            let id = C::Scalar::from(peer.peer_id);
-            participants.push(id);
-        }
-
-        trace!("Participants [{}], {:?}", participants.len(), participants);
-
-        let participant_list = ParticipantList::new(participants.as_slice())
-            .map_err(|e| unexpected_err(e, Some("Error creating participant list".to_owned())))?;
-        let params = PreSignatureParams {
-            threshold,
-            participant_list: participant_list.clone(),
-        };
-
-        trace!("Params: {:?}", params);
-        let self_participant_id =
-            Option::from(NonZeroScalar::<C>::new(participants[self_peer_ordinal])).ok_or(
-                unexpected_err("Could not get self participant id".to_string(), None),
-            )?;
+        let (params, self_participant_id) = self.presignature_params::<C>(peers, threshold)?;
+        let participant_list = params.participant_list.clone();
 
         let mut self_participant = PreSignatureParticipant::<C>::new(&self_participant_id, &params)
             .map_err(|e| unexpected_err(e, Some("Error creating participant".to_owned())))?;
@@ -179,6 +159,149 @@ impl DamFastState {
         Ok(pre_sig)
     }
 
+    #[doc = "Creates `count` presignatures with the peers at once, sending the payloads of all of them to each peer in every round."]
+    pub async fn create_presignatures_for_peers<C>(
+        &self,
+        txn_prefix: &str,
+        peers: &SimplePeerCollection,
+        threshold: usize,
+        count: usize,
+    ) -> Result<Vec<PreSignature<C>>>
+    where
+        C: PrimeCurve + CurveArithmetic + DigestPrimitive,
+        C::ProjectivePoint: GroupEncoding + HDDerivable + CompressedBytes,
+        C::Scalar: HDDeriver + From<PeerId> + CompressedBytes,
+        <FieldBytesSize<C> as Add>::Output: ArrayLength<u8>,
+    {
+        let (params, self_participant_id) = self.presignature_params::<C>(peers, threshold)?;
+        let mut self_participants = (0..count)
+            .map(|_| PreSignatureParticipant::<C>::new(&self_participant_id, &params))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| unexpected_err(e, Some("Error creating participant".to_owned())))?;
+
+        for round_no in 1..4 {
+            let cm = CommsManager::new_with_peers(
+                &self.state,
+                txn_prefix,
+                peers,
+                &format!("batch{}", round_no),
+            )
+            .await?;
+
+            // the payloads for each peer, in the order of the presignatures
+            let mut outgoing = vec![Vec::with_capacity(count); peers.0.len()];
+            for self_participant in self_participants.iter_mut() {
+                let round_generator = self_participant
+                    .run()
+                    .map_err(|e| unexpected_err(e, Some("Error running round".to_owned())))?;
+                for payload in round_generator.iter() {
+                    if payload.id.as_ref() == self_participant_id.as_ref() {
+                        continue;
+                    }
+                    outgoing[payload.ordinal].push(payload.round_payload);
+                }
+            }
+
+            for (ordinal, payloads) in outgoing.into_iter().enumerate() {
+                if payloads.is_empty() {
+                    continue;
+                }
+                let dest_peer = &peers.0[ordinal];
+                if let Err(e) = cm
+                    .send_direct::<Vec<RoundPayload<C>>>(dest_peer, payloads)
+                    .await
+                {
+                    error!(
+                        "Failed to send payloads to peer {} for round {}: {}",
+                        dest_peer.peer_id, round_no, e
+                    );
+                }
+            }
+
+            let round_data = cm.collect::<Vec<RoundPayload<C>>>().await?;
+            for (id, payloads) in round_data {
+                if payloads.len() != count {
+                    return Err(unexpected_err(
+                        format!(
+                            "Peer {} sent {} payloads for round {} instead of {}",
+                            id,
+                            payloads.len(),
+                            round_no,
+                            count
+                        ),
+                        None,
+                    ));
+                }
+                for (self_participant, payload) in self_participants.iter_mut().zip(payloads) {
+                    if let Err(e) = self_participant.receive(payload) {
+                        error!(
+                            "Failed to receive payload from peer {} for round {}: {}",
+                            id, round_no, e
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut pre_sigs = Vec::with_capacity(count);
+        for self_participant in self_participants.iter_mut() {
+            let result = self_participant
+                .run()
+                .map_err(|e| unexpected_err(e, Some("Error running final round".to_owned())))?;
+            pre_sigs.push(
+                result
+                    .output()
+                    .expect_or_err("Error getting final presig.")?,
+            );
+        }
+        debug!(
+            "Successfully generated {} presignatures for {}.",
+            count, txn_prefix
+        );
+
+        Ok(pre_sigs)
+    }
+
+    fn presignature_params<C>(
+        &self,
+        peers: &SimplePeerCollection,
+        threshold: usize,
+    ) -> Result<(PreSignatureParams<C>, NonZeroScalar<C>)>
+    where
+        C: PrimeCurve + CurveArithmetic + DigestPrimitive,
+        C::ProjectivePoint: GroupEncoding + HDDerivable + CompressedBytes,
+        C::Scalar: HDDeriver + From<PeerId> + CompressedBytes,
+        <FieldBytesSize<C> as Add>::Output: ArrayLength<u8>,
+    {
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let mut participants = Vec::with_capacity(peers.0.len());
+        let mut self_peer_ordinal = 0;
+        for (i, peer) in peers.0.iter().enumerate() {
+            if self_peer.peer_id == peer.peer_id {
+                self_peer_ordinal = i;
+            }
+            let id = C::Scalar::from(peer.peer_id);
+            participants.push(id);
+        }
+
+        trace!("Participants [{}], {:?}", participants.len(), participants);
+
+        let participant_list = ParticipantList::new(participants.as_slice())
+            .map_err(|e| unexpected_err(e, Some("Error creating participant list".to_owned())))?;
+        let params = PreSignatureParams {
+            threshold,
+            participant_list,
+        };
+
+        trace!("Params: {:?}", params);
+        let self_participant_id =
+            Option::from(NonZeroScalar::<C>::new(participants[self_peer_ordinal])).ok_or(
+                unexpected_err("Could not get self participant id".to_string(), None),
+            )?;
+
+        Ok((params, self_participant_id))
+    }
+
     #[instrument(level = "debug", skip_all)]
     pub async fn get_presign(
         &self,
@@ -283,11 +406,142 @@ impl DamFastState {
         );
         let self_peer = peers.peer_at_address(&self.state.addr)?;
 
-        let signature_share = EcdsaSignedMessageShare {
+        self.signed_message_share::<C>(
+            message_bytes,
+            &signature_share,
+            &pk,
+            &peer_id,
+            self_peer.peer_id,
+        )
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub async fn sign_batch_with_pubkey_internal<C>(
+        &mut self,
+        messages: &[Vec<u8>],
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        request_id: Vec<u8>,
+        node_set: &[NodeSet],
+    ) -> Result<Vec<EcdsaSignedMessageShare>>
+    where
+        C: PrimeCurve + CurveArithmetic + DigestPrimitive + SignatureCurve,
+        C::ProjectivePoint: GroupEncoding + HDDerivable + CompressedBytes,
+        C::AffinePoint: Serialize,
+        C::Scalar: HDDeriver + From<PeerId> + Serialize + CompressedBytes,
+        <FieldBytesSize<C> as Add>::Output: ArrayLength<u8>,
+    {
+        let peers = self.state.peer_state.peers();
+        let signing_peers = peers.peers_for_nodeset(node_set);
+        let threshold = node_set.len() as u16;
+
+        let key_id = tweak_preimage.expect_or_err("No hd_key_id provided!")?;
+        let txn_prefix = &bytes_to_hex(&request_id);
+
+        // don't use up presignatures on a batch that can't be signed
+        let message_len = self.signing_scheme.ecdsa_message_len();
+        if let Some(i) = messages.iter().position(|m| m.len() != message_len) {
+            return Err(unexpected_err(
+                format!(
+                    "Message {} of the batch is not {} bytes.  Please hash it before sending it to the node to sign.",
+                    i, message_len
+                ),
+                None,
+            ));
+        }
+        let request_ids = (0..messages.len())
+            .map(|i| batch_item_request_id(&request_id, i))
+            .collect::<Vec<_>>();
+
+        // create the presignatures together, or take one from the pool for each message
+        let max_presign_count = DataVersionReader::read_field_unchecked(
+            &self.state.chain_data_config_manager.generic_config,
+            |config| config.max_presign_count,
+        );
+        let presigs = if max_presign_count == 0 {
+            self.create_presignatures_for_peers::<C>(
+                txn_prefix,
+                &signing_peers,
+                threshold as usize,
+                messages.len(),
+            )
+            .await?
+        } else {
+            // each message has its own request id and leader, so request their presignatures at once
+            let presigs = futures::future::try_join_all(messages.iter().zip(&request_ids).map(
+                |(message_bytes, item_request_id)| {
+                    self.get_presign(
+                        message_bytes,
+                        item_request_id.clone(),
+                        &bytes_to_hex(item_request_id),
+                        threshold,
+                        signing_peers.clone(),
+                    )
+                },
+            ))
+            .await?;
+            presigs
+                .into_iter()
+                .map(|presig| {
+                    let presig = presig.expect_or_err("No presignature found!")?;
+                    Ok(*presig.share.unwrap::<C>())
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        debug!(
+            "Got {} presigns for batch signing: {}",
+            presigs.len(),
+            txn_prefix
+        );
+
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let mut signature_shares = Vec::with_capacity(messages.len());
+        for ((message_bytes, item_request_id), presig) in
+            messages.iter().zip(&request_ids).zip(&presigs)
+        {
+            let (signature_share, pk, _, peer_id) = self
+                .generate_signature_share_from_key_id::<C>(
+                    message_bytes,
+                    root_pubkeys.clone(),
+                    presig,
+                    item_request_id,
+                    &signing_peers,
+                    &key_id,
+                )
+                .await?;
+            signature_shares.push(self.signed_message_share::<C>(
+                message_bytes,
+                &signature_share,
+                &pk,
+                &peer_id,
+                self_peer.peer_id,
+            )?);
+        }
+
+        Ok(signature_shares)
+    }
+
+    fn signed_message_share<C>(
+        &self,
+        message_bytes: &[u8],
+        signature_share: &SignatureShare<C>,
+        pk: &C::ProjectivePoint,
+        share_id: &NonZeroScalar<C>,
+        self_peer_id: PeerId,
+    ) -> Result<EcdsaSignedMessageShare>
+    where
+        C: PrimeCurve + CurveArithmetic + DigestPrimitive + SignatureCurve,
+        C::ProjectivePoint: GroupEncoding + HDDerivable + CompressedBytes,
+        C::AffinePoint: Serialize,
+        C::Scalar: HDDeriver + Serialize + CompressedBytes,
+        <FieldBytesSize<C> as Add>::Output: ArrayLength<u8>,
+    {
+        Ok(EcdsaSignedMessageShare {
             digest: hex::encode(message_bytes),
             result: "success".to_string(),
-            peer_id: self_peer.peer_id.to_string(),
-            share_id: serde_json::to_string(peer_id.as_ref())
+            peer_id: self_peer_id.to_string(),
+            share_id: serde_json::to_string(share_id.as_ref())
                 .expect_or_err("Error serializing share_id")?,
             signature_share: serde_json::to_string(&signature_share.s)
                 .expect_or_err("Error serializing signature share")?,
@@ -296,9 +550,7 @@ impl DamFastState {
             compressed_public_key: format!("\"{}\"", pk.to_compressed_hex()),
             public_key: format!("\"{}\"", pk.to_uncompressed_hex()),
             sig_type: self.signing_scheme.to_string(),
-        };
-
-        Ok(signature_share)
+        })
     }
 
     pub async fn generate_signature_share_from_key_id<C>(
@@ -353,6 +605,15 @@ impl DamFastState {
             &self.state.key_cache,
         )
         .await?;
+        let (sk, pk) = match &self.derivation_path {
+            Some(derivation_path) => derive_child_keyshare::<C::ProjectivePoint>(
+                derivation_path,
+                self.signing_scheme.curve_type(),
+                sk,
+                pk,
+            )?,
+            None => (sk, pk),
+        };
 
         debug!("Derived public key: {:?}", pk.to_compressed_hex());
 
@@ -399,6 +660,23 @@ impl DamFastState {
 
 #[async_trait::async_trait]
 impl Signable for DamFastState {
+    fn set_derivation_path(&mut self, derivation_path: DerivationPath) -> Result<()> {
+        match self.signing_scheme {
+            SigningScheme::EcdsaK256Sha256 => {
+                self.derivation_path = Some(derivation_path);
+                Ok(())
+            }
+            _ => Err(unexpected_err_code(
+                format!(
+                    "Derivation paths are not supported by {}",
+                    self.signing_scheme
+                ),
+                EC::NodeSignatureNotSupported,
+                None,
+            )),
+        }
+    }
+
     #[doc = "Sign using a specifically identified public key.  This pubkey is the result of PKP generation."]
     #[instrument(level = "debug", skip_all)]
     async fn sign_with_pubkey(
@@ -467,4 +745,70 @@ impl Signable for DamFastState {
 
         Ok(sig_share.into())
     }
+
+    #[doc = "Signs all the messages with presignatures that are created together or taken from the pool."]
+    #[instrument(level = "debug", skip_all)]
+    async fn sign_batch_with_pubkey(
+        &mut self,
+        messages: &[Vec<u8>],
+        public_key: Vec<u8>,
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        request_id: Vec<u8>,
+        epoch: Option<u64>,
+        nodeset: &[NodeSet],
+    ) -> Result<Vec<SignableOutput>> {
+        let df_sig_shares = match self.signing_scheme {
+            SigningScheme::EcdsaK256Sha256 => {
+                self.sign_batch_with_pubkey_internal::<k256::Secp256k1>(
+                    messages,
+                    root_pubkeys,
+                    tweak_preimage,
+                    request_id,
+                    nodeset,
+                )
+                .await
+            }
+            SigningScheme::EcdsaP256Sha256 => {
+                self.sign_batch_with_pubkey_internal::<p256::NistP256>(
+                    messages,
+                    root_pubkeys,
+                    tweak_preimage,
+                    request_id,
+                    nodeset,
+                )
+                .await
+            }
+            SigningScheme::EcdsaP384Sha384 => {
+                self.sign_batch_with_pubkey_internal::<p384::NistP384>(
+                    messages,
+                    root_pubkeys,
+                    tweak_preimage,
+                    request_id,
+                    nodeset,
+                )
+                .await
+            }
+            _ => Err(unexpected_err(
+                format!("Unsupported signing scheme: {}", self.signing_scheme),
+                None,
+            )),
+        };
+
+        let sig_shares = match df_sig_shares {
+            Ok(shares) => {
+                for _ in &shares {
+                    metrics::counter::add_one(metrics::tss::TssMetrics::SignatureShare, &[]);
+                }
+                shares
+            }
+            Err(e) => {
+                metrics::counter::add_one(metrics::tss::TssMetrics::SignatureShareFail, &[]);
+                error!("Error signing batch: {:?}", e);
+                return Err(e);
+            }
+        };
+
+        Ok(sig_shares.into_iter().map(Into::into).collect())
+    }
 }
@@ -1,9 +1,13 @@
 use crate::error::{EC, parser_err, unexpected_err, unexpected_err_code};
 use crate::p2p_comms::CommsManager;
 use crate::peers::peer_state::models::SimplePeer;
-use crate::tss::common::hd_keys::get_derived_keyshare;
+use crate::tasks::frost_nonce_manager::models::{
+    FrostNonce, FrostNonceMessage, FrostNonceRequest, pooled_nonce_or_round1,
+};
+use crate::tss::common::hd_keys::{derive_child_keyshare, get_derived_keyshare};
 use crate::tss::common::signing_scheme::signing_scheme_to_frost_scheme;
 use crate::tss::common::traits::signable::Signable;
+use crate::version::DataVersionReader;
 use crate::{
     error::Result,
     metrics,
@@ -15,11 +19,12 @@ use hd_keys_curves::{HDDerivable, HDDeriver};
 use lit_core::error::Unexpected;
 use lit_core::utils::binary::bytes_to_hex;
 use lit_frost::{
-    Identifier, KeyPackage, Scheme, SignatureShare, SigningCommitments, SigningShare, VerifyingKey,
-    VerifyingShare,
+    Identifier, KeyPackage, Scheme, SignatureShare, SigningCommitments, SigningNonces,
+    SigningShare, VerifyingKey, VerifyingShare,
 };
 use lit_node_core::CompressedBytes;
 use lit_node_core::CurveType;
+use lit_node_core::DerivationPath;
 use lit_node_core::NodeSet;
 use lit_node_core::PeerId;
 use lit_node_core::{FrostSignedMessageShare, SignableOutput, SigningAlgorithm, SigningScheme};
@@ -31,6 +36,8 @@ pub struct FrostState {
     pub state: Arc<TssState>,
     pub dkg_type: DkgType,
     pub signing_scheme: SigningScheme,
+    // signs with the child key at this path, if any
+    pub derivation_path: Option<DerivationPath>,
 }
 
 impl FrostState {
@@ -47,6 +54,7 @@ impl FrostState {
             state,
             dkg_type,
             signing_scheme,
+            derivation_path: None,
         }
     }
 
@@ -66,24 +74,9 @@ impl FrostState {
         SigningCommitments,
         VerifyingShare,
     )> {
-        if !signature_scheme.supports_algorithm(SigningAlgorithm::Schnorr) {
-            let msg = format!(
-                "Requested signature scheme {:?} does not support Schnorr",
-                signature_scheme
-            );
-            return Err(unexpected_err_code(
-                "Unsupported signature curve for Schnorr signature",
-                EC::NodeSignatureNotSupported,
-                Some(msg),
-            ));
-        }
-
-        // setup communications
-        let round = "frost1";
-        let cm = CommsManager::new_with_peers(&self.state, txn_prefix, peers, round).await?;
+        check_supports_schnorr(signature_scheme)?;
 
         // setup signing protocol
-        let mut rng = rand::rngs::OsRng;
         let self_peer = peers.peer_at_address(&self.state.addr)?;
         let scheme: Scheme = signing_scheme_to_frost_scheme(signature_scheme)?;
         let identifier = self.peer_id_to_frost_identifier(self_peer.peer_id)?;
@@ -96,7 +89,132 @@ impl FrostState {
             )
         })?;
 
-        // round1
+        // round1, unless the peers pre-generated nonces together
+        let max_nonce_count = DataVersionReader::read_field_unchecked(
+            &self.state.chain_data_config_manager.generic_config,
+            |config| config.max_presign_count,
+        );
+        let frost_nonce = match max_nonce_count {
+            0 => None,
+            _ => pooled_nonce_or_round1(self.get_frost_nonce(txn_prefix, message, peers).await),
+        };
+        let (nonces, commitments, signing_commitments) = match frost_nonce {
+            Some(frost_nonce) => {
+                let signing_commitments = frost_nonce
+                    .commitments_for_peers(peers)?
+                    .into_iter()
+                    .map(|(peer_id, commitments)| {
+                        Ok((self.peer_id_to_frost_identifier(peer_id)?, commitments))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let commitments = frost_nonce.own_commitments(self_peer.peer_id)?;
+                (frost_nonce.nonces, commitments, signing_commitments)
+            }
+            None => {
+                self.signing_round1(txn_prefix, peers, scheme, secret_share, &identifier)
+                    .await?
+            }
+        };
+
+        // round 2
+        let key_package = key_package(&identifier, secret_share, group_key, threshold)?;
+
+        let signature_share = scheme
+            .signing_round2(message, &signing_commitments, &nonces, &key_package)
+            .map_err(|e| {
+                unexpected_err_code(e, EC::NodeUnknownError, Some("Signing Round 2".to_string()))
+            })?;
+
+        Ok((identifier, signature_share, commitments, verifying_share))
+    }
+
+    #[doc = "Signs several messages with the same key.  Round 1 runs for all of them in a single exchange of commitments, after which each message is signed locally."]
+    pub async fn sign_batch_internal(
+        &self,
+        txn_prefix: &str,
+        peers: &SimplePeerCollection,
+        messages: &[Vec<u8>],
+        group_key: &VerifyingKey,
+        secret_share: &SigningShare,
+        threshold: usize,
+    ) -> Result<
+        Vec<(
+            Identifier,
+            SignatureShare,
+            SigningCommitments,
+            VerifyingShare,
+        )>,
+    > {
+        check_supports_schnorr(self.signing_scheme)?;
+
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let scheme: Scheme = signing_scheme_to_frost_scheme(self.signing_scheme)?;
+        let identifier = self.peer_id_to_frost_identifier(self_peer.peer_id)?;
+        let verifying_share = scheme.verifying_share(secret_share).map_err(|e| {
+            unexpected_err_code(
+                e,
+                EC::NodeUnknownError,
+                Some("VerifyingShare::try_from".to_string()),
+            )
+        })?;
+        let key_package = key_package(&identifier, secret_share, group_key, threshold)?;
+
+        // round 1 for every message at once
+        let nonces = self
+            .create_nonces_for_peers(txn_prefix, peers, messages.len())
+            .await?;
+
+        messages
+            .iter()
+            .zip(nonces)
+            .map(|(message, (nonces, commitments))| {
+                let own_commitments = commitments
+                    .iter()
+                    .find(|(peer_id, _)| *peer_id == self_peer.peer_id)
+                    .map(|(_, c)| c.clone())
+                    .expect_or_err("No commitments of our own for the batch")?;
+                let signing_commitments = commitments
+                    .into_iter()
+                    .map(|(peer_id, c)| Ok((self.peer_id_to_frost_identifier(peer_id)?, c)))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let signature_share = scheme
+                    .signing_round2(message, &signing_commitments, &nonces, &key_package)
+                    .map_err(|e| {
+                        unexpected_err_code(
+                            e,
+                            EC::NodeUnknownError,
+                            Some("Signing Round 2".to_string()),
+                        )
+                    })?;
+
+                Ok((
+                    identifier.clone(),
+                    signature_share,
+                    own_commitments,
+                    verifying_share.clone(),
+                ))
+            })
+            .collect()
+    }
+
+    async fn signing_round1(
+        &self,
+        txn_prefix: &str,
+        peers: &SimplePeerCollection,
+        scheme: Scheme,
+        secret_share: &SigningShare,
+        identifier: &Identifier,
+    ) -> Result<(
+        SigningNonces,
+        SigningCommitments,
+        Vec<(Identifier, SigningCommitments)>,
+    )> {
+        // setup communications
+        let round = "frost1";
+        let cm = CommsManager::new_with_peers(&self.state, txn_prefix, peers, round).await?;
+
+        let mut rng = rand::rngs::OsRng;
         let (nonces, commitments) = scheme.signing_round1(secret_share, &mut rng).map_err(|e| {
             unexpected_err_code(e, EC::NodeUnknownError, Some("Signing Round 1".to_string()))
         })?;
@@ -114,35 +232,104 @@ impl FrostState {
             signing_commitments.push((remote_identifier, peer_commitments));
         }
 
-        let threshold = match NonZeroU16::new(
-            threshold
-                .try_into()
-                .map_err(|_| parser_err("Unable to convert threshold to 16-bit integer", None))?,
-        ) {
-            Some(threshold) => threshold,
-            None => {
-                return Err(unexpected_err_code(
-                    "threshold must be greater than 0",
-                    EC::NodeUnknownError,
-                    Some("Signing Round 1".to_string()),
-                ));
-            }
-        };
-        // round 2
-        let key_package = KeyPackage {
-            identifier: identifier.clone(),
-            secret_share: secret_share.clone(),
-            verifying_key: group_key.clone(),
-            threshold,
+        Ok((nonces, commitments, signing_commitments))
+    }
+
+    #[doc = "Generates nonces for `count` future signatures with the peers, and exchanges their commitments.  Returns the nonces with the commitments of every peer."]
+    pub async fn create_nonces_for_peers(
+        &self,
+        txn_prefix: &str,
+        peers: &SimplePeerCollection,
+        count: usize,
+    ) -> Result<Vec<(SigningNonces, Vec<(PeerId, SigningCommitments)>)>> {
+        let round = "frost_nonces";
+        let cm = CommsManager::new_with_peers(&self.state, txn_prefix, peers, round).await?;
+
+        let mut rng = rand::rngs::OsRng;
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let scheme: Scheme = signing_scheme_to_frost_scheme(self.signing_scheme)?;
+        // Round 1 only uses the share as extra entropy for the nonces, so any scalar will do
+        // before we know which key the nonces will sign with.
+        let entropy = SigningShare {
+            scheme,
+            value: self.peer_id_to_scalar_bytes(PeerId::random(&mut rng))?,
         };
 
-        let signature_share = scheme
-            .signing_round2(message, &signing_commitments, &nonces, &key_package)
-            .map_err(|e| {
-                unexpected_err_code(e, EC::NodeUnknownError, Some("Signing Round 2".to_string()))
+        let mut nonces = Vec::with_capacity(count);
+        let mut commitments = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (n, c) = scheme.signing_round1(&entropy, &mut rng).map_err(|e| {
+                unexpected_err_code(
+                    e,
+                    EC::NodeUnknownError,
+                    Some("Nonce generation".to_string()),
+                )
             })?;
+            nonces.push(n);
+            commitments.push(c);
+        }
 
-        Ok((identifier, signature_share, commitments, verifying_share))
+        let r_commitments = cm
+            .broadcast_and_collect::<Vec<SigningCommitments>, Vec<SigningCommitments>>(
+                commitments.clone(),
+            )
+            .await?;
+        if let Some((peer_id, _)) = r_commitments.iter().find(|(_, c)| c.len() != count) {
+            return Err(unexpected_err(
+                format!(
+                    "Peer {} sent the wrong number of nonce commitments",
+                    peer_id
+                ),
+                None,
+            ));
+        }
+
+        Ok(nonces
+            .into_iter()
+            .zip(commitments)
+            .enumerate()
+            .map(|(i, (nonces, commitments))| {
+                let mut all_commitments = vec![(self_peer.peer_id, commitments)];
+                all_commitments.extend(
+                    r_commitments
+                        .iter()
+                        .map(|(peer_id, c)| (*peer_id, c[i].clone())),
+                );
+                (nonces, all_commitments)
+            })
+            .collect())
+    }
+
+    #[doc = "Asks the FROST nonce manager for nonces that the peers pre-generated together.  Returns None if there are none for these peers, in which case every peer runs round 1 live."]
+    async fn get_frost_nonce(
+        &self,
+        txn_prefix: &str,
+        message: &[u8],
+        peers: &SimplePeerCollection,
+    ) -> Result<Option<FrostNonce>> {
+        let req = FrostNonceRequest {
+            message_bytes: message.to_vec(),
+            txn_prefix: txn_prefix.to_string(),
+            peers: peers.clone(),
+            signing_scheme: self.signing_scheme,
+        };
+
+        let (tx, rx) = flume::bounded(1);
+        let msg = FrostNonceMessage::RequestNonce(req, tx);
+        let ps = self.state.peer_state.as_ref();
+        ps.frost_nonce_tx.send_async(msg).await.map_err(|e| {
+            unexpected_err(
+                e,
+                Some("Could not send request to FROST nonce manager".into()),
+            )
+        })?;
+
+        rx.recv_async().await.map_err(|e| {
+            unexpected_err(
+                e,
+                Some("Could not receive response from FROST nonce manager".into()),
+            )
+        })?
     }
 
     async fn derive_frost_signing_components<G>(
@@ -171,6 +358,15 @@ impl FrostState {
             &self.state.key_cache,
         )
         .await?;
+        let (sk, pk) = match &self.derivation_path {
+            Some(derivation_path) => derive_child_keyshare::<G>(
+                derivation_path,
+                self.signing_scheme.curve_type(),
+                sk,
+                pk,
+            )?,
+            None => (sk, pk),
+        };
 
         let scheme = signing_scheme_to_frost_scheme(self.signing_scheme)?;
         let vk = VerifyingKey {
@@ -184,147 +380,209 @@ impl FrostState {
         Ok((vk, signing_share))
     }
 
-    fn peer_id_to_frost_identifier(&self, peer_id: PeerId) -> Result<Identifier> {
-        let bytes = match self.signing_scheme.curve_type() {
-            CurveType::K256 => k256::Scalar::from(peer_id).to_bytes().to_vec(),
-            CurveType::P256 => p256::Scalar::from(peer_id).to_bytes().to_vec(),
-            CurveType::P384 => p384::Scalar::from(peer_id).to_bytes().to_vec(),
-            CurveType::Ed25519 | CurveType::Ristretto25519 => {
-                curve25519_dalek::Scalar::from(peer_id).to_bytes().to_vec()
-            }
-            CurveType::Ed448 => ed448_goldilocks::Scalar::from(peer_id)
-                .to_bytes_rfc_8032()
-                .to_vec(),
-            CurveType::RedJubjub => jubjub::Scalar::from(peer_id).to_bytes().to_vec(),
-            CurveType::RedDecaf377 => decaf377::Fr::from(peer_id).to_bytes().to_vec(),
-            _ => {
-                // Shouldn't happen but just in case
-                return Err(unexpected_err(
-                    "Unsupported curve type for frost_identifier",
-                    None,
-                ));
-            }
-        };
-        let scheme = signing_scheme_to_frost_scheme(self.signing_scheme)?;
-        Ok(Identifier { scheme, id: bytes })
-    }
-}
-
-#[async_trait::async_trait]
-impl Signable for FrostState {
-    async fn sign_with_pubkey(
-        &mut self,
-        message_bytes: &[u8],
-        public_key: Vec<u8>,
+    async fn derive_signing_components(
+        &self,
         root_pubkeys: Option<Vec<String>>,
-        tweak_preimage: Option<Vec<u8>>,
-        request_id: Vec<u8>,
-        epoch: Option<u64>,
-        nodeset: &[NodeSet],
-    ) -> Result<SignableOutput> {
-        let txn_prefix = bytes_to_hex(&request_id);
-        let peers = self.state.peer_state.peers();
-        let signing_peers = peers.peers_for_nodeset(nodeset);
-        let self_peer = peers.peer_at_address(&self.state.addr)?;
-        let threshold = nodeset.len();
-        let key_id = tweak_preimage.expect_or_err("No hd_key_id provided!")?;
-        let realm_id = self.state.peer_state.realm_id();
-        let epoch = epoch.unwrap_or(self.state.peer_state.epoch());
-        let (vk, signing_share) = match self.signing_scheme {
+        key_id: &[u8],
+        self_peer: &SimplePeer,
+        epoch: u64,
+    ) -> Result<(VerifyingKey, SigningShare)> {
+        let components = match self.signing_scheme {
             SigningScheme::SchnorrK256Sha256 | SigningScheme::SchnorrK256Taproot => {
-                let deriver = k256::Scalar::create(&key_id, self.signing_scheme.id_sign_ctx());
+                let deriver = k256::Scalar::create(key_id, self.signing_scheme.id_sign_ctx());
                 self.derive_frost_signing_components::<k256::ProjectivePoint>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrP256Sha256 => {
-                let deriver = p256::Scalar::create(&key_id, self.signing_scheme.id_sign_ctx());
+                let deriver = p256::Scalar::create(key_id, self.signing_scheme.id_sign_ctx());
                 self.derive_frost_signing_components::<p256::ProjectivePoint>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrP384Sha384 => {
-                let deriver = p384::Scalar::create(&key_id, self.signing_scheme.id_sign_ctx());
+                let deriver = p384::Scalar::create(key_id, self.signing_scheme.id_sign_ctx());
                 self.derive_frost_signing_components::<p384::ProjectivePoint>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrEd25519Sha512 => {
                 let deriver = vsss_rs::curve25519::WrappedScalar::create(
-                    &key_id,
+                    key_id,
                     self.signing_scheme.id_sign_ctx(),
                 );
                 self.derive_frost_signing_components::<vsss_rs::curve25519::WrappedEdwards>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrRistretto25519Sha512 | SigningScheme::SchnorrkelSubstrate => {
                 let deriver = vsss_rs::curve25519::WrappedScalar::create(
-                    &key_id,
+                    key_id,
                     self.signing_scheme.id_sign_ctx(),
                 );
                 self.derive_frost_signing_components::<vsss_rs::curve25519::WrappedRistretto>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrEd448Shake256 => {
                 let deriver =
-                    ed448_goldilocks::Scalar::create(&key_id, self.signing_scheme.id_sign_ctx());
+                    ed448_goldilocks::Scalar::create(key_id, self.signing_scheme.id_sign_ctx());
                 self.derive_frost_signing_components::<ed448_goldilocks::EdwardsPoint>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrRedJubjubBlake2b512 => {
-                let deriver = jubjub::Scalar::create(&key_id, self.signing_scheme.id_sign_ctx());
+                let deriver = jubjub::Scalar::create(key_id, self.signing_scheme.id_sign_ctx());
                 self.derive_frost_signing_components::<jubjub::SubgroupPoint>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
             }
             SigningScheme::SchnorrRedDecaf377Blake2b512 => {
-                let deriver = decaf377::Fr::create(&key_id, self.signing_scheme.id_sign_ctx());
+                let deriver = decaf377::Fr::create(key_id, self.signing_scheme.id_sign_ctx());
                 self.derive_frost_signing_components::<decaf377::Element>(
                     deriver,
                     root_pubkeys,
-                    &self_peer,
+                    self_peer,
                     epoch,
                 )
-                .await?
+                .await
+            }
+            _ => Err(unexpected_err(
+                format!("Unsupported schnorr type: {}", self.signing_scheme),
+                None,
+            )),
+        };
+        components
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn signed_message_share(
+        &self,
+        message_bytes: &[u8],
+        self_peer: &SimplePeer,
+        id: &Identifier,
+        sig_share: &SignatureShare,
+        commitments: &SigningCommitments,
+        vk_share: &VerifyingShare,
+        vk: &VerifyingKey,
+    ) -> Result<SignableOutput> {
+        Ok(FrostSignedMessageShare {
+            message: hex::encode(message_bytes),
+            result: "success".to_string(),
+            peer_id: self_peer.peer_id.to_string(),
+            share_id: serde_json::to_string(id).expect_or_err("Error serializing share_id")?,
+            signature_share: serde_json::to_string(sig_share)
+                .expect_or_err("Error serializing signature_share")?,
+            signing_commitments: serde_json::to_string(commitments)
+                .expect_or_err("Error serializing signing_commitments")?,
+            verifying_share: serde_json::to_string(vk_share)
+                .expect_or_err("Error serializing verifying_share")?,
+            public_key: serde_json::to_string(vk).expect_or_err("Error serializing public_key")?,
+            sig_type: self.signing_scheme.to_string(),
+        }
+        .into())
+    }
+
+    fn peer_id_to_frost_identifier(&self, peer_id: PeerId) -> Result<Identifier> {
+        let bytes = self.peer_id_to_scalar_bytes(peer_id)?;
+        let scheme = signing_scheme_to_frost_scheme(self.signing_scheme)?;
+        Ok(Identifier { scheme, id: bytes })
+    }
+
+    fn peer_id_to_scalar_bytes(&self, peer_id: PeerId) -> Result<Vec<u8>> {
+        let bytes = match self.signing_scheme.curve_type() {
+            CurveType::K256 => k256::Scalar::from(peer_id).to_bytes().to_vec(),
+            CurveType::P256 => p256::Scalar::from(peer_id).to_bytes().to_vec(),
+            CurveType::P384 => p384::Scalar::from(peer_id).to_bytes().to_vec(),
+            CurveType::Ed25519 | CurveType::Ristretto25519 => {
+                curve25519_dalek::Scalar::from(peer_id).to_bytes().to_vec()
             }
+            CurveType::Ed448 => ed448_goldilocks::Scalar::from(peer_id)
+                .to_bytes_rfc_8032()
+                .to_vec(),
+            CurveType::RedJubjub => jubjub::Scalar::from(peer_id).to_bytes().to_vec(),
+            CurveType::RedDecaf377 => decaf377::Fr::from(peer_id).to_bytes().to_vec(),
             _ => {
+                // Shouldn't happen but just in case
                 return Err(unexpected_err(
-                    format!("Unsupported schnorr type: {}", self.signing_scheme),
+                    "Unsupported curve type for frost_identifier",
                     None,
                 ));
             }
         };
+        Ok(bytes)
+    }
+}
+
+#[async_trait::async_trait]
+impl Signable for FrostState {
+    fn set_derivation_path(&mut self, derivation_path: DerivationPath) -> Result<()> {
+        match self.signing_scheme {
+            SigningScheme::SchnorrK256Sha256
+            | SigningScheme::SchnorrK256Taproot
+            | SigningScheme::SchnorrEd25519Sha512 => {
+                self.derivation_path = Some(derivation_path);
+                Ok(())
+            }
+            _ => Err(unexpected_err_code(
+                format!(
+                    "Derivation paths are not supported by {}",
+                    self.signing_scheme
+                ),
+                EC::NodeSignatureNotSupported,
+                None,
+            )),
+        }
+    }
+
+    async fn sign_with_pubkey(
+        &mut self,
+        message_bytes: &[u8],
+        public_key: Vec<u8>,
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        request_id: Vec<u8>,
+        epoch: Option<u64>,
+        nodeset: &[NodeSet],
+    ) -> Result<SignableOutput> {
+        let txn_prefix = bytes_to_hex(&request_id);
+        let peers = self.state.peer_state.peers();
+        let signing_peers = peers.peers_for_nodeset(nodeset);
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let threshold = nodeset.len();
+        let key_id = tweak_preimage.expect_or_err("No hd_key_id provided!")?;
+        let epoch = epoch.unwrap_or(self.state.peer_state.epoch());
+        let (vk, signing_share) = self
+            .derive_signing_components(root_pubkeys, &key_id, &self_peer, epoch)
+            .await?;
 
         let df_sig_share = self
             .sign_internal(
@@ -350,20 +608,121 @@ impl Signable for FrostState {
             }
         };
 
-        Ok(FrostSignedMessageShare {
-            message: hex::encode(message_bytes),
-            result: "success".to_string(),
-            peer_id: self_peer.peer_id.to_string(),
-            share_id: serde_json::to_string(&id).expect_or_err("Error serializing share_id")?,
-            signature_share: serde_json::to_string(&sig_share)
-                .expect_or_err("Error serializing signature_share")?,
-            signing_commitments: serde_json::to_string(&commitments)
-                .expect_or_err("Error serializing signing_commitments")?,
-            verifying_share: serde_json::to_string(&vk_share)
-                .expect_or_err("Error serializing verifying_share")?,
-            public_key: serde_json::to_string(&vk).expect_or_err("Error serializing public_key")?,
-            sig_type: self.signing_scheme.to_string(),
-        }
-        .into())
+        self.signed_message_share(
+            message_bytes,
+            &self_peer,
+            &id,
+            &sig_share,
+            &commitments,
+            &vk_share,
+            &vk,
+        )
     }
+
+    #[doc = "Signs all the messages with nonces that the peers generate together in a single round."]
+    async fn sign_batch_with_pubkey(
+        &mut self,
+        messages: &[Vec<u8>],
+        public_key: Vec<u8>,
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        request_id: Vec<u8>,
+        epoch: Option<u64>,
+        nodeset: &[NodeSet],
+    ) -> Result<Vec<SignableOutput>> {
+        let txn_prefix = bytes_to_hex(&request_id);
+        let peers = self.state.peer_state.peers();
+        let signing_peers = peers.peers_for_nodeset(nodeset);
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let threshold = nodeset.len();
+        let key_id = tweak_preimage.expect_or_err("No hd_key_id provided!")?;
+        let epoch = epoch.unwrap_or(self.state.peer_state.epoch());
+        let (vk, signing_share) = self
+            .derive_signing_components(root_pubkeys, &key_id, &self_peer, epoch)
+            .await?;
+
+        let df_sig_shares = self
+            .sign_batch_internal(
+                &txn_prefix,
+                &signing_peers,
+                messages,
+                &vk,
+                &signing_share,
+                threshold,
+            )
+            .await;
+
+        let sig_shares = match df_sig_shares {
+            Ok(shares) => {
+                for _ in &shares {
+                    metrics::counter::add_one(metrics::tss::TssMetrics::SignatureShare, &[]);
+                }
+                shares
+            }
+            Err(e) => {
+                metrics::counter::add_one(metrics::tss::TssMetrics::SignatureShareFail, &[]);
+                error!("Error signing batch: {:?}", e);
+                return Err(e);
+            }
+        };
+
+        messages
+            .iter()
+            .zip(sig_shares)
+            .map(|(message_bytes, (id, sig_share, commitments, vk_share))| {
+                self.signed_message_share(
+                    message_bytes,
+                    &self_peer,
+                    &id,
+                    &sig_share,
+                    &commitments,
+                    &vk_share,
+                    &vk,
+                )
+            })
+            .collect()
+    }
+}
+
+fn check_supports_schnorr(signature_scheme: SigningScheme) -> Result<()> {
+    if !signature_scheme.supports_algorithm(SigningAlgorithm::Schnorr) {
+        let msg = format!(
+            "Requested signature scheme {:?} does not support Schnorr",
+            signature_scheme
+        );
+        return Err(unexpected_err_code(
+            "Unsupported signature curve for Schnorr signature",
+            EC::NodeSignatureNotSupported,
+            Some(msg),
+        ));
+    }
+    Ok(())
+}
+
+fn key_package(
+    identifier: &Identifier,
+    secret_share: &SigningShare,
+    group_key: &VerifyingKey,
+    threshold: usize,
+) -> Result<KeyPackage> {
+    let threshold = match NonZeroU16::new(
+        threshold
+            .try_into()
+            .map_err(|_| parser_err("Unable to convert threshold to 16-bit integer", None))?,
+    ) {
+        Some(threshold) => threshold,
+        None => {
+            return Err(unexpected_err_code(
+                "threshold must be greater than 0",
+                EC::NodeUnknownError,
+                Some("Signing Round 1".to_string()),
+            ));
+        }
+    };
+    Ok(KeyPackage {
+        identifier: identifier.clone(),
+        secret_share: secret_share.clone(),
+        verifying_key: group_key.clone(),
+        threshold,
+    })
 }
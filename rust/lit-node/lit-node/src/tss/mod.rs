@@ -3,4 +3,5 @@ pub mod common;
 pub mod dkg;
 pub mod ecdsa_damfast;
 pub mod frost;
+pub mod musig2;
 pub mod util;
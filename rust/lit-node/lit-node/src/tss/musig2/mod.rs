@@ -0,0 +1,249 @@
+use crate::error::{EC, Result, unexpected_err, validation_err_code};
+use crate::metrics;
+use crate::tss::common::hd_keys::get_derived_keyshare;
+use crate::tss::common::tss_state::TssState;
+use elliptic_curve::Field;
+use hd_keys_curves::HDDeriver;
+use lit_core::error::Unexpected;
+use lit_core::utils::binary::bytes_to_hex;
+use lit_node_core::{
+    CompressedBytes, CurveType, MuSig2SecretNonce, MuSig2Session, NodeSet, PeerId, SigningScheme,
+};
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+
+// the second phase must follow the first within this time
+const NONCE_TTL: Duration = Duration::from_secs(300);
+const MAX_PENDING_NONCES: u64 = 100_000;
+
+#[doc = "The secret nonces that this node committed to in the first phase of MuSig2 signatures, until the second phase uses them.  Each one is removed when it signs, so it can never sign twice."]
+pub struct MuSig2NonceCache {
+    nonces: Cache<String, Arc<MuSig2SecretNonce>>,
+}
+
+impl MuSig2NonceCache {
+    pub fn new() -> Self {
+        MuSig2NonceCache {
+            nonces: Cache::builder()
+                .max_capacity(MAX_PENDING_NONCES)
+                .time_to_live(NONCE_TTL)
+                .build(),
+        }
+    }
+
+    fn key(pubkey: &str, session_id: &str) -> String {
+        format!(
+            "{}_{}",
+            pubkey.trim_start_matches("0x").to_lowercase(),
+            session_id
+        )
+    }
+}
+
+impl Default for MuSig2NonceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MuSig2State {
+    pub state: Arc<TssState>,
+}
+
+impl MuSig2State {
+    pub fn new(state: Arc<TssState>) -> Self {
+        MuSig2State { state }
+    }
+
+    #[doc = "Commits to fresh nonces for a signature with the PKP, returning this node's peer id and its part of the PKP's public nonce."]
+    pub async fn create_nonce(
+        &self,
+        nonce_cache: &MuSig2NonceCache,
+        pubkey: &str,
+        session_id: &str,
+    ) -> Result<(PeerId, Vec<u8>)> {
+        if session_id.is_empty() {
+            return Err(invalid_session("MuSig2 sessions need an id".to_string()));
+        }
+
+        let mut rng = rand::rngs::OsRng;
+        let secret_nonce = Arc::new(
+            MuSig2SecretNonce::new(
+                k256::Scalar::random(&mut rng),
+                k256::Scalar::random(&mut rng),
+            )
+            .map_err(|e| unexpected_err(e, None))?,
+        );
+        let public_nonce = secret_nonce.public_nonce();
+
+        let entry = nonce_cache
+            .nonces
+            .entry(MuSig2NonceCache::key(pubkey, session_id))
+            .or_insert_with(async move { secret_nonce })
+            .await;
+        if !entry.is_fresh() {
+            return Err(invalid_session(format!(
+                "MuSig2 session {} already has a nonce",
+                session_id
+            )));
+        }
+        Ok((
+            self.state.peer_state.peer_id_in_current_epoch()?,
+            public_nonce,
+        ))
+    }
+
+    #[doc = "Signs with the nonce committed to in the first phase, and the share of the PKP's key weighted for the node set.  The shares of the node set add up to the PKP's partial signature."]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn sign(
+        &self,
+        nonce_cache: &MuSig2NonceCache,
+        pubkey: &str,
+        session_id: &str,
+        message: &[u8],
+        aggregate_nonce: &[u8],
+        public_keys: &[Vec<u8>],
+        taproot_merkle_root: Option<&[u8]>,
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        epoch: Option<u64>,
+        nodeset: &[NodeSet],
+    ) -> Result<(PeerId, Vec<u8>)> {
+        // the nonce is gone once it's taken, even if signing fails
+        let secret_nonce = nonce_cache
+            .nonces
+            .remove(&MuSig2NonceCache::key(pubkey, session_id))
+            .await
+            .ok_or_else(|| {
+                invalid_session(format!(
+                    "MuSig2 session {} has no nonce, or it was used or expired",
+                    session_id
+                ))
+            })?;
+
+        let result = self
+            .sign_internal(
+                &secret_nonce,
+                message,
+                aggregate_nonce,
+                public_keys,
+                taproot_merkle_root,
+                root_pubkeys,
+                tweak_preimage,
+                epoch,
+                nodeset,
+            )
+            .await;
+        match &result {
+            Ok(_) => metrics::counter::add_one(metrics::tss::TssMetrics::SignatureShare, &[]),
+            Err(e) => {
+                metrics::counter::add_one(metrics::tss::TssMetrics::SignatureShareFail, &[]);
+                error!("Error signing MuSig2 session {}: {:?}", session_id, e);
+            }
+        }
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn sign_internal(
+        &self,
+        secret_nonce: &MuSig2SecretNonce,
+        message: &[u8],
+        aggregate_nonce: &[u8],
+        public_keys: &[Vec<u8>],
+        taproot_merkle_root: Option<&[u8]>,
+        root_pubkeys: Option<Vec<String>>,
+        tweak_preimage: Option<Vec<u8>>,
+        epoch: Option<u64>,
+        nodeset: &[NodeSet],
+    ) -> Result<(PeerId, Vec<u8>)> {
+        let peers = self.state.peer_state.peers();
+        let signing_peers = peers.peers_for_nodeset(nodeset);
+        let self_peer = peers.peer_at_address(&self.state.addr)?;
+        let key_id = tweak_preimage.expect_or_err("No hd_key_id provided!")?;
+        let root_pubkeys = root_pubkeys.expect_or_err("No root pubkeys provided!")?;
+        let epoch = epoch.unwrap_or(self.state.peer_state.epoch());
+
+        let deriver =
+            k256::Scalar::create(&key_id, SigningScheme::SchnorrK256Taproot.id_sign_ctx());
+        let (secret_share, public_key) = get_derived_keyshare::<k256::ProjectivePoint>(
+            deriver,
+            &root_pubkeys,
+            CurveType::K256,
+            &bytes_to_hex(self_peer.staker_address.as_bytes()),
+            &self_peer.peer_id,
+            epoch,
+            self.state.peer_state.realm_id(),
+            &self.state.key_cache,
+        )
+        .await?;
+
+        let session = MuSig2Session::new(
+            &public_key.to_compressed(),
+            public_keys,
+            taproot_merkle_root,
+            aggregate_nonce,
+            message,
+        )
+        .map_err(|e| validation_err_code(e, EC::NodeInvalidMuSig2Session, None))?;
+
+        let lagrange = lagrange_coefficient(self_peer.peer_id, &signing_peers.peer_ids())?;
+        Ok((
+            self_peer.peer_id,
+            session.sign(secret_nonce, &(lagrange * secret_share)),
+        ))
+    }
+}
+
+// the Lagrange coefficient at zero of this node's share, among the shares of the signing peers
+fn lagrange_coefficient(peer_id: PeerId, signing_peers: &[PeerId]) -> Result<k256::Scalar> {
+    let own = k256::Scalar::from(peer_id);
+    let mut numerator = k256::Scalar::ONE;
+    let mut denominator = k256::Scalar::ONE;
+    let mut found = false;
+    for other in signing_peers {
+        if *other == peer_id {
+            found = true;
+            continue;
+        }
+        let other = k256::Scalar::from(*other);
+        numerator *= other;
+        denominator *= other - own;
+    }
+    if !found {
+        return Err(unexpected_err("This node is not in the node set", None));
+    }
+    Option::<k256::Scalar>::from(denominator.invert())
+        .map(|inverse| numerator * inverse)
+        .ok_or_else(|| unexpected_err("The node set has duplicate peers", None))
+}
+
+fn invalid_session(msg: String) -> crate::error::Error {
+    validation_err_code(msg, EC::NodeInvalidMuSig2Session, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lagrange_coefficients_recover_the_secret() {
+        let peers = (0..3)
+            .map(|_| PeerId::random(&mut rand::rngs::OsRng))
+            .collect::<Vec<_>>();
+        let secret = k256::Scalar::from(42u64);
+        let coefficient = k256::Scalar::from(7u64);
+        let recovered = peers
+            .iter()
+            .map(|peer_id| {
+                let share = secret + coefficient * k256::Scalar::from(*peer_id);
+                lagrange_coefficient(*peer_id, &peers).unwrap() * share
+            })
+            .fold(k256::Scalar::ZERO, |sum, weighted| sum + weighted);
+        assert_eq!(recovered, secret);
+
+        assert!(lagrange_coefficient(PeerId::random(&mut rand::rngs::OsRng), &peers).is_err());
+    }
+}
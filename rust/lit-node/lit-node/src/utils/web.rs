@@ -1,3 +1,4 @@
+use crate::access_control::block::without_block_pins;
 use crate::auth::auth_material::JsonAuthSigExtendedRef;
 use crate::client_session::ClientSession;
 use crate::error::parser_err_code;
@@ -9,6 +10,7 @@ use crate::models::RequestConditions;
 use crate::models::auth::SessionKeySignedMessageV2;
 use crate::tss::common::tss_state::TssState;
 use crate::utils::encoding;
+use base64_light::{base64_decode, base64_encode_bytes};
 use ethers::utils::keccak256;
 use ipfs_hasher::IpfsHasher;
 use iri_string::spec::UriSpec;
@@ -570,11 +572,98 @@ pub async fn get_ipfs_file(
     Ok(text_result)
 }
 
+/// Fetch a binary file, such as a WebAssembly module, from IPFS and verify it against its CID.
+/// Binaries share the cache of `get_ipfs_file`, base64 encoded under a key of their own.
+#[instrument(level = "debug", skip(ipfs_cache, http_client))]
+pub async fn get_ipfs_binary_file(
+    ipfs_id: &String,
+    cfg: &LitConfig,
+    ipfs_cache: Cache<String, Arc<String>>,
+    http_client: reqwest::Client,
+) -> Result<Vec<u8>> {
+    let cache_key = format!("binary:{ipfs_id}");
+    if let Some(cached_file) = ipfs_cache.get(&cache_key).await {
+        return Ok(base64_decode(&cached_file));
+    }
+    let bytes = retrieve_bytes_from_ipfs(ipfs_id, cfg, http_client).await?;
+    ipfs_cache
+        .insert(cache_key, Arc::new(base64_encode_bytes(&bytes)))
+        .await;
+    Ok(bytes)
+}
+
+async fn retrieve_bytes_from_ipfs(
+    ipfs_id: &String,
+    cfg: &LitConfig,
+    http_client: reqwest::Client,
+) -> Result<Vec<u8>> {
+    let start_time = SystemTime::now();
+    let resp = fetch_from_ipfs(ipfs_id, cfg, http_client).await?;
+    let bytes = resp.bytes().await.map_err(|e| {
+        conversion_err(
+            e,
+            Some("Failed to get bytes from response during IPFS fetch".into()),
+        )
+    })?;
+
+    check_ipfs_file_size(bytes.len())?;
+    lit_core::utils::ipfs::verify_ipfs_cid(ipfs_id, &bytes)?;
+
+    let elapsed = SystemTime::now()
+        .duration_since(start_time)
+        .map_err(|e| unexpected_err(e, Some("Unable to get duration".into())))?;
+    debug!(
+        "Retrieved {} bytes from IPFS in {}ms",
+        bytes.len(),
+        elapsed.as_millis()
+    );
+
+    Ok(bytes.to_vec())
+}
+
 async fn retrieve_from_ipfs(
     ipfs_id: &String,
     cfg: &LitConfig,
     http_client: reqwest::Client,
 ) -> Result<String> {
+    let start_time = SystemTime::now();
+    let req = fetch_from_ipfs(ipfs_id, cfg, http_client).await?;
+    let text_result = req.text().await.map_err(|e| {
+        conversion_err(
+            e,
+            Some("Failed to get text from response during IPFS fetch".into()),
+        )
+    })?;
+
+    check_ipfs_file_size(text_result.len())?;
+
+    // verify the hash
+    let ipfs_hasher = IpfsHasher::default();
+    let cid = ipfs_hasher.compute(text_result.as_bytes());
+    if cid != ipfs_id.clone() {
+        return Err(ipfs_err(
+            format!(
+                "Error getting code from ipfs url.  Hash mismatch.  Expected: {}  Actual: {}",
+                ipfs_id, cid
+            ),
+            None,
+        ));
+    }
+
+    let end_time = SystemTime::now();
+    let elapsed = end_time
+        .duration_since(start_time)
+        .map_err(|e| unexpected_err(e, Some("Unable to get duration".into())))?;
+    debug!("Retrieved from IPFS in {}ms", elapsed.as_millis());
+
+    Ok(text_result)
+}
+
+async fn fetch_from_ipfs(
+    ipfs_id: &String,
+    cfg: &LitConfig,
+    http_client: reqwest::Client,
+) -> Result<reqwest::Response> {
     let default_entry = RpcEntry::new(RpcKind::IPFS, cfg.ipfs_gateway(), None, None);
 
     let rpc_resolver_struct = &RPC_RESOLVER;
@@ -592,7 +681,6 @@ async fn retrieve_from_ipfs(
         .first()
         .expect("ipfs_gateways should always have one entry");
 
-    let start_time = SystemTime::now();
     // TODO: set a max filesize for retrieval
     // TODO: use apikey & headers
     let url = gateway.url().replace("{}", ipfs_id.as_str());
@@ -617,43 +705,18 @@ async fn retrieve_from_ipfs(
         )
         .add_detail(format!("Error getting ipfs file: {}", ipfs_id)));
     }
-    let text_result = req.text().await.map_err(|e| {
-        conversion_err(
-            e,
-            Some("Failed to get text from response during IPFS fetch".into()),
-        )
-    })?;
 
-    if text_result.len() > 30000000 {
-        return Err(ipfs_err(
-            format!(
-                "Error getting code from ipfs url. File too large: {}",
-                text_result.len()
-            ),
-            None,
-        ));
-    }
+    Ok(req)
+}
 
-    // verify the hash
-    let ipfs_hasher = IpfsHasher::default();
-    let cid = ipfs_hasher.compute(text_result.as_bytes());
-    if cid != ipfs_id.clone() {
+fn check_ipfs_file_size(len: usize) -> Result<()> {
+    if len > 30000000 {
         return Err(ipfs_err(
-            format!(
-                "Error getting code from ipfs url.  Hash mismatch.  Expected: {}  Actual: {}",
-                ipfs_id, cid
-            ),
+            format!("Error getting code from ipfs url. File too large: {}", len),
             None,
         ));
     }
-
-    let end_time = SystemTime::now();
-    let elapsed = end_time
-        .duration_since(start_time)
-        .map_err(|e| unexpected_err(e, Some("Unable to get duration".into())))?;
-    debug!("Retrieved from IPFS in {}ms", elapsed.as_millis());
-
-    Ok(text_result)
+    Ok(())
 }
 
 #[instrument(level = "trace")]
@@ -661,10 +724,11 @@ pub fn hash_access_control_conditions(req: RequestConditions) -> Result<String>
     // hash the access control condition and thing to decrypt
     let mut hasher = Sha256::new();
 
+    // block pins only affect evaluation, so they aren't part of the identity
     // we need to check if we got passed an access control condition or an evm contract condition
     if let Some(access_control_conditions) = &req.access_control_conditions {
         let stringified_access_control_conditions =
-            serde_json::to_string(access_control_conditions)
+            serde_json::to_string(&without_block_pins(access_control_conditions))
                 .expect_or_err("Could not stringify")?;
         trace!(
             "stringified_access_control_conditions: {:?}",
@@ -673,7 +737,8 @@ pub fn hash_access_control_conditions(req: RequestConditions) -> Result<String>
         hasher.update(stringified_access_control_conditions.as_bytes());
     } else if let Some(evm_contract_conditions) = &req.evm_contract_conditions {
         let stringified_access_control_conditions =
-            serde_json::to_string(evm_contract_conditions).expect_or_err("Could not stringify")?;
+            serde_json::to_string(&without_block_pins(evm_contract_conditions))
+                .expect_or_err("Could not stringify")?;
         trace!(
             "stringified_access_control_conditions: {:?}",
             stringified_access_control_conditions
@@ -713,7 +778,7 @@ pub fn hash_access_control_conditions(req: RequestConditions) -> Result<String>
         }
     } else if let Some(unified_access_control_conditions) = &req.unified_access_control_conditions {
         let stringified_access_control_conditions =
-            serde_json::to_string(unified_access_control_conditions)
+            serde_json::to_string(&without_block_pins(unified_access_control_conditions))
                 .expect_or_err("Could not stringify")?;
         trace!(
             "stringified_access_control_conditions: {:?}",
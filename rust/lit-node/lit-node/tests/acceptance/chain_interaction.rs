@@ -121,6 +121,8 @@ async fn test_encryption_decryption_eip1271(
                 comparator: "=".to_string(),
                 value: format!("0x{}", bytes_to_hex(contract_address.as_bytes())),
             },
+            block_number: None,
+            block_tag: None,
         },
     )]);
 
@@ -299,6 +299,7 @@ pub fn get_session_sigs_for_auth(
             expiration: session_sig_expiration_time.to_rfc3339_opts(SecondsFormat::Millis, true),
             node_address: node.socket_address.clone(),
             max_price: max_price.unwrap_or(U256::MAX),
+            delegations: vec![],
         };
 
         let message = serde_json::to_string(&session_key_signed_message).unwrap();
@@ -469,6 +470,7 @@ pub async fn get_session_sigs_and_node_set_for_pkp(
             expiration: session_sig_expiration_time.to_rfc3339_opts(SecondsFormat::Millis, true),
             node_address: node.socket_address.to_owned(),
             max_price: max_price.unwrap_or(U256::MAX),
+            delegations: vec![],
         };
 
         let message = serde_json::to_string(&session_key_signed_message)?;
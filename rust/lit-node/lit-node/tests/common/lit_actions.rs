@@ -232,6 +232,46 @@ pub async fn execute_lit_action_session_sigs(
     auth_methods: Option<Vec<AuthMethod>>,
     session_sigs_and_node_set: &[SessionSigAndNodeSet],
     epoch: u64,
+) -> Result<Vec<GenericResponse<JsonExecutionResponse>>> {
+    send_lit_action_session_sigs(
+        lit_action_code,
+        ipfs_id,
+        js_params,
+        auth_methods,
+        session_sigs_and_node_set,
+        epoch,
+        false,
+    )
+    .await
+}
+
+/// Like [`execute_lit_action_session_sigs`], but asks each node for a trace of the execution.
+pub async fn execute_traced_lit_action_session_sigs(
+    lit_action_code: Option<String>,
+    js_params: Option<serde_json::Value>,
+    session_sigs_and_node_set: &[SessionSigAndNodeSet],
+    epoch: u64,
+) -> Result<Vec<GenericResponse<JsonExecutionResponse>>> {
+    send_lit_action_session_sigs(
+        lit_action_code,
+        None,
+        js_params,
+        None,
+        session_sigs_and_node_set,
+        epoch,
+        true,
+    )
+    .await
+}
+
+async fn send_lit_action_session_sigs(
+    lit_action_code: Option<String>,
+    ipfs_id: Option<String>,
+    js_params: Option<serde_json::Value>,
+    auth_methods: Option<Vec<AuthMethod>>,
+    session_sigs_and_node_set: &[SessionSigAndNodeSet],
+    epoch: u64,
+    trace: bool,
 ) -> Result<Vec<GenericResponse<JsonExecutionResponse>>> {
     info!("executing lit action with session sigs");
     // Generate JSON body for each port
@@ -255,6 +295,8 @@ pub async fn execute_lit_action_session_sigs(
                         epoch,
                         node_set: nodes.clone(),
                         invocation: Invocation::Sync,
+                        deterministic: false,
+                        trace,
                     };
                     lit_sdk::EndpointRequest {
                         node_set: sig_and_nodeset.node.clone(),
@@ -305,6 +347,8 @@ pub async fn execute_lit_action_auth_sig(
         epoch,
         node_set: node_set.iter().map(|(n, _)| n.clone()).collect(),
         invocation: Invocation::Sync,
+        deterministic: false,
+        trace: false,
     };
     let my_private_key = OsRng.r#gen();
     let response = lit_sdk::ExecuteFunctionRequest::new()
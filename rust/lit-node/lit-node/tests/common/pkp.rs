@@ -97,6 +97,8 @@ pub async fn generate_data_to_send_with_epoch(
         signing_scheme,
         epoch,
         node_set: node_set.to_vec(),
+        derivation_path: None,
+        proof_of_possession: false,
     };
     Ok(data_to_send)
 }
@@ -153,6 +155,8 @@ pub async fn generate_session_sigs_and_send_signing_requests(
                         signing_scheme,
                         epoch,
                         node_set: nodes.clone(),
+                        derivation_path: None,
+                        proof_of_possession: false,
                     };
                     lit_sdk::EndpointRequest {
                         identity_key: sig_and_nodeset.identity_key,
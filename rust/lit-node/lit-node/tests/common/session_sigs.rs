@@ -162,6 +162,8 @@ pub async fn get_pkp_sign(
                             signing_scheme: SigningScheme::EcdsaK256Sha256,
                             epoch: 2, // Hardcoded as at other places in the tests
                             node_set: nodes.clone(),
+                            derivation_path: None,
+                            proof_of_possession: false,
                         };
 
                         // json_body_vec.push(json_body);
@@ -199,6 +201,8 @@ pub async fn get_pkp_sign(
             signing_scheme: SigningScheme::EcdsaK256Sha256,
             epoch: 2, // Hardcoded as at other places in the tests
             node_set: nodes.clone(),
+            derivation_path: None,
+            proof_of_possession: false,
         };
         let my_secret_key = rand::rngs::OsRng.r#gen();
         let responses = lit_sdk::PKPSigningRequest::new()
@@ -69,6 +69,8 @@ pub fn prepare_test_encryption_parameters() -> TestEncryptionParameters {
                     comparator: ">=".to_string(),
                     value: "0".to_string(),
                 },
+                block_number: None,
+                block_tag: None,
             }),
         )]);
 
@@ -127,6 +129,8 @@ pub fn prepare_test_encryption_parameters_with_wallet_address(
                 comparator: "=".to_string(),
                 value: wallet_address,
             },
+            block_number: None,
+            block_tag: None,
         },
     )]);
 
@@ -314,6 +318,7 @@ pub async fn retrieve_decryption_key(
         data_to_encrypt_hash: test_encryption_parameters.data_to_encrypt_hash.clone(),
         auth_sig: AuthSigItem::Single(auth_sig.to_owned()),
         epoch,
+        block_numbers: Default::default(),
     };
     info!("Sending payload {:?}", payload);
     let my_secret_key = rand::rngs::OsRng.r#gen();
@@ -373,6 +378,7 @@ pub async fn retrieve_decryption_key_session_sigs_with_version(
             data_to_encrypt_hash: test_encryption_parameters.data_to_encrypt_hash.clone(),
             auth_sig: AuthSigItem::Single(session_sig_and_nodeset.session_sig.clone()),
             epoch,
+            block_numbers: Default::default(),
         };
 
         endpoint_requests.push(lit_sdk::EndpointRequest {
@@ -10,14 +10,18 @@ use lit_fast_ecdsa::SignatureShare;
 use lit_node::peers::peer_state::models::SimplePeerCollection;
 use lit_node::tasks::presign_manager::models::{PreSignatureValue, Presign};
 use lit_node::tss::common::dkg_type::DkgType;
+use lit_node::tss::common::traits::signable::Signable;
 use lit_node::tss::common::tss_state::TssState;
 use lit_node::tss::ecdsa_damfast::DamFastState;
 use lit_node::utils::traits::SignatureCurve;
 use lit_node_core::CompressedBytes;
+use lit_node_core::CompressedHex;
+use lit_node_core::DerivationPath;
 use lit_node_core::PeerId;
 use lit_node_core::SigningScheme;
 use serde::Serialize;
 use std::ops::Add;
+use std::str::FromStr;
 use std::sync::Arc;
 use test_case::test_case;
 use tokio::task::JoinHandle;
@@ -78,7 +82,21 @@ where
     <FieldBytesSize<C> as Add>::Output: ArrayLength<u8>,
 {
     let node_count = 3;
-    do_sign_with_pubkey(_c, signing_scheme, node_count, 0).await;
+    do_sign_with_pubkey(_c, signing_scheme, node_count, 0, None).await;
+}
+
+#[tokio::test]
+#[doc = "Test that a signature with the child of a PKP at a derivation path verifies under the child's public key."]
+async fn sign_with_derivation_path() {
+    let derivation_path = DerivationPath::from_str("m/44/60/0/0/7").unwrap();
+    do_sign_with_pubkey(
+        k256::Secp256k1,
+        SigningScheme::EcdsaK256Sha256,
+        3,
+        0,
+        Some(derivation_path),
+    )
+    .await;
 }
 
 pub async fn do_sign_with_pubkey<C>(
@@ -86,6 +104,7 @@ pub async fn do_sign_with_pubkey<C>(
     signing_scheme: SigningScheme,
     num_nodes: usize,
     _node_change: i16,
+    derivation_path: Option<DerivationPath>,
 ) where
     C: PrimeCurve + CurveArithmetic + DigestPrimitive + SignatureCurve,
     C::ProjectivePoint: GroupEncoding + HDDerivable + CompressedBytes,
@@ -160,6 +179,11 @@ pub async fn do_sign_with_pubkey<C>(
 
         let node = vnc.node_by_staker_address(peer.staker_address).unwrap();
         let mut damfast_state = node.damfast_state(signing_scheme).clone();
+        if let Some(derivation_path) = &derivation_path {
+            damfast_state
+                .set_derivation_path(derivation_path.clone())
+                .unwrap();
+        }
         let peers = signing_peers.clone();
 
         let hd_root_keys = hd_root_keys.clone();
@@ -219,6 +243,28 @@ pub async fn do_sign_with_pubkey<C>(
         sig.verify_digest(&message_bytes, &public_key.into())
     );
 
+    if let Some(derivation_path) = &derivation_path {
+        // the child of the PKP that the root keys and key id derive
+        let root_keys = hd_root_keys
+            .iter()
+            .map(|k| C::ProjectivePoint::from_compressed_hex(k).unwrap())
+            .collect::<Vec<_>>();
+        let deriver = C::Scalar::create(key_id, signing_scheme.id_sign_ctx());
+        let pkp_public_key = deriver.hd_derive_public_key(&root_keys);
+        let child_public_key = derivation_path
+            .derive_public_key(signing_scheme.curve_type(), &pkp_public_key.to_compressed())
+            .unwrap();
+
+        assert_eq!(
+            C::ProjectivePoint::from(public_key).to_compressed(),
+            child_public_key
+        );
+        assert!(
+            sig.verify_digest(&message_bytes, &public_key.into())
+                .is_ok()
+        );
+    }
+
     // cait sith
 
     // info!("Combining /w Cait-Sith");
@@ -300,5 +346,6 @@ fn get_damfast_ecdsa_state(state: Arc<TssState>, signing_scheme: SigningScheme)
         state,
         signing_scheme,
         dkg_type: DkgType::Standard,
+        derivation_path: None,
     }
 }
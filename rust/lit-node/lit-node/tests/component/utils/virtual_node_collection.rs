@@ -22,7 +22,10 @@ use lit_node::{
         peer_reviewer::PeerComplaint,
         peer_state::models::{PeerValidatorStatus, SimplePeer},
     },
-    tasks::{presign_manager::models::PresignMessage, utils::generate_hash},
+    tasks::{
+        frost_nonce_manager::models::FrostNonceMessage, presign_manager::models::PresignMessage,
+        utils::generate_hash,
+    },
     tss::{
         common::{
             models::{NodeTransmissionDetails, RoundData},
@@ -433,6 +436,7 @@ async fn load_virtual_node_defaults(
         Arc::new(ChainDataConfigManager::new(cfg.clone(), peer_checker_tx.clone()).await);
     let (pr_tx, _pr_rx) = new_traced_unbounded_channel();
     let (ps_tx, _ps_rx) = flume::bounded(10000);
+    let (frost_nonce_tx, _frost_nonce_rx) = flume::bounded(10000);
     let peer_state = Arc::new(
         new_peer_state(
             addr.clone(),
@@ -442,6 +446,7 @@ async fn load_virtual_node_defaults(
             chain_data_manager.clone(),
             testnet,
             ps_tx.clone(),
+            frost_nonce_tx,
             peer_checker_tx.clone(),
         )
         .await,
@@ -469,6 +474,7 @@ async fn new_peer_state(
     chain_data_config_manager: Arc<ChainDataConfigManager>,
     testnet: &Testnet,
     ps_tx: flume::Sender<PresignMessage>,
+    frost_nonce_tx: flume::Sender<FrostNonceMessage>,
     peer_checker_tx: flume::Sender<PeerCheckerMessage>,
 ) -> PeerState {
     let cfg = lit_config.load_full();
@@ -518,6 +524,7 @@ async fn new_peer_state(
         wallet_keys: EthWalletKeys::random(&mut rand::rngs::OsRng),
         lit_config,
         ps_tx,
+        frost_nonce_tx,
         tss_state: Weak::new(),
         client_grpc_channels: Default::default(),
         auto_join: false,
@@ -3,8 +3,8 @@ pub mod litactions {
         generate_authsig, get_session_sigs_and_node_set_for_pkp, get_session_sigs_for_auth,
     };
     use crate::common::lit_actions::{
-        execute_lit_action_session_sigs, generate_pkp_check_is_permitted_pkp_action,
-        generate_session_sigs_and_execute_lit_action,
+        execute_lit_action_session_sigs, execute_traced_lit_action_session_sigs,
+        generate_pkp_check_is_permitted_pkp_action, generate_session_sigs_and_execute_lit_action,
     };
     use crate::common::pkp::{SignedDatak256, recombine_shares_using_wasm};
     use crate::common::setup_logging;
@@ -497,6 +497,8 @@ pub mod litactions {
                     comparator: ">".to_string(),
                     value: "0".to_string(),
                 },
+                block_number: None,
+                block_tag: None,
             }),
         )]
     }
@@ -519,6 +521,8 @@ pub mod litactions {
                     "115792089237316195423570985008687907853269984665640564039457584007913129639935"
                         .to_string(),
             },
+            block_number: None,
+            block_tag: None,
         }),
     )]
     }
@@ -543,6 +547,8 @@ pub mod litactions {
                     comparator: "=".to_string(),
                     value: derived_ipfs_id,
                 },
+                block_number: None,
+                block_tag: None,
             }),
         )]
     }
@@ -587,6 +593,8 @@ pub mod litactions {
                     comparator: "=".to_string(),
                     value: "3".to_string(),
                 },
+                block_number: None,
+                block_tag: None,
             }),
         )]
     }
@@ -800,6 +808,77 @@ pub mod litactions {
         assert!(valid_sign_no_combine(execute_resp, params, ""));
     }
 
+    #[tokio::test]
+    async fn lit_action_trace() {
+        setup_logging();
+        let (_testnet, validator_collection, end_user) = TestSetupBuilder::default().build().await;
+
+        let node_set = validator_collection.random_threshold_nodeset().await;
+        let node_set = get_identity_pubkeys_from_node_set(&node_set).await;
+        let epoch = validator_collection
+            .actions()
+            .get_current_epoch(U256::from(1))
+            .await
+            .as_u64();
+        let session_sigs_and_node_set = get_session_sigs_for_auth(
+            &node_set,
+            vec![LitResourceAbilityRequest {
+                resource: LitResourceAbilityRequestResource {
+                    resource: "*".to_string(),
+                    resource_prefix: LitResourcePrefix::LA.to_string(),
+                },
+                ability: LitAbility::LitActionExecution.to_string(),
+            }],
+            Some(end_user.wallet.clone()),
+            None,
+            None,
+        );
+
+        const SLEEP_MS: u64 = 200;
+        let lit_action_code = format!(
+            r#"(async () => {{
+                console.log("first");
+                await new Promise((resolve) => setTimeout(resolve, {SLEEP_MS}));
+                Lit.Actions.setResponse({{ response: "second" }});
+            }})();"#
+        );
+        let execute_resp = execute_traced_lit_action_session_sigs(
+            Some(data_encoding::BASE64.encode(lit_action_code.as_bytes())),
+            None,
+            &session_sigs_and_node_set,
+            epoch,
+        )
+        .await
+        .expect("Could not execute lit action");
+
+        assert!(!execute_resp.is_empty());
+        for resp in execute_resp {
+            assert!(resp.ok, "Lit action failed: {:?}", resp.error);
+            let data = resp.data.expect("No response data");
+            assert_eq!(data.response, "second");
+            let trace = data.trace.expect("No execution trace");
+            assert!(!trace.truncated);
+
+            let ops = trace
+                .ops
+                .iter()
+                .map(|op| op.op.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(ops, vec!["Print", "SetResponse"]);
+            assert!(trace.ops.iter().all(|op| op.call_depth == 0));
+            assert!(trace.ops.iter().all(|op| op.error.is_none()));
+
+            let (print, set_response) = (&trace.ops[0], &trace.ops[1]);
+            assert!(trace.started_at <= print.started_at);
+            assert!(print.started_at <= print.ended_at);
+            assert!(set_response.started_at <= set_response.ended_at);
+            assert!(
+                set_response.started_at >= print.ended_at + SLEEP_MS,
+                "SetResponse should start after the action slept: {trace:?}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn sign_as_action() {
         setup_logging();
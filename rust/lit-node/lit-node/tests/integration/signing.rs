@@ -19,7 +19,8 @@ use std::{io::BufRead, time::Duration};
 use test_case::test_case;
 use tracing::{error, info};
 
-const ALL_SIGNING_SCHEMES: [SigningScheme; 14] = [
+const ALL_SIGNING_SCHEMES: [SigningScheme; 15] = [
+    SigningScheme::Bls12381,
     SigningScheme::Bls12381G1ProofOfPossession,
     SigningScheme::SchnorrEd25519Sha512,
     SigningScheme::SchnorrK256Sha256,
@@ -6,7 +6,10 @@ use lit_node_core::{
         Bls12381G2Impl, PublicKey, Signature, SignatureSchemes, SignatureShare, TimeCryptCiphertext,
     },
     request::EncryptionSignRequest as InnerEncryptionSignRequest,
-    response::{EncryptionSignResponse as InnerEncryptionSignResponse, GenericResponse},
+    response::{
+        EncryptionSignResponse as InnerEncryptionSignResponse, GenericResponse,
+        JsonAccessControlExplainResponse,
+    },
 };
 use std::{collections::HashMap, marker::PhantomData};
 use uuid::Uuid;
@@ -35,6 +38,30 @@ impl EncryptionSignRequestBuilder {
     }
 }
 
+/// The response type for access control explain requests
+pub type ExplainAccessControlResponse = Response<GenericResponse<JsonAccessControlExplainResponse>>;
+
+/// The access control explain request struct
+pub type ExplainAccessControlRequest = EncryptedMulticastRequest<
+    ExplainAccessControlRequestBuilder,
+    InnerEncryptionSignRequest,
+    GenericResponse<JsonAccessControlExplainResponse>,
+>;
+
+encrypted_multicast_builder!(
+    ExplainAccessControlRequestBuilder,
+    InnerEncryptionSignRequest,
+    GenericResponse<JsonAccessControlExplainResponse>,
+    "/web/access_control/explain/v2"
+);
+
+impl ExplainAccessControlRequestBuilder {
+    /// Check that the inner request fields are set
+    fn request_checks(&self) -> SdkResult<()> {
+        Ok(())
+    }
+}
+
 /// Time Lock Encryption
 pub fn encrypt_time_lock(
     public_key: &PublicKey<Bls12381G2Impl>,
@@ -46,6 +46,12 @@ pub enum SdkError {
     /// Signature verification error
     #[error("Signature does not verify with the given message and public key")]
     SignatureVerify,
+    /// Errors from deriving child keys
+    #[error("Key derivation error: {0}")]
+    Derivation(String),
+    /// Errors from MuSig2 signing
+    #[error("MuSig2 error: {0}")]
+    MuSig2(String),
     /// Errors from admin endpoints
     #[error("Admin endpoint error: {0}")]
     Admin(String),
@@ -59,6 +65,8 @@ impl From<lit_node_core::Error> for SdkError {
         match e {
             lit_node_core::Error::Parse(e) => SdkError::Parse(e),
             lit_node_core::Error::InvalidType(e) => SdkError::InvalidType(e),
+            lit_node_core::Error::Derivation(e) => SdkError::Derivation(e),
+            lit_node_core::Error::MuSig2(e) => SdkError::MuSig2(e),
         }
     }
 }
@@ -20,7 +20,10 @@ mod sev_snp;
 pub mod signature;
 
 pub use common::*;
-pub use encryption::{EncryptionSignRequest, EncryptionSignRequestBuilder, EncryptionSignResponse};
+pub use encryption::{
+    EncryptionSignRequest, EncryptionSignRequestBuilder, EncryptionSignResponse,
+    ExplainAccessControlRequest, ExplainAccessControlRequestBuilder, ExplainAccessControlResponse,
+};
 pub use error::*;
 pub use execute_function::*;
 pub use handshake::*;
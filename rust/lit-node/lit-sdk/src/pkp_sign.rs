@@ -1,8 +1,15 @@
 use crate::common::{EncryptedMulticastRequest, EndpointRequest, Response, UrlPrefix};
 use crate::{SdkError, SdkResult};
 use lit_node_core::{
-    request::JsonPKPSigningRequest,
-    response::{GenericResponse, JsonPKPSigningResponse},
+    CurveType, DerivationPath,
+    request::{
+        JsonPKPBatchSigningRequest, JsonPKPMuSig2NonceRequest, JsonPKPMuSig2SigningRequest,
+        JsonPKPSigningRequest,
+    },
+    response::{
+        GenericResponse, JsonPKPBatchSigningResponse, JsonPKPMuSig2NonceResponse,
+        JsonPKPMuSig2SigningResponse, JsonPKPSigningResponse,
+    },
 };
 use std::{collections::HashMap, marker::PhantomData};
 use uuid::Uuid;
@@ -41,3 +48,152 @@ impl PKPSigningRequestBuilder {
         Ok(())
     }
 }
+
+/// The response type for pkp batch signing calls
+pub type PKPBatchSigningResponse = Response<GenericResponse<JsonPKPBatchSigningResponse>>;
+
+/// The pkp batch signing request struct
+pub type PKPBatchSigningRequest = EncryptedMulticastRequest<
+    PKPBatchSigningRequestBuilder,
+    JsonPKPBatchSigningRequest,
+    GenericResponse<JsonPKPBatchSigningResponse>,
+>;
+
+encrypted_multicast_builder!(
+    PKPBatchSigningRequestBuilder,
+    JsonPKPBatchSigningRequest,
+    GenericResponse<JsonPKPBatchSigningResponse>,
+    "/web/pkp/sign/batch/v2"
+);
+
+impl PKPBatchSigningRequestBuilder {
+    /// Check that the inner request fields are set
+    fn request_checks(&self) -> SdkResult<()> {
+        let Some(node_set) = &self.node_set else {
+            return Ok(());
+        };
+        for (i, endpoint) in node_set.iter().enumerate() {
+            if endpoint.body.pubkey.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No pubkey is specified at '{}'",
+                    i + 1
+                )));
+            }
+            if endpoint.body.messages.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No messages are specified at '{}'",
+                    i + 1
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The response type for the nonce phase of pkp MuSig2 signing calls
+pub type PKPMuSig2NonceResponse = Response<GenericResponse<JsonPKPMuSig2NonceResponse>>;
+
+/// The pkp MuSig2 nonce request struct
+pub type PKPMuSig2NonceRequest = EncryptedMulticastRequest<
+    PKPMuSig2NonceRequestBuilder,
+    JsonPKPMuSig2NonceRequest,
+    GenericResponse<JsonPKPMuSig2NonceResponse>,
+>;
+
+encrypted_multicast_builder!(
+    PKPMuSig2NonceRequestBuilder,
+    JsonPKPMuSig2NonceRequest,
+    GenericResponse<JsonPKPMuSig2NonceResponse>,
+    "/web/pkp/musig2/nonce/v2"
+);
+
+impl PKPMuSig2NonceRequestBuilder {
+    /// Check that the inner request fields are set
+    fn request_checks(&self) -> SdkResult<()> {
+        let Some(node_set) = &self.node_set else {
+            return Ok(());
+        };
+        for (i, endpoint) in node_set.iter().enumerate() {
+            if endpoint.body.pubkey.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No pubkey is specified at '{}'",
+                    i + 1
+                )));
+            }
+            if endpoint.body.session_id.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No session id is specified at '{}'",
+                    i + 1
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The response type for the signing phase of pkp MuSig2 signing calls
+pub type PKPMuSig2SigningResponse = Response<GenericResponse<JsonPKPMuSig2SigningResponse>>;
+
+/// The pkp MuSig2 signing request struct
+pub type PKPMuSig2SigningRequest = EncryptedMulticastRequest<
+    PKPMuSig2SigningRequestBuilder,
+    JsonPKPMuSig2SigningRequest,
+    GenericResponse<JsonPKPMuSig2SigningResponse>,
+>;
+
+encrypted_multicast_builder!(
+    PKPMuSig2SigningRequestBuilder,
+    JsonPKPMuSig2SigningRequest,
+    GenericResponse<JsonPKPMuSig2SigningResponse>,
+    "/web/pkp/musig2/sign/v2"
+);
+
+impl PKPMuSig2SigningRequestBuilder {
+    /// Check that the inner request fields are set
+    fn request_checks(&self) -> SdkResult<()> {
+        let Some(node_set) = &self.node_set else {
+            return Ok(());
+        };
+        for (i, endpoint) in node_set.iter().enumerate() {
+            if endpoint.body.pubkey.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No pubkey is specified at '{}'",
+                    i + 1
+                )));
+            }
+            if endpoint.body.session_id.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No session id is specified at '{}'",
+                    i + 1
+                )));
+            }
+            if endpoint.body.aggregate_nonce.is_empty() {
+                return Err(SdkError::Build(format!(
+                    "No aggregate nonce is specified at '{}'",
+                    i + 1
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The public key of the child of a PKP at a derivation path, which signatures requested with
+/// that `derivation_path` verify under.  Keys are hex encoded, with or without a `0x` prefix,
+/// and the child key is returned in the compressed encoding of the curve.
+pub fn pkp_child_public_key(
+    curve_type: CurveType,
+    pubkey: &str,
+    derivation_path: &DerivationPath,
+) -> SdkResult<String> {
+    let public_key = hex::decode(pubkey.trim_start_matches("0x"))?;
+    let child = derivation_path.derive_public_key(curve_type, &public_key)?;
+    Ok(hex::encode(child))
+}
+
+/// The BIP-32 extended public key of the child of a secp256k1 PKP at a derivation path, which
+/// wallets and indexers can import to watch the child and derive further children from
+pub fn pkp_xpub(pubkey: &str, derivation_path: &DerivationPath) -> SdkResult<String> {
+    let public_key = hex::decode(pubkey.trim_start_matches("0x"))?;
+    Ok(derivation_path.xpub(&public_key)?)
+}
@@ -10,7 +10,7 @@ use elliptic_curve_tools::{group, prime_field};
 use lit_node_core::{
     CompressedBytes, CompressedHex, CurveType, EcdsaSignedMessageShare, KeyFormatPreference,
     PeerId, SignableOutput, SigningAlgorithm, SigningScheme,
-    blsful::{self, Bls12381G2Impl, PublicKey, Signature},
+    blsful::{self, AggregateSignature, Bls12381G2Impl, ProofOfPossession, PublicKey, Signature},
     hd_keys_curves_wasm::{
         HDDerivable, HDDeriver,
         elliptic_curve::{
@@ -449,6 +449,124 @@ pub fn verify_signature(
     }
 }
 
+/// Combine the shares of a proof of possession of a BLS PKP, which the nodes return when
+/// signing with [`SigningScheme::Bls12381G1ProofOfPossession`], `proof_of_possession` set and
+/// the compressed public key of the PKP as the message.
+///
+/// The output is needed to aggregate signatures of the PKP over the same message as others.
+pub fn combine_bls_proof_of_possession(
+    signature_shares: &[SignableOutput],
+) -> SdkResult<SignedDataOutput> {
+    let mut shares = Vec::with_capacity(signature_shares.len());
+    let mut public_key = None;
+    for signature_share in signature_shares {
+        if let SignableOutput::BlsSignedMessageShare(bls_msg_share) = signature_share {
+            if bls_msg_share.result == "success" {
+                shares.push(serde_json::from_str::<
+                    blsful::SignatureShare<Bls12381G2Impl>,
+                >(&bls_msg_share.signature_share)?);
+                public_key = Some(serde_json::from_str::<PublicKey<Bls12381G2Impl>>(
+                    &bls_msg_share.public_key,
+                )?);
+            }
+        }
+    }
+    let public_key = public_key.ok_or_else(|| {
+        SdkError::SignatureCombine("no bls proof of possession shares found".to_string())
+    })?;
+    let proof = match Signature::<Bls12381G2Impl>::from_shares(&shares)? {
+        Signature::ProofOfPossession(signature) => ProofOfPossession::<Bls12381G2Impl>(signature),
+        _ => {
+            return Err(SdkError::SignatureCombine(
+                "the shares are not of a proof of possession".to_string(),
+            ));
+        }
+    };
+    proof.verify(public_key)?;
+    Ok(SignedDataOutput {
+        signature: serde_json::to_string(&proof)?,
+        verifying_key: public_key.0.to_compressed_hex(),
+        signed_data: hex::encode(public_key.0.to_compressed()),
+        recovery_id: None,
+    })
+}
+
+/// Aggregate the BLS signatures of several PKPs, as returned by
+/// [`combine_and_verify_signature_shares`], into one signature.
+///
+/// Signatures with [`SigningScheme::Bls12381G1ProofOfPossession`] can be over the same message,
+/// as in Ethereum consensus, so each of their PKPs must have a proof of possession from
+/// [`combine_bls_proof_of_possession`] among `proofs_of_possession`. Otherwise one PKP could
+/// pick its key to cancel out the others. Signatures with [`SigningScheme::Bls12381`] must be
+/// over distinct messages, as in Filecoin. All the signatures must use the same scheme.
+pub fn aggregate_bls_signatures(
+    packages: &[SignedDataOutput],
+    proofs_of_possession: &[SignedDataOutput],
+) -> SdkResult<String> {
+    if packages.is_empty() {
+        return Err(SdkError::SignatureCombine(
+            "no bls signatures to aggregate".to_string(),
+        ));
+    }
+    let signatures = packages
+        .iter()
+        .map(|package| serde_json::from_str::<Signature<Bls12381G2Impl>>(&package.signature))
+        .collect::<Result<Vec<_>, _>>()?;
+    let aggregate_signature = AggregateSignature::<Bls12381G2Impl>::from_signatures(&signatures)?;
+    aggregate_signature.verify(&bls_signed_data(packages, proofs_of_possession)?)?;
+    Ok(serde_json::to_string(&aggregate_signature)?)
+}
+
+/// Verify a signature from [`aggregate_bls_signatures`] against the public keys and messages
+/// of the PKPs that signed, and the proofs of possession of their keys
+pub fn verify_aggregate_bls_signature(
+    aggregate_signature: &str,
+    packages: &[SignedDataOutput],
+    proofs_of_possession: &[SignedDataOutput],
+) -> SdkResult<()> {
+    let aggregate_signature: AggregateSignature<Bls12381G2Impl> =
+        serde_json::from_str(aggregate_signature)?;
+    aggregate_signature.verify(&bls_signed_data(packages, proofs_of_possession)?)?;
+    Ok(())
+}
+
+fn bls_signed_data(
+    packages: &[SignedDataOutput],
+    proofs_of_possession: &[SignedDataOutput],
+) -> SdkResult<Vec<(PublicKey<Bls12381G2Impl>, Vec<u8>)>> {
+    let proofs_of_possession = proofs_of_possession
+        .iter()
+        .map(|proof| {
+            let public_key: PublicKey<Bls12381G2Impl> =
+                serde_json::from_str(&format!("\"{}\"", &proof.verifying_key))?;
+            let proof: ProofOfPossession<Bls12381G2Impl> = serde_json::from_str(&proof.signature)?;
+            Ok((public_key, proof))
+        })
+        .collect::<SdkResult<Vec<_>>>()?;
+
+    packages
+        .iter()
+        .map(|package| {
+            let public_key: PublicKey<Bls12381G2Impl> =
+                serde_json::from_str(&format!("\"{}\"", &package.verifying_key))?;
+            let signature: Signature<Bls12381G2Impl> = serde_json::from_str(&package.signature)?;
+            if let Signature::ProofOfPossession(_) = signature {
+                let proven = proofs_of_possession
+                    .iter()
+                    .any(|(pk, proof)| *pk == public_key && proof.verify(public_key).is_ok());
+                if !proven {
+                    return Err(SdkError::SignatureCombine(format!(
+                        "no valid proof of possession of bls public key {}",
+                        package.verifying_key
+                    )));
+                }
+            }
+            let message = hex::decode(&package.signed_data)?;
+            Ok((public_key, message))
+        })
+        .collect()
+}
+
 /// Convert the signing_scheme to a frost scheme
 pub fn signing_scheme_to_frost_scheme(value: SigningScheme) -> SdkResult<lit_frost::Scheme> {
     match value {
@@ -594,3 +712,132 @@ pub fn get_lit_action_public_key(
     let key_id = keccak256(format!("lit_action_{}", action_ipfs_id));
     get_derived_public_key(signing_scheme, &key_id, root_keys)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lit_node_core::BlsSignedMessageShare;
+    use lit_node_core::blsful::inner_types::{G1Projective, G2Projective, Scalar};
+    use lit_node_core::blsful::vsss_rs::{IdentifierPrimeField, Share, ValueGroup};
+    use lit_node_core::blsful::{
+        BlsSignaturePop, HashToPoint, Pairing, SecretKey, SignatureSchemes,
+    };
+
+    fn bls_package(seed: &[u8], scheme: SignatureSchemes, message: &[u8]) -> SignedDataOutput {
+        let secret_key = SecretKey::<Bls12381G2Impl>::from_hash(seed);
+        let signature = secret_key.sign(scheme, message).unwrap();
+        SignedDataOutput {
+            signature: serde_json::to_string(&signature).unwrap(),
+            verifying_key: secret_key.public_key().0.to_compressed_hex(),
+            signed_data: hex::encode(message),
+            recovery_id: None,
+        }
+    }
+
+    fn bls_proof_of_possession(seed: &[u8]) -> SignedDataOutput {
+        let secret_key = SecretKey::<Bls12381G2Impl>::from_hash(seed);
+        let public_key = secret_key.public_key();
+        SignedDataOutput {
+            signature: serde_json::to_string(&secret_key.proof_of_possession().unwrap()).unwrap(),
+            verifying_key: public_key.0.to_compressed_hex(),
+            signed_data: hex::encode(public_key.0.to_compressed()),
+            recovery_id: None,
+        }
+    }
+
+    /// The shares of a proof of possession that nodes holding shares of `secret`, on a
+    /// polynomial of degree 2, return for `message`.
+    fn bls_proof_of_possession_shares(secret: Scalar, message: &[u8]) -> Vec<SignableOutput> {
+        let polynomial = [secret, Scalar::from(7u64), Scalar::from(11u64)];
+        let public_key = PublicKey::<Bls12381G2Impl>(G1Projective::GENERATOR * secret);
+        let point = <Bls12381G2Impl as HashToPoint>::hash_to_point(
+            message,
+            <Bls12381G2Impl as BlsSignaturePop>::POP_DST,
+        );
+        (1..=4u64)
+            .map(|id| {
+                let x = Scalar::from(id);
+                let value = polynomial
+                    .iter()
+                    .rev()
+                    .fold(Scalar::from(0u64), |acc, c| acc * x + c);
+                let share = blsful::SignatureShare::<Bls12381G2Impl>::ProofOfPossession(
+                    <Bls12381G2Impl as Pairing>::SignatureShare::with_identifier_and_value(
+                        IdentifierPrimeField(x),
+                        ValueGroup::<G2Projective>(point * value),
+                    ),
+                );
+                BlsSignedMessageShare {
+                    message: hex::encode(message),
+                    result: "success".to_string(),
+                    peer_id: id.to_string(),
+                    share_id: serde_json::to_string(&x).unwrap(),
+                    signature_share: serde_json::to_string(&share).unwrap(),
+                    verifying_share: String::new(),
+                    public_key: serde_json::to_string(&public_key).unwrap(),
+                    sig_type: SigningScheme::Bls12381G1ProofOfPossession.to_string(),
+                }
+                .into()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bls_proof_of_possession_shares_combine() {
+        let secret = Scalar::from(42u64);
+        let secret_key = SecretKey::<Bls12381G2Impl>(secret);
+        let public_key = secret_key.public_key();
+        let shares = bls_proof_of_possession_shares(secret, &public_key.0.to_compressed());
+
+        let proof = combine_bls_proof_of_possession(&shares[1..]).unwrap();
+        assert_eq!(
+            proof.signature,
+            serde_json::to_string(&secret_key.proof_of_possession().unwrap()).unwrap()
+        );
+        assert_eq!(proof.verifying_key, public_key.0.to_compressed_hex());
+        assert_eq!(proof.signed_data, hex::encode(public_key.0.to_compressed()));
+
+        // a proof of possession signs the public key
+        let shares = bls_proof_of_possession_shares(secret, b"attestation");
+        assert!(combine_bls_proof_of_possession(&shares).is_err());
+        assert!(combine_bls_proof_of_possession(&[]).is_err());
+    }
+
+    #[test]
+    fn bls_signatures_aggregate_across_pkps() {
+        let seeds = [b"pkp 1".as_slice(), b"pkp 2", b"pkp 3"];
+        let attestations = seeds
+            .map(|seed| bls_package(seed, SignatureSchemes::ProofOfPossession, b"attestation"));
+        let proofs = seeds.map(bls_proof_of_possession);
+        let aggregate_signature = aggregate_bls_signatures(&attestations, &proofs).unwrap();
+        verify_aggregate_bls_signature(&aggregate_signature, &attestations, &proofs).unwrap();
+        assert!(
+            verify_aggregate_bls_signature(&aggregate_signature, &attestations[..2], &proofs)
+                .is_err()
+        );
+
+        // same-message signatures need a proof of possession of every key
+        assert!(aggregate_bls_signatures(&attestations, &proofs[..2]).is_err());
+        assert!(
+            verify_aggregate_bls_signature(&aggregate_signature, &attestations, &proofs[1..])
+                .is_err()
+        );
+        let mut forged_proofs = proofs.clone();
+        forged_proofs[0].signature = proofs[1].signature.clone();
+        assert!(
+            verify_aggregate_bls_signature(&aggregate_signature, &attestations, &forged_proofs)
+                .is_err()
+        );
+
+        let blocks = [
+            bls_package(b"pkp 1", SignatureSchemes::Basic, b"block 1"),
+            bls_package(b"pkp 2", SignatureSchemes::Basic, b"block 2"),
+        ];
+        let aggregate_signature = aggregate_bls_signatures(&blocks, &[]).unwrap();
+        verify_aggregate_bls_signature(&aggregate_signature, &blocks, &[]).unwrap();
+
+        let mut forged = blocks.clone();
+        forged[1].signed_data = hex::encode(b"block 3");
+        assert!(verify_aggregate_bls_signature(&aggregate_signature, &forged, &[]).is_err());
+    }
+}